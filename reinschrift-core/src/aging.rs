@@ -0,0 +1,70 @@
+//! Priority aging: tasks left overdue climb in priority.
+//!
+//! A task overdue by more than `days` days gains one level per `days` days
+//! overdue (no priority → `pri:C` → `pri:B` → `pri:A`). The raise is written
+//! to the line together with an `aged:` token naming the previous priority,
+//! so it is visible in the file and in the task history and can be undone
+//! with [`revert`].
+
+use anyhow::Result;
+use chrono::{Datelike, NaiveDate};
+
+use crate::data::{self, Aging, TodoItem};
+
+/// The level a task without priority starts from, one below `pri:C`.
+const UNSET_LEVEL: char = 'D';
+
+/// The priority aging gives `item` on `today`, `None` if it stays as it is.
+/// Aging only ever raises, so a priority set higher by hand is kept.
+pub fn aged_priority(item: &TodoItem, days: u32, today: NaiveDate) -> Option<char> {
+    if days == 0 || item.done || item.aged == Some(Aging::Off) {
+        return None;
+    }
+    let due = item.due.filter(|due| due.year() != 9999)?;
+    let overdue = (today - due).num_days();
+    if overdue <= i64::from(days) {
+        return None;
+    }
+    let original = match item.aged {
+        Some(Aging::Raised(original)) => original,
+        _ => item.priority,
+    };
+    let start = original.unwrap_or(UNSET_LEVEL);
+    let steps = u32::try_from(overdue / i64::from(days)).unwrap_or(u32::MAX);
+    let target = char::from_u32((start as u32).saturating_sub(steps).max('A' as u32)).unwrap_or('A');
+    match item.priority {
+        Some(current) if current <= target => None,
+        _ => Some(target),
+    }
+}
+
+/// Raises the priority of every task overdue by more than `days` days.
+/// Returns the number of tasks changed.
+pub fn age_priorities(days: u32, today: NaiveDate) -> Result<usize> {
+    let mut aged = 0;
+    for item in data::load_todos()? {
+        let Some(priority) = aged_priority(&item, days, today) else {
+            continue;
+        };
+        let mut updated = item.clone();
+        updated.priority = Some(priority);
+        if !matches!(item.aged, Some(Aging::Raised(_))) {
+            updated.aged = Some(Aging::Raised(item.priority));
+        }
+        data::update_todo_details(&updated)?;
+        aged += 1;
+    }
+    Ok(aged)
+}
+
+/// Restores the priority from before aging and keeps the task from being
+/// aged again.
+pub fn revert(item: &TodoItem) -> Result<()> {
+    let Some(Aging::Raised(original)) = item.aged else {
+        return Ok(());
+    };
+    let mut updated = item.clone();
+    updated.priority = original;
+    updated.aged = Some(Aging::Off);
+    data::update_todo_details(&updated)
+}
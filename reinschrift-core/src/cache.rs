@@ -0,0 +1,307 @@
+//! Offline copy of remote databases.
+//!
+//! WebDAV and GIO databases are mirrored to a file below
+//! `~/.cache/reinschrift/offline`. Reads and saves only touch that copy, so a
+//! slow or unreachable share never holds up the window; a background thread
+//! pushes saved changes and pulls outside ones. Before pushing, the remote
+//! fingerprint is compared with the one the copy is based on: if the remote
+//! changed in the meantime nothing is overwritten and the copy is marked as
+//! in conflict until [`resolve`] picks a side.
+
+use std::collections::hash_map::DefaultHasher;
+use std::env;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::data::{database_location, get_backend_config, BackendConfig};
+use crate::i18n::t;
+use crate::storage::{self, Storage};
+
+/// The copy of one remote database as stored on disk.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct CachedCopy {
+    content: String,
+    /// Remote fingerprint of the version `content` was based on.
+    fingerprint: String,
+    /// Saved locally but not yet pushed.
+    pending: bool,
+    /// The remote version that changed while saves were pending.
+    #[serde(default)]
+    conflict: Option<RemoteVersion>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct RemoteVersion {
+    content: String,
+    fingerprint: String,
+}
+
+/// How the copy of the active database relates to the remote.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SyncStatus {
+    Synced,
+    /// Saves wait to be pushed.
+    Pending,
+    /// The last push or pull failed with this message.
+    Offline(String),
+    /// The remote changed while saves were pending, see [`conflict`].
+    Conflict,
+}
+
+/// Both versions of a database that was changed locally and remotely.
+#[derive(Clone, Debug)]
+pub struct Conflict {
+    pub mine: String,
+    pub theirs: String,
+}
+
+/// Serializes access to the copy files between the window and the worker.
+static COPY_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+static SYNCING: AtomicBool = AtomicBool::new(false);
+/// Location and message of the last failed sync.
+static LAST_ERROR: Lazy<Mutex<Option<(String, String)>>> = Lazy::new(|| Mutex::new(None));
+
+/// Whether databases of this backend are kept as offline copies.
+pub fn is_cached(config: &BackendConfig) -> bool {
+    matches!(config, BackendConfig::WebDav { .. } | BackendConfig::Uri(_))
+}
+
+fn cache_dir() -> Option<PathBuf> {
+    let cache = env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(cache.join("reinschrift").join("offline"))
+}
+
+/// The file holding the copy of `location`, see [`copy_file_name`].
+pub fn copy_path(location: &str) -> Option<PathBuf> {
+    cache_dir().map(|dir| dir.join(copy_file_name(location)))
+}
+
+/// The name of the copy of `location`: its first letters, digits, `.` and
+/// `-` to tell the files apart by eye, then an FNV-1a hash of the whole
+/// location, so no two locations share a file and long ones still fit.
+pub fn copy_file_name(location: &str) -> String {
+    let prefix: String = location
+        .chars()
+        .take(40)
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+        .collect();
+    let hash = location
+        .bytes()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3));
+    format!("{prefix}-{hash:016x}.json")
+}
+
+fn load_copy(location: &str) -> Option<CachedCopy> {
+    let path = copy_path(location)?;
+    let raw = fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&raw) {
+        Ok(copy) => Some(copy),
+        Err(err) => {
+            tracing::warn!("Ignoring unreadable offline copy of {location}: {err}");
+            None
+        }
+    }
+}
+
+/// Writes through a temporary file so an interrupted save keeps the old copy.
+fn store_copy(location: &str, copy: &CachedCopy) -> Result<()> {
+    let path = copy_path(location).context(t("offline_copy_error"))?;
+    let error = || t("write_error").replace("{}", &path.display().to_string());
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(error)?;
+    }
+    let temporary = path.with_extension("json.tmp");
+    fs::write(&temporary, serde_json::to_string(copy)?).with_context(error)?;
+    fs::rename(&temporary, &path).with_context(error)
+}
+
+fn lock() -> std::sync::MutexGuard<'static, ()> {
+    COPY_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn set_error(location: &str, error: Option<String>) {
+    if let Ok(mut last) = LAST_ERROR.lock() {
+        *last = error.map(|message| (location.to_string(), message));
+    }
+}
+
+/// Whether the active database has an offline copy to work from.
+pub fn has_copy() -> bool {
+    is_cached(&get_backend_config()) && load_copy(&database_location()).is_some()
+}
+
+/// `None` unless the active database is remote and has a copy.
+pub fn status() -> Option<SyncStatus> {
+    let location = database_location();
+    if !is_cached(&get_backend_config()) {
+        return None;
+    }
+    let copy = load_copy(&location)?;
+    if copy.conflict.is_some() {
+        return Some(SyncStatus::Conflict);
+    }
+    let error = LAST_ERROR.lock().ok().and_then(|last| last.clone());
+    match error {
+        Some((failed, message)) if failed == location => Some(SyncStatus::Offline(message)),
+        _ if copy.pending => Some(SyncStatus::Pending),
+        _ => Some(SyncStatus::Synced),
+    }
+}
+
+/// Both versions if the active database is in conflict.
+pub fn conflict() -> Option<Conflict> {
+    let copy = load_copy(&database_location())?;
+    let theirs = copy.conflict?;
+    Some(Conflict { mine: copy.content, theirs: theirs.content })
+}
+
+/// Ends a conflict: with `keep_mine` the local version is pushed over the
+/// remote one, otherwise the local changes are dropped.
+pub fn resolve(keep_mine: bool) -> Result<()> {
+    let location = database_location();
+    {
+        let _guard = lock();
+        let Some(mut copy) = load_copy(&location) else {
+            return Ok(());
+        };
+        let Some(theirs) = copy.conflict.take() else {
+            return Ok(());
+        };
+        tracing::info!(%location, keep_mine, "offline conflict resolved");
+        copy.fingerprint = theirs.fingerprint;
+        if !keep_mine {
+            copy.content = theirs.content;
+            copy.pending = false;
+        }
+        store_copy(&location, &copy)?;
+    }
+    sync_in_background();
+    Ok(())
+}
+
+/// Starts [`sync`] on a worker thread unless one is already running.
+pub fn sync_in_background() {
+    let config = get_backend_config();
+    if !is_cached(&config) || SYNCING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let location = database_location();
+    std::thread::spawn(move || {
+        match sync(config, &location) {
+            Ok(()) => set_error(&location, None),
+            Err(err) => {
+                tracing::warn!(%location, "offline sync failed: {err:#}");
+                set_error(&location, Some(format!("{err:#}")));
+            }
+        }
+        SYNCING.store(false, Ordering::SeqCst);
+    });
+}
+
+/// Brings the copy of `config` and the remote together: pending saves are
+/// pushed if the remote is still at the version they were based on, outside
+/// changes are pulled otherwise. Blocks on the network.
+fn sync(config: BackendConfig, location: &str) -> Result<()> {
+    let remote = storage::open_remote(config);
+    let fingerprint = remote.fingerprint()?;
+    let Some(copy) = load_copy(location) else {
+        return Ok(());
+    };
+    if copy.conflict.is_some() || (!copy.pending && copy.fingerprint == fingerprint) {
+        return Ok(());
+    }
+    if !copy.pending {
+        let content = remote.read()?;
+        let _guard = lock();
+        let mut current = load_copy(location).unwrap_or_default();
+        if current.pending {
+            // Saved while we were downloading; the next sync pushes it.
+            return Ok(());
+        }
+        tracing::info!(%location, "pulled remote changes into the offline copy");
+        current.content = content;
+        current.fingerprint = fingerprint;
+        return store_copy(location, &current);
+    }
+    if copy.fingerprint != fingerprint {
+        let theirs = RemoteVersion { content: remote.read()?, fingerprint };
+        let _guard = lock();
+        let mut current = load_copy(location).unwrap_or_default();
+        tracing::warn!(%location, "remote changed while offline saves were pending");
+        current.conflict = Some(theirs);
+        return store_copy(location, &current);
+    }
+    remote.write(&copy.content)?;
+    let fingerprint = remote.fingerprint()?;
+    let _guard = lock();
+    let mut current = load_copy(location).unwrap_or_default();
+    // Later saves stay pending, now based on what was just pushed.
+    current.pending = current.content != copy.content;
+    current.fingerprint = fingerprint;
+    tracing::info!(%location, bytes = copy.content.len(), "pushed offline changes");
+    store_copy(location, &current)
+}
+
+/// A remote database seen through its offline copy.
+pub struct CachedStorage {
+    pub config: BackendConfig,
+}
+
+impl Storage for CachedStorage {
+    fn read(&self) -> Result<String> {
+        let location = database_location();
+        if let Some(copy) = load_copy(&location) {
+            sync_in_background();
+            return Ok(copy.content);
+        }
+        // First use of this database: nothing to fall back on yet.
+        let remote = storage::open_remote(self.config.clone());
+        let content = remote.read()?;
+        let copy = CachedCopy {
+            content: content.clone(),
+            fingerprint: remote.fingerprint().unwrap_or_default(),
+            pending: false,
+            conflict: None,
+        };
+        let _guard = lock();
+        if let Err(err) = store_copy(&location, &copy) {
+            tracing::warn!("Could not keep an offline copy: {err:#}");
+        }
+        Ok(content)
+    }
+
+    fn write(&self, content: &str) -> Result<()> {
+        let location = database_location();
+        {
+            let _guard = lock();
+            let mut copy = load_copy(&location).unwrap_or_default();
+            copy.content = content.to_string();
+            copy.pending = true;
+            store_copy(&location, &copy)?;
+        }
+        sync_in_background();
+        Ok(())
+    }
+
+    /// Changes with the copy, which follows the remote through the
+    /// background sync; also gives that sync a nudge.
+    fn fingerprint(&self) -> Result<String> {
+        let Some(copy) = load_copy(&database_location()) else {
+            return storage::open_remote(self.config.clone()).fingerprint();
+        };
+        sync_in_background();
+        let mut hasher = DefaultHasher::new();
+        copy.content.hash(&mut hasher);
+        Ok(format!("{}-{}-{:x}", copy.fingerprint, copy.pending, hasher.finish()))
+    }
+}
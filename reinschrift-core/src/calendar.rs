@@ -0,0 +1,145 @@
+//! Calendar events shown next to the day's tasks.
+//!
+//! Events come from an iCalendar (`.ics`) file or URL, for example the
+//! personal calendar Evolution Data Server keeps in
+//! `~/.local/share/evolution/calendar/system/calendar.ics`. Only what
+//! planning needs is read: summary and time of each `VEVENT`. Times with a
+//! `TZID` are taken as local time, and recurring events only show up on
+//! their first date.
+
+use std::fs;
+
+use anyhow::{Context, Result};
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+
+use crate::i18n::t;
+
+/// One calendar entry, read-only.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    pub summary: String,
+    /// Local start time; midnight for all-day events.
+    pub start: NaiveDateTime,
+    /// Local end time, exclusive. Missing for events without `DTEND`.
+    pub end: Option<NaiveDateTime>,
+    pub all_day: bool,
+}
+
+impl Event {
+    /// Whether the event takes place on `day`.
+    pub fn occurs_on(&self, day: NaiveDate) -> bool {
+        let first = self.start.date();
+        let last = match self.end {
+            // An end at midnight belongs to the day before.
+            Some(end) if end > self.start => (end - chrono::Duration::seconds(1)).date(),
+            _ => first,
+        };
+        first <= day && day <= last
+    }
+}
+
+/// A `VEVENT` while its properties are read.
+#[derive(Default)]
+struct Partial {
+    summary: String,
+    start: Option<(NaiveDateTime, bool)>,
+    end: Option<NaiveDateTime>,
+}
+
+/// Reads the events of `source`: an `http(s)://` or `webcal://` URL, a
+/// `file://` URI or a path.
+pub fn load(source: &str) -> Result<Vec<Event>> {
+    let source = source.trim();
+    let text = if let Some(rest) = source.strip_prefix("webcal://") {
+        fetch(&format!("https://{rest}"))?
+    } else if source.starts_with("http://") || source.starts_with("https://") {
+        fetch(source)?
+    } else {
+        let path = source.strip_prefix("file://").unwrap_or(source);
+        fs::read_to_string(path).with_context(|| t("read_error").replace("{}", path))?
+    };
+    Ok(parse(&text))
+}
+
+fn fetch(url: &str) -> Result<String> {
+    let response = reqwest::blocking::get(url)
+        .and_then(|response| response.error_for_status())
+        .with_context(|| t("calendar_fetch_error").replace("{}", url))?;
+    Ok(response.text()?)
+}
+
+/// The events of an iCalendar document. Entries without a usable start are
+/// skipped.
+pub fn parse(text: &str) -> Vec<Event> {
+    let mut events = Vec::new();
+    let mut current: Option<Partial> = None;
+    for line in unfold(text) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (property, params) = name.split_once(';').unwrap_or((name, ""));
+        match (property.to_ascii_uppercase().as_str(), current.as_mut()) {
+            ("BEGIN", _) if value.eq_ignore_ascii_case("VEVENT") => current = Some(Partial::default()),
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VEVENT") => {
+                if let Some(Partial { summary, start: Some((start, all_day)), end }) = current.take() {
+                    events.push(Event { summary, start, end, all_day });
+                }
+            }
+            ("SUMMARY", Some(event)) => event.summary = unescape(value),
+            ("DTSTART", Some(event)) => event.start = parse_time(params, value),
+            ("DTEND", Some(event)) => event.end = parse_time(params, value).map(|(end, _)| end),
+            _ => {}
+        }
+    }
+    events.sort_by_key(|event| (!event.all_day, event.start));
+    events
+}
+
+/// The events on `day`, all-day ones first.
+pub fn on_day(events: &[Event], day: NaiveDate) -> Vec<&Event> {
+    events.iter().filter(|event| event.occurs_on(day)).collect()
+}
+
+/// Joins folded lines, which continue with a leading space or tab.
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Start or end time as local time, and whether it is a whole day.
+fn parse_time(params: &str, value: &str) -> Option<(NaiveDateTime, bool)> {
+    let value = value.trim();
+    let whole_day = params.split(';').any(|param| param.eq_ignore_ascii_case("VALUE=DATE"));
+    if whole_day || value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return Some((date.and_time(NaiveTime::MIN), true));
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some((Utc.from_utc_datetime(&time).with_timezone(&Local).naive_local(), false));
+    }
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok().map(|time| (time, false))
+}
+
+fn unescape(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => text.push(' '),
+            Some(other) => text.push(other),
+            None => {}
+        }
+    }
+    text
+}
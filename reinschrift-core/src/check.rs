@@ -0,0 +1,103 @@
+//! Validation of the database for `reinschrift check`.
+//!
+//! Collects what the parser skipped (see [`data::Problem`]) plus the
+//! problems that only show across tasks: `^markers` used twice and `dep:`
+//! entries pointing at no task. Each finding is a [`Diagnostic`] with a
+//! stable `code`, so scripts and pre-commit hooks can match on it.
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::data::{self, TodoItem};
+use crate::i18n::t;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    /// Probably a mistake, but the file reads fine.
+    Warning,
+}
+
+/// One finding, in the format of `check --json`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    /// The `#include`d file the line is in; `None` for the database itself.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    /// 1-based line number.
+    pub line: usize,
+    pub severity: Severity,
+    /// E.g. `invalid_date` or `duplicate_key`.
+    pub code: String,
+    pub message: String,
+}
+
+/// Loads the active database and lists everything wrong with it, sorted by
+/// file and line. Fails only if the database cannot be read.
+pub fn check_database() -> Result<Vec<Diagnostic>> {
+    let items = data::load_todos()?;
+    let mut diagnostics: Vec<Diagnostic> = data::load_problems()
+        .into_iter()
+        .map(|problem| Diagnostic {
+            severity: if problem.kind.is_warning() { Severity::Warning } else { Severity::Error },
+            code: problem.kind.code(),
+            message: problem.message(),
+            file: problem.file,
+            line: problem.line,
+        })
+        .collect();
+    diagnostics.extend(duplicate_keys(&items));
+    diagnostics.extend(unknown_dependencies(&items));
+    diagnostics.sort_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)));
+    Ok(diagnostics)
+}
+
+/// Every task line after the first one that uses a `^marker`.
+fn duplicate_keys(items: &[TodoItem]) -> Vec<Diagnostic> {
+    let mut first: HashMap<&str, &TodoItem> = HashMap::new();
+    let mut diagnostics = Vec::new();
+    for item in items {
+        let Some(marker) = item.key.marker.as_deref() else {
+            continue;
+        };
+        let Some(earlier) = first.get(marker) else {
+            first.insert(marker, item);
+            continue;
+        };
+        let message = t("check_duplicate_key")
+            .replacen("{}", marker, 1)
+            .replacen("{}", &(earlier.key.line_index + 1).to_string(), 1);
+        diagnostics.push(Diagnostic {
+            file: item.key.file.clone(),
+            line: item.key.line_index + 1,
+            severity: Severity::Error,
+            code: "duplicate_key".to_string(),
+            message,
+        });
+    }
+    diagnostics
+}
+
+/// `dep:` entries whose marker no task carries.
+fn unknown_dependencies(items: &[TodoItem]) -> Vec<Diagnostic> {
+    let markers: HashSet<&str> = items.iter().filter_map(|item| item.key.marker.as_deref()).collect();
+    items
+        .iter()
+        .flat_map(|item| {
+            item.depends_on
+                .iter()
+                .filter(|marker| !markers.contains(marker.as_str()))
+                .map(move |marker| Diagnostic {
+                    file: item.key.file.clone(),
+                    line: item.key.line_index + 1,
+                    severity: Severity::Error,
+                    code: "unknown_dependency".to_string(),
+                    message: t("check_unknown_dependency").replace("{}", marker),
+                })
+        })
+        .collect()
+}
@@ -0,0 +1,146 @@
+//! Alphabetical order of titles, projects and contexts in the UI language.
+//!
+//! Strings are compared in three passes, as in the Unicode collation
+//! algorithm: first by their letters with accents and case removed, then by
+//! accents, then by case, lower case first. `Äpfel` therefore sorts with
+//! `apfel` rather than after `Zucker`, and `Ähre` right after `Ahre`.
+//!
+//! Languages that treat some accented letters as letters of their own are
+//! tailored: Swedish puts `å`, `ä` and `ö` after `z`, Spanish `ñ` after `n`.
+//! Everything else follows German and English dictionaries. Only Latin
+//! letters carry accents here; other scripts sort by code point.
+
+use std::cmp::Ordering;
+
+use crate::i18n;
+
+/// Accented letters by the letter they are sorted with. The position in the
+/// string orders the accents among each other.
+const ACCENTED: [(char, &str); 19] = [
+    ('a', "àáâãäåāăą"),
+    ('c', "çćĉċč"),
+    ('d', "ďđ"),
+    ('e', "èéêëẽēĕėęě"),
+    ('g', "ĝğġģ"),
+    ('h', "ĥħ"),
+    ('i', "ìíîïĩīĭįı"),
+    ('j', "ĵ"),
+    ('k', "ķ"),
+    ('l', "ĺļľŀł"),
+    ('n', "ñńņňŉ"),
+    ('o', "òóôõöøōŏő"),
+    ('r', "ŕŗř"),
+    ('s', "śŝşš"),
+    ('t', "ţťŧ"),
+    ('u', "ùúûüũūŭůűų"),
+    ('w', "ŵ"),
+    ('y', "ýÿŷ"),
+    ('z', "źżž"),
+];
+
+/// Letters written as two, `ß` as `ss`.
+const EXPANDED: [(char, &str); 4] = [('ß', "ss"), ('æ', "ae"), ('œ', "oe"), ('þ', "th")];
+
+/// Letters sorted after `after`, in this order.
+struct Tailoring {
+    after: char,
+    letters: &'static [&'static str],
+}
+
+const SWEDISH: Tailoring = Tailoring { after: 'z', letters: &["å", "äæ", "öø"] };
+const SPANISH: Tailoring = Tailoring { after: 'n', letters: &["ñ"] };
+
+/// The sort weights of one string, compared level by level.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CollationKey {
+    primary: Vec<u32>,
+    secondary: Vec<u8>,
+    tertiary: Vec<u8>,
+    text: String,
+}
+
+/// See [`Collator::optional_key`].
+pub type OptionalKey = (bool, Option<CollationKey>);
+
+#[derive(Clone, Copy)]
+pub struct Collator {
+    tailoring: Option<&'static Tailoring>,
+}
+
+impl Collator {
+    /// The collator of a language code such as `sv` or `de_CH`.
+    pub fn for_locale(locale: &str) -> Self {
+        let tailoring = match locale.split(['_', '-']).next().unwrap_or_default() {
+            "sv" => Some(&SWEDISH),
+            "es" => Some(&SPANISH),
+            _ => None,
+        };
+        Collator { tailoring }
+    }
+
+    /// The collator of [`i18n::current_locale`].
+    pub fn current() -> Self {
+        Self::for_locale(&i18n::current_locale())
+    }
+
+    pub fn key(&self, text: &str) -> CollationKey {
+        let mut key = CollationKey {
+            primary: Vec::new(),
+            secondary: Vec::new(),
+            tertiary: Vec::new(),
+            text: text.to_string(),
+        };
+        for original in text.chars() {
+            let case = u8::from(original.is_uppercase());
+            for letter in original.to_lowercase() {
+                let (weights, accent) = self.weights(letter);
+                for weight in weights {
+                    key.primary.push(weight);
+                    key.secondary.push(accent);
+                    key.tertiary.push(case);
+                }
+            }
+        }
+        key
+    }
+
+    /// Key of a text that may be missing; missing ones sort last.
+    pub fn optional_key(&self, text: Option<&str>) -> OptionalKey {
+        (text.is_none(), text.map(|text| self.key(text)))
+    }
+
+    /// Builds both keys on every call; sorts should use [`sort`] or cache
+    /// the keys.
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        self.key(a).cmp(&self.key(b))
+    }
+
+    /// Primary weights of a lower-case letter and the rank of its accent.
+    /// The weight of a plain letter is its code point shifted by two, which
+    /// leaves room for the tailored letters after it.
+    fn weights(&self, letter: char) -> (Vec<u32>, u8) {
+        let plain = |letter: char| (letter as u32) << 2;
+        if let Some(tailoring) = self.tailoring {
+            let tailored = tailoring.letters.iter().position(|letters| letters.contains(letter));
+            if let Some(index) = tailored {
+                return (vec![plain(tailoring.after) + 1 + index as u32], 0);
+            }
+        }
+        if let Some((_, spelled)) = EXPANDED.iter().find(|(expanded, _)| *expanded == letter) {
+            return (spelled.chars().map(plain).collect(), 1);
+        }
+        for (base, accented) in ACCENTED {
+            if let Some(index) = accented.chars().position(|candidate| candidate == letter) {
+                return (vec![plain(base)], index as u8 + 1);
+            }
+        }
+        (vec![plain(letter)], 0)
+    }
+}
+
+/// Sorts `items` by `text` in the order of the UI language, building the
+/// key of each item once.
+pub fn sort<T>(items: &mut [T], text: impl Fn(&T) -> &str) {
+    let collator = Collator::current();
+    items.sort_by_cached_key(|item| collator.key(text(item)));
+}
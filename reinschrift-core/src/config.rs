@@ -0,0 +1,145 @@
+//! Defaults from `$XDG_CONFIG_HOME/reinschrift/config.toml`.
+//!
+//! The file is optional and every key in it too:
+//!
+//! ```toml
+//! database = "~/Notes/todo.md"
+//! language = "en"
+//! fallback-languages = ["en", "de"]
+//! date-format = "%d.%m.%Y"
+//! hide-completed = true
+//!
+//! [hooks]
+//! on-add = "notify-send 'New task'"
+//! ```
+//!
+//! [`load`] reads it once at startup, before the command line, whose flags
+//! take precedence. The hook commands run with `sh -c` in place of the
+//! scripts in [`hooks::hooks_dir`](crate::hooks::hooks_dir) of the same
+//! event.
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use anyhow::{bail, Context, Result};
+use chrono::format::{Item, StrftimeItems};
+use chrono::NaiveDate;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use crate::i18n::{t, LANGUAGES};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    /// Path or URI of the database, `~/` expanded.
+    pub database: Option<String>,
+    pub language: Option<String>,
+    /// Languages for strings the chosen one lacks, see
+    /// [`i18n::set_fallback_languages`](crate::i18n::set_fallback_languages).
+    pub fallback_languages: Option<Vec<String>>,
+    /// `strftime` format of the dates shown, e.g. `%d.%m.%Y`.
+    pub date_format: Option<String>,
+    pub hide_completed: Option<bool>,
+    pub hooks: HookCommands,
+}
+
+/// Shell commands run on task events, see [`crate::hooks`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct HookCommands {
+    pub on_add: Option<String>,
+    pub on_complete: Option<String>,
+    pub on_delete: Option<String>,
+}
+
+static CONFIG: Lazy<Mutex<Config>> = Lazy::new(|| Mutex::new(Config::default()));
+
+/// `$XDG_CONFIG_HOME/reinschrift`, falling back to `~/.config`.
+pub fn config_dir() -> Option<PathBuf> {
+    let config = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config.join("reinschrift"))
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("config.toml"))
+}
+
+/// Reads `config.toml` if there is one. A file that cannot be read or
+/// parsed leaves the defaults in place and is reported.
+pub fn load() -> Result<()> {
+    let Some(path) = config_path() else {
+        return Ok(());
+    };
+    let raw = match fs::read_to_string(&path) {
+        Ok(raw) => raw,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err).with_context(|| t("config_error").replace("{}", &path.display().to_string())),
+    };
+    let config = parse(&raw).with_context(|| t("config_error").replace("{}", &path.display().to_string()))?;
+    set(config);
+    Ok(())
+}
+
+/// `raw` as a [`Config`], with `~/` expanded and the date format and
+/// fallback languages checked.
+pub fn parse(raw: &str) -> Result<Config> {
+    let mut config: Config = toml::from_str(raw)?;
+    if let Some(database) = &config.database {
+        config.database = Some(expand_home(database));
+    }
+    let invalid = config.date_format.as_deref().filter(|format| try_format(NaiveDate::MIN, format).is_none());
+    if let Some(format) = invalid {
+        bail!(t("config_date_format_error").replace("{}", format));
+    }
+    let unknown = config.fallback_languages.iter().flatten().find(|code| !LANGUAGES.contains(&code.as_str()));
+    if let Some(code) = unknown {
+        bail!(t("config_language_error").replace("{}", code));
+    }
+    Ok(config)
+}
+
+/// Replaces the loaded configuration.
+pub fn set(config: Config) {
+    if let Ok(mut current) = CONFIG.lock() {
+        *current = config;
+    }
+}
+
+pub fn get() -> Config {
+    CONFIG.lock().map(|config| config.clone()).unwrap_or_default()
+}
+
+/// `date` in the configured format, ISO 8601 by default or if the format
+/// does not fit a date.
+pub fn format_date(date: NaiveDate) -> String {
+    get()
+        .date_format
+        .and_then(|format| try_format(date, &format))
+        .unwrap_or_else(|| date.format("%Y-%m-%d").to_string())
+}
+
+/// `date` in `format`, `None` for formats that are invalid or ask for a
+/// time. `to_string` would panic on those.
+fn try_format(date: NaiveDate, format: &str) -> Option<String> {
+    let items = StrftimeItems::new(format);
+    if items.clone().any(|item| matches!(item, Item::Error)) {
+        return None;
+    }
+    let mut text = String::new();
+    write!(text, "{}", date.format_with_items(items)).ok()?;
+    Some(text)
+}
+
+fn expand_home(location: &str) -> String {
+    match (location.strip_prefix("~/"), env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest).to_string_lossy().into_owned(),
+        _ => location.to_string(),
+    }
+}
@@ -0,0 +1,2120 @@
+//! The todo data model and line-level editing of the Markdown file.
+
+use std::collections::{HashSet, VecDeque};
+use std::env;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::history::{self, HistoryEvent};
+use crate::hooks::{self, HookEvent};
+use crate::i18n::t;
+use crate::journal;
+use crate::lock;
+use crate::starter::{self, StarterTemplate};
+use crate::storage;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Datelike, FixedOffset, Local, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use gio::prelude::*;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Where the todo file is stored; see [`crate::storage`] for the I/O.
+#[derive(Clone, Debug)]
+pub enum BackendConfig {
+    Local(PathBuf),
+    WebDav {
+        url: String,
+        path: Option<String>,
+        username: Option<String>,
+        password: Option<String>,
+    },
+    /// A file reached through GIO and GVfs, e.g. `sftp://nas/todo.md` or
+    /// `davs://cloud.example.org/dav/todo.md`.
+    Uri(String),
+    /// A document that is never written to disk, see [`crate::demo`].
+    Memory,
+}
+
+static BACKEND_CONFIG: Lazy<Mutex<BackendConfig>> = Lazy::new(|| {
+    let configured = default_todo_path();
+    Mutex::new(BackendConfig::Local(configured))
+});
+
+/// Replaces the backend used by all subsequent reads and writes.
+pub fn set_backend_config(config: BackendConfig) {
+    if let Ok(mut c) = BACKEND_CONFIG.lock() {
+        *c = config;
+    }
+}
+
+pub fn get_backend_config() -> BackendConfig {
+    BACKEND_CONFIG.lock().unwrap().clone()
+}
+
+/// What went wrong reading or editing the database. The errors of this
+/// module are these, wrapped in [`anyhow::Error`]; callers that explain them
+/// to users find them with `err.downcast_ref::<DataError>()`.
+#[derive(Debug)]
+pub enum DataError {
+    /// No database was chosen yet.
+    NoDatabase,
+    /// The database or a file it includes does not exist (any more).
+    NotFound { location: String },
+    PermissionDenied { location: String },
+    /// Reading or writing failed for another reason, given as the source.
+    Io { location: String, writing: bool, source: std::io::Error },
+    /// Line `line` (1-based) is not a task line that can be rewritten.
+    ParseError { line: usize },
+    /// The task is in none of the lines.
+    TaskNotFound,
+    /// Several lines carry the same `^marker`.
+    AmbiguousMarker { marker: String, lines: Vec<usize> },
+    /// Line `line` (1-based) was changed outside the app so that it is no
+    /// longer the task being saved.
+    Conflict { line: usize },
+    /// The file changed after a save failed, so retrying it would undo that.
+    Outdated,
+    /// Another process kept the database locked for an edit of its own.
+    Busy { location: String },
+    EmptyTitle,
+    EmptyName,
+}
+
+impl DataError {
+    /// Sorts an I/O error of `location` into the variants above.
+    pub fn from_io(location: impl Into<String>, writing: bool, source: std::io::Error) -> Self {
+        let location = location.into();
+        match source.kind() {
+            std::io::ErrorKind::NotFound => DataError::NotFound { location },
+            std::io::ErrorKind::PermissionDenied => DataError::PermissionDenied { location },
+            _ => DataError::Io { location, writing, source },
+        }
+    }
+}
+
+impl std::fmt::Display for DataError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            DataError::NoDatabase => t("no_database_configured"),
+            DataError::NotFound { location } => t("error_not_found").replace("{}", location),
+            DataError::PermissionDenied { location } => t("error_permission_denied").replace("{}", location),
+            DataError::Io { location, writing, .. } => {
+                t(if *writing { "write_error" } else { "read_error" }).replace("{}", location)
+            }
+            DataError::ParseError { line } => t("line_update_error").replace("{}", &line.to_string()),
+            DataError::TaskNotFound => t("todo_not_found"),
+            DataError::AmbiguousMarker { marker, lines } => {
+                let numbers: Vec<String> = lines.iter().map(usize::to_string).collect();
+                t("ambiguous_key_error").replacen("{}", marker, 1).replacen("{}", &numbers.join(", "), 1)
+            }
+            DataError::Conflict { line } => t("error_conflict").replace("{}", &line.to_string()),
+            DataError::Outdated => t("error_outdated"),
+            DataError::Busy { location } => t("error_busy").replace("{}", location),
+            DataError::EmptyTitle => t("title_empty_error"),
+            DataError::EmptyName => t("tag_name_empty"),
+        };
+        f.write_str(&message)
+    }
+}
+
+impl std::error::Error for DataError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DataError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+static TODO_PATH: Lazy<Mutex<PathBuf>> = Lazy::new(|| {
+    Mutex::new(default_todo_path())
+});
+
+/// The database path from `TODOS_DB_PATH`, else [`standard_todo_path`];
+/// empty if neither is known.
+pub fn default_todo_path() -> PathBuf {
+    env::var("TODOS_DB_PATH")
+        .map(PathBuf::from)
+        .ok()
+        .or_else(standard_todo_path)
+        .unwrap_or_default()
+}
+
+/// `$XDG_DATA_HOME/reinschrift/todo.md`, the database used when none was
+/// chosen.
+pub fn standard_todo_path() -> Option<PathBuf> {
+    let data = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share")))?;
+    Some(data.join("reinschrift").join("todo.md"))
+}
+
+/// Creates a [`StarterTemplate::Simple`] database at `path` unless a file
+/// is there already.
+pub fn create_database(path: &Path) -> Result<()> {
+    if path.exists() {
+        return Ok(());
+    }
+    starter::create(path, StarterTemplate::Simple)
+}
+
+/// The database location and its content at the last [`load_todos`], for
+/// [`recreate_database`].
+static LAST_LOADED: Lazy<Mutex<Option<(String, String)>>> = Lazy::new(|| Mutex::new(None));
+
+/// Writes the configured database again after it was deleted: with the
+/// content it had when it was last loaded, or as a new
+/// [`StarterTemplate::Simple`] database. Does nothing if the file is back.
+pub fn recreate_database() -> Result<()> {
+    let _lock = lock_for(None)?;
+    let config = get_backend_config();
+    match storage::open(config.clone()).read() {
+        Ok(_) => return Ok(()),
+        Err(err) if matches!(err.downcast_ref::<DataError>(), Some(DataError::NotFound { .. })) => {}
+        Err(err) => return Err(err),
+    }
+    let location = database_location();
+    let last = LAST_LOADED.lock().ok().and_then(|last| last.clone());
+    let content = match last {
+        Some((loaded, content)) if loaded == location => content,
+        _ => StarterTemplate::Simple.content(Local::now().date_naive()),
+    };
+    // `git checkout` of another branch may have taken the folder as well.
+    let folder = match &config {
+        BackendConfig::Local(path) => path.parent().filter(|dir| !dir.as_os_str().is_empty()),
+        _ => None,
+    };
+    if let Some(dir) = folder {
+        fs::create_dir_all(dir).map_err(|err| DataError::from_io(dir.display().to_string(), true, err))?;
+    }
+    write_content(None, content)?;
+    tracing::info!(location = %location, "recreated deleted database");
+    Ok(())
+}
+
+/// Creates [`standard_todo_path`] if it is `path` and missing, so the app
+/// works without being pointed at a file first. Returns whether it did.
+pub fn create_standard_database(path: &Path) -> Result<bool> {
+    if standard_todo_path().as_deref() != Some(path) || path.exists() {
+        return Ok(false);
+    }
+    create_database(path)?;
+    Ok(true)
+}
+
+static LINK_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[\[([^\]]+)\]\]").unwrap());
+static PROJECT_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\+([^\s]+)").unwrap());
+static CONTEXT_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"@([^\s]+)").unwrap());
+/// `due:2024-06-01`, optionally with a time, `T14:00`, and a UTC offset,
+/// `T14:00+02:00` or `T12:00Z`.
+static DUE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?:^|\s)due:(\d{4}-\d{2}-\d{2}(?:T\d{2}:\d{2}(?:Z|[+-]\d{2}:\d{2})?)?)").unwrap());
+static START_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?:^|\s)start:(\d{4}-\d{2}-\d{2})").unwrap());
+static CREATED_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?:^|\s)created:(\d{4}-\d{2}-\d{2})").unwrap());
+static ID_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\^([A-Za-z0-9]+)").unwrap());
+static COMPLETION_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s✅\s\d{4}-\d{2}-\d{2}").unwrap());
+static COMPLETED_ON_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"✅\s(\d{4}-\d{2}-\d{2})").unwrap());
+static RECUR_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?:^|\s)rec:([^\s]+)").unwrap());
+static PINNED_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?:^|\s)⭐(?:\s|$)").unwrap());
+static ESTIMATE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?:^|\s)est:((?:\d+h)?(?:\d+m)?)(?:\s|$)").unwrap());
+/// `remind:2025-01-31T09:00`, or `alarm:` as other apps call it.
+static REMIND_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?:^|\s)(?:remind|alarm):(\d{4}-\d{2}-\d{2}T\d{2}:\d{2})").unwrap());
+
+/// Format of the `remind:` token, local time without seconds.
+pub const REMIND_FORMAT: &str = "%Y-%m-%dT%H:%M";
+/// `#include other.todo`, relative to the file containing the line.
+static INCLUDE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^#include\s+(.+?)\s*$").unwrap());
+static PRIORITY_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?:^|\s)pri:([A-Za-z])\b").unwrap());
+static DEP_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?:^|\s)dep:(\^?[A-Za-z0-9]+(?:,\^?[A-Za-z0-9]+)*)").unwrap());
+static AGED_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?:^|\s)aged:(off|[A-Za-z-])(?:\s|$)").unwrap());
+/// Any `key:value` token of the known fields, to check values the patterns above skip.
+static FIELD_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?:^|\s)(start|due|remind|alarm|rec|pri|aged|dep|est|created):(\S*)").unwrap());
+/// A list item with a checkbox that is not `[ ]` or `[x]`, e.g. `- []` or `* [ ]`.
+static MALFORMED_TASK_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s*[-*+]\s*\[[^\]]{0,3}\]").unwrap());
+/// A `key:value` token in general, to spot misspelled fields such as `dua:`.
+static ANY_FIELD_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?:^|\s)([a-z][a-z_-]*):(\S+)").unwrap());
+static DEP_LIST_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\^?[A-Za-z0-9]+(?:,\^?[A-Za-z0-9]+)*$").unwrap());
+
+/// A section, project or context name. The same few names repeat across
+/// thousands of tasks, so each distinct one is stored once in a string table
+/// and copies share it; equal names compare by pointer. Names stay in the
+/// table until exit.
+#[derive(Clone)]
+pub struct Name(Arc<str>);
+
+static NAMES: Lazy<Mutex<HashSet<Arc<str>>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+impl Name {
+    pub fn new(name: &str) -> Self {
+        let mut names = NAMES.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(shared) = names.get(name) {
+            return Name(Arc::clone(shared));
+        }
+        let shared: Arc<str> = Arc::from(name);
+        names.insert(Arc::clone(&shared));
+        Name(shared)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for Name {
+    fn default() -> Self {
+        Name::new("")
+    }
+}
+
+impl std::ops::Deref for Name {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Name {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Name {
+    fn from(name: &str) -> Self {
+        Name::new(name)
+    }
+}
+
+impl From<String> for Name {
+    fn from(name: String) -> Self {
+        Name::new(&name)
+    }
+}
+
+impl From<&String> for Name {
+    fn from(name: &String) -> Self {
+        Name::new(name)
+    }
+}
+
+impl From<Name> for String {
+    fn from(name: Name) -> Self {
+        name.0.to_string()
+    }
+}
+
+/// Every name is interned by [`Name::new`], so different pointers always
+/// hold different text.
+impl PartialEq for Name {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Name {}
+
+impl PartialEq<str> for Name {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Name {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for Name {
+    fn eq(&self, other: &String) -> bool {
+        *self.0 == **other
+    }
+}
+
+impl PartialOrd for Name {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Name {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl std::hash::Hash for Name {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl std::borrow::Borrow<str> for Name {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for Name {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::fmt::Debug for Name {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl Serialize for Name {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Name {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Name::from)
+    }
+}
+
+/// Identifies a task line: by its `^marker` when present, else by position.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TodoKey {
+    pub line_index: usize,
+    pub marker: Option<String>,
+    /// The `#include`d file the line is in; `None` for the database itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+}
+
+/// One `- [ ]` / `- [x]` line of the todo file.
+///
+/// The serde form is the public JSON schema of `list --json`, `watch --json`,
+/// the HTTP API and JSON imports (see the README), so fields must not be
+/// renamed. Missing fields deserialize to their defaults, so imports only
+/// need a `title`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TodoItem {
+    pub key: TodoKey,
+    pub title: String,
+    pub section: Name,
+    pub project: Option<Name>,
+    pub context: Option<Name>,
+    /// First day of work on the task, `start:2025-01-27`.
+    pub start: Option<NaiveDate>,
+    pub due: Option<NaiveDate>,
+    /// Local time of day a timed task is due, `due:2024-06-01T14:00`; all-day
+    /// tasks have none. `due` is the local date then.
+    pub due_time: Option<NaiveTime>,
+    /// Seconds east of UTC the due time is written in, for `due:` values
+    /// with an offset such as `+02:00`. Without one the time is the wall
+    /// clock time wherever the task is read, so it stays put across DST.
+    pub due_offset: Option<i32>,
+    /// Local time of a reminder, `remind:2025-01-31T09:00`, independent of
+    /// the due date. `alarm:` is read the same and written as `remind:`.
+    pub remind: Option<NaiveDateTime>,
+    pub reference: Option<String>,
+    pub recurrence: Option<String>,
+    /// `pri:A` (highest) to `pri:Z`, stored upper-case.
+    pub priority: Option<char>,
+    /// Expected effort in minutes, `est:1h30m`.
+    pub estimate: Option<u32>,
+    /// Starred with `⭐`, listed above all other tasks.
+    pub pinned: bool,
+    /// `^marker`s of the tasks blocking this one, `dep:abc,def`.
+    pub depends_on: Vec<String>,
+    /// Whether [`crate::aging`] raised the priority, `aged:C`.
+    pub aged: Option<Aging>,
+    pub done: bool,
+    /// Date of the `✅` marker; done tasks without one are stamped today when saved.
+    pub completed: Option<NaiveDate>,
+    /// Day the task was added, `created:2025-01-27`; [`add_todo`] stamps it.
+    pub created: Option<NaiveDate>,
+}
+
+/// The `aged:` token left by [`crate::aging`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Aging {
+    /// The priority was raised; holds the one before (`aged:C`, `aged:-` for none).
+    Raised(Option<char>),
+    /// The raise was undone, the task is not aged again (`aged:off`).
+    Off,
+}
+
+impl Aging {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "off" => Some(Aging::Off),
+            "-" => Some(Aging::Raised(None)),
+            _ => value
+                .chars()
+                .next()
+                .filter(|c| c.is_ascii_alphabetic())
+                .map(|c| Aging::Raised(Some(c.to_ascii_uppercase()))),
+        }
+    }
+
+    fn value(self) -> String {
+        match self {
+            Aging::Raised(Some(priority)) => priority.to_string(),
+            Aging::Raised(None) => "-".to_string(),
+            Aging::Off => "off".to_string(),
+        }
+    }
+
+    fn token(self) -> String {
+        format!("aged:{}", self.value())
+    }
+}
+
+impl TodoItem {
+    /// Names of the fields that are compared when diffing or merging tasks,
+    /// in display order. Section and position are not part of a task's content.
+    pub const FIELDS: [&'static str; 15] = [
+        "title", "project", "context", "start", "due", "remind", "reference", "recurrence", "priority", "aged", "estimate",
+        "depends_on", "pinned", "done", "created",
+    ];
+
+    /// Text form of one of [`TodoItem::FIELDS`], `None` if unset.
+    pub fn field_text(&self, field: &str) -> Option<String> {
+        match field {
+            "title" => Some(self.title.clone()),
+            "section" => Some(self.section.to_string()),
+            "project" => self.project.as_deref().map(str::to_string),
+            "context" => self.context.as_deref().map(str::to_string),
+            "start" => self.start.map(|d| d.format("%Y-%m-%d").to_string()),
+            "due" => self.due.map(|due| due_value(due, self.due_time, self.due_offset)),
+            "remind" => self.remind.map(|r| r.format(REMIND_FORMAT).to_string()),
+            "reference" => self.reference.clone(),
+            "recurrence" => self.recurrence.clone(),
+            "priority" => self.priority.map(String::from),
+            "aged" => self.aged.map(Aging::value),
+            "estimate" => self.estimate.map(format_estimate),
+            "depends_on" => (!self.depends_on.is_empty()).then(|| self.depends_on.join(",")),
+            "pinned" => self.pinned.then(|| "⭐".to_string()),
+            "done" => Some(if self.done { "x" } else { " " }.to_string()),
+            "created" => self.created.map(|d| d.format("%Y-%m-%d").to_string()),
+            _ => None,
+        }
+    }
+
+    /// When a timed task is due, `None` for all-day tasks and those without
+    /// due date.
+    pub fn due_at(&self) -> Option<DateTime<Local>> {
+        Some(local_datetime(self.due?.and_time(self.due_time?)))
+    }
+
+    /// The reference if it points to a local file or folder, `[[file:///…]]`.
+    pub fn file_link(&self) -> Option<&str> {
+        self.reference.as_deref().filter(|reference| reference.starts_with("file://"))
+    }
+}
+
+/// `naive` wall clock time as a moment in the local time zone. A time the
+/// clocks skip when DST starts is moved on by the length of the gap, as the
+/// clock would show it; one they repeat when DST ends is its first pass.
+pub fn local_datetime(naive: NaiveDateTime) -> DateTime<Local> {
+    match Local.from_local_datetime(&naive) {
+        LocalResult::Single(moment) => moment,
+        // Not always in order for time zones read from the system.
+        LocalResult::Ambiguous(one, other) => one.min(other),
+        LocalResult::None => {
+            // The gaps are an hour or two long, so three hours earlier is
+            // still on the old offset.
+            let before = Local
+                .offset_from_local_datetime(&(naive - chrono::Duration::hours(3)))
+                .earliest()
+                .map_or(0, |offset| offset.local_minus_utc());
+            Local.from_utc_datetime(&(naive - chrono::Duration::seconds(i64::from(before))))
+        }
+    }
+}
+
+/// Local date, local time and written offset of a `due:` value.
+fn parse_due(value: &str) -> Option<(NaiveDate, Option<NaiveTime>, Option<i32>)> {
+    let Some((date, time)) = value.split_once('T') else {
+        return NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().map(|date| (date, None, None));
+    };
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+    let (clock, offset) = (time.get(..5)?, time.get(5..)?);
+    let clock = NaiveTime::parse_from_str(clock, "%H:%M").ok()?;
+    if offset.is_empty() {
+        return Some((date, Some(clock), None));
+    }
+    let offset = if offset == "Z" { FixedOffset::east_opt(0)? } else { offset.parse::<FixedOffset>().ok()? };
+    let local = offset.from_local_datetime(&date.and_time(clock)).single()?.with_timezone(&Local).naive_local();
+    Some((local.date(), Some(local.time()), Some(offset.local_minus_utc())))
+}
+
+/// The `due:` value of a local date and time, in `offset` if given.
+fn due_value(date: NaiveDate, time: Option<NaiveTime>, offset: Option<i32>) -> String {
+    let Some(time) = time else {
+        return date.format("%Y-%m-%d").to_string();
+    };
+    let local = local_datetime(date.and_time(time));
+    match offset.and_then(FixedOffset::east_opt) {
+        Some(offset) if offset.local_minus_utc() == 0 => local.with_timezone(&offset).format("%Y-%m-%dT%H:%MZ").to_string(),
+        Some(offset) => local.with_timezone(&offset).format("%Y-%m-%dT%H:%M%:z").to_string(),
+        None => local.naive_local().format(DUE_TIME_FORMAT).to_string(),
+    }
+}
+
+/// Format of a timed `due:` value without offset.
+pub const DUE_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+pub fn todo_path() -> PathBuf {
+    TODO_PATH
+        .lock()
+        .expect("todo path lock poisoned")
+        .clone()
+}
+
+/// Points the local backend at `new_path`.
+pub fn set_todo_path(new_path: PathBuf) {
+    if let Ok(mut path) = TODO_PATH.lock() {
+        *path = new_path.clone();
+    }
+    set_backend_config(BackendConfig::Local(new_path));
+}
+
+/// Whether `location` is a URI for [`BackendConfig::Uri`] rather than a
+/// local path: it has a scheme other than `file`.
+pub fn is_remote_uri(location: &str) -> bool {
+    gio::glib::Uri::peek_scheme(location).is_some_and(|scheme| scheme != "file")
+}
+
+/// Uses the database at `location`, a path, `file://` URI or one of the
+/// remote URIs GIO supports. [`todo_path`] then holds the location as given.
+pub fn set_database(location: &str) {
+    if !is_remote_uri(location) {
+        let path = location
+            .starts_with("file://")
+            .then(|| gio::File::for_uri(location).path())
+            .flatten()
+            .unwrap_or_else(|| PathBuf::from(location));
+        set_todo_path(path);
+        return;
+    }
+    if let Ok(mut path) = TODO_PATH.lock() {
+        *path = PathBuf::from(location);
+    }
+    set_backend_config(BackendConfig::Uri(location.to_string()));
+}
+
+/// Human readable location of the configured database, also used as a key
+/// for per-database settings.
+pub fn database_location() -> String {
+    match get_backend_config() {
+        BackendConfig::Local(path) => path.display().to_string(),
+        BackendConfig::WebDav { url, path, .. } => match path {
+            Some(p) => format!("{}/{}", url.trim_end_matches('/'), p.trim_start_matches('/')),
+            None => url,
+        },
+        BackendConfig::Uri(uri) => uri,
+        BackendConfig::Memory => "memory:demo".to_string(),
+    }
+}
+
+/// State of the most recent write, for status displays.
+#[derive(Clone, Debug)]
+pub enum SaveStatus {
+    Saving(DateTime<Local>),
+    Saved(DateTime<Local>),
+    Failed(DateTime<Local>, String),
+}
+
+impl SaveStatus {
+    pub fn time(&self) -> DateTime<Local> {
+        match self {
+            SaveStatus::Saving(at) | SaveStatus::Saved(at) | SaveStatus::Failed(at, _) => *at,
+        }
+    }
+}
+
+static SAVE_STATUS: Lazy<Mutex<Option<SaveStatus>>> = Lazy::new(|| Mutex::new(None));
+
+/// `None` until something was written in this process.
+pub fn save_status() -> Option<SaveStatus> {
+    SAVE_STATUS.lock().ok().and_then(|status| status.clone())
+}
+
+fn set_save_status(status: SaveStatus) {
+    if let Ok(mut current) = SAVE_STATUS.lock() {
+        *current = Some(status);
+    }
+}
+
+/// The last save that failed on every try, kept for [`retry_failed_write`].
+struct FailedWrite {
+    file: Option<PathBuf>,
+    content: String,
+    /// Of the stored file after the failure; a different one means it was
+    /// changed since and the content is outdated.
+    fingerprint: Option<String>,
+}
+
+static FAILED_WRITE: Lazy<Mutex<Option<FailedWrite>>> = Lazy::new(|| Mutex::new(None));
+
+/// Whether a change is waiting for [`retry_failed_write`].
+pub fn has_failed_write() -> bool {
+    FAILED_WRITE.lock().map(|failed| failed.is_some()).unwrap_or(false)
+}
+
+/// Tries the failed save again, unless the file was changed since.
+pub fn retry_failed_write() -> Result<()> {
+    let Some(file) = FAILED_WRITE.lock().ok().and_then(|failed| failed.as_ref().map(|failed| failed.file.clone())) else {
+        return Ok(());
+    };
+    // Locked before taking the change, so a busy database keeps it for later.
+    let _lock = lock_for(file.as_deref())?;
+    let Some(failed) = FAILED_WRITE.lock().ok().and_then(|mut failed| failed.take()) else {
+        return Ok(());
+    };
+    let current = storage::open(backend_for(failed.file.as_deref())).fingerprint().ok();
+    if current != failed.fingerprint {
+        bail!(DataError::Outdated);
+    }
+    write_content(failed.file.as_deref(), failed.content)
+}
+
+/// Files pulled in by `#include` lines during the last [`load_todos`].
+static INCLUDED_FILES: Lazy<Mutex<Vec<PathBuf>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+pub fn included_files() -> Vec<PathBuf> {
+    INCLUDED_FILES.lock().map(|files| files.clone()).unwrap_or_default()
+}
+
+/// See [`storage::Storage::fingerprint`]. Covers the included files as well,
+/// so editing one of them counts as a change of the database.
+pub fn get_fingerprint() -> Result<String> {
+    let mut fingerprint = storage::open(get_backend_config()).fingerprint()?;
+    for file in included_files() {
+        let included = storage::open(BackendConfig::Local(file)).fingerprint().unwrap_or_default();
+        fingerprint.push('|');
+        fingerprint.push_str(&included);
+    }
+    Ok(fingerprint)
+}
+
+/// The backend holding `file`, an included file, or the database for `None`.
+fn backend_for(file: Option<&Path>) -> BackendConfig {
+    match file {
+        Some(path) => BackendConfig::Local(path.to_path_buf()),
+        None => get_backend_config(),
+    }
+}
+
+/// Locks `file` like [`backend_for`] chooses it, for one read-modify-write
+/// cycle.
+fn lock_for(file: Option<&Path>) -> Result<lock::DatabaseLock> {
+    lock::acquire(&backend_for(file))
+}
+
+fn read_content(file: Option<&Path>) -> Result<String> {
+    let started = Instant::now();
+    let content = storage::open(backend_for(file)).read()?;
+    tracing::debug!(bytes = content.len(), elapsed = ?started.elapsed(), "database read");
+    Ok(content)
+}
+
+pub(crate) fn write_content(file: Option<&Path>, content: String) -> Result<()> {
+    let started = Instant::now();
+    set_save_status(SaveStatus::Saving(Local::now()));
+    let config = backend_for(file);
+    let local_path = match &config {
+        BackendConfig::Local(path) => Some(path.clone()),
+        BackendConfig::WebDav { .. } | BackendConfig::Uri(_) | BackendConfig::Memory => None,
+    };
+    // Without the journal a crash mid-write is not recoverable, but the save
+    // itself may still work, e.g. in a read-only folder.
+    if let Some(Err(err)) = local_path.as_ref().map(|path| journal::record(path, &content)) {
+        tracing::warn!("Saving without journal: {err:#}");
+    }
+    let storage = storage::open(config);
+    let result = storage::write_with_retry(storage.as_ref(), &content);
+    // Also removes what a failed journal write left behind.
+    if let (Some(path), Ok(())) = (&local_path, &result) {
+        journal::clear(path);
+    }
+    let mut failed = FAILED_WRITE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    match &result {
+        Ok(()) => {
+            tracing::info!(bytes = content.len(), elapsed = ?started.elapsed(), "database saved");
+            set_save_status(SaveStatus::Saved(Local::now()));
+            // Any older failed save is outdated by this one.
+            *failed = None;
+        }
+        Err(err) => {
+            tracing::error!(elapsed = ?started.elapsed(), "saving database failed: {err:#}");
+            set_save_status(SaveStatus::Failed(Local::now(), format!("{err:#}")));
+            let fingerprint = storage.fingerprint().ok();
+            *failed = Some(FailedWrite { file: file.map(Path::to_path_buf), content, fingerprint });
+        }
+    }
+    result
+}
+
+/// A `^marker` that is shared by more than one task line of one file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DuplicateKey {
+    pub marker: String,
+    /// The `#include`d file of the lines; `None` for the database itself.
+    pub file: Option<PathBuf>,
+    pub line_indices: Vec<usize>,
+}
+
+/// Lists markers that resolve to more than one line of `items`. Each file
+/// has markers of its own, like it has its own line numbers.
+pub fn find_duplicate_keys(items: &[TodoItem]) -> Vec<DuplicateKey> {
+    let mut duplicates: Vec<DuplicateKey> = Vec::new();
+    for item in items {
+        let Some(marker) = &item.key.marker else {
+            continue;
+        };
+        if let Some(existing) = duplicates.iter_mut().find(|d| &d.marker == marker && d.file == item.key.file) {
+            existing.line_indices.push(item.key.line_index);
+        } else {
+            duplicates.push(DuplicateKey {
+                marker: marker.clone(),
+                file: item.key.file.clone(),
+                line_indices: vec![item.key.line_index],
+            });
+        }
+    }
+    duplicates.retain(|d| d.line_indices.len() > 1);
+    duplicates
+}
+
+/// Why part of a line was not read as intended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProblemKind {
+    /// Looks like a task, but the checkbox is neither `[ ]` nor `[x]`.
+    MalformedTask,
+    InvalidDate,
+    InvalidTime,
+    InvalidEstimate,
+    InvalidPriority,
+    InvalidRecurrence,
+    InvalidDependency,
+    InvalidAging,
+    UnreadableInclude,
+    /// A `key:value` token with a key the format does not know. Often just
+    /// part of the title, so only a warning.
+    UnknownField,
+}
+
+impl ProblemKind {
+    pub(crate) fn message_key(self) -> &'static str {
+        match self {
+            ProblemKind::MalformedTask => "problem_malformed_task",
+            ProblemKind::InvalidDate => "problem_invalid_date",
+            ProblemKind::InvalidTime => "problem_invalid_time",
+            ProblemKind::InvalidEstimate => "problem_invalid_estimate",
+            ProblemKind::InvalidPriority => "problem_invalid_priority",
+            ProblemKind::InvalidRecurrence => "problem_invalid_recurrence",
+            ProblemKind::InvalidDependency => "problem_invalid_dependency",
+            ProblemKind::InvalidAging => "problem_invalid_aging",
+            ProblemKind::UnreadableInclude => "problem_unreadable_include",
+            ProblemKind::UnknownField => "problem_unknown_field",
+        }
+    }
+
+    /// Stable name for machine-readable output, e.g. `invalid_date`.
+    pub fn code(self) -> String {
+        serde_json::to_value(self)
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_string))
+            .unwrap_or_default()
+    }
+
+    /// Whether the line may well be fine as it is.
+    pub fn is_warning(self) -> bool {
+        self == ProblemKind::UnknownField
+    }
+}
+
+/// Something in the file the parser skipped; the rest of the line and the
+/// file are read as usual.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Problem {
+    /// The `#include`d file the line is in; `None` for the database itself.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    /// 1-based line number.
+    pub line: usize,
+    pub kind: ProblemKind,
+    /// The offending text, e.g. `due:2025-02-30`.
+    pub text: String,
+}
+
+impl Problem {
+    pub fn message(&self) -> String {
+        t(self.kind.message_key()).replace("{}", &self.text)
+    }
+}
+
+static PROBLEMS: Lazy<Mutex<Vec<Problem>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// The problems found by the last [`load_todos`].
+pub fn load_problems() -> Vec<Problem> {
+    PROBLEMS.lock().map(|problems| problems.clone()).unwrap_or_default()
+}
+
+/// What happened to a task between two loads of the file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Edited,
+    Completed,
+    Reopened,
+    Removed,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TodoChange {
+    pub event: ChangeKind,
+    /// The task after the change, or before it for `Removed`.
+    pub task: TodoItem,
+}
+
+/// Compares two loads of the file. Tasks are matched by `^marker`, else by
+/// title and section, and finally by line position, so an edited title of
+/// an unmarked task still counts as an edit if it stayed in place. Tasks of
+/// different `#include`d files never match.
+pub fn diff_todos(old: &[TodoItem], new: &[TodoItem]) -> Vec<TodoChange> {
+    fn identity(item: &TodoItem) -> (Option<&Path>, Option<&str>, &str, &str) {
+        let file = item.key.file.as_deref();
+        match &item.key.marker {
+            Some(marker) => (file, Some(marker), "", ""),
+            None => (file, None, &item.title, &item.section),
+        }
+    }
+
+    let mut old_matched = vec![false; old.len()];
+    let mut pairs: Vec<(Option<usize>, usize)> = Vec::new();
+    for (new_index, item) in new.iter().enumerate() {
+        let found = old
+            .iter()
+            .enumerate()
+            .position(|(i, candidate)| !old_matched[i] && identity(candidate) == identity(item));
+        if let Some(i) = found {
+            old_matched[i] = true;
+        }
+        pairs.push((found, new_index));
+    }
+    for pair in pairs.iter_mut().filter(|(old_index, _)| old_index.is_none()) {
+        let key = &new[pair.1].key;
+        let found = old
+            .iter()
+            .enumerate()
+            .position(|(i, candidate)| !old_matched[i] && candidate.key.line_index == key.line_index && candidate.key.file == key.file);
+        if let Some(i) = found {
+            old_matched[i] = true;
+            pair.0 = Some(i);
+        }
+    }
+
+    let mut changes = Vec::new();
+    for (old_index, new_index) in pairs {
+        let task = new[new_index].clone();
+        let Some(old_index) = old_index else {
+            changes.push(TodoChange { event: ChangeKind::Added, task });
+            continue;
+        };
+        let before = &old[old_index];
+        let event = match (before.done, task.done) {
+            (false, true) => Some(ChangeKind::Completed),
+            (true, false) => Some(ChangeKind::Reopened),
+            _ => {
+                let mut moved = before.clone();
+                moved.key.line_index = task.key.line_index;
+                (moved != task).then_some(ChangeKind::Edited)
+            }
+        };
+        if let Some(event) = event {
+            changes.push(TodoChange { event, task });
+        }
+    }
+    for (item, matched) in old.iter().zip(old_matched) {
+        if !matched {
+            changes.push(TodoChange { event: ChangeKind::Removed, task: item.clone() });
+        }
+    }
+    changes
+}
+
+/// Reads the database and, for a local one, every file it pulls in with
+/// `#include`. Tasks of an included file carry its path in their key and
+/// start out in a section named after the file.
+pub fn load_todos() -> Result<Vec<TodoItem>> {
+    let content = read_content(None)?;
+    if let Ok(mut last) = LAST_LOADED.lock() {
+        *last = Some((database_location(), content.clone()));
+    }
+    let mut problems = Vec::new();
+    let mut items = parse_document(&content, t("no_section"), None, &mut problems);
+
+    let mut included = Vec::new();
+    if let BackendConfig::Local(database) = get_backend_config() {
+        let mut seen = HashSet::from([database.clone()]);
+        let mut pending: VecDeque<(Option<PathBuf>, usize, PathBuf)> = include_directives(&content, &database)
+            .into_iter()
+            .map(|(line_index, file)| (None, line_index, file))
+            .collect();
+        while let Some((parent, line_index, file)) = pending.pop_front() {
+            if !seen.insert(file.clone()) {
+                continue;
+            }
+            match fs::read_to_string(&file) {
+                Ok(text) => {
+                    let section = file.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+                    items.extend(parse_document(&text, section, Some(&file), &mut problems));
+                    let nested = include_directives(&text, &file);
+                    pending.extend(nested.into_iter().map(|(index, nested)| (Some(file.clone()), index, nested)));
+                }
+                Err(err) => {
+                    tracing::warn!(file = %file.display(), "skipping unreadable include: {err}");
+                    problems.push(Problem {
+                        file: parent,
+                        line: line_index + 1,
+                        kind: ProblemKind::UnreadableInclude,
+                        text: file.display().to_string(),
+                    });
+                }
+            }
+            // Missing files stay watched so that creating them shows up.
+            included.push(file);
+        }
+    }
+    if let Ok(mut files) = INCLUDED_FILES.lock() {
+        *files = included;
+    }
+    problems.sort_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)));
+    if !problems.is_empty() {
+        tracing::warn!(count = problems.len(), "database has lines that could not be read completely");
+    }
+    if let Ok(mut last) = PROBLEMS.lock() {
+        *last = problems;
+    }
+
+    tracing::debug!(tasks = items.len(), "database parsed");
+    Ok(items)
+}
+
+/// Targets of the `#include` lines in `content`, which was read from `file`,
+/// with their line index.
+fn include_directives(content: &str, file: &Path) -> Vec<(usize, PathBuf)> {
+    let dir = file.parent().unwrap_or(Path::new(""));
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| INCLUDE_RE.captures(line.trim()).map(|caps| (index, caps)))
+        .map(|(index, caps)| (index, normalize_path(&dir.join(caps[1].trim_matches('"')))))
+        .collect()
+}
+
+/// Resolves `.` and `..` without touching the file system, so that one file
+/// reached along different relative paths is recognised as the same.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if matches!(normalized.components().next_back(), Some(Component::Normal(_))) => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Parses all task lines of a Markdown document, e.g. another database.
+pub fn parse_todos(content: &str) -> Vec<TodoItem> {
+    parse_document(content, t("no_section"), None, &mut Vec::new())
+}
+
+fn parse_document(content: &str, first_section: String, file: Option<&Path>, problems: &mut Vec<Problem>) -> Vec<TodoItem> {
+    let mut items = Vec::new();
+    let mut current_section = first_section;
+
+    for (line_index, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("###") {
+            current_section = trimmed.trim_start_matches('#').trim().to_string();
+            continue;
+        }
+
+        let item = parse_line(line, line_index, &current_section);
+        let found = line_problems(line, item.is_some());
+        problems.extend(found.into_iter().map(|(kind, text)| Problem {
+            file: file.map(Path::to_path_buf),
+            line: line_index + 1,
+            kind,
+            text,
+        }));
+        if let Some(mut item) = item {
+            item.key.file = file.map(Path::to_path_buf);
+            items.push(item);
+        }
+    }
+    items
+}
+
+/// What [`parse_document`] would report for `line` on its own.
+pub(crate) fn problems_in_line(line: &str) -> Vec<(ProblemKind, String)> {
+    line_problems(line, parse_line(line, 0, "").is_some())
+}
+
+/// Field values of a task line that [`parse_line`] cannot read and drops,
+/// or the line itself if it looks like a task but is not one.
+fn line_problems(line: &str, is_task: bool) -> Vec<(ProblemKind, String)> {
+    if !is_task {
+        if MALFORMED_TASK_RE.is_match(line) {
+            return vec![(ProblemKind::MalformedTask, line.trim().to_string())];
+        }
+        return Vec::new();
+    }
+    let mut problems = Vec::new();
+    for caps in FIELD_RE.captures_iter(line) {
+        let value = &caps[2];
+        let kind = match &caps[1] {
+            "start" | "created" if NaiveDate::parse_from_str(value, "%Y-%m-%d").is_err() => ProblemKind::InvalidDate,
+            "due" if parse_due(value).is_none() => ProblemKind::InvalidDate,
+            "remind" | "alarm" if NaiveDateTime::parse_from_str(value, REMIND_FORMAT).is_err() => ProblemKind::InvalidTime,
+            "est" if parse_estimate(value).is_none() => ProblemKind::InvalidEstimate,
+            "pri" if !(value.len() == 1 && value.chars().all(|c| c.is_ascii_alphabetic())) => ProblemKind::InvalidPriority,
+            "rec" if !["daily", "weekly", "monthly"].contains(&value.to_lowercase().as_str()) => ProblemKind::InvalidRecurrence,
+            "dep" if !DEP_LIST_RE.is_match(value) => ProblemKind::InvalidDependency,
+            "aged" if !(value == "off" || (value.len() == 1 && Aging::parse(value).is_some())) => ProblemKind::InvalidAging,
+            _ => continue,
+        };
+        problems.push((kind, caps[0].trim().to_string()));
+    }
+    for caps in ANY_FIELD_RE.captures_iter(line) {
+        let key = &caps[1];
+        let known = ["start", "due", "remind", "alarm", "rec", "pri", "aged", "dep", "est", "created"].contains(&key);
+        // Links such as `https://…` or `mailto:…` in the title.
+        let link = caps[2].starts_with("//") || ["mailto", "tel", "urn"].contains(&key);
+        if !known && !link {
+            problems.push((ProblemKind::UnknownField, caps[0].trim().to_string()));
+        }
+    }
+    problems
+}
+
+/// Checks or unchecks a task and adds/removes its `✅ date` marker.
+pub fn toggle_todo(key: &TodoKey, done: bool) -> Result<()> {
+    let file = key.file.as_deref();
+    let _lock = lock_for(file)?;
+    let content = read_content(file)?;
+    let mut lines: Vec<String> = content.lines().map(|line| line.to_string()).collect();
+    let had_trailing_newline = content.ends_with('\n');
+
+    let index = resolve_line_index(&lines, key)?;
+    let updated_line = rewrite_line(&lines[index], done)
+        .context(DataError::ParseError { line: index + 1 })?;
+    lines[index] = updated_line;
+
+    let mut output = lines.join("\n");
+    if had_trailing_newline {
+        output.push('\n');
+    }
+
+    write_content(file, output)?;
+
+    Ok(())
+}
+
+/// Marks `todo` done or open. Completing an overdue recurring task records
+/// it as done today, and every completed recurring task spawns its next
+/// occurrence.
+pub fn complete_todo(todo: &TodoItem, done: bool) -> Result<()> {
+    let today = Local::now().date_naive();
+    let is_historic = todo.due.map(|d| d < today).unwrap_or(false);
+    let is_recurring = todo.recurrence.is_some();
+
+    if done && is_historic && is_recurring {
+        let mut updated = todo.clone();
+        updated.due = Some(today);
+        updated.done = true;
+        update_todo_details(&updated)?;
+    } else {
+        toggle_todo(&todo.key, done)?;
+        history::record(if done { HistoryEvent::Completed } else { HistoryEvent::Reopened }, todo);
+    }
+
+    if !done {
+        return Ok(());
+    }
+    let mut completed = todo.clone();
+    completed.done = true;
+    hooks::run(HookEvent::Complete, &completed);
+    if let Some(next_due) = todo.recurrence.as_deref().and_then(|rule| next_due_date(todo.due, rule)) {
+        let mut next_item = todo.clone();
+        next_item.done = false;
+        next_item.due = Some(next_due);
+        next_item.created = Some(today);
+        if let Err(err) = add_todo_full(&next_item) {
+            tracing::error!("Failed to add recurring task: {err}");
+        }
+    }
+    Ok(())
+}
+
+pub fn set_due_today(key: &TodoKey) -> Result<NaiveDate> {
+    let today = Local::now().date_naive();
+    let mut updated = None;
+    let previous = update_line(key, |line| {
+        let line = rewrite_due(line, today)?;
+        updated = Some(line.clone());
+        Ok(line)
+    })?;
+    let before = parse_line(&previous, key.line_index, "");
+    let after = updated.and_then(|line| parse_line(&line, key.line_index, ""));
+    if let (Some(before), Some(after)) = (before, after) {
+        history::record_update(&before, &after);
+    }
+    Ok(today)
+}
+
+/// Re-renders the task line from `item`, keeping its position.
+pub fn update_todo_details(item: &TodoItem) -> Result<()> {
+    let rendered = render_line(item)?;
+    let previous = update_line(&item.key, |_| Ok(rendered))?;
+    if let Some(before) = parse_line(&previous, item.key.line_index, &item.section) {
+        history::record_update(&before, item);
+    }
+    Ok(())
+}
+
+/// A task whose line was changed outside the app while it was being edited,
+/// with some fields changed on both sides.
+#[derive(Clone, Debug)]
+pub struct MergeConflict {
+    /// The task as it was loaded, the common ancestor of both versions.
+    pub base: TodoItem,
+    /// Our edit with all non-conflicting outside changes merged in.
+    pub mine: TodoItem,
+    /// The task as it is in the file now.
+    pub theirs: TodoItem,
+    /// Fields changed to different values on both sides.
+    pub fields: Vec<&'static str>,
+}
+
+/// Saves `mine`, an edited copy of `base`, merging field by field with
+/// outside changes made to the same task since `base` was loaded. If both
+/// sides changed a field differently nothing is written and the conflict is
+/// returned for the user to resolve.
+pub fn update_todo_merged(base: &TodoItem, mine: &TodoItem) -> Result<Option<MergeConflict>> {
+    let _lock = lock_for(base.key.file.as_deref())?;
+    let content = read_content(base.key.file.as_deref())?;
+    let lines: Vec<String> = content.lines().map(|line| line.to_string()).collect();
+    let index = locate_task(&lines, base)?;
+    let mut theirs = parse_line(&lines[index], index, &base.section).ok_or(DataError::Conflict { line: index + 1 })?;
+    theirs.key.file = base.key.file.clone();
+
+    let mut merged = mine.clone();
+    merged.key = theirs.key.clone();
+    let mut fields = Vec::new();
+    for field in TodoItem::FIELDS {
+        let (ancestor, ours, other) = (base.field_text(field), mine.field_text(field), theirs.field_text(field));
+        if other == ancestor || other == ours {
+            continue;
+        }
+        if ours == ancestor {
+            take_field(&mut merged, &theirs, field);
+        } else {
+            fields.push(field);
+        }
+    }
+    if !fields.is_empty() {
+        tracing::info!(task = %base.title, ?fields, "conflicting outside edit");
+        return Ok(Some(MergeConflict { base: base.clone(), mine: merged, theirs, fields }));
+    }
+    update_todo_details(&merged)?;
+    Ok(None)
+}
+
+fn take_field(target: &mut TodoItem, source: &TodoItem, field: &str) {
+    match field {
+        "title" => target.title = source.title.clone(),
+        "project" => target.project = source.project.clone(),
+        "context" => target.context = source.context.clone(),
+        "start" => target.start = source.start,
+        "created" => target.created = source.created,
+        "due" => {
+            target.due = source.due;
+            target.due_time = source.due_time;
+            target.due_offset = source.due_offset;
+        }
+        "remind" => target.remind = source.remind,
+        "reference" => target.reference = source.reference.clone(),
+        "recurrence" => target.recurrence = source.recurrence.clone(),
+        "priority" => target.priority = source.priority,
+        "aged" => target.aged = source.aged,
+        "estimate" => target.estimate = source.estimate,
+        "depends_on" => target.depends_on = source.depends_on.clone(),
+        "pinned" => target.pinned = source.pinned,
+        "done" => target.done = source.done,
+        _ => {}
+    }
+}
+
+/// Finds the current line of `base`. Without a marker, lines inserted above
+/// it shift the index, so the title is used to find it again.
+fn locate_task(lines: &[String], base: &TodoItem) -> Result<usize> {
+    let index = resolve_line_index(lines, &base.key)?;
+    if base.key.marker.is_some() {
+        return Ok(index);
+    }
+    let title_at = |i: usize| parse_line(&lines[i], i, "").map(|item| item.title);
+    if title_at(index).as_deref() == Some(base.title.as_str()) {
+        return Ok(index);
+    }
+    let nearest = (0..lines.len())
+        .filter(|&i| title_at(i).as_deref() == Some(base.title.as_str()))
+        .min_by_key(|&i| i.abs_diff(index));
+    Ok(nearest.unwrap_or(index))
+}
+
+/// Scheme of links to a single task, `reinschrift://task/<marker>`.
+pub const TASK_URI_SCHEME: &str = "reinschrift";
+
+/// Link that opens `marker`'s task in the app.
+pub fn task_uri(marker: &str) -> String {
+    format!("{TASK_URI_SCHEME}://task/{marker}")
+}
+
+/// Resolves the id of a task link or `reinschrift show`: a marker, with or
+/// without `^`, or else a title, ignoring case. Of several tasks with that
+/// title the first open one wins, so a routine's done instances do not
+/// shadow the current one.
+pub fn find_task<'a>(items: &'a [TodoItem], id: &str) -> Option<&'a TodoItem> {
+    let id = id.trim();
+    let marker = id.strip_prefix('^').unwrap_or(id);
+    let title = id.to_lowercase();
+    items.iter().find(|item| item.key.marker.as_deref() == Some(marker)).or_else(|| {
+        items.iter().filter(|item| item.title.to_lowercase() == title).min_by_key(|item| item.done)
+    })
+}
+
+/// A short marker no task of `items` uses yet.
+fn unused_marker(items: &[TodoItem]) -> String {
+    const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+    let mut seed = Local::now().timestamp_nanos_opt().unwrap_or_default().unsigned_abs();
+    loop {
+        let marker: String = (0..6)
+            .map(|i| DIGITS[((seed >> (i * 5)) % DIGITS.len() as u64) as usize] as char)
+            .collect();
+        if !items.iter().any(|item| item.key.marker.as_deref() == Some(marker.as_str())) {
+            return marker;
+        }
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+    }
+}
+
+/// The `^marker` of `item`, appending a new one to its line if it has none,
+/// so that links to the task keep working after edits and moves.
+pub fn ensure_marker(item: &TodoItem) -> Result<String> {
+    if let Some(marker) = &item.key.marker {
+        return Ok(marker.clone());
+    }
+    let marker = unused_marker(&load_todos()?);
+    update_line(&item.key, |line| Ok(format!("{} ^{marker}", line.trim_end())))?;
+    Ok(marker)
+}
+
+/// The two kinds of inline tags a task can carry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tag {
+    /// `+project`
+    Project,
+    /// `@context`
+    Context,
+}
+
+impl Tag {
+    fn regex(self) -> &'static Regex {
+        match self {
+            Tag::Project => &PROJECT_RE,
+            Tag::Context => &CONTEXT_RE,
+        }
+    }
+
+    pub fn sigil(self) -> char {
+        match self {
+            Tag::Project => '+',
+            Tag::Context => '@',
+        }
+    }
+}
+
+/// A tag name as stored: without sigils and spaces, `None` if nothing is left.
+pub fn normalize_tag(text: &str) -> Option<String> {
+    normalize_token(Some(text))
+}
+
+/// Replaces the tag `old` by `new` (both without sigil) on every task line of
+/// the database and its included files, writing each file once. Returns the
+/// number of tasks changed.
+pub fn rename_tag(tag: Tag, old: &str, new: &str) -> Result<usize> {
+    let Some(new) = normalize_tag(new) else {
+        bail!(DataError::EmptyName);
+    };
+    let files = std::iter::once(None).chain(included_files().into_iter().map(Some));
+    let mut changes = Vec::new();
+    for file in files {
+        let _lock = lock_for(file.as_deref())?;
+        let content = read_content(file.as_deref())?;
+        let mut lines: Vec<String> = content.lines().map(|line| line.to_string()).collect();
+        let before = changes.len();
+        for (index, line) in lines.iter_mut().enumerate() {
+            let Some(previous) = parse_line(line, index, "") else {
+                continue;
+            };
+            let renamed = tag.regex().replace_all(line, |caps: &regex::Captures| {
+                if &caps[1] == old {
+                    format!("{}{new}", tag.sigil())
+                } else {
+                    caps[0].to_string()
+                }
+            });
+            if renamed != line.as_str() {
+                *line = renamed.into_owned();
+                if let Some(updated) = parse_line(line, index, "") {
+                    changes.push((previous, updated));
+                }
+            }
+        }
+        if changes.len() == before {
+            continue;
+        }
+        let mut output = lines.join("\n");
+        if content.ends_with('\n') {
+            output.push('\n');
+        }
+        write_content(file.as_deref(), output)?;
+    }
+    for (before, after) in &changes {
+        history::record_update(before, after);
+    }
+    Ok(changes.len())
+}
+
+/// [`ensure_marker`] for each task of `items` without one, for exports
+/// whose format needs a lasting identity. Each file is written once, and not
+/// at all when every task already has a marker.
+pub fn ensure_markers(items: &mut [TodoItem]) -> Result<()> {
+    let mut files: Vec<Option<PathBuf>> = Vec::new();
+    for item in items.iter().filter(|item| item.key.marker.is_none()) {
+        if !files.contains(&item.key.file) {
+            files.push(item.key.file.clone());
+        }
+    }
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    let mut known = load_todos()?;
+    for file in files {
+        let _lock = lock_for(file.as_deref())?;
+        let content = read_content(file.as_deref())?;
+        let mut lines: Vec<String> = content.lines().map(|line| line.to_string()).collect();
+        for item in items.iter_mut().filter(|item| item.key.marker.is_none() && item.key.file == file) {
+            let index = resolve_line_index(&lines, &item.key)?;
+            let marker = unused_marker(&known);
+            lines[index] = format!("{} ^{marker}", lines[index].trim_end());
+            item.key.marker = Some(marker);
+            known.push(item.clone());
+        }
+        let mut output = lines.join("\n");
+        if content.ends_with('\n') {
+            output.push('\n');
+        }
+        write_content(file.as_deref(), output)?;
+    }
+    Ok(())
+}
+
+pub fn delete_todo(item: &TodoItem) -> Result<()> {
+    delete_line(&item.key)?;
+    history::record(HistoryEvent::Deleted, item);
+    hooks::run(HookEvent::Delete, item);
+    Ok(())
+}
+
+/// Appends a new open task due today, before the first `---` separator.
+pub fn add_todo(title: &str) -> Result<()> {
+    let title = title.trim();
+    if title.is_empty() {
+        bail!(DataError::EmptyTitle);
+    }
+    let today = Local::now().date_naive().format("%Y-%m-%d");
+    let line = format!("- [ ] {title} due:{today} created:{today}");
+    insert_line(line.clone())?;
+    if let Some(item) = parse_line(&line, 0, "") {
+        history::record(HistoryEvent::Added, &item);
+        hooks::run(HookEvent::Add, &item);
+    }
+    Ok(())
+}
+
+/// Appends an open task with all metadata of `item`, stamped `created:`
+/// today unless it already has a creation date.
+pub fn add_todo_full(item: &TodoItem) -> Result<()> {
+    let mut clone = item.clone();
+    clone.done = false;
+    clone.key = TodoKey::default();
+    clone.created.get_or_insert_with(|| Local::now().date_naive());
+    let line = render_line(&clone)?;
+    insert_line(line)?;
+    history::record(HistoryEvent::Added, &clone);
+    hooks::run(HookEvent::Add, &clone);
+    Ok(())
+}
+
+/// Parses free text with inline tokens (`+project @context due:…`) as an
+/// open task without section.
+pub fn parse_task_text(text: &str) -> Option<TodoItem> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    parse_line(&format!("- [ ] {text}"), 0, "")
+}
+
+/// What [`import_todos`] did.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Imported {
+    pub added: usize,
+    /// Tasks whose `^marker` was already in the file and were rewritten there.
+    pub updated: usize,
+}
+
+impl Imported {
+    /// "Added 3 task(s)", and how many were updated if any were.
+    pub fn summary(&self) -> String {
+        if self.updated == 0 {
+            return t("tasks_imported").replace("{}", &self.added.to_string());
+        }
+        t("tasks_imported_updated")
+            .replacen("{}", &self.added.to_string(), 1)
+            .replacen("{}", &self.updated.to_string(), 1)
+    }
+}
+
+/// Adds several tasks with a single write. A task whose `section` names a
+/// `###` heading is appended to the end of that section, creating the heading
+/// before the `---` separator if needed; others go where [`add_todo`] puts them.
+/// A task with a `^marker` the file already has updates that line instead
+/// with the fields it sets, so importing the same tasks again does not
+/// duplicate them.
+pub fn import_todos(items: &[TodoItem]) -> Result<Imported> {
+    let _lock = lock_for(None)?;
+    let content = read_content(None)?;
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+    let no_section = t("no_section");
+    let existing = parse_todos(&content);
+
+    // Updates first, while the line numbers still hold.
+    let mut updated = Vec::new();
+    let mut added = Vec::new();
+    for item in items {
+        let known = item
+            .key
+            .marker
+            .as_ref()
+            .and_then(|marker| existing.iter().find(|other| other.key.marker.as_ref() == Some(marker)));
+        match known {
+            Some(before) => {
+                // Fields the other side does not know, such as `est:`, stay.
+                let mut after = before.clone();
+                for field in TodoItem::FIELDS.iter().filter(|field| item.field_text(field).is_some()) {
+                    take_field(&mut after, item, field);
+                }
+                after.completed = item.completed.or(before.completed).filter(|_| after.done);
+                lines[before.key.line_index] = render_line(&after)?;
+                updated.push((before, after));
+            }
+            None => added.push(item),
+        }
+    }
+    for item in &added {
+        let line = render_line(item)?;
+        let section = item.section.trim();
+        if section.is_empty() || section == no_section {
+            let separator = lines
+                .iter()
+                .position(|l| l.trim() == "---")
+                .unwrap_or(lines.len());
+            lines.insert(separator, line);
+        } else {
+            insert_into_section(&mut lines, section, line);
+        }
+    }
+
+    let mut output = lines.join("\n");
+    if content.ends_with('\n') {
+        output.push('\n');
+    }
+    write_content(None, output)?;
+    for (before, after) in &updated {
+        history::record_update(before, after);
+    }
+    for item in &added {
+        history::record(HistoryEvent::Added, item);
+        hooks::run(HookEvent::Add, item);
+    }
+    Ok(Imported { added: added.len(), updated: updated.len() })
+}
+
+/// Appends `line` to the end of the `### section` block, creating the heading
+/// before the `---` separator if needed.
+fn insert_into_section(lines: &mut Vec<String>, section: &str, line: String) {
+    let heading = lines.iter().position(|l| {
+        let trimmed = l.trim();
+        trimmed.starts_with("###") && trimmed.trim_start_matches('#').trim() == section
+    });
+    match heading {
+        Some(heading) => {
+            let mut end = lines[heading + 1..]
+                .iter()
+                .position(|l| l.trim_start().starts_with('#') || l.trim() == "---")
+                .map(|offset| heading + 1 + offset)
+                .unwrap_or(lines.len());
+            while end > heading + 1 && lines[end - 1].trim().is_empty() {
+                end -= 1;
+            }
+            lines.insert(end, line);
+        }
+        None => {
+            let separator = lines
+                .iter()
+                .position(|l| l.trim() == "---")
+                .unwrap_or(lines.len());
+            let mut block = Vec::new();
+            if separator > 0 && !lines[separator - 1].trim().is_empty() {
+                block.push(String::new());
+            }
+            block.push(format!("### {section}"));
+            block.push(line);
+            lines.splice(separator..separator, block);
+        }
+    }
+}
+
+/// Moves the open tasks of past daily sections (`### 2024-05-12`) to the end
+/// of the section for `today`, creating it if needed. Done tasks stay where
+/// they are. Returns the number of tasks moved.
+pub fn roll_over_daily_sections(today: NaiveDate) -> Result<usize> {
+    let _lock = lock_for(None)?;
+    let content = read_content(None)?;
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+
+    let mut past_section = false;
+    let mut moved = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('#') || trimmed == "---" {
+            past_section = trimmed.starts_with("###")
+                && NaiveDate::parse_from_str(trimmed.trim_start_matches('#').trim(), "%Y-%m-%d")
+                    .is_ok_and(|day| day < today);
+            continue;
+        }
+        if past_section && parse_line(line, index, "").is_some_and(|item| !item.done) {
+            moved.push(index);
+        }
+    }
+    if moved.is_empty() {
+        return Ok(0);
+    }
+
+    let moved_lines: Vec<String> = moved.iter().rev().map(|&index| lines.remove(index)).collect();
+    let section = today.format("%Y-%m-%d").to_string();
+    for line in moved_lines.into_iter().rev() {
+        insert_into_section(&mut lines, &section, line);
+    }
+
+    let mut output = lines.join("\n");
+    if content.ends_with('\n') {
+        output.push('\n');
+    }
+    write_content(None, output)?;
+    Ok(moved.len())
+}
+
+fn insert_line(line: String) -> Result<()> {
+    let _lock = lock_for(None)?;
+    let content = read_content(None)?;
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+
+    let insert_index = lines
+        .iter()
+        .position(|l| l.trim() == "---")
+        .unwrap_or(lines.len());
+
+    lines.insert(insert_index, line);
+
+    let mut output = lines.join("\n");
+    if content.ends_with('\n') {
+        output.push('\n');
+    }
+
+    write_content(None, output)?;
+    Ok(())
+}
+
+fn parse_line(line: &str, line_index: usize, section: &str) -> Option<TodoItem> {
+    let trimmed = line.trim_start();
+    let (done, rest) = if let Some(body) = trimmed.strip_prefix("- [x]") {
+        (true, body.trim())
+    } else if let Some(body) = trimmed.strip_prefix("- [X]") {
+        (true, body.trim())
+    } else if let Some(body) = trimmed.strip_prefix("- [ ]") {
+        (false, body.trim())
+    } else {
+        return None;
+    };
+
+    let title = extract_title(rest);
+    let project = capture_token(&PROJECT_RE, rest).map(Name::from);
+    let context = capture_token(&CONTEXT_RE, rest).map(Name::from);
+    let (due, due_time, due_offset) = match capture_token(&DUE_RE, rest).and_then(|value| parse_due(&value)) {
+        Some((date, time, offset)) => (Some(date), time, offset),
+        None => (None, None, None),
+    };
+    let start = capture_token(&START_RE, rest).and_then(|value| NaiveDate::parse_from_str(&value, "%Y-%m-%d").ok());
+    let recurrence = capture_token(&RECUR_RE, rest);
+    let remind = capture_token(&REMIND_RE, rest).and_then(|value| NaiveDateTime::parse_from_str(&value, REMIND_FORMAT).ok());
+    let priority = capture_token(&PRIORITY_RE, rest)
+        .and_then(|value| value.chars().next())
+        .map(|c| c.to_ascii_uppercase());
+    let pinned = pin_marker(rest).is_some();
+    let aged = capture_token(&AGED_RE, rest).and_then(|value| Aging::parse(&value));
+    let depends_on = capture_token(&DEP_RE, rest).map(|value| parse_dependencies(&value)).unwrap_or_default();
+    let estimate = capture_token(&ESTIMATE_RE, rest).and_then(|value| parse_estimate(&value));
+    let reference = capture_token(&LINK_RE, rest);
+    let marker = capture_token(&ID_RE, rest);
+    let completed = capture_token(&COMPLETED_ON_RE, rest)
+        .and_then(|value| NaiveDate::parse_from_str(&value, "%Y-%m-%d").ok())
+        .filter(|_| done);
+    let created = capture_token(&CREATED_RE, rest).and_then(|value| NaiveDate::parse_from_str(&value, "%Y-%m-%d").ok());
+
+    Some(TodoItem {
+        key: TodoKey {
+            line_index,
+            marker,
+            file: None,
+        },
+        title,
+        section: Name::new(section),
+        project,
+        context,
+        start,
+        due,
+        due_time,
+        due_offset,
+        remind,
+        reference,
+        recurrence,
+        priority,
+        estimate,
+        pinned,
+        depends_on,
+        aged,
+        done,
+        completed,
+        created,
+    })
+}
+
+/// Markers of a `dep:` list such as `abc,^def`, without `^` and duplicates.
+pub fn parse_dependencies(text: &str) -> Vec<String> {
+    let mut markers: Vec<String> = Vec::new();
+    for marker in text.split([',', ' ']).map(|m| m.trim().trim_start_matches('^')) {
+        if !marker.is_empty() && !markers.iter().any(|m| m == marker) {
+            markers.push(marker.to_string());
+        }
+    }
+    markers
+}
+
+/// Minutes of an estimate like `1h30m`, `2h` or `45m`.
+pub fn parse_estimate(text: &str) -> Option<u32> {
+    let text = text.trim();
+    let (hours, minutes) = match text.split_once('h') {
+        Some((hours, rest)) => (hours.parse::<u32>().ok()?, rest),
+        None => (0, text),
+    };
+    let minutes = match minutes.strip_suffix('m') {
+        Some(minutes) => minutes.parse::<u32>().ok()?,
+        None if minutes.is_empty() && text.ends_with('h') => 0,
+        None => return None,
+    };
+    hours.checked_mul(60)?.checked_add(minutes).filter(|total| *total > 0)
+}
+
+/// The `est:` form of `minutes`, e.g. `1h30m`.
+pub fn format_estimate(minutes: u32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{m}m"),
+        (h, 0) => format!("{h}h"),
+        (h, m) => format!("{h}h{m}m"),
+    }
+}
+
+fn capture_token(regex: &Regex, text: &str) -> Option<String> {
+    regex
+        .captures(text)
+        .and_then(|caps| caps.get(1).map(|m| m.as_str().trim().to_string()))
+}
+
+/// Byte offset of the `⭐` that pins the task: a star of its own followed
+/// only by metadata, as [`render_line`] writes it. A star between words,
+/// e.g. from the emoji chooser, is part of the title.
+fn pin_marker(rest: &str) -> Option<usize> {
+    PINNED_RE.find_iter(rest).map(|found| found.start()).find(|&start| {
+        let after = rest[start..].trim_start().trim_start_matches('⭐').trim_start();
+        after.is_empty() || TITLE_MARKERS.iter().any(|marker| after.starts_with(marker))
+    })
+}
+
+/// Tokens that end the title. Symbols do so wherever they appear, even
+/// inside a word; `key:` fields only at the start of a word, so a title like
+/// "Write test: parser" keeps its "test:".
+const TITLE_MARKERS: [&str; 15] =
+    ["+", "@", "start:", "due:", "remind:", "alarm:", "rec:", "pri:", "aged:", "dep:", "est:", "created:", "[[", "✅", "^"];
+
+/// Byte offset of the first place `marker` ends the title, see [`TITLE_MARKERS`].
+fn title_marker(rest: &str, marker: &str) -> Option<usize> {
+    if !marker.ends_with(':') {
+        return rest.find(marker);
+    }
+    rest.match_indices(marker)
+        .map(|(idx, _)| idx)
+        .find(|&idx| rest[..idx].chars().next_back().is_none_or(char::is_whitespace))
+}
+
+fn extract_title(rest: &str) -> String {
+    let mut cut = pin_marker(rest).unwrap_or(rest.len());
+    for marker in TITLE_MARKERS {
+        if let Some(idx) = title_marker(rest, marker) {
+            if idx < cut {
+                cut = idx;
+            }
+        }
+    }
+
+    let raw = if cut == rest.len() { rest } else { &rest[..cut] };
+    let cleaned = raw.trim();
+
+    if cleaned.is_empty() {
+        rest.trim().to_string()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+fn find_lines_by_marker(lines: &[String], marker: &str) -> Vec<usize> {
+    let needle = format!("^{marker}");
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.split_whitespace().any(|token| token == needle))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Locates the line a key refers to. Writes are refused while the marker is
+/// ambiguous, otherwise a toggle could silently hit the wrong entry.
+fn resolve_line_index(lines: &[String], key: &TodoKey) -> Result<usize> {
+    if let Some(marker) = &key.marker {
+        let matches = find_lines_by_marker(lines, marker);
+        if matches.len() > 1 {
+            let lines = matches.iter().map(|i| i + 1).collect();
+            bail!(DataError::AmbiguousMarker { marker: marker.clone(), lines });
+        }
+        if let Some(index) = matches.first() {
+            return Ok(*index);
+        }
+    }
+    if key.line_index < lines.len() {
+        return Ok(key.line_index);
+    }
+    Err(DataError::TaskNotFound.into())
+}
+
+/// Replaces the line of `key` with `rewrite(line)` and returns the old line.
+fn update_line<F>(key: &TodoKey, rewrite: F) -> Result<String>
+where
+    F: FnOnce(&str) -> Result<String>,
+{
+    let file = key.file.as_deref();
+    let _lock = lock_for(file)?;
+    let content = read_content(file)?;
+    let mut lines: Vec<String> = content.lines().map(|line| line.to_string()).collect();
+    let had_trailing_newline = content.ends_with('\n');
+
+    let index = resolve_line_index(&lines, key)?;
+    let updated_line = rewrite(&lines[index])
+        .context(DataError::ParseError { line: index + 1 })?;
+    let previous = std::mem::replace(&mut lines[index], updated_line);
+
+    let mut output = lines.join("\n");
+    if had_trailing_newline {
+        output.push('\n');
+    }
+
+    write_content(file, output)?;
+
+    Ok(previous)
+}
+
+fn delete_line(key: &TodoKey) -> Result<()> {
+    let file = key.file.as_deref();
+    let _lock = lock_for(file)?;
+    let content = read_content(file)?;
+    let mut lines: Vec<String> = content.lines().map(|line| line.to_string()).collect();
+    let had_trailing_newline = content.ends_with('\n');
+
+    let index = resolve_line_index(&lines, key)?;
+    lines.remove(index);
+
+    let mut output = lines.join("\n");
+    if had_trailing_newline && !output.is_empty() {
+        output.push('\n');
+    }
+
+    write_content(file, output)?;
+
+    Ok(())
+}
+
+fn rewrite_line(line: &str, done: bool) -> Result<String> {
+    let mut updated = line.to_string();
+    let has_checked = updated.contains("- [x]") || updated.contains("- [X]");
+    let has_unchecked = updated.contains("- [ ]");
+
+    if done {
+        if !has_checked {
+            if has_unchecked {
+                updated = updated.replacen("- [ ]", "- [x]", 1);
+            } else {
+                bail!(t("no_checkbox_error"));
+            }
+        } else if updated.contains("- [X]") {
+            updated = updated.replacen("- [X]", "- [x]", 1);
+        }
+    } else if has_checked {
+        updated = updated.replacen("- [x]", "- [ ]", 1);
+        updated = updated.replacen("- [X]", "- [ ]", 1);
+    } else if !has_unchecked {
+        bail!(t("no_checkbox_error"));
+    }
+
+    updated = apply_completion_marker(&updated, done);
+
+    Ok(updated)
+}
+
+fn add_months(date: NaiveDate, months: i32) -> Option<NaiveDate> {
+    let total_months = date.year() * 12 + (date.month0() as i32) + months;
+    let new_year = total_months.div_euclid(12);
+    let new_month0 = total_months.rem_euclid(12);
+    let new_month = (new_month0 + 1) as u32;
+
+    // Find last valid day of target month
+    let last_day = (28..=31)
+        .rev()
+        .find_map(|d| NaiveDate::from_ymd_opt(new_year, new_month, d))?;
+
+    let day = date.day().min(last_day.day());
+    NaiveDate::from_ymd_opt(new_year, new_month, day)
+}
+
+/// Next due date after today for a `rec:` rule (`daily`, `weekly`, `monthly`).
+pub fn next_due_date(current_due: Option<NaiveDate>, rule: &str) -> Option<NaiveDate> {
+    let mut next = current_due.unwrap_or_else(|| Local::now().date_naive());
+    let today = Local::now().date_naive();
+
+    loop {
+        next = match rule.to_lowercase().as_str() {
+            "daily" => next.checked_add_signed(chrono::Duration::days(1))?,
+            "weekly" => next.checked_add_signed(chrono::Duration::days(7))?,
+            "monthly" => add_months(next, 1)?,
+            _ => return None,
+        };
+
+        if next > today {
+            break;
+        }
+    }
+    Some(next)
+}
+
+pub(crate) fn render_line(item: &TodoItem) -> Result<String> {
+    let title = item.title.trim();
+    if title.is_empty() {
+        bail!(DataError::EmptyTitle);
+    }
+
+    let checkbox = if item.done { "- [x]" } else { "- [ ]" };
+    let mut parts = vec![format!("{checkbox} {title}")];
+    if item.pinned {
+        parts.push("⭐".to_string());
+    }
+
+    if let Some(project) = normalize_token(item.project.as_deref()) {
+        parts.push(format!("+{project}"));
+    }
+    if let Some(context) = normalize_token(item.context.as_deref()) {
+        parts.push(format!("@{context}"));
+    }
+    if let Some(priority) = item.priority.filter(|c| c.is_ascii_alphabetic()) {
+        parts.push(format!("pri:{}", priority.to_ascii_uppercase()));
+    }
+    if let Some(aged) = item.aged {
+        parts.push(aged.token());
+    }
+    if !item.depends_on.is_empty() {
+        parts.push(format!("dep:{}", item.depends_on.join(",")));
+    }
+    if let Some(estimate) = item.estimate.filter(|minutes| *minutes > 0) {
+        parts.push(format!("est:{}", format_estimate(estimate)));
+    }
+    if let Some(start) = item.start {
+        parts.push(format!("start:{}", start.format("%Y-%m-%d")));
+    }
+    if let Some(due) = item.due {
+        parts.push(format!("due:{}", due_value(due, item.due_time, item.due_offset)));
+    }
+    if let Some(remind) = item.remind {
+        parts.push(format!("remind:{}", remind.format(REMIND_FORMAT)));
+    }
+    if let Some(recur) = normalize_token(item.recurrence.as_deref()) {
+        parts.push(format!("rec:{recur}"));
+    }
+    if let Some(created) = item.created {
+        parts.push(format!("created:{}", created.format("%Y-%m-%d")));
+    }
+    if let Some(reference) = normalize_reference(item.reference.as_deref()) {
+        parts.push(format!("[[{reference}]]"));
+    }
+
+    if item.done {
+        let completed = item.completed.unwrap_or_else(|| Local::now().date_naive());
+        parts.push(format!("✅ {}", completed.format("%Y-%m-%d")));
+    }
+
+    if let Some(marker) = &item.key.marker {
+        if !marker.is_empty() {
+            parts.push(format!("^{marker}"));
+        }
+    }
+
+    Ok(parts.join(" "))
+}
+
+fn normalize_token(value: Option<&str>) -> Option<String> {
+    value
+        .map(|s| {
+            let trimmed = s.trim().replace(char::is_whitespace, "");
+            // Remove all leading + or @
+            let chars = trimmed.chars();
+            let mut out = String::new();
+            let mut found = false;
+            for c in chars {
+                if !found && (c == '+' || c == '@') {
+                    continue;
+                }
+                found = true;
+                out.push(c);
+            }
+            out
+        })
+        .filter(|s| !s.is_empty())
+}
+
+fn normalize_reference(value: Option<&str>) -> Option<String> {
+    value
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+fn apply_completion_marker(line: &str, done: bool) -> String {
+    if done {
+        if let Some(m) = COMPLETION_RE.find(line) {
+            // Bereits vorhanden. Prüfen, ob es nach der ID steht.
+            if let Some(id_m) = ID_RE.find(line) {
+                if m.start() > id_m.start() {
+                    let done_str = m.as_str().to_string();
+                    let mut s = line.to_string();
+                    s.replace_range(m.range(), "");
+                    if let Some(new_id_m) = ID_RE.find(&s) {
+                        s.insert_str(new_id_m.start(), &done_str);
+                        return s;
+                    }
+                }
+            }
+            line.to_string()
+        } else {
+            // Hinzufügen
+            let today = Local::now().date_naive().format("%Y-%m-%d");
+            let done_marker = format!(" ✅ {today}");
+            if let Some(id_m) = ID_RE.find(line) {
+                let mut s = line.to_string();
+                s.insert_str(id_m.start(), &done_marker);
+                s
+            } else {
+                format!("{line}{done_marker}")
+            }
+        }
+    } else {
+        COMPLETION_RE.replace(line, "").to_string()
+    }
+}
+
+/// Moves the due date of `line` to `new_due`, keeping its time of day.
+fn rewrite_due(line: &str, new_due: NaiveDate) -> Result<String> {
+    let current = capture_token(&DUE_RE, line).and_then(|value| parse_due(&value));
+    let segment = match current {
+        Some((_, time, offset)) => format!("due:{}", due_value(new_due, time, offset)),
+        None => format!("due:{}", new_due.format("%Y-%m-%d")),
+    };
+    if let Some(value) = DUE_RE.captures(line).and_then(|caps| caps.get(1)) {
+        let start = value.start() - "due:".len();
+        Ok(format!("{}{segment}{}", &line[..start], &line[value.end()..]))
+    } else {
+        Ok(insert_due_segment(line, &segment))
+    }
+}
+
+fn insert_due_segment(line: &str, segment: &str) -> String {
+    const MARKERS: [&str; 9] = [" +", " @", " remind:", " alarm:", " rec:", " created:", " [[", " ✅", " ^"];
+    let mut insert_at = line.len();
+    for marker in MARKERS {
+        if let Some(idx) = line.find(marker) {
+            insert_at = insert_at.min(idx);
+        }
+    }
+
+    let (head, tail) = line.split_at(insert_at);
+    let needs_space = !head.ends_with(' ') && !head.is_empty();
+    if needs_space {
+        format!("{head} {segment}{tail}")
+    } else {
+        format!("{head}{segment}{tail}")
+    }
+}
@@ -0,0 +1,102 @@
+//! Sample data for `--demo`.
+//!
+//! [`start`] points the data layer at an in-memory document (see
+//! [`BackendConfig::Memory`]) filled with tasks that show most features:
+//! projects, places, due and overdue dates, priorities, estimates, a pinned
+//! and a recurring task, dependencies and a completed one. Dates are
+//! relative to today, so screenshots always look current.
+
+use anyhow::Result;
+use chrono::{Datelike, Duration, Local, NaiveDate};
+
+use crate::data::{self, BackendConfig, Name, TodoItem};
+use crate::i18n::t;
+use crate::storage;
+
+/// Whether the data lives in memory only; history, hooks and drafts are
+/// not written then.
+pub fn is_active() -> bool {
+    matches!(data::get_backend_config(), BackendConfig::Memory)
+}
+
+/// Switches to the in-memory database with the sample tasks.
+pub fn start() -> Result<()> {
+    storage::set_memory_document(sample_document(Local::now().date_naive())?);
+    data::set_backend_config(BackendConfig::Memory);
+    tracing::info!("demo mode, nothing is written to disk");
+    Ok(())
+}
+
+fn task(title_key: &str, project: Option<&str>, context: Option<&str>) -> TodoItem {
+    TodoItem {
+        title: t(title_key),
+        project: project.map(Name::from),
+        context: context.map(Name::from),
+        ..TodoItem::default()
+    }
+}
+
+/// The sample database as Markdown, with dates counted from `today`.
+pub fn sample_document(today: NaiveDate) -> Result<String> {
+    let day = |offset: i64| today + Duration::days(offset);
+    let next_monday = day(7 - i64::from(today.weekday().num_days_from_monday()));
+
+    let mut dentist = task("demo_call_dentist", Some("health"), Some("phone"));
+    dentist.due = Some(day(1));
+    dentist.priority = Some('B');
+    let mut groceries = task("demo_groceries", Some("home"), Some("shop"));
+    groceries.due = Some(today);
+    groceries.estimate = Some(30);
+    let mut article = task("demo_read_article", Some("learning"), None);
+    article.estimate = Some(20);
+
+    let mut flights = task("demo_book_flights", Some("travel"), None);
+    flights.due = Some(day(-1));
+    flights.priority = Some('A');
+    flights.pinned = true;
+    let mut figures = task("demo_collect_figures", Some("report"), Some("office"));
+    figures.due = Some(day(2));
+    figures.estimate = Some(60);
+    figures.key.marker = Some("figures".to_string());
+    let mut report = task("demo_quarterly_report", Some("report"), Some("office"));
+    report.due = Some(day(5));
+    report.priority = Some('A');
+    report.estimate = Some(180);
+    report.depends_on = vec!["figures".to_string()];
+    report.key.marker = Some("report".to_string());
+    let mut slides = task("demo_review_slides", Some("report"), None);
+    slides.start = Some(day(3));
+    slides.due = Some(day(6));
+    slides.depends_on = vec!["report".to_string()];
+    let mut meeting = task("demo_team_meeting", None, Some("office"));
+    meeting.due = Some(next_monday);
+    meeting.recurrence = Some("weekly".to_string());
+
+    let mut plants = task("demo_water_plants", Some("home"), None);
+    plants.due = Some(today);
+    plants.recurrence = Some("weekly".to_string());
+    let mut bike = task("demo_fix_bike", Some("home"), Some("garage"));
+    bike.estimate = Some(45);
+    let mut passport = task("demo_renew_passport", Some("travel"), None);
+    passport.due = Some(day(30));
+    passport.remind = day(25).and_hms_opt(9, 0, 0);
+    let mut rent = task("demo_pay_rent", Some("home"), None);
+    rent.done = true;
+    rent.completed = Some(day(-2));
+
+    let sections = [
+        (t("starter_inbox"), vec![dentist, groceries, article]),
+        (t("demo_section_work"), vec![flights, figures, report, slides, meeting]),
+        (t("demo_section_home"), vec![plants, bike, passport, rent]),
+    ];
+    let mut blocks = Vec::new();
+    for (heading, tasks) in sections {
+        let mut block = format!("### {heading}\n\n");
+        for task in &tasks {
+            block.push_str(&data::render_line(task)?);
+            block.push('\n');
+        }
+        blocks.push(block);
+    }
+    Ok(blocks.join("\n"))
+}
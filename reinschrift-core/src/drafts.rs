@@ -0,0 +1,129 @@
+//! Unsaved input of the add and edit forms, kept across closes and crashes.
+//!
+//! While the quick-add entry or a task dialog holds text that was not
+//! saved, the GUI stores it with [`store`] in
+//! `$XDG_DATA_HOME/reinschrift/drafts.json`, one [`Draft`] per database. A
+//! draft found at the next launch is offered for restoring; saving or
+//! cancelling the form removes it.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::data::{database_location, TodoItem};
+use crate::demo;
+
+/// The fields of the task dialog as typed, invalid dates included.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EditFields {
+    pub title: String,
+    pub project: String,
+    pub context: String,
+    pub start: String,
+    pub due: String,
+    pub remind: String,
+    pub estimate: String,
+    pub depends_on: String,
+    pub reference: Option<String>,
+    pub recurrence: String,
+    pub done: bool,
+}
+
+/// A task dialog left open with changes.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EditDraft {
+    /// The task as it was when the dialog opened.
+    pub task: TodoItem,
+    pub fields: EditFields,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Draft {
+    pub saved: DateTime<Local>,
+    pub database: String,
+    /// Text of the quick-add entry.
+    #[serde(default)]
+    pub quick_add: Option<String>,
+    #[serde(default)]
+    pub edit: Option<EditDraft>,
+}
+
+impl Draft {
+    pub fn is_empty(&self) -> bool {
+        self.quick_add.is_none() && self.edit.is_none()
+    }
+}
+
+pub fn drafts_path() -> Option<PathBuf> {
+    let data = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share")))?;
+    Some(data.join("reinschrift").join("drafts.json"))
+}
+
+fn read_all() -> Vec<Draft> {
+    let Some(raw) = drafts_path().and_then(|path| fs::read_to_string(path).ok()) else {
+        return Vec::new();
+    };
+    serde_json::from_str(&raw).unwrap_or_else(|err| {
+        tracing::warn!("Ignoring unreadable drafts: {err}");
+        Vec::new()
+    })
+}
+
+fn write_all(drafts: &[Draft]) -> Result<()> {
+    let Some(path) = drafts_path() else {
+        return Ok(());
+    };
+    if drafts.is_empty() {
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+        return Ok(());
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // Written aside and renamed, so a crash mid-write keeps the older drafts.
+    let temporary = path.with_extension("json.tmp");
+    fs::write(&temporary, serde_json::to_string_pretty(drafts)?)?;
+    fs::rename(&temporary, &path)?;
+    Ok(())
+}
+
+/// The draft left for the current database, if any.
+pub fn pending() -> Option<Draft> {
+    let database = database_location();
+    read_all().into_iter().find(|draft| draft.database == database && !draft.is_empty())
+}
+
+/// Replaces the draft of the current database; an empty one removes it.
+pub fn store(quick_add: Option<String>, edit: Option<EditDraft>) -> Result<()> {
+    if demo::is_active() {
+        return Ok(());
+    }
+    let database = database_location();
+    let mut drafts = read_all();
+    let had_draft = drafts.iter().any(|draft| draft.database == database);
+    drafts.retain(|draft| draft.database != database);
+    let draft = Draft { saved: Local::now(), database, quick_add, edit };
+    if !draft.is_empty() {
+        drafts.push(draft);
+    } else if !had_draft {
+        return Ok(());
+    }
+    write_all(&drafts)
+}
+
+/// Forgets the draft of the current database.
+pub fn discard() {
+    if let Err(err) = store(None, None) {
+        tracing::warn!("Could not remove draft: {err}");
+    }
+}
@@ -0,0 +1,196 @@
+//! Finding and merging tasks that were entered more than once.
+//!
+//! Titles are compared after [`normalize_title`]: the same words in any
+//! order, or a small edit distance relative to the length, count as the
+//! same task. [`merge`] keeps one task of a group, fills its empty fields
+//! from the others and deletes them; dependencies on a deleted task move
+//! to the kept one.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+
+use crate::data::{self, TodoItem};
+
+/// Titles at least this similar (1.0 = equal) are duplicates.
+const SIMILARITY: f64 = 0.85;
+/// Shorter normalized titles must match exactly; "call" and "cell" differ.
+const MIN_FUZZY_LENGTH: usize = 8;
+
+/// Lower-case words of `title` without punctuation, separated by single spaces.
+pub fn normalize_title(title: &str) -> String {
+    title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+struct Title {
+    chars: Vec<char>,
+    words: HashSet<String>,
+}
+
+impl Title {
+    fn new(title: &str) -> Self {
+        let normalized = normalize_title(title);
+        Title {
+            words: normalized.split(' ').map(str::to_string).collect(),
+            chars: normalized.chars().collect(),
+        }
+    }
+
+    fn matches(&self, other: &Title) -> bool {
+        if self.chars.is_empty() || other.chars.is_empty() {
+            return false;
+        }
+        if self.chars == other.chars || self.words == other.words {
+            return true;
+        }
+        let longest = self.chars.len().max(other.chars.len());
+        if longest < MIN_FUZZY_LENGTH {
+            return false;
+        }
+        // Cheap bound first: the length alone already costs this many edits.
+        let allowed = ((1.0 - SIMILARITY) * longest as f64).floor() as usize;
+        if self.chars.len().abs_diff(other.chars.len()) > allowed {
+            return false;
+        }
+        levenshtein(&self.chars, &other.chars) <= allowed
+    }
+}
+
+fn root(parent: &mut [usize], mut index: usize) -> usize {
+    while parent[index] != index {
+        parent[index] = parent[parent[index]];
+        index = parent[index];
+    }
+    index
+}
+
+/// Groups of open tasks with the same or almost the same title, in file
+/// order within and across groups.
+pub fn find_duplicates(items: &[TodoItem]) -> Vec<Vec<TodoItem>> {
+    let open: Vec<&TodoItem> = items.iter().filter(|item| !item.done).collect();
+    let titles: Vec<Title> = open.iter().map(|item| Title::new(&item.title)).collect();
+    // Union-find over the tasks, so "a ~ b" and "b ~ c" end up in one group.
+    let mut parent: Vec<usize> = (0..open.len()).collect();
+    for i in 0..open.len() {
+        for j in i + 1..open.len() {
+            if titles[i].matches(&titles[j]) {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                parent[b.max(a)] = a.min(b);
+            }
+        }
+    }
+
+    let mut groups: Vec<Vec<TodoItem>> = Vec::new();
+    let mut group_of_root = HashMap::new();
+    for (index, item) in open.iter().enumerate() {
+        let group = *group_of_root.entry(root(&mut parent, index)).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[group].push((*item).clone());
+    }
+    groups.retain(|group| group.len() > 1);
+    groups
+}
+
+/// `keep` with every field it leaves empty taken from the first of `others`
+/// that has it, the highest priority, all dependencies and the pin of any.
+/// If `keep` has no `^marker` it takes over the first one of `others`.
+pub fn merged(keep: &TodoItem, others: &[TodoItem]) -> TodoItem {
+    let mut merged = keep.clone();
+    for other in others {
+        merged.project = merged.project.or_else(|| other.project.clone());
+        merged.context = merged.context.or_else(|| other.context.clone());
+        merged.start = merged.start.or(other.start);
+        // The due time and its offset only make sense with their own date.
+        if merged.due.is_none() {
+            merged.due = other.due;
+            merged.due_time = other.due_time;
+            merged.due_offset = other.due_offset;
+        }
+        merged.remind = merged.remind.or(other.remind);
+        merged.reference = merged.reference.or_else(|| other.reference.clone());
+        merged.recurrence = merged.recurrence.or_else(|| other.recurrence.clone());
+        merged.estimate = merged.estimate.or(other.estimate);
+        merged.created = merged.created.or(other.created);
+        merged.priority = match (merged.priority, other.priority) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        merged.pinned |= other.pinned;
+        merged.key.marker = merged.key.marker.or_else(|| other.key.marker.clone());
+        for marker in &other.depends_on {
+            if !merged.depends_on.contains(marker) {
+                merged.depends_on.push(marker.clone());
+            }
+        }
+    }
+    // The group does not wait for itself.
+    let own: Vec<&String> = std::iter::once(keep).chain(others).filter_map(|item| item.key.marker.as_ref()).collect();
+    merged.depends_on.retain(|marker| !own.contains(&marker));
+    merged
+}
+
+/// Saves [`merged`] in place of `keep`, deletes `others` and points
+/// dependencies on them at the kept task.
+pub fn merge(keep: &TodoItem, others: &[TodoItem]) -> Result<()> {
+    let mut result = merged(keep, others);
+    let dropped: Vec<String> = others
+        .iter()
+        .filter_map(|other| other.key.marker.clone())
+        .filter(|marker| result.key.marker.as_ref() != Some(marker))
+        .collect();
+
+    // Bottom up, so the line numbers of the rest stay valid.
+    let mut doomed: Vec<&TodoItem> = others.iter().collect();
+    doomed.sort_by(|a, b| (&b.key.file, b.key.line_index).cmp(&(&a.key.file, a.key.line_index)));
+    for other in doomed {
+        data::delete_todo(other)?;
+        if other.key.file == keep.key.file && other.key.line_index < keep.key.line_index {
+            result.key.line_index -= 1;
+        }
+    }
+    // A marker taken over from a deleted task is on no line any more, so the
+    // kept task is found by its line number.
+    data::update_todo_details(&result)?;
+
+    let Some(marker) = result.key.marker.clone() else {
+        return Ok(());
+    };
+    if dropped.is_empty() {
+        return Ok(());
+    }
+    for mut item in data::load_todos()? {
+        if !item.depends_on.iter().any(|dependency| dropped.contains(dependency)) {
+            continue;
+        }
+        let mut depends_on = Vec::new();
+        for dependency in item.depends_on {
+            let dependency = if dropped.contains(&dependency) { marker.clone() } else { dependency };
+            if !depends_on.contains(&dependency) && item.key.marker.as_ref() != Some(&dependency) {
+                depends_on.push(dependency);
+            }
+        }
+        item.depends_on = depends_on;
+        data::update_todo_details(&item)?;
+    }
+    Ok(())
+}
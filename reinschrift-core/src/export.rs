@@ -0,0 +1,244 @@
+//! Read-only snapshots of a task list in other formats.
+
+use chrono::{Local, NaiveDate, TimeZone, Utc};
+use serde_json::{json, Value};
+
+use crate::data::TodoItem;
+use crate::i18n::t;
+
+/// Timestamp format of Taskwarrior's JSON, always in UTC.
+pub const TASKWARRIOR_DATE_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+const HTML_STYLE: &str = "body { font-family: system-ui, sans-serif; max-width: 48rem; margin: 2rem auto; padding: 0 1rem; color: #222; }
+h1 { font-size: 1.6rem; }
+h2 { font-size: 1.1rem; margin-top: 1.6rem; color: #555; border-bottom: 1px solid #ddd; padding-bottom: .2rem; }
+ul { list-style: none; padding: 0; }
+li { padding: .35rem 0; }
+li::before { content: \"☐ \"; }
+li.done::before { content: \"☑ \"; }
+li.done .title { text-decoration: line-through; color: #888; }
+.meta { display: block; margin-left: 1.4rem; font-size: .85rem; color: #777; }
+footer { margin-top: 2rem; font-size: .8rem; color: #999; }";
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn meta_parts(item: &TodoItem) -> Vec<String> {
+    let mut parts = Vec::new();
+    if let Some(project) = &item.project {
+        parts.push(format!("+{project}"));
+    }
+    if let Some(context) = &item.context {
+        parts.push(format!("@{context}"));
+    }
+    if let Some(priority) = item.priority {
+        parts.push(format!("pri:{priority}"));
+    }
+    if let Some(due) = item.due {
+        parts.push(due.format("%Y-%m-%d").to_string());
+    }
+    parts
+}
+
+fn html_meta(item: &TodoItem) -> String {
+    escape_html(&meta_parts(item).join(" · "))
+}
+
+/// Renders `items` as a self-contained HTML page, grouped by section in the
+/// order the sections first appear. `footer` is shown below the list.
+pub fn to_html(title: &str, items: &[TodoItem], footer: &str) -> String {
+    let mut sections: Vec<(&str, Vec<&TodoItem>)> = Vec::new();
+    for item in items {
+        match sections.iter_mut().find(|(name, _)| item.section == *name) {
+            Some((_, members)) => members.push(item),
+            None => sections.push((&item.section, vec![item])),
+        }
+    }
+
+    let mut body = String::new();
+    for (section, members) in sections {
+        body.push_str(&format!("<h2>{}</h2>\n<ul>\n", escape_html(section)));
+        for item in members {
+            let class = if item.done { " class=\"done\"" } else { "" };
+            let meta = html_meta(item);
+            body.push_str(&format!("<li{class}><span class=\"title\">{}</span>", escape_html(&item.title)));
+            if !meta.is_empty() {
+                body.push_str(&format!("<span class=\"meta\">{meta}</span>"));
+            }
+            body.push_str("</li>\n");
+        }
+        body.push_str("</ul>\n");
+    }
+
+    format!(
+        "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<title>{title}</title>
+<style>
+{HTML_STYLE}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+{body}<footer>{footer}</footer>
+</body>
+</html>
+",
+        title = escape_html(title),
+        footer = escape_html(footer),
+    )
+}
+
+/// The open tasks of a digest: overdue ones first, then those due `today`,
+/// each group by due date. The section holds the group's heading.
+pub fn digest_items(items: &[TodoItem], today: NaiveDate) -> Vec<TodoItem> {
+    let mut overdue: Vec<TodoItem> = Vec::new();
+    let mut due_today: Vec<TodoItem> = Vec::new();
+    for item in items.iter().filter(|item| !item.done) {
+        match item.due {
+            Some(due) if due < today => overdue.push(TodoItem { section: t("digest_overdue").into(), ..item.clone() }),
+            Some(due) if due == today => due_today.push(TodoItem { section: t("digest_today").into(), ..item.clone() }),
+            _ => {}
+        }
+    }
+    overdue.sort_by_key(|item| (item.due, item.due_at()));
+    overdue.extend(due_today);
+    overdue
+}
+
+pub fn digest_subject(today: NaiveDate) -> String {
+    t("digest_subject").replace("{}", &today.format("%Y-%m-%d").to_string())
+}
+
+/// The digest as plain text, e.g. for a mail body.
+pub fn digest_text(items: &[TodoItem], today: NaiveDate) -> String {
+    let items = digest_items(items, today);
+    if items.is_empty() {
+        return t("digest_empty") + "\n";
+    }
+    let mut text = String::new();
+    let mut section = None;
+    for item in &items {
+        if section != Some(&item.section) {
+            if section.is_some() {
+                text.push('\n');
+            }
+            text.push_str(&format!("{}\n", item.section));
+            section = Some(&item.section);
+        }
+        let mut line = format!("- {}", item.title);
+        let meta = meta_parts(item);
+        if !meta.is_empty() {
+            line.push_str(&format!("  ({})", meta.join(" · ")));
+        }
+        text.push_str(&line);
+        text.push('\n');
+    }
+    text
+}
+
+/// The digest as a standalone HTML page.
+pub fn digest_html(items: &[TodoItem], today: NaiveDate) -> String {
+    let items = digest_items(items, today);
+    let footer = if items.is_empty() { t("digest_empty") } else { String::new() };
+    to_html(&digest_subject(today), &items, &footer)
+}
+
+/// Local midnight of `date` in Taskwarrior's UTC notation, which is what
+/// `task add due:2025-01-31` stores.
+fn taskwarrior_date(date: NaiveDate) -> Option<String> {
+    let midnight = Local.from_local_datetime(&date.and_time(chrono::NaiveTime::MIN)).earliest()?;
+    Some(midnight.with_timezone(&Utc).format(TASKWARRIOR_DATE_FORMAT).to_string())
+}
+
+/// Taskwarrior identifies tasks by UUID. Markers that came from an import
+/// are the UUID without dashes, so they are turned back into one; others get
+/// a UUID hashed from the marker (FNV-1a, version 8), the same on every
+/// export, so `task import` updates the tasks instead of adding them again.
+pub fn taskwarrior_uuid(marker: &str) -> String {
+    let hex = if marker.len() == 32 && marker.chars().all(|c| c.is_ascii_hexdigit()) {
+        marker.to_lowercase()
+    } else {
+        let mut hash: u128 = 0x6c62272e07bb014262b821756295c58d;
+        for byte in marker.bytes() {
+            hash = (hash ^ u128::from(byte)).wrapping_mul(0x0000000001000000000000000000013b);
+        }
+        // Version 8 ("custom") and the RFC 4122 variant.
+        hash = (hash & !(0xf << 76) & !(0x3 << 62)) | (0x8 << 76) | (0x2 << 62);
+        format!("{hash:032x}")
+    };
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+fn taskwarrior_priority(priority: char) -> &'static str {
+    match priority {
+        'A' => "H",
+        'B' => "M",
+        _ => "L",
+    }
+}
+
+/// Renders `items` as the JSON array `task export` prints, for `task import`.
+/// Projects map to projects, the context to a tag, `pri:A`/`B`/`C…` to
+/// `H`/`M`/`L` and the `[[reference]]` to an annotation. Sections have no
+/// Taskwarrior counterpart and are left out. Only tasks with a `^marker`
+/// get a UUID, see [`data::ensure_markers`](crate::data::ensure_markers).
+pub fn to_taskwarrior(items: &[TodoItem]) -> String {
+    let now = Utc::now().format(TASKWARRIOR_DATE_FORMAT).to_string();
+    let tasks: Vec<Value> = items
+        .iter()
+        .map(|item| {
+            // Without a creation date `task import` stamps the entry itself;
+            // `now` would change the task on every export.
+            let entry = item.created.and_then(taskwarrior_date);
+            let mut task = json!({
+                "description": item.title,
+                "status": if item.done { "completed" } else { "pending" },
+            });
+            if let Some(entry) = &entry {
+                task["entry"] = json!(entry);
+            }
+            if let Some(marker) = item.key.marker.as_deref() {
+                task["uuid"] = json!(taskwarrior_uuid(marker));
+            }
+            if let Some(project) = &item.project {
+                task["project"] = json!(project);
+            }
+            if let Some(context) = &item.context {
+                task["tags"] = json!([context]);
+            }
+            if let Some(due) = item.due.and_then(taskwarrior_date) {
+                task["due"] = json!(due);
+            }
+            if let Some(priority) = item.priority {
+                task["priority"] = json!(taskwarrior_priority(priority));
+            }
+            if let Some(recurrence) = &item.recurrence {
+                task["recur"] = json!(recurrence);
+            }
+            if let Some(end) = item.completed.filter(|_| item.done).and_then(taskwarrior_date) {
+                task["end"] = json!(end);
+            }
+            if let Some(reference) = &item.reference {
+                task["annotations"] = json!([{ "entry": entry.as_ref().unwrap_or(&now), "description": reference }]);
+            }
+            task
+        })
+        .collect();
+    serde_json::to_string_pretty(&tasks).unwrap_or_else(|_| "[]".to_string())
+}
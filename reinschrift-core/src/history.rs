@@ -0,0 +1,160 @@
+//! Append-only change log per task, kept outside the Markdown file.
+//!
+//! Every add, edit, postponement, completion and deletion saved through
+//! [`crate::data`] appends one JSON line to
+//! `$XDG_DATA_HOME/reinschrift/history.jsonl`, tagged with the database it
+//! happened in. Tasks are identified by their `^id` marker or, without one,
+//! by their title; renames are recorded so the log follows the task.
+
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::data::{database_location, TodoItem};
+use crate::demo;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryEvent {
+    Added,
+    Edited,
+    /// Only the due date moved, and to a later day.
+    Postponed,
+    Completed,
+    Reopened,
+    Deleted,
+}
+
+/// One field that differs between the task before and after a change.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub time: DateTime<Local>,
+    pub database: String,
+    /// Identity of the task after the change, see [`task_id`].
+    pub task: String,
+    /// Identity before the change, if it was renamed.
+    #[serde(default)]
+    pub previous: Option<String>,
+    pub event: HistoryEvent,
+    #[serde(default)]
+    pub changes: Vec<FieldChange>,
+}
+
+pub fn history_path() -> Option<PathBuf> {
+    let data = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share")))?;
+    Some(data.join("reinschrift").join("history.jsonl"))
+}
+
+/// `^marker` if the task has one, its title otherwise.
+pub fn task_id(item: &TodoItem) -> String {
+    match &item.key.marker {
+        Some(marker) => format!("^{marker}"),
+        None => item.title.clone(),
+    }
+}
+
+fn field_changes(before: &TodoItem, after: &TodoItem) -> Vec<FieldChange> {
+    // Completion is the event itself, not a field change.
+    ["section"]
+        .into_iter()
+        .chain(TodoItem::FIELDS.into_iter().filter(|field| *field != "done"))
+        .map(|field| (field, before.field_text(field), after.field_text(field)))
+        .filter(|(_, from, to)| from != to)
+        .map(|(field, from, to)| FieldChange { field: field.to_string(), from, to })
+        .collect()
+}
+
+fn append(entry: &HistoryEntry) -> Result<()> {
+    let Some(path) = history_path().filter(|_| !demo::is_active()) else {
+        return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+/// Logs `event` for `item`. Failures are only logged: the history must never
+/// block a save.
+pub fn record(event: HistoryEvent, item: &TodoItem) {
+    record_entry(event, item, None, Vec::new());
+}
+
+/// Logs the change from `before` to `after`, classifying it as completion,
+/// reopening, postponement or plain edit.
+pub fn record_update(before: &TodoItem, after: &TodoItem) {
+    let changes = field_changes(before, after);
+    let event = match (before.done, after.done) {
+        (false, true) => HistoryEvent::Completed,
+        (true, false) => HistoryEvent::Reopened,
+        _ if changes.is_empty() => return,
+        _ => {
+            let postponed = matches!((before.due, after.due), (Some(from), Some(to)) if to > from);
+            if postponed && changes.len() == 1 {
+                HistoryEvent::Postponed
+            } else {
+                HistoryEvent::Edited
+            }
+        }
+    };
+    let previous = Some(task_id(before)).filter(|id| *id != task_id(after));
+    record_entry(event, after, previous, changes);
+}
+
+fn record_entry(event: HistoryEvent, item: &TodoItem, previous: Option<String>, changes: Vec<FieldChange>) {
+    let entry = HistoryEntry {
+        time: Local::now(),
+        database: database_location(),
+        task: task_id(item),
+        previous,
+        event,
+        changes,
+    };
+    if let Err(err) = append(&entry) {
+        tracing::warn!("Could not write task history: {err}");
+    }
+}
+
+/// All entries of `item` in the current database, oldest first, following
+/// renames back to earlier titles.
+pub fn for_task(item: &TodoItem) -> Vec<HistoryEntry> {
+    let Some(raw) = history_path().and_then(|path| fs::read_to_string(path).ok()) else {
+        return Vec::new();
+    };
+    let database = database_location();
+    let mut ids = vec![task_id(item)];
+    let mut entries: Vec<HistoryEntry> = Vec::new();
+    let all = raw
+        .lines()
+        .filter_map(|line| serde_json::from_str::<HistoryEntry>(line).ok())
+        .filter(|entry| entry.database == database)
+        .collect::<Vec<_>>();
+    for entry in all.into_iter().rev() {
+        if !ids.contains(&entry.task) {
+            continue;
+        }
+        if let Some(previous) = &entry.previous {
+            ids.push(previous.clone());
+        }
+        entries.push(entry);
+    }
+    entries.reverse();
+    entries
+}
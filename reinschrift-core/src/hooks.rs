@@ -0,0 +1,146 @@
+//! User scripts run on task events, so the list can be bridged to other
+//! systems without patches.
+//!
+//! An executable `on-add`, `on-complete` or `on-delete` in
+//! `$XDG_CONFIG_HOME/reinschrift/hooks/`, or the command of the same name in
+//! the `[hooks]` table of `config.toml` (see [`crate::config`]), is started
+//! after the change was saved. It receives the task as one JSON object (see [`TodoItem`]) on stdin
+//! and the event name in `REINSCHRIFT_EVENT`. Hooks run in the background;
+//! their failures are logged but never undo the change, and one still running
+//! after a minute is stopped.
+
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use crate::config;
+use crate::data::TodoItem;
+use crate::demo;
+
+/// How long saving waits for a hook to take its input.
+const PAYLOAD_TIMEOUT: Duration = Duration::from_secs(2);
+/// How long a hook may run before it is killed.
+const HOOK_TIMEOUT: Duration = Duration::from_secs(60);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookEvent {
+    Add,
+    Complete,
+    Delete,
+}
+
+impl HookEvent {
+    fn name(self) -> &'static str {
+        match self {
+            HookEvent::Add => "add",
+            HookEvent::Complete => "complete",
+            HookEvent::Delete => "delete",
+        }
+    }
+}
+
+/// `$XDG_CONFIG_HOME/reinschrift/hooks`, falling back to `~/.config`.
+pub fn hooks_dir() -> Option<PathBuf> {
+    config::config_dir().map(|dir| dir.join("hooks"))
+}
+
+/// The configured command for `event`, run by the shell, and its text for
+/// the log.
+fn configured_command(event: HookEvent) -> Option<(Command, String)> {
+    let hooks = config::get().hooks;
+    let command = match event {
+        HookEvent::Add => hooks.on_add,
+        HookEvent::Complete => hooks.on_complete,
+        HookEvent::Delete => hooks.on_delete,
+    }
+    .filter(|command| !command.trim().is_empty())?;
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(&command);
+    Some((shell, command))
+}
+
+/// The executable script for `event` in [`hooks_dir`], and its path.
+fn script(event: HookEvent) -> Option<(Command, String)> {
+    let path = hooks_dir()?.join(format!("on-{}", event.name()));
+    let executable = path
+        .metadata()
+        .map(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false);
+    executable.then(|| (Command::new(&path), path.display().to_string()))
+}
+
+/// Starts the hook for `event`, if the user installed one.
+pub fn run(event: HookEvent, item: &TodoItem) {
+    if demo::is_active() {
+        return;
+    }
+    let Some((mut command, hook)) = configured_command(event).or_else(|| script(event)) else {
+        return;
+    };
+    let payload = match serde_json::to_string(item) {
+        Ok(payload) => payload,
+        Err(err) => {
+            tracing::warn!("hook payload: {err}");
+            return;
+        }
+    };
+
+    let spawned = command
+        .env("REINSCHRIFT_EVENT", event.name())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn();
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(err) => {
+            tracing::warn!(%hook, "could not start hook: {err}");
+            return;
+        }
+    };
+    tracing::debug!(%hook, "hook started");
+
+    // The payload is written from its own thread, so a hook that never reads
+    // stdin cannot block the save once the pipe buffer is full. The caller
+    // still waits for it a little: command line runs exit right after, which
+    // would end the writer before it got to run. Dropping stdin closes it, so
+    // the hook sees EOF.
+    if let Some(mut stdin) = child.stdin.take() {
+        let (done_tx, done_rx) = mpsc::channel();
+        let writer_hook = hook.clone();
+        std::thread::spawn(move || {
+            if let Err(err) = stdin.write_all(payload.as_bytes()) {
+                tracing::warn!(hook = %writer_hook, "could not write to hook: {err}");
+            }
+            let _ = done_tx.send(());
+        });
+        if done_rx.recv_timeout(PAYLOAD_TIMEOUT).is_err() {
+            tracing::warn!(%hook, "hook does not read its input");
+        }
+    }
+    // Reap the child off the caller's thread; a slow hook must not block the
+    // window, and one that hangs is stopped after HOOK_TIMEOUT.
+    std::thread::spawn(move || {
+        let deadline = Instant::now() + HOOK_TIMEOUT;
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) if !status.success() => tracing::warn!(%hook, %status, "hook failed"),
+                Ok(Some(_)) => {}
+                Ok(None) if Instant::now() < deadline => {
+                    std::thread::sleep(POLL_INTERVAL);
+                    continue;
+                }
+                Ok(None) => {
+                    tracing::warn!(%hook, "hook timed out after {}s, stopping it", HOOK_TIMEOUT.as_secs());
+                    let _ = child.kill();
+                    let _ = child.wait();
+                }
+                Err(err) => tracing::warn!(%hook, "hook did not finish: {err}"),
+            }
+            break;
+        }
+    });
+}
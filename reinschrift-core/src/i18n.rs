@@ -0,0 +1,342 @@
+//! Lookup of translated strings from the embedded JSON catalogs.
+//!
+//! A key is looked up in the requested languages first and then in the
+//! fallback languages ([`DEFAULT_FALLBACK`] unless `config.toml` sets
+//! `fallback-languages`), one key at a time: a catalog lacking a single
+//! string still supplies all others.
+//!
+//! With `--i18n-debug` ([`enable_debug`]) every translated string is shown
+//! between `⟦` and `⟧`, so text that never went through [`t`] stands out,
+//! and keys missing from the current language are logged with the code
+//! asking for them. On exit a report lists those keys and every key one
+//! catalog has and another lacks.
+//!
+//! [`PSEUDO_LANGUAGE`] is no real language but the English text accented,
+//! lengthened and bracketed, to check the layout before translating.
+//!
+//! Numbers shown to the user go through [`format_count`], [`format_number`],
+//! [`format_percent`] and [`format_duration`], which follow the separators
+//! and percent sign placement of [`current_locale`].
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write as _;
+use std::panic::Location;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// Language codes with an embedded translation catalog.
+pub const LANGUAGES: [&str; 6] = ["de", "en", "es", "fr", "ja", "sv"];
+
+/// Languages tried after the requested ones, in order.
+pub const DEFAULT_FALLBACK: [&str; 2] = ["en", "de"];
+
+/// Code of the pseudo-locale, `--language x-pseudo`.
+pub const PSEUDO_LANGUAGE: &str = "x-pseudo";
+
+/// Where the `--i18n-debug` report goes, in the current folder.
+pub const REPORT_FILE: &str = "reinschrift-i18n-report.txt";
+
+static OVERRIDE_LANG: OnceLock<Mutex<Option<String>>> = OnceLock::new();
+/// Replaces [`DEFAULT_FALLBACK`] once set.
+static FALLBACK: Mutex<Option<Vec<String>>> = Mutex::new(None);
+static DEBUG: AtomicBool = AtomicBool::new(false);
+/// Keys the current language lacks, with the call sites that asked for them.
+static MISSING: Mutex<BTreeMap<String, BTreeSet<String>>> = Mutex::new(BTreeMap::new());
+
+/// Forces a language code instead of the one taken from the environment.
+pub fn set_language(lang: String) {
+    let m = OVERRIDE_LANG.get_or_init(|| Mutex::new(None));
+    if let Ok(mut guard) = m.lock() {
+        *guard = Some(lang);
+    }
+}
+
+/// Sets the languages tried, in order, for keys the requested languages
+/// lack. Codes without a catalog are skipped.
+pub fn set_fallback_languages(codes: Vec<String>) {
+    if let Ok(mut fallback) = FALLBACK.lock() {
+        *fallback = Some(codes);
+    }
+}
+
+/// The fallback chain in effect.
+pub fn fallback_languages() -> Vec<String> {
+    FALLBACK
+        .lock()
+        .ok()
+        .and_then(|fallback| fallback.clone())
+        .unwrap_or_else(|| DEFAULT_FALLBACK.map(str::to_string).to_vec())
+}
+
+/// Locale names in order of preference, read from the same variables as
+/// gettext (`LANGUAGE`, `LC_ALL`, `LC_MESSAGES`, `LANG`).
+fn language_names() -> Vec<String> {
+    let mut names = Vec::new();
+    if let Ok(list) = std::env::var("LANGUAGE") {
+        names.extend(list.split(':').filter(|s| !s.is_empty()).map(str::to_string));
+    }
+    for var in ["LC_ALL", "LC_MESSAGES", "LANG"] {
+        if let Ok(value) = std::env::var(var)
+            && !value.is_empty()
+        {
+            names.push(value);
+            break;
+        }
+    }
+    names
+}
+
+/// The parsed catalog of language `code`. Each of the embedded catalogs is
+/// only parsed once its language is looked up, so startup pays for one.
+fn catalog(code: &str) -> Option<&'static HashMap<String, String>> {
+    static CATALOGS: [OnceLock<HashMap<String, String>>; LANGUAGES.len()] = [const { OnceLock::new() }; LANGUAGES.len()];
+    let index = LANGUAGES.iter().position(|lang| *lang == code)?;
+    Some(CATALOGS[index].get_or_init(|| {
+        let json = match LANGUAGES[index] {
+            "de" => include_str!("i18n/de.json"),
+            "en" => include_str!("i18n/en.json"),
+            "es" => include_str!("i18n/es.json"),
+            "fr" => include_str!("i18n/fr.json"),
+            "ja" => include_str!("i18n/ja.json"),
+            _ => include_str!("i18n/sv.json"),
+        };
+        serde_json::from_str(json).unwrap_or_else(|err| panic!("Failed to parse {code}.json: {err}"))
+    }))
+}
+
+/// The forced language or else the locale names from the environment.
+fn requested_languages() -> Vec<String> {
+    if let Some(Some(override_lang)) = OVERRIDE_LANG.get().map(|m| m.lock().ok().and_then(|g| g.clone())) {
+        vec![override_lang]
+    } else {
+        language_names()
+    }
+}
+
+/// The locale whose language [`t`] translates to, without encoding, e.g.
+/// `de_AT` or `fr`; the first fallback language when none of the requested
+/// ones has a catalog, and `en` for the pseudo-locale.
+pub fn current_locale() -> String {
+    for name in requested_languages() {
+        if name == PSEUDO_LANGUAGE {
+            return "en".to_string();
+        }
+        let locale = name.split(['.', '@']).next().unwrap_or_default();
+        let code = locale.split('_').next().unwrap_or_default();
+        if LANGUAGES.contains(&code) {
+            return locale.to_string();
+        }
+    }
+    fallback_languages()
+        .into_iter()
+        .find(|code| LANGUAGES.contains(&code.as_str()))
+        .unwrap_or_else(|| "en".to_string())
+}
+
+/// Returns the translation of `key` for the current language, falling back
+/// to the [`fallback_languages`] and finally to the key itself.
+#[track_caller]
+pub fn t(key: &str) -> String {
+    let text = lookup(key);
+    if !DEBUG.load(Ordering::Relaxed) {
+        return text;
+    }
+    let code = current_locale().split('_').next().unwrap_or_default().to_string();
+    if !catalog(&code).is_some_and(|map| map.contains_key(key)) {
+        note_missing(key, &code, Location::caller());
+    }
+    format!("⟦{text}⟧")
+}
+
+fn lookup(key: &str) -> String {
+    let mut codes = Vec::new();
+    for lang in requested_languages() {
+        let lang_str = lang.as_str();
+        let lang_code = lang_str.split('_').next().unwrap_or(lang_str).split('.').next().unwrap_or(lang_str);
+        if lang_code == PSEUDO_LANGUAGE {
+            let english = catalog("en").and_then(|map| map.get(key));
+            return pseudolocalize(english.map_or(key, String::as_str));
+        }
+        codes.push(lang_code.to_string());
+    }
+    codes.extend(fallback_languages());
+    codes
+        .iter()
+        .find_map(|code| catalog(code).and_then(|map| map.get(key)))
+        .cloned()
+        .unwrap_or_else(|| key.to_string())
+}
+
+/// `text` as the pseudo-locale shows it: every letter accented, about a
+/// third longer, and in brackets. A label cut short loses its `]`, a
+/// sentence glued together from pieces shows several pairs, and text that
+/// skipped [`t`] stays plain. Placeholders like `{}` and accelerator names
+/// like `<Primary>` are kept as they are.
+pub fn pseudolocalize(text: &str) -> String {
+    const PLAIN: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    const ACCENTED: &str = "àƀçđéƒĝĥîĵķļɱñöþǫŕšŧûṽŵẋýžÀƁÇĐÉƑĜĤÎĴĶĻṀÑÖÞǪŔŠŦÛṼŴẊÝŽ";
+    let mut pseudo = String::from("[");
+    let mut letters: usize = 0;
+    let mut verbatim_until = None;
+    for c in text.chars() {
+        match verbatim_until {
+            Some(end) => {
+                if c == end {
+                    verbatim_until = None;
+                }
+                pseudo.push(c);
+            }
+            None if c == '{' || c == '<' => {
+                verbatim_until = Some(if c == '{' { '}' } else { '>' });
+                pseudo.push(c);
+            }
+            None => {
+                let accented = PLAIN.find(c).and_then(|index| ACCENTED.chars().nth(index));
+                if c.is_alphabetic() {
+                    letters += 1;
+                }
+                pseudo.push(accented.unwrap_or(c));
+            }
+        }
+    }
+    pseudo.push_str(&"~".repeat(letters.div_ceil(3)));
+    pseudo.push(']');
+    pseudo
+}
+
+/// Turns on the translator debug mode of `--i18n-debug`. The report is
+/// written to [`REPORT_FILE`] once the returned guard is dropped.
+pub fn enable_debug() -> DebugReport {
+    DEBUG.store(true, Ordering::Relaxed);
+    DebugReport { path: PathBuf::from(REPORT_FILE) }
+}
+
+/// Writes the `--i18n-debug` report when dropped, at the end of `main`.
+#[must_use]
+pub struct DebugReport {
+    path: PathBuf,
+}
+
+impl Drop for DebugReport {
+    fn drop(&mut self) {
+        match std::fs::write(&self.path, debug_report()) {
+            Ok(()) => tracing::info!(path = %self.path.display(), "translation report written"),
+            Err(err) => tracing::warn!("Could not write {}: {err}", self.path.display()),
+        }
+    }
+}
+
+fn note_missing(key: &str, code: &str, caller: &Location) {
+    let Ok(mut missing) = MISSING.lock() else {
+        return;
+    };
+    let callers = missing.entry(key.to_string()).or_default();
+    if callers.is_empty() {
+        tracing::warn!(key, language = code, caller = %caller, "missing translation");
+    }
+    callers.insert(caller.to_string());
+}
+
+/// The keys looked up without a translation in the current language, then
+/// per catalog the keys another catalog has and it lacks.
+pub fn debug_report() -> String {
+    let mut report = format!("Looked up without a translation in {}:\n", current_locale());
+    if let Ok(missing) = MISSING.lock() {
+        for (key, callers) in missing.iter() {
+            let callers: Vec<&str> = callers.iter().map(String::as_str).collect();
+            let _ = writeln!(report, "  {key}  ({})", callers.join(", "));
+        }
+    }
+
+    let catalogs: Vec<(&str, &HashMap<String, String>)> =
+        LANGUAGES.iter().filter_map(|code| catalog(code).map(|map| (*code, map))).collect();
+    let keys: BTreeSet<&String> = catalogs.iter().flat_map(|(_, map)| map.keys()).collect();
+    for (code, map) in &catalogs {
+        let absent: Vec<&&String> = keys.iter().filter(|key| !map.contains_key(key.as_str())).collect();
+        let _ = writeln!(report, "\nMissing from {code}.json ({}):", absent.len());
+        for key in absent {
+            let _ = writeln!(report, "  {key}");
+        }
+    }
+    report
+}
+
+/// How a locale writes numbers, after the CLDR data ICU uses.
+struct NumberFormat {
+    decimal: char,
+    group: char,
+    /// Shortest integer part that is grouped: Spanish writes `1234` but
+    /// `12.345`.
+    min_grouping: usize,
+    minus: char,
+    /// Between the number and `%`, if anything.
+    percent_space: Option<char>,
+}
+
+fn number_format() -> NumberFormat {
+    const NBSP: char = '\u{a0}';
+    const NARROW_NBSP: char = '\u{202f}';
+    let locale = current_locale();
+    let code = locale.split('_').next().unwrap_or_default();
+    let (decimal, group, min_grouping, minus, percent_space) = match (code, locale.as_str()) {
+        (_, "de_CH" | "de_LI") => ('.', '’', 4, '-', None),
+        ("de", _) => (',', '.', 4, '-', Some(NBSP)),
+        ("es", _) => (',', '.', 5, '-', Some(NBSP)),
+        ("fr", _) => (',', NARROW_NBSP, 4, '-', Some(NARROW_NBSP)),
+        ("sv", _) => (',', NBSP, 4, '\u{2212}', Some(NBSP)),
+        _ => ('.', ',', 4, '-', None),
+    };
+    NumberFormat { decimal, group, min_grouping, minus, percent_space }
+}
+
+/// `value` with `decimals` places, e.g. `1.234,5` in German.
+pub fn format_number(value: f64, decimals: usize) -> String {
+    let format = number_format();
+    let digits = format!("{:.*}", decimals, value.abs());
+    let (integer, fraction) = digits.split_once('.').unwrap_or((&digits, ""));
+
+    let mut formatted = String::new();
+    if value < 0.0 && digits.chars().any(|c| c.is_ascii_digit() && c != '0') {
+        formatted.push(format.minus);
+    }
+    for (index, digit) in integer.chars().enumerate() {
+        let remaining = integer.len() - index;
+        if index > 0 && remaining % 3 == 0 && integer.len() >= format.min_grouping {
+            formatted.push(format.group);
+        }
+        formatted.push(digit);
+    }
+    if !fraction.is_empty() {
+        formatted.push(format.decimal);
+        formatted.push_str(fraction);
+    }
+    formatted
+}
+
+/// A count of tasks, days and the like, grouped by thousands.
+pub fn format_count(count: usize) -> String {
+    format_number(count as f64, 0)
+}
+
+/// `fraction` (`0.42`) as a percentage with `decimals` places, e.g.
+/// `42 %` in German and `42%` in English.
+pub fn format_percent(fraction: f64, decimals: usize) -> String {
+    let number = format_number(fraction * 100.0, decimals);
+    match number_format().percent_space {
+        Some(space) => format!("{number}{space}%"),
+        None => format!("{number}%"),
+    }
+}
+
+/// `duration` to the minute, e.g. `1 h 05 min`.
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = (duration.as_secs() + 30) / 60;
+    if minutes < 60 {
+        return t("duration_minutes").replace("{}", &format_count(minutes as usize));
+    }
+    t("duration_hours")
+        .replacen("{}", &format_count((minutes / 60) as usize), 1)
+        .replacen("{}", &format!("{:02}", minutes % 60), 1)
+}
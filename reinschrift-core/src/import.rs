@@ -0,0 +1,400 @@
+//! Bringing tasks over from other databases and todo apps.
+//!
+//! Importers only turn a foreign format into [`TodoItem`]s; writing them is
+//! left to [`data::import_todos`], so the UI can preview them first.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use serde_json::Value;
+
+use crate::data::{self, Name, TodoItem, TodoKey};
+use crate::export;
+use crate::i18n::t;
+
+/// What [`merge_file`] did.
+#[derive(Clone, Debug, Default)]
+pub struct MergeReport {
+    pub added: Vec<TodoItem>,
+    /// Existing tasks that took over changes from the file.
+    pub updated: usize,
+    /// Tasks skipped because an identical one already exists.
+    pub duplicates: usize,
+}
+
+/// Same content in all fields that matter, regardless of position or marker.
+fn same_task(a: &TodoItem, b: &TodoItem) -> bool {
+    TodoItem::FIELDS.iter().all(|field| a.field_text(field) == b.field_text(field))
+}
+
+/// Imports the tasks of the Markdown database at `path`. A task with the
+/// `^id` marker of an existing one, or else with its title and section, is
+/// that task and takes over what changed in the file, so edits made on
+/// another machine update it instead of adding a copy. An existing task
+/// without a marker gets one for that. The rest is added: sections are
+/// matched to existing headings ignoring case and new ones are created.
+pub fn merge_file(path: &Path) -> Result<MergeReport> {
+    let content = fs::read_to_string(path)
+        .with_context(|| t("read_error").replace("{}", &path.display().to_string()))?;
+    let current = data::load_todos()?;
+    let sections: Vec<&Name> = current.iter().map(|item| &item.section).collect();
+    // Only tasks of the database itself are updated in place; a marker used
+    // in an included file is dropped so it stays unique.
+    let (own, included): (Vec<&TodoItem>, Vec<&TodoItem>) = current.iter().partition(|item| item.key.file.is_none());
+
+    let mut report = MergeReport::default();
+    let mut changes = Vec::new();
+    for mut item in data::parse_todos(&content) {
+        if let Some(existing) = sections.iter().find(|s| s.eq_ignore_ascii_case(&item.section)) {
+            item.section = Name::clone(existing);
+        }
+        let marker = item.key.marker.clone();
+        if marker.is_some() && included.iter().any(|other| other.key.marker == marker) {
+            item.key.marker = None;
+        }
+        let known = own
+            .iter()
+            .find(|other| marker.is_some() && other.key.marker == marker)
+            .or_else(|| {
+                own.iter().find(|other| {
+                    (other.key.marker.is_none() || item.key.marker.is_none())
+                        && other.title == item.title
+                        && other.section == item.section
+                })
+            });
+        match known {
+            Some(existing) if same_task(existing, &item) => report.duplicates += 1,
+            Some(existing) => {
+                item.key.marker = Some(data::ensure_marker(existing)?);
+                changes.push(item);
+            }
+            None if report.added.iter().any(|other| same_task(other, &item)) => report.duplicates += 1,
+            None => report.added.push(item),
+        }
+    }
+
+    if !changes.is_empty() || !report.added.is_empty() {
+        changes.extend(report.added.iter().cloned());
+        report.updated = data::import_todos(&changes)?.updated;
+    }
+    tracing::info!(
+        added = report.added.len(),
+        updated = report.updated,
+        duplicates = report.duplicates,
+        file = %path.display(),
+        "merged database"
+    );
+    Ok(report)
+}
+
+fn parse_date(text: &str) -> Option<NaiveDate> {
+    let text = text.trim();
+    // Graph timestamps like 2025-01-31T00:00:00.0000000 carry the date first.
+    let date = text.split(['T', ' ']).next().unwrap_or(text);
+    ["%Y-%m-%d", "%m/%d/%Y", "%d.%m.%Y", "%-m/%-d/%Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(date, format).ok())
+}
+
+fn parse_time(text: &str) -> Option<NaiveTime> {
+    ["%H:%M:%S", "%H:%M", "%I:%M:%S %p", "%I:%M %p"]
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(text.trim(), format).ok())
+}
+
+/// A Microsoft Graph `dateTimeTimeZone` object. UTC values are converted to
+/// local time; other zones are taken as they are.
+fn graph_date_time(value: &Value) -> Option<NaiveDateTime> {
+    let raw = value.get("dateTime")?.as_str()?;
+    let trimmed = raw.split('.').next().unwrap_or(raw);
+    let parsed = NaiveDateTime::parse_from_str(trimmed, "%Y-%m-%dT%H:%M:%S").ok()?;
+    let zone = value.get("timeZone").and_then(Value::as_str).unwrap_or("UTC");
+    if zone.eq_ignore_ascii_case("UTC") {
+        Some(Utc.from_utc_datetime(&parsed).with_timezone(&Local).naive_local())
+    } else {
+        Some(parsed)
+    }
+}
+
+fn importance_priority(importance: &str) -> Option<char> {
+    match importance.trim().to_lowercase().as_str() {
+        "high" | "hoch" => Some('A'),
+        "low" | "niedrig" => Some('C'),
+        _ => None,
+    }
+}
+
+fn graph_task(task: &Value, list: &str) -> Option<TodoItem> {
+    let title = task.get("title")?.as_str()?.trim();
+    if title.is_empty() {
+        return None;
+    }
+    let text = |key: &str| task.get(key).and_then(Value::as_str).unwrap_or("");
+    let done = text("status").eq_ignore_ascii_case("completed");
+    let reminder_on = task.get("isReminderOn").and_then(Value::as_bool).unwrap_or(true);
+    Some(TodoItem {
+        title: title.to_string(),
+        section: Name::new(list),
+        due: task.get("dueDateTime").and_then(graph_date_time).map(|d| d.date()),
+        remind: task.get("reminderDateTime").and_then(graph_date_time).filter(|_| reminder_on),
+        priority: importance_priority(text("importance")),
+        done,
+        completed: task
+            .get("completedDateTime")
+            .and_then(graph_date_time)
+            .map(|d| d.date())
+            .filter(|_| done),
+        ..TodoItem::default()
+    })
+}
+
+/// `{"value": [...]}` as returned by Graph, or the array itself.
+fn graph_array(value: &Value) -> &[Value] {
+    value
+        .get("value")
+        .unwrap_or(value)
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+}
+
+fn microsoft_json(value: &Value) -> Vec<TodoItem> {
+    let mut items = Vec::new();
+    for entry in graph_array(value) {
+        match entry.get("tasks") {
+            // A list with its tasks, the shape of Graph based exporters.
+            Some(tasks) => {
+                let list = entry.get("displayName").and_then(Value::as_str).unwrap_or("");
+                items.extend(graph_array(tasks).iter().filter_map(|task| graph_task(task, list)));
+            }
+            None => {
+                let list = ["listName", "list", "parentList"]
+                    .iter()
+                    .find_map(|key| entry.get(*key).and_then(Value::as_str))
+                    .unwrap_or("");
+                items.extend(graph_task(entry, list));
+            }
+        }
+    }
+    items
+}
+
+/// Outlook's task CSV export, with English or German column names.
+fn outlook_csv(content: &str) -> Result<Vec<TodoItem>> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(content.as_bytes());
+    let headers: Vec<String> = reader.headers()?.iter().map(|h| h.trim().to_lowercase()).collect();
+    let column = |names: &[&str]| headers.iter().position(|h| names.contains(&h.as_str()));
+    let Some(subject) = column(&["subject", "betreff", "title"]) else {
+        bail!(t("import_unknown_format"));
+    };
+    let due = column(&["due date", "fälligkeitsdatum"]);
+    let reminder_on = column(&["reminder on/off", "erinnerung ein/aus"]);
+    let reminder_date = column(&["reminder date", "erinnerungsdatum"]);
+    let reminder_time = column(&["reminder time", "erinnerungszeit"]);
+    let completed = column(&["date completed", "erledigt am"]);
+    let priority = column(&["priority", "priorität"]);
+    let status = column(&["status"]);
+    let list = column(&["folder", "ordner", "list", "liste"]);
+
+    let mut items = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let field = |index: Option<usize>| index.and_then(|i| record.get(i)).map(str::trim).unwrap_or("");
+        let title = field(Some(subject));
+        if title.is_empty() {
+            continue;
+        }
+        let completed_on = parse_date(field(completed));
+        let done = completed_on.is_some() || ["completed", "erledigt"].contains(&field(status).to_lowercase().as_str());
+        let reminder_enabled = !["false", "falsch", "aus", "off"].contains(&field(reminder_on).to_lowercase().as_str());
+        let remind = parse_date(field(reminder_date))
+            .filter(|_| reminder_enabled)
+            .map(|date| date.and_time(parse_time(field(reminder_time)).unwrap_or(NaiveTime::MIN)));
+        items.push(TodoItem {
+            title: title.to_string(),
+            section: Name::new(field(list)),
+            due: parse_date(field(due)),
+            remind,
+            priority: importance_priority(field(priority)),
+            done,
+            completed: completed_on.filter(|_| done),
+            ..TodoItem::default()
+        });
+    }
+    Ok(items)
+}
+
+/// Reads a Microsoft To Do export: Graph style JSON (lists with their
+/// tasks, or a flat task array) or Outlook's task CSV. Lists become
+/// sections, importance becomes `pri:A`/`pri:C` and reminders `remind:`.
+pub fn microsoft_todo(content: &str) -> Result<Vec<TodoItem>> {
+    let content = content.trim_start_matches('\u{feff}');
+    if content.trim_start().starts_with(['{', '[']) {
+        let value: Value = serde_json::from_str(content)?;
+        return Ok(microsoft_json(&value));
+    }
+    outlook_csv(content)
+}
+
+/// An RFC 3339 timestamp as used by the Google Tasks API, in local time.
+fn rfc3339_local(text: &str) -> Option<NaiveDateTime> {
+    let parsed = DateTime::parse_from_rfc3339(text.trim()).ok()?;
+    Some(parsed.with_timezone(&Local).naive_local())
+}
+
+fn google_task(task: &Value, list: &str) -> Option<TodoItem> {
+    let flag = |key: &str| task.get(key).and_then(Value::as_bool).unwrap_or(false);
+    if flag("deleted") {
+        return None;
+    }
+    let title = task.get("title")?.as_str()?.trim();
+    if title.is_empty() {
+        return None;
+    }
+    let text = |key: &str| task.get(key).and_then(Value::as_str).unwrap_or("");
+    let done = text("status") == "completed";
+    Some(TodoItem {
+        title: title.to_string(),
+        section: Name::new(list),
+        // Due dates carry no time; Google stores them as midnight UTC.
+        due: parse_date(text("due")),
+        done,
+        completed: rfc3339_local(text("completed")).map(|d| d.date()).filter(|_| done),
+        ..TodoItem::default()
+    })
+}
+
+/// Reads `Tasks.json` from a Google Takeout archive. Every task list becomes
+/// a section; completed tasks are kept as done with their completion date.
+pub fn google_tasks(content: &str) -> Result<Vec<TodoItem>> {
+    let value: Value = serde_json::from_str(content.trim_start_matches('\u{feff}'))?;
+    let Some(lists) = value.get("items").and_then(Value::as_array) else {
+        bail!(t("import_unknown_format"));
+    };
+    let mut items = Vec::new();
+    for list in lists {
+        let name = list.get("title").and_then(Value::as_str).unwrap_or("");
+        let tasks = list.get("items").and_then(Value::as_array).map(Vec::as_slice).unwrap_or(&[]);
+        items.extend(tasks.iter().filter_map(|task| google_task(task, name)));
+    }
+    Ok(items)
+}
+
+/// A Taskwarrior UTC timestamp like `20250131T230000Z`, in local time.
+fn taskwarrior_date_time(text: &str) -> Option<NaiveDateTime> {
+    let parsed = NaiveDateTime::parse_from_str(text, export::TASKWARRIOR_DATE_FORMAT).ok()?;
+    Some(Utc.from_utc_datetime(&parsed).with_timezone(&Local).naive_local())
+}
+
+fn taskwarrior_task(task: &Value) -> Option<TodoItem> {
+    let text = |key: &str| task.get(key).and_then(Value::as_str).unwrap_or("");
+    // Recurring templates only spawn the pending instances, which are exported too.
+    if matches!(text("status"), "deleted" | "recurring") {
+        return None;
+    }
+    let title = text("description").trim();
+    if title.is_empty() {
+        return None;
+    }
+    let done = text("status") == "completed";
+    let non_empty = |value: &str| Some(value.trim().to_string()).filter(|v| !v.is_empty());
+    let first_tag = task
+        .get("tags")
+        .and_then(Value::as_array)
+        .and_then(|tags| tags.first())
+        .and_then(Value::as_str);
+    let first_annotation = task
+        .get("annotations")
+        .and_then(Value::as_array)
+        .and_then(|notes| notes.first())
+        .and_then(|note| note.get("description"))
+        .and_then(Value::as_str);
+    Some(TodoItem {
+        key: TodoKey {
+            line_index: 0,
+            marker: Some(text("uuid").replace('-', "")).filter(|uuid| !uuid.is_empty()),
+            file: None,
+        },
+        title: title.to_string(),
+        project: non_empty(text("project")).map(Name::from),
+        context: first_tag.and_then(non_empty).map(Name::from),
+        due: taskwarrior_date_time(text("due")).map(|d| d.date()),
+        reference: first_annotation.map(|note| note.replace(['[', ']'], "")).and_then(|note| non_empty(&note)),
+        recurrence: non_empty(text("recur")),
+        priority: match text("priority") {
+            "H" => Some('A'),
+            "M" => Some('B'),
+            "L" => Some('C'),
+            _ => None,
+        },
+        done,
+        completed: taskwarrior_date_time(text("end")).map(|d| d.date()).filter(|_| done),
+        ..TodoItem::default()
+    })
+}
+
+/// The box and text of a Markdown task list item, `- [x] Call Anna`,
+/// after any bullet (`-`, `*`, `+`) or number (`1.`, `1)`).
+fn checklist_item(line: &str) -> Option<(bool, &str)> {
+    let numbered = line.trim_start_matches(|c: char| c.is_ascii_digit());
+    let rest = match line.strip_prefix(['-', '*', '+']) {
+        Some(rest) => rest,
+        None if numbered.len() < line.len() => numbered.strip_prefix(['.', ')'])?,
+        None => return None,
+    };
+    let rest = rest.strip_prefix(char::is_whitespace)?.trim_start();
+    let (done, text) = match rest.get(..3)? {
+        "[ ]" => (false, &rest[3..]),
+        "[x]" | "[X]" => (true, &rest[3..]),
+        _ => return None,
+    };
+    let text = text.trim();
+    (!text.is_empty()).then_some((done, text))
+}
+
+/// Reads a pasted Markdown checklist, such as the database lines or a
+/// GitHub issue, with the done boxes kept. Headings of any level become
+/// sections; lines that are not task list items are skipped.
+pub fn markdown_checklist(content: &str) -> Vec<TodoItem> {
+    let mut document = String::new();
+    for line in content.trim_start_matches('\u{feff}').lines() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            document.push_str(&format!("### {}\n", line.trim_start_matches('#').trim()));
+        } else if let Some((done, text)) = checklist_item(line) {
+            document.push_str(&format!("- [{}] {text}\n", if done { 'x' } else { ' ' }));
+        }
+    }
+    data::parse_todos(&document)
+}
+
+/// Reads the JSON array printed by `task export`. The project, first tag
+/// (as context), due date, priority, recurrence and first annotation (as
+/// reference) are kept; the UUID becomes the `^marker` so that
+/// [`export::to_taskwarrior`] writes the tasks back under the same identity.
+/// A UUID that [`export::taskwarrior_uuid`] made from a marker of the open
+/// database maps back to that marker, so [`data::import_todos`] updates the
+/// task rather than adding it again.
+pub fn taskwarrior(content: &str) -> Result<Vec<TodoItem>> {
+    let value: Value = serde_json::from_str(content.trim_start_matches('\u{feff}'))?;
+    let Some(tasks) = value.as_array() else {
+        bail!(t("import_unknown_format"));
+    };
+    let mut items: Vec<TodoItem> = tasks.iter().filter_map(taskwarrior_task).collect();
+    // Without a database yet, e.g. in the first start assistant, all are new.
+    let markers: Vec<(String, String)> = data::load_todos()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|item| item.key.marker)
+        .map(|marker| (export::taskwarrior_uuid(&marker).replace('-', ""), marker))
+        .collect();
+    for item in &mut items {
+        let known = item.key.marker.as_ref().and_then(|uuid| markers.iter().find(|(hashed, _)| hashed.eq_ignore_ascii_case(uuid)));
+        if let Some((_, marker)) = known {
+            item.key.marker = Some(marker.clone());
+        }
+    }
+    Ok(items)
+}
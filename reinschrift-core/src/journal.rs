@@ -0,0 +1,94 @@
+//! Write-ahead journal protecting local saves against crashes and power loss.
+//!
+//! Before a local database or an included file is overwritten, the complete
+//! new content is flushed to a hidden `.<name>.journal` file next to it. The
+//! journal is removed once the real write succeeded, so a journal found at
+//! startup means the last save may not have reached the disk.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::data::{get_backend_config, included_files, BackendConfig};
+use crate::i18n::t;
+
+/// A save that was started but never confirmed as finished.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub created: DateTime<Local>,
+    pub database: PathBuf,
+    pub content: String,
+}
+
+pub fn journal_path(database: &Path) -> PathBuf {
+    let name = database
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    database.with_file_name(format!(".{name}.journal"))
+}
+
+/// Durably records `content` as the intended next state of `database`.
+pub fn record(database: &Path, content: &str) -> Result<()> {
+    let entry = JournalEntry {
+        created: Local::now(),
+        database: database.to_path_buf(),
+        content: content.to_string(),
+    };
+    let path = journal_path(database);
+    let mut file = fs::File::create(&path)
+        .with_context(|| t("journal_write_error").replace("{}", &path.display().to_string()))?;
+    file.write_all(serde_json::to_string(&entry)?.as_bytes())?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Marks the save of `database` as complete.
+pub fn clear(database: &Path) {
+    let path = journal_path(database);
+    if !path.exists() {
+        return;
+    }
+    if let Err(err) = fs::remove_file(&path) {
+        tracing::warn!("Could not remove journal {}: {err}", path.display());
+    }
+}
+
+/// The unfinished saves of the configured local database and of its
+/// `#include`d files, as far as the last load found them.
+pub fn pending() -> Vec<JournalEntry> {
+    let database = match get_backend_config() {
+        BackendConfig::Local(database) if !database.as_os_str().is_empty() => Some(database),
+        _ => None,
+    };
+    database.into_iter().chain(included_files()).filter_map(|file| pending_for(&file)).collect()
+}
+
+fn pending_for(database: &Path) -> Option<JournalEntry> {
+    let raw = fs::read_to_string(journal_path(database)).ok()?;
+    match serde_json::from_str::<JournalEntry>(&raw) {
+        Ok(entry) => Some(entry),
+        Err(err) => {
+            // A torn journal means the crash happened before the real write began.
+            tracing::warn!("Ignoring unreadable journal: {err}");
+            clear(database);
+            None
+        }
+    }
+}
+
+/// Finishes the interrupted save by writing the journaled content.
+pub fn replay(entry: &JournalEntry) -> Result<()> {
+    fs::write(&entry.database, &entry.content)
+        .with_context(|| t("write_error").replace("{}", &entry.database.display().to_string()))?;
+    clear(&entry.database);
+    Ok(())
+}
+
+pub fn discard(entry: &JournalEntry) {
+    clear(&entry.database);
+}
@@ -0,0 +1,52 @@
+//! Core of Reinschrift: the Markdown todo format and where it is stored.
+//!
+//! This crate has no GTK dependency so that the GUI, command line tools and
+//! tests can share the same parser and writers.
+//!
+//! - [`data`] parses task lines and applies edits to the file.
+//! - [`storage`] reads and writes the file on disk or over WebDAV.
+//! - [`cache`] keeps offline copies of remote databases in sync.
+//! - [`demo`] fills an in-memory database with sample tasks for `--demo`.
+//! - [`check`] validates the database for `reinschrift check`.
+//! - [`export`] renders read-only snapshots such as HTML pages.
+//! - [`duplicates`] finds tasks entered twice and merges them.
+//! - [`drafts`] keeps unsaved form input for restoring after a crash.
+//! - [`history`] keeps an append-only change log per task.
+//! - [`hooks`] runs user scripts when tasks are added, completed or deleted.
+//! - [`import`] brings tasks over from other databases.
+//! - [`repair`] proposes fixes for lines that cannot be read.
+//! - [`starter`] lays out new databases with example sections.
+//! - [`stats`] counts completions for the statistics page.
+//! - [`aging`] raises the priority of tasks left overdue.
+//! - [`calendar`] reads calendar events to show next to the day's tasks.
+//! - [`suggest`] ranks open tasks to propose what to do next.
+//! - [`templates`] saves task sets and creates them again with fresh due dates.
+//! - [`journal`] guards local saves against interruption.
+//! - [`lock`] keeps concurrent edits of a local database from interleaving.
+//! - [`config`] reads the defaults of `config.toml`.
+//! - [`i18n`] looks up the translated user-facing messages.
+//! - [`collation`] orders names alphabetically in the UI language.
+
+pub mod aging;
+pub mod cache;
+pub mod calendar;
+pub mod check;
+pub mod collation;
+pub mod config;
+pub mod data;
+pub mod demo;
+pub mod drafts;
+pub mod duplicates;
+pub mod export;
+pub mod history;
+pub mod hooks;
+pub mod import;
+pub mod i18n;
+pub mod journal;
+pub mod lock;
+pub mod repair;
+pub mod starter;
+pub mod stats;
+pub mod storage;
+pub mod suggest;
+pub mod templates;
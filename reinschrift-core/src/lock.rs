@@ -0,0 +1,99 @@
+//! Advisory lock around read-modify-write cycles on local databases.
+//!
+//! Every edit reads the file, changes some lines and writes it back. Two
+//! Reinschrift windows, or the command line and the GUI, doing that at the
+//! same moment would each write their own version and drop the other's
+//! change. Edits therefore first take an exclusive lock on a hidden
+//! `.<name>.lock` file next to the database and hold it until the write is
+//! done. The lock is advisory: editors that do not know it are not kept out,
+//! which the fingerprint checks already cover.
+//!
+//! Remote and in-memory databases are not locked.
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+
+use crate::data::{BackendConfig, DataError};
+
+/// How long an edit waits for another process to finish its own.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+thread_local! {
+    /// Databases locked by this thread, so nested edits do not wait for
+    /// themselves.
+    static HELD: RefCell<HashSet<PathBuf>> = RefCell::new(HashSet::new());
+}
+
+pub fn lock_path(database: &Path) -> PathBuf {
+    let name = database
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    database.with_file_name(format!(".{name}.lock"))
+}
+
+/// Held while a database is being edited; dropping it releases the lock.
+#[must_use]
+pub struct DatabaseLock {
+    held: Option<(PathBuf, File)>,
+}
+
+impl Drop for DatabaseLock {
+    fn drop(&mut self) {
+        if let Some((database, file)) = self.held.take() {
+            let _ = file.unlock();
+            HELD.with(|held| held.borrow_mut().remove(&database));
+        }
+    }
+}
+
+/// Locks the database of `config`, waiting up to [`LOCK_TIMEOUT`] for
+/// another process holding it.
+///
+/// Fails with [`DataError::Busy`] when the wait runs out. Where the lock
+/// file cannot be created, such as in a read-only folder, the edit goes
+/// ahead unlocked.
+pub fn acquire(config: &BackendConfig) -> Result<DatabaseLock> {
+    let unlocked = DatabaseLock { held: None };
+    let BackendConfig::Local(database) = config else {
+        return Ok(unlocked);
+    };
+    if database.as_os_str().is_empty() || HELD.with(|held| held.borrow().contains(database)) {
+        return Ok(unlocked);
+    }
+    let path = lock_path(database);
+    let file = match OpenOptions::new().create(true).truncate(false).write(true).open(&path) {
+        Ok(file) => file,
+        Err(err) => {
+            tracing::debug!("Editing without lock, {} not created: {err}", path.display());
+            return Ok(unlocked);
+        }
+    };
+    let started = Instant::now();
+    loop {
+        match file.try_lock() {
+            Ok(()) => break,
+            Err(TryLockError::WouldBlock) if started.elapsed() < LOCK_TIMEOUT => thread::sleep(POLL_INTERVAL),
+            Err(TryLockError::WouldBlock) => {
+                tracing::warn!(path = %path.display(), "database stayed locked");
+                return Err(DataError::Busy { location: database.display().to_string() }.into());
+            }
+            Err(TryLockError::Error(err)) => {
+                tracing::debug!("Editing without lock, {} not lockable: {err}", path.display());
+                return Ok(unlocked);
+            }
+        }
+    }
+    if started.elapsed() >= POLL_INTERVAL {
+        tracing::debug!(waited = ?started.elapsed(), "database lock taken");
+    }
+    HELD.with(|held| held.borrow_mut().insert(database.clone()));
+    Ok(DatabaseLock { held: Some((database.clone(), file)) })
+}
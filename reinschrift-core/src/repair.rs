@@ -0,0 +1,273 @@
+//! Repairs for lines the parser cannot read.
+//!
+//! [`scan`] goes through the local database and its included files line by
+//! line, including lines that are no valid UTF-8 and make loading fail. Each
+//! broken line gets a [`Repair`] with the fix we can propose: stray quotes
+//! around field values removed, dates such as `due:3.2.2025` written as
+//! `YYYY-MM-DD`, a `- []` checkbox completed, Latin-1 text re-encoded.
+//! [`apply`] writes the chosen fixes and moves lines set aside to
+//! `<file>.rejected`, so nothing is lost.
+
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use chrono::{NaiveDate, NaiveTime};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+use crate::data::{self, BackendConfig, DataError};
+use crate::i18n::t;
+use crate::lock;
+
+/// A field value wrapped in straight or typographic quotes, `due:"2025-02-03"`.
+static QUOTED_FIELD_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(start|due|remind|alarm|rec|pri|aged|dep|est|created):["'“”‘’„«»]+([^"'“”‘’„«»\s]*)["'“”‘’„«»]+"#).unwrap()
+});
+static DATE_FIELD_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(^|\s)(start|due|created):(\S+)").unwrap());
+static REMIND_FIELD_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(^|\s)(remind|alarm):(\S+)").unwrap());
+static PRIORITY_FIELD_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(^|\s)pri:([a-z])(\s|$)").unwrap());
+/// A checkbox with the wrong bullet or spacing, `* [ ]`, `-[x]` or `- []`.
+static CHECKBOX_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\s*)[-*+]\s*\[\s*([xX]?)\s*\]\s*").unwrap());
+
+/// Date spellings seen in hand-edited files, tried in this order.
+const DATE_FORMATS: [&str; 4] = ["%Y-%m-%d", "%Y/%m/%d", "%Y.%m.%d", "%d.%m.%Y"];
+
+/// One line that needs attention.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Repair {
+    /// The `#include`d file the line is in; `None` for the database itself.
+    pub file: Option<PathBuf>,
+    /// 1-based line number.
+    pub line: usize,
+    /// The line as read, invalid bytes shown as `�`.
+    pub original: String,
+    /// What is wrong with it, as messages.
+    pub problems: Vec<String>,
+    /// The corrected line, if one of the fixes solves all problems.
+    pub proposal: Option<String>,
+    /// Not valid UTF-8: the line has to be replaced or set aside.
+    pub undecodable: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RepairAction {
+    /// Replace the line by the proposal.
+    Fix,
+    /// Move the line to the `.rejected` file.
+    Quarantine,
+    Keep,
+}
+
+impl Repair {
+    /// Fix when possible; unreadable bytes are set aside, anything else
+    /// still loads and is kept.
+    pub fn default_action(&self) -> RepairAction {
+        if self.proposal.is_some() {
+            RepairAction::Fix
+        } else if self.undecodable {
+            RepairAction::Quarantine
+        } else {
+            RepairAction::Keep
+        }
+    }
+}
+
+/// Where [`apply`] puts the lines set aside from `path`.
+pub fn rejected_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".rejected");
+    PathBuf::from(name)
+}
+
+fn database_path() -> Result<PathBuf> {
+    match data::get_backend_config() {
+        BackendConfig::Local(path) if !path.as_os_str().is_empty() => Ok(path),
+        BackendConfig::Local(_) => bail!(DataError::NoDatabase),
+        BackendConfig::WebDav { .. } | BackendConfig::Uri(_) | BackendConfig::Memory => bail!(t("repair_local_only")),
+    }
+}
+
+/// Lists the broken lines of the local database and the files it included
+/// at the last successful load.
+pub fn scan() -> Result<Vec<Repair>> {
+    let database = database_path()?;
+    let mut repairs = scan_file(&database, None)?;
+    for file in data::included_files() {
+        match scan_file(&file, Some(file.clone())) {
+            Ok(found) => repairs.extend(found),
+            Err(err) => tracing::warn!(file = %file.display(), "not scanning include: {err:#}"),
+        }
+    }
+    Ok(repairs)
+}
+
+/// The lines of `path` without line endings, and whether the last one had one.
+fn read_lines(path: &Path) -> Result<(Vec<Vec<u8>>, bool)> {
+    let bytes = fs::read(path).with_context(|| t("read_error").replace("{}", &path.display().to_string()))?;
+    let mut lines: Vec<Vec<u8>> = bytes
+        .split(|byte| *byte == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line).to_vec())
+        .collect();
+    // `split` yields an empty rest after the final newline.
+    let trailing_newline = bytes.ends_with(b"\n");
+    if trailing_newline {
+        lines.pop();
+    }
+    Ok((lines, trailing_newline))
+}
+
+fn scan_file(path: &Path, file: Option<PathBuf>) -> Result<Vec<Repair>> {
+    let mut repairs = Vec::new();
+    for (index, raw) in read_lines(path)?.0.iter().enumerate() {
+        let repair = match std::str::from_utf8(raw) {
+            Ok(line) => {
+                let problems = errors_in(line);
+                if problems.is_empty() {
+                    continue;
+                }
+                let fixed = fix_line(line);
+                let proposal = (fixed != line && errors_in(&fixed).is_empty()).then_some(fixed);
+                Repair { file: file.clone(), line: index + 1, original: line.to_string(), problems, proposal, undecodable: false }
+            }
+            Err(_) => {
+                // Usually text saved as Latin-1 or Windows-1252 by another editor.
+                let latin1: String = raw.iter().map(|byte| char::from(*byte)).collect();
+                let fixed = fix_line(&latin1);
+                let proposal = if errors_in(&fixed).is_empty() { fixed } else { latin1 };
+                Repair {
+                    file: file.clone(),
+                    line: index + 1,
+                    original: String::from_utf8_lossy(raw).into_owned(),
+                    problems: vec![t("repair_invalid_encoding")],
+                    proposal: Some(proposal),
+                    undecodable: true,
+                }
+            }
+        };
+        repairs.push(repair);
+    }
+    Ok(repairs)
+}
+
+/// Messages for the problems of `line` that lose information; warnings
+/// such as unknown fields are left to `reinschrift check`.
+fn errors_in(line: &str) -> Vec<String> {
+    data::problems_in_line(line)
+        .into_iter()
+        .filter(|(kind, _)| !kind.is_warning())
+        .map(|(kind, text)| t(kind.message_key()).replace("{}", &text))
+        .collect()
+}
+
+/// Applies every fix that could help; [`scan_file`] only proposes the
+/// result if no problem is left.
+fn fix_line(line: &str) -> String {
+    let mut fixed = QUOTED_FIELD_RE.replace_all(line, "$1:$2").into_owned();
+    if let Some(caps) = CHECKBOX_RE.captures(&fixed) {
+        let mark = if caps[2].is_empty() { " " } else { "x" };
+        let prefix = format!("{}- [{mark}] ", &caps[1]);
+        fixed = format!("{prefix}{}", &fixed[caps[0].len()..]);
+    }
+    fixed = DATE_FIELD_RE
+        .replace_all(&fixed, |caps: &Captures| {
+            let value = normalize_date(&caps[3]).unwrap_or_else(|| caps[3].to_string());
+            format!("{}{}:{value}", &caps[1], &caps[2])
+        })
+        .into_owned();
+    fixed = REMIND_FIELD_RE
+        .replace_all(&fixed, |caps: &Captures| {
+            let value = normalize_remind(&caps[3]).unwrap_or_else(|| caps[3].to_string());
+            format!("{}{}:{value}", &caps[1], &caps[2])
+        })
+        .into_owned();
+    PRIORITY_FIELD_RE
+        .replace_all(&fixed, |caps: &Captures| format!("{}pri:{}{}", &caps[1], caps[2].to_uppercase(), &caps[3]))
+        .into_owned()
+}
+
+/// `value` as `YYYY-MM-DD`, `None` if it is no date we can read.
+fn normalize_date(value: &str) -> Option<String> {
+    if value.len() == 8 && value.bytes().all(|byte| byte.is_ascii_digit()) {
+        let date = NaiveDate::from_ymd_opt(value[..4].parse().ok()?, value[4..6].parse().ok()?, value[6..].parse().ok()?)?;
+        return Some(date.format("%Y-%m-%d").to_string());
+    }
+    DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+        .map(|date| date.format("%Y-%m-%d").to_string())
+}
+
+/// `value` as `YYYY-MM-DDTHH:MM`, accepting a space-free `T9` or `T0930`.
+fn normalize_remind(value: &str) -> Option<String> {
+    let (date, time) = value.split_once('T')?;
+    let time = match time.len() {
+        1 | 2 => NaiveTime::from_hms_opt(time.parse().ok()?, 0, 0)?,
+        4 if !time.contains(':') => NaiveTime::parse_from_str(time, "%H%M").ok()?,
+        _ => NaiveTime::parse_from_str(time, "%H:%M").ok()?,
+    };
+    Some(format!("{}T{}", normalize_date(date)?, time.format("%H:%M")))
+}
+
+/// Carries out `choices` and returns how many lines changed. Fails without
+/// writing anything if a file no longer has the scanned line.
+pub fn apply(choices: &[(Repair, RepairAction)]) -> Result<usize> {
+    let database = database_path()?;
+    let mut by_file: BTreeMap<Option<PathBuf>, Vec<&(Repair, RepairAction)>> = BTreeMap::new();
+    for choice in choices {
+        by_file.entry(choice.0.file.clone()).or_default().push(choice);
+    }
+
+    let mut changed = 0;
+    let mut writes = Vec::new();
+    // All files stay locked until the last one is written.
+    let mut locks = Vec::new();
+    for (file, choices) in by_file {
+        let path = file.clone().unwrap_or_else(|| database.clone());
+        locks.push(lock::acquire(&BackendConfig::Local(path.clone()))?);
+        let (raw_lines, trailing_newline) = read_lines(&path)?;
+        let mut lines: Vec<Option<String>> = raw_lines
+            .iter()
+            .map(|raw| Some(String::from_utf8_lossy(raw).into_owned()))
+            .collect();
+        let mut rejected = Vec::new();
+        for (repair, action) in choices {
+            let index = repair.line - 1;
+            if lines.get(index).and_then(|line| line.as_deref()) != Some(repair.original.as_str()) {
+                bail!(t("repair_file_changed").replace("{}", &path.display().to_string()));
+            }
+            match (action, &repair.proposal) {
+                (RepairAction::Fix, Some(proposal)) => lines[index] = Some(proposal.clone()),
+                (RepairAction::Keep, _) if !repair.undecodable => continue,
+                _ => {
+                    rejected.push(raw_lines[index].clone());
+                    lines[index] = None;
+                }
+            }
+            changed += 1;
+        }
+        let mut content = lines.into_iter().flatten().collect::<Vec<_>>().join("\n");
+        if trailing_newline {
+            content.push('\n');
+        }
+        writes.push((file, path, content, rejected));
+    }
+
+    for (file, path, content, rejected) in writes {
+        // Set aside first: a failed save then leaves a duplicate, not a loss.
+        if !rejected.is_empty() {
+            let rejected_file = rejected_path(&path);
+            let error = || t("write_error").replace("{}", &rejected_file.display().to_string());
+            let mut out = OpenOptions::new().create(true).append(true).open(&rejected_file).with_context(error)?;
+            for line in &rejected {
+                out.write_all(line).with_context(error)?;
+                out.write_all(b"\n").with_context(error)?;
+            }
+            tracing::info!(file = %rejected_file.display(), lines = rejected.len(), "set aside unreadable lines");
+        }
+        data::write_content(file.as_deref(), content)?;
+    }
+    Ok(changed)
+}
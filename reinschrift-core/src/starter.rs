@@ -0,0 +1,111 @@
+//! Section layouts for new databases, used by `reinschrift init`, the
+//! "New task list" action and the first run.
+//!
+//! A fresh file starts with a few `###` sections and example tasks, so the
+//! format explains itself when it is opened in an editor.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use chrono::{Datelike, Duration, Local, NaiveDate};
+
+use crate::i18n::t;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StarterTemplate {
+    /// One inbox section.
+    Simple,
+    /// Inbox, next actions, waiting for, projects and someday/maybe.
+    Gtd,
+    /// A `### YYYY-MM-DD` section per day of the current week, as used by
+    /// the daily rollover.
+    Weekly,
+}
+
+impl StarterTemplate {
+    pub const ALL: [StarterTemplate; 3] = [StarterTemplate::Simple, StarterTemplate::Gtd, StarterTemplate::Weekly];
+
+    pub fn as_key(self) -> &'static str {
+        match self {
+            StarterTemplate::Simple => "simple",
+            StarterTemplate::Gtd => "gtd",
+            StarterTemplate::Weekly => "weekly",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|template| template.as_key() == key)
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            StarterTemplate::Simple => t("starter_simple"),
+            StarterTemplate::Gtd => t("starter_gtd"),
+            StarterTemplate::Weekly => t("starter_weekly"),
+        }
+    }
+
+    pub fn description(self) -> String {
+        match self {
+            StarterTemplate::Simple => t("starter_simple_desc"),
+            StarterTemplate::Gtd => t("starter_gtd_desc"),
+            StarterTemplate::Weekly => t("starter_weekly_desc"),
+        }
+    }
+
+    /// The file content, with the week of `today` for [`StarterTemplate::Weekly`].
+    pub fn content(self, today: NaiveDate) -> String {
+        let sections: Vec<(String, Vec<String>)> = match self {
+            StarterTemplate::Simple => {
+                vec![(t("starter_inbox"), vec![t("starter_task_add"), t("starter_task_done")])]
+            }
+            StarterTemplate::Gtd => vec![
+                (t("starter_inbox"), vec![t("starter_task_add"), t("starter_task_sort")]),
+                (t("starter_next"), vec![t("starter_task_done")]),
+                (t("starter_waiting"), Vec::new()),
+                (t("starter_projects"), Vec::new()),
+                (t("starter_someday"), Vec::new()),
+            ],
+            StarterTemplate::Weekly => {
+                let monday = today - Duration::days(i64::from(today.weekday().num_days_from_monday()));
+                (0..7)
+                    .map(|offset| {
+                        let day = monday + Duration::days(offset);
+                        let tasks = if offset == 0 { vec![t("starter_task_plan_week")] } else { Vec::new() };
+                        (day.format("%Y-%m-%d").to_string(), tasks)
+                    })
+                    .collect()
+            }
+        };
+        let blocks: Vec<String> = sections
+            .into_iter()
+            .map(|(heading, tasks)| {
+                let mut block = format!("### {heading}\n");
+                if !tasks.is_empty() {
+                    block.push('\n');
+                }
+                for task in tasks {
+                    block.push_str(&format!("- [ ] {task}\n"));
+                }
+                block
+            })
+            .collect();
+        blocks.join("\n")
+    }
+}
+
+/// Writes a new database at `path` laid out as `template`, creating its
+/// folder. Fails if the file exists.
+pub fn create(path: &Path, template: StarterTemplate) -> Result<()> {
+    if path.exists() {
+        bail!(t("init_exists").replace("{}", &path.display().to_string()));
+    }
+    let error = || t("write_error").replace("{}", &path.display().to_string());
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).with_context(error)?;
+    }
+    fs::write(path, template.content(Local::now().date_naive())).with_context(error)?;
+    tracing::info!(path = %path.display(), template = template.as_key(), "created database");
+    Ok(())
+}
@@ -0,0 +1,38 @@
+//! Figures about completed work for the statistics page.
+
+use std::collections::BTreeMap;
+
+use chrono::{Days, NaiveDate};
+
+use crate::data::TodoItem;
+
+/// Number of tasks completed per day, from their `✅ date` markers. Done
+/// tasks without a date are not counted.
+pub fn completions_per_day(items: &[TodoItem]) -> BTreeMap<NaiveDate, usize> {
+    let mut counts = BTreeMap::new();
+    for date in items.iter().filter(|item| item.done).filter_map(|item| item.completed) {
+        *counts.entry(date).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Consecutive days with at least one completion up to `today`. A day
+/// without completions yet does not break the streak before it is over.
+pub fn current_streak(counts: &BTreeMap<NaiveDate, usize>, today: NaiveDate) -> usize {
+    let mut day = today;
+    if !counts.contains_key(&day) {
+        let Some(yesterday) = day.checked_sub_days(Days::new(1)) else {
+            return 0;
+        };
+        day = yesterday;
+    }
+    let mut streak = 0;
+    while counts.contains_key(&day) {
+        streak += 1;
+        match day.checked_sub_days(Days::new(1)) {
+            Some(previous) => day = previous,
+            None => break,
+        }
+    }
+    streak
+}
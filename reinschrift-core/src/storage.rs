@@ -0,0 +1,374 @@
+//! Storage backends the todo file can live in.
+
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use gio::prelude::*;
+use once_cell::sync::Lazy;
+use reqwest::blocking::Client;
+
+use crate::cache;
+use crate::data::{set_backend_config, BackendConfig, DataError};
+use crate::i18n::t;
+
+/// Raw access to the Markdown document behind a [`BackendConfig`].
+///
+/// Implementations only move whole documents around; all line-level editing
+/// happens in [`crate::data`].
+pub trait Storage {
+    fn read(&self) -> Result<String>;
+    fn write(&self, content: &str) -> Result<()>;
+    /// A cheap token that changes whenever the stored document changes.
+    fn fingerprint(&self) -> Result<String>;
+}
+
+/// Tries of a write before it counts as failed, and the pause before the
+/// second; each further pause is three times as long (0.1 s, 0.3 s, 0.9 s).
+const WRITE_ATTEMPTS: u32 = 4;
+const FIRST_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Writes `content` to `storage`, retrying with growing pauses: saves to
+/// network file systems, or to files a sync tool such as Dropbox or
+/// Syncthing holds for a moment, fail now and then and work a bit later.
+/// Only the error of the last try is returned.
+pub fn write_with_retry(storage: &dyn Storage, content: &str) -> Result<()> {
+    let mut delay = FIRST_RETRY_DELAY;
+    let mut attempt = 1;
+    loop {
+        let err = match storage.write(content) {
+            Ok(()) => return Ok(()),
+            Err(err) => err,
+        };
+        // Missing files and folders don't come back by waiting, nor does
+        // write permission.
+        let lasting = matches!(
+            err.downcast_ref::<DataError>(),
+            Some(DataError::NoDatabase | DataError::NotFound { .. } | DataError::PermissionDenied { .. })
+        );
+        if lasting || attempt == WRITE_ATTEMPTS {
+            return Err(err);
+        }
+        tracing::warn!(attempt, "saving failed, trying again in {delay:?}: {err:#}");
+        std::thread::sleep(delay);
+        delay *= 3;
+        attempt += 1;
+    }
+}
+
+/// Returns the storage implementation for the given configuration. Remote
+/// databases are accessed through their [`cache::CachedStorage`] copy.
+pub fn open(config: BackendConfig) -> Box<dyn Storage> {
+    if cache::is_cached(&config) {
+        return Box::new(cache::CachedStorage { config });
+    }
+    open_remote(config)
+}
+
+/// Like [`open`], but talks to a remote database directly.
+pub fn open_remote(config: BackendConfig) -> Box<dyn Storage> {
+    match config {
+        BackendConfig::Local(path) => Box::new(LocalStorage { path }),
+        BackendConfig::WebDav { url, path, username, password } => Box::new(WebDavStorage {
+            url,
+            path,
+            username,
+            password,
+        }),
+        BackendConfig::Uri(uri) => Box::new(GioStorage { uri }),
+        BackendConfig::Memory => Box::new(MemoryStorage),
+    }
+}
+
+/// The document of [`BackendConfig::Memory`] and how often it was written.
+static MEMORY: Lazy<Mutex<(String, u64)>> = Lazy::new(|| Mutex::new((String::new(), 0)));
+
+/// Replaces the in-memory document, e.g. with the `--demo` tasks.
+pub fn set_memory_document(content: String) {
+    let mut memory = MEMORY.lock().expect("memory document lock poisoned");
+    memory.0 = content;
+    memory.1 += 1;
+}
+
+/// The current in-memory document.
+pub fn memory_document() -> String {
+    MEMORY.lock().expect("memory document lock poisoned").0.clone()
+}
+
+/// Keeps the document in this process only; it is gone when the app quits.
+pub struct MemoryStorage;
+
+impl Storage for MemoryStorage {
+    fn read(&self) -> Result<String> {
+        Ok(memory_document())
+    }
+
+    fn write(&self, content: &str) -> Result<()> {
+        set_memory_document(content.to_string());
+        Ok(())
+    }
+
+    fn fingerprint(&self) -> Result<String> {
+        Ok(MEMORY.lock().expect("memory document lock poisoned").1.to_string())
+    }
+}
+
+pub struct LocalStorage {
+    pub path: PathBuf,
+}
+
+impl Storage for LocalStorage {
+    fn read(&self) -> Result<String> {
+        if self.path.as_os_str().is_empty() {
+            bail!(DataError::NoDatabase);
+        }
+        fs::read_to_string(&self.path).map_err(|err| DataError::from_io(self.path.display().to_string(), false, err).into())
+    }
+
+    fn write(&self, content: &str) -> Result<()> {
+        fs::write(&self.path, content).map_err(|err| DataError::from_io(self.path.display().to_string(), true, err).into())
+    }
+
+    /// Modification time and inode, so a file replaced by another one, as
+    /// `git checkout` does, counts as changed even with the same mtime.
+    fn fingerprint(&self) -> Result<String> {
+        let metadata = fs::metadata(&self.path).map_err(|err| DataError::from_io(self.path.display().to_string(), false, err))?;
+        let mtime = metadata.modified().map_err(|err| DataError::from_io(self.path.display().to_string(), false, err))?;
+        Ok(format!("{:?}-{}", mtime, metadata.ino()))
+    }
+}
+
+/// A remote file read and written with GIO, so every location GVfs can
+/// mount works: SFTP, SMB, WebDAV, … The location has to be mounted first,
+/// which the window does with a password prompt where needed.
+pub struct GioStorage {
+    pub uri: String,
+}
+
+impl GioStorage {
+    fn file(&self) -> gio::File {
+        gio::File::for_uri(&self.uri)
+    }
+
+    /// Adds how to mount the location to the error of an unmounted one.
+    fn explain(&self, err: gio::glib::Error, message: String) -> anyhow::Error {
+        let location = self.uri.clone();
+        if err.matches(gio::IOErrorEnum::NotFound) {
+            return DataError::NotFound { location }.into();
+        }
+        if err.matches(gio::IOErrorEnum::PermissionDenied) {
+            return DataError::PermissionDenied { location }.into();
+        }
+        if err.matches(gio::IOErrorEnum::NotMounted) {
+            return anyhow::anyhow!("{message}: {}", t("uri_not_mounted").replace("{}", &self.uri));
+        }
+        anyhow::anyhow!("{message}: {err}")
+    }
+}
+
+impl Storage for GioStorage {
+    fn read(&self) -> Result<String> {
+        let (bytes, _) = self
+            .file()
+            .load_contents(gio::Cancellable::NONE)
+            .map_err(|err| self.explain(err, t("read_error").replace("{}", &self.uri)))?;
+        String::from_utf8(bytes.to_vec()).with_context(|| t("read_error").replace("{}", &self.uri))
+    }
+
+    fn write(&self, content: &str) -> Result<()> {
+        self.file()
+            .replace_contents(content.as_bytes(), None, false, gio::FileCreateFlags::NONE, gio::Cancellable::NONE)
+            .map_err(|err| self.explain(err, t("write_error").replace("{}", &self.uri)))?;
+        Ok(())
+    }
+
+    fn fingerprint(&self) -> Result<String> {
+        let info = self
+            .file()
+            .query_info("etag::value,time::modified", gio::FileQueryInfoFlags::NONE, gio::Cancellable::NONE)
+            .map_err(|err| self.explain(err, t("read_error").replace("{}", &self.uri)))?;
+        let etag = info.etag().map(|etag| etag.to_string()).unwrap_or_default();
+        let modified = info.attribute_uint64(gio::FILE_ATTRIBUTE_TIME_MODIFIED);
+        Ok(format!("{etag}-{modified}"))
+    }
+}
+
+pub struct WebDavStorage {
+    pub url: String,
+    pub path: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl WebDavStorage {
+    fn construct_url(&self, base: &str) -> String {
+        if let Some(p) = &self.path {
+            format!("{}/{}", base.trim_end_matches('/'), p.trim_start_matches('/'))
+        } else {
+            base.to_string()
+        }
+    }
+
+    /// Nextcloud fallback: if the configured URL fails, retry below
+    /// `remote.php/dav/files/USER` and remember the base that worked.
+    fn with_fallback<T>(&self, attempt: impl Fn(&str) -> Result<T>) -> Result<T> {
+        let full_url = self.construct_url(&self.url);
+        tracing::debug!(url = %full_url, "WebDAV request");
+        match attempt(&full_url) {
+            Ok(value) => Ok(value),
+            Err(e) => {
+                if let Some(user) = &self.username {
+                    if !self.url.contains("remote.php/dav/files") {
+                        let candidate_base = format!("{}/remote.php/dav/files/{}", self.url.trim_end_matches('/'), user);
+                        let candidate_full = self.construct_url(&candidate_base);
+                        if let Ok(value) = attempt(&candidate_full) {
+                            tracing::info!(url = %candidate_base, "WebDAV: switching to Nextcloud files URL");
+                            // Update internal config to use this working base URL for future requests
+                            set_backend_config(BackendConfig::WebDav {
+                                url: candidate_base,
+                                path: self.path.clone(),
+                                username: self.username.clone(),
+                                password: self.password.clone(),
+                            });
+                            return Ok(value);
+                        }
+                    }
+                }
+                if e.to_string().contains("404 Not Found") {
+                    bail!("WebDAV error: 404 Not Found. (Hint: For Nextcloud, ensure URL ends with /remote.php/dav/files/USERNAME)");
+                }
+                Err(e)
+            }
+        }
+    }
+
+    fn authorize(&self, req: reqwest::blocking::RequestBuilder) -> reqwest::blocking::RequestBuilder {
+        if let (Some(u), Some(p)) = (&self.username, &self.password) {
+            req.basic_auth(u, Some(p))
+        } else {
+            req
+        }
+    }
+}
+
+impl Storage for WebDavStorage {
+    fn read(&self) -> Result<String> {
+        let client = Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()?;
+
+        self.with_fallback(|target_url| {
+            let resp = self.authorize(client.get(target_url)).send()?;
+            if !resp.status().is_success() {
+                if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                    bail!("404 Not Found");
+                }
+                bail!("WebDAV error: {}", resp.status());
+            }
+            Ok(resp.text()?)
+        })
+    }
+
+    fn write(&self, content: &str) -> Result<()> {
+        let client = Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()?;
+
+        self.with_fallback(|target_url| {
+            let req = self.authorize(client.put(target_url)).body(content.to_string());
+            let resp = req.send()?;
+            if !resp.status().is_success() {
+                if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                    bail!("404 Not Found");
+                }
+                bail!("WebDAV error: {}", resp.status());
+            }
+            Ok(())
+        })
+    }
+
+    fn fingerprint(&self) -> Result<String> {
+        let client = Client::builder()
+            .timeout(std::time::Duration::from_secs(5))
+            .build()?;
+
+        let full_url = self.construct_url(&self.url);
+        let resp = self.authorize(client.head(&full_url)).send()?;
+        if !resp.status().is_success() {
+            bail!("WebDAV error: {}", resp.status());
+        }
+
+        let etag = resp.headers().get("etag").and_then(|v| v.to_str().ok()).unwrap_or("");
+        let last_mod = resp.headers().get("last-modified").and_then(|v| v.to_str().ok()).unwrap_or("");
+
+        Ok(format!("{}-{}", etag, last_mod))
+    }
+}
+
+/// Checks whether a WebDAV file is reachable with the given credentials,
+/// including the Nextcloud path fallback. Does not touch the active config.
+pub fn test_webdav_connection(base_url: &str, path: Option<&str>, username: Option<&str>, password: Option<&str>) -> Result<()> {
+    let client = Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()?;
+
+    let construct_url = |base: &str| -> String {
+        if let Some(p) = path {
+            format!("{}/{}", base.trim_end_matches('/'), p.trim_start_matches('/'))
+        } else {
+            base.to_string()
+        }
+    };
+
+    let try_connect = |target_url: &str| -> Result<()> {
+        // Try HEAD first
+        let mut req = client.head(target_url);
+        if let (Some(u), Some(p)) = (username, password) {
+            req = req.basic_auth(u, Some(p));
+        }
+
+        let resp = req.send()?;
+        if resp.status().is_success() {
+            return Ok(());
+        }
+
+        // Fallback to GET
+        let mut req_get = client.get(target_url);
+        if let (Some(u), Some(p)) = (username, password) {
+            req_get = req_get.basic_auth(u, Some(p));
+        }
+        let resp_get = req_get.send()?;
+        if !resp_get.status().is_success() {
+             if resp_get.status() == reqwest::StatusCode::NOT_FOUND {
+                 bail!("404 Not Found");
+             }
+             bail!("HTTP {}", resp_get.status());
+        }
+        Ok(())
+    };
+
+    let full_url = construct_url(base_url);
+    match try_connect(&full_url) {
+        Ok(_) => return Ok(()),
+        Err(e) => {
+            // If it failed, and we have a username, try to guess Nextcloud path
+            if let Some(user) = username {
+                if !base_url.contains("remote.php/dav/files") {
+                    let candidate_base = format!("{}/remote.php/dav/files/{}", base_url.trim_end_matches('/'), user);
+                    let candidate_full = construct_url(&candidate_base);
+                    if try_connect(&candidate_full).is_ok() {
+                        // Success with fallback!
+                        // We don't update config here because this is just a test function.
+                        // But we return Ok to indicate connection is possible.
+                        return Ok(());
+                    }
+                }
+            }
+            // Return original error with context
+            bail!(t("connection_error").replace("{}", &full_url).replace("{}", &e.to_string()));
+        }
+    }
+}
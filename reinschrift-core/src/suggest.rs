@@ -0,0 +1,50 @@
+//! Picks the task to work on next for "What should I do now?".
+
+use chrono::{Datelike, NaiveDate};
+
+use crate::data::TodoItem;
+
+/// How much one skip lowers a task's score; three skips outweigh `pri:A`.
+const SKIP_PENALTY: f64 = 1.5;
+
+/// Higher is more urgent. Overdue and soon due tasks, high priorities and
+/// quick wins rank up; tasks parked as "sometimes" and ones the user keeps
+/// skipping rank down.
+pub fn score(item: &TodoItem, today: NaiveDate, skips: u32) -> f64 {
+    let mut score = 0.0;
+    match item.due {
+        Some(due) if due.year() == 9999 => score -= 1.0,
+        Some(due) if due < today => score += 4.0 + ((today - due).num_days().min(7) as f64) * 0.2,
+        Some(due) if due == today => score += 3.0,
+        Some(due) if (due - today).num_days() <= 3 => score += 2.0,
+        Some(_) => score += 0.5,
+        None => {}
+    }
+    if let Some(priority) = item.priority {
+        // pri:A adds 3, B 2, C 1; later letters nothing.
+        score += (3 - (priority as i32 - 'A' as i32).clamp(0, 3)) as f64;
+    }
+    match item.estimate {
+        Some(minutes) if minutes <= 15 => score += 1.0,
+        Some(minutes) if minutes > 120 => score -= 1.0,
+        _ => {}
+    }
+    if item.pinned {
+        score += 1.0;
+    }
+    score - skips as f64 * SKIP_PENALTY
+}
+
+/// The open task of `items` with the highest [`score`]; ties go to the one
+/// further up in the file. `skips` reports how often a task was skipped.
+pub fn next_action(items: &[TodoItem], today: NaiveDate, skips: impl Fn(&TodoItem) -> u32) -> Option<&TodoItem> {
+    items
+        .iter()
+        .filter(|item| !item.done)
+        .map(|item| (item, score(item, today, skips(item))))
+        .fold(None, |best: Option<(&TodoItem, f64)>, (item, score)| match best {
+            Some((_, best_score)) if best_score >= score => best,
+            _ => Some((item, score)),
+        })
+        .map(|(item, _)| item)
+}
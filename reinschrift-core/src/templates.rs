@@ -0,0 +1,115 @@
+//! Reusable sets of tasks such as a release checklist.
+//!
+//! Every template is a Markdown file in `~/.config/reinschrift/templates/`
+//! named after the template. Its task lines use the usual tokens, except that
+//! due dates are stored relative to the day the template is used, as
+//! `due:+3d`.
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use chrono::{Days, NaiveDate};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::collation;
+use crate::config;
+use crate::data::{self, Name, TodoItem, TodoKey};
+use crate::i18n::t;
+
+static RELATIVE_DUE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?:^|\s)due:\+(\d+)d(?:\s|$)").unwrap());
+
+/// `$XDG_CONFIG_HOME/reinschrift/templates`, falling back to `~/.config`.
+pub fn templates_dir() -> Option<PathBuf> {
+    config::config_dir().map(|dir| dir.join("templates"))
+}
+
+fn template_path(name: &str) -> Result<PathBuf> {
+    let name = name.trim();
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        bail!(t("template_name_error"));
+    }
+    let dir = templates_dir().context(t("template_dir_error"))?;
+    Ok(dir.join(format!("{name}.md")))
+}
+
+/// Names of the saved templates, sorted.
+pub fn list() -> Vec<String> {
+    let Some(entries) = templates_dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+        .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+        .collect();
+    collation::sort(&mut names, |name| name);
+    names
+}
+
+/// Saves `items` as template `name`, replacing an existing one. Due dates
+/// become offsets from `today`; past ones count as due on the day of use.
+/// Completion, reminders, start dates, markers and sections are not kept.
+pub fn save(name: &str, items: &[TodoItem], today: NaiveDate) -> Result<PathBuf> {
+    let path = template_path(name)?;
+    let mut lines = vec![format!("# {}", name.trim())];
+    for item in items {
+        let offset = item.due.map(|due| (due - today).num_days().max(0));
+        let task = TodoItem {
+            key: TodoKey::default(),
+            section: Name::default(),
+            start: None,
+            due: None,
+            due_time: None,
+            due_offset: None,
+            remind: None,
+            done: false,
+            completed: None,
+            created: None,
+            aged: None,
+            // Markers are not kept, so links between the tasks cannot be either.
+            depends_on: Vec::new(),
+            ..item.clone()
+        };
+        let mut line = data::render_line(&task)?;
+        if let Some(offset) = offset {
+            line.push_str(&format!(" due:+{offset}d"));
+        }
+        lines.push(line);
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| t("write_error").replace("{}", &dir.display().to_string()))?;
+    }
+    fs::write(&path, lines.join("\n") + "\n")
+        .with_context(|| t("write_error").replace("{}", &path.display().to_string()))?;
+    Ok(path)
+}
+
+/// The tasks of template `name` for `section`, with relative due dates
+/// resolved against `today`. Nothing is written yet.
+pub fn instantiate(name: &str, section: &str, today: NaiveDate) -> Result<Vec<TodoItem>> {
+    let path = template_path(name)?;
+    let content = fs::read_to_string(&path)
+        .with_context(|| t("read_error").replace("{}", &path.display().to_string()))?;
+    let mut items = Vec::new();
+    for line in content.lines() {
+        let Some(text) = line.trim_start().strip_prefix("- [ ]").or_else(|| line.trim_start().strip_prefix("- [x]")) else {
+            continue;
+        };
+        let offset = RELATIVE_DUE_RE
+            .captures(text)
+            .and_then(|caps| caps[1].parse::<u64>().ok());
+        let text = RELATIVE_DUE_RE.replace(text, " ");
+        let Some(mut item) = data::parse_task_text(&text) else {
+            continue;
+        };
+        if let Some(offset) = offset {
+            item.due = today.checked_add_days(Days::new(offset));
+        }
+        item.section = Name::new(section.trim());
+        items.push(item);
+    }
+    Ok(items)
+}
@@ -0,0 +1,31 @@
+//! `alarm:` as another name for `remind:`.
+
+use chrono::NaiveDate;
+use reinschrift_core::data::{self, BackendConfig};
+use reinschrift_core::storage;
+
+#[test]
+fn alarm_sets_the_reminder() {
+    let invoice = data::parse_task_text("Pay invoice due:2025-01-31 alarm:2025-01-29T09:00 +home").expect("task parses");
+    assert_eq!(invoice.title, "Pay invoice");
+    assert_eq!(invoice.due, NaiveDate::from_ymd_opt(2025, 1, 31));
+    assert_eq!(invoice.remind, NaiveDate::from_ymd_opt(2025, 1, 29).and_then(|day| day.and_hms_opt(9, 0, 0)));
+    assert_eq!(invoice.project.as_deref(), Some("home"));
+}
+
+#[test]
+fn alarm_inside_a_word_is_no_reminder() {
+    let wake = data::parse_task_text("Fix the snoozealarm:2025-01-29T09:00 button").expect("task parses");
+    assert_eq!(wake.title, "Fix the snoozealarm:2025-01-29T09:00 button");
+    assert_eq!(wake.remind, None);
+}
+
+#[test]
+fn edits_write_remind() {
+    storage::set_memory_document("- [ ] Pay invoice alarm:2025-01-29T09:00\n".to_string());
+    data::set_backend_config(BackendConfig::Memory);
+    let mut invoice = data::load_todos().unwrap().remove(0);
+    invoice.remind = NaiveDate::from_ymd_opt(2025, 1, 28).and_then(|day| day.and_hms_opt(9, 0, 0));
+    data::update_todo_details(&invoice).unwrap();
+    assert_eq!(storage::memory_document(), "- [ ] Pay invoice remind:2025-01-28T09:00\n");
+}
@@ -0,0 +1,37 @@
+//! Alphabetical order with umlauts, accents and case.
+
+use reinschrift_core::collation::Collator;
+
+fn sorted(locale: &str, words: &[&str]) -> Vec<String> {
+    let collator = Collator::for_locale(locale);
+    let mut words: Vec<String> = words.iter().map(|word| word.to_string()).collect();
+    words.sort_by(|a, b| collator.compare(a, b));
+    words
+}
+
+#[test]
+fn umlauts_sort_with_their_letter_in_german() {
+    assert_eq!(sorted("de", &["Zucker", "Äpfel", "apfel", "Birnen", "Öl", "Ofen"]), ["apfel", "Äpfel", "Birnen", "Ofen", "Öl", "Zucker"]);
+    assert_eq!(sorted("de_CH", &["Strasse", "Straße", "Strassen"]), ["Strasse", "Straße", "Strassen"]);
+}
+
+#[test]
+fn accents_and_case_break_ties_only() {
+    assert_eq!(sorted("fr", &["élève", "Eleve", "eleve", "elevé", "étage"]), ["eleve", "Eleve", "elevé", "élève", "étage"]);
+    assert_eq!(sorted("en", &["banana", "Apple", "apple", "Cherry"]), ["apple", "Apple", "banana", "Cherry"]);
+}
+
+#[test]
+fn swedish_and_spanish_letters_of_their_own() {
+    assert_eq!(sorted("sv", &["ö", "å", "ä", "z", "o"]), ["o", "z", "å", "ä", "ö"]);
+    assert_eq!(sorted("es", &["ñu", "nube", "oso"]), ["nube", "ñu", "oso"]);
+    assert_eq!(sorted("de", &["ñu", "nube", "oso"]), ["ñu", "nube", "oso"]);
+}
+
+#[test]
+fn missing_names_sort_last() {
+    let collator = Collator::for_locale("de");
+    let mut projects = vec![None, Some("Zucker"), Some("Äpfel")];
+    projects.sort_by_cached_key(|project| collator.optional_key(*project));
+    assert_eq!(projects, [Some("Äpfel"), Some("Zucker"), None]);
+}
@@ -0,0 +1,47 @@
+//! Reading `config.toml` with [`config::parse`].
+
+use chrono::NaiveDate;
+use reinschrift_core::config::{self, Config};
+
+#[test]
+fn every_key_is_read() {
+    let config = config::parse(
+        r#"
+database = "/srv/todo.md"
+language = "fr"
+date-format = "%d.%m.%Y"
+hide-completed = false
+
+[hooks]
+on-complete = "notify-send done"
+"#,
+    )
+    .unwrap();
+    assert_eq!(config.database.as_deref(), Some("/srv/todo.md"));
+    assert_eq!(config.language.as_deref(), Some("fr"));
+    assert_eq!(config.hide_completed, Some(false));
+    assert_eq!(config.hooks.on_complete.as_deref(), Some("notify-send done"));
+    assert_eq!(config.hooks.on_add, None);
+
+    config::set(config);
+    assert_eq!(config::format_date(NaiveDate::from_ymd_opt(2025, 3, 9).unwrap()), "09.03.2025");
+    config::set(Config { date_format: Some("%d.%m.%Y %H:%M".to_string()), ..Config::default() });
+    assert_eq!(config::format_date(NaiveDate::from_ymd_opt(2025, 3, 9).unwrap()), "2025-03-09");
+    config::set(Config::default());
+    assert_eq!(config::format_date(NaiveDate::from_ymd_opt(2025, 3, 9).unwrap()), "2025-03-09");
+}
+
+#[test]
+fn mistakes_are_reported() {
+    assert!(config::parse("date-format = \"%Q\"").is_err());
+    assert!(config::parse("date-format = \"%d.%m.%Y %H:%M\"").is_err());
+    assert!(config::parse("hide-complete = true").is_err());
+    assert_eq!(config::parse("").unwrap(), Config::default());
+}
+
+#[test]
+fn fallback_languages_must_have_a_catalog() {
+    let config = config::parse("fallback-languages = [\"sv\", \"en\"]").unwrap();
+    assert_eq!(config.fallback_languages, Some(vec!["sv".to_string(), "en".to_string()]));
+    assert!(config::parse("fallback-languages = [\"tlh\"]").is_err());
+}
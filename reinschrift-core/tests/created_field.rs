@@ -0,0 +1,40 @@
+//! The `created:` date of a task.
+
+use chrono::{Local, NaiveDate};
+use reinschrift_core::data::{self, BackendConfig};
+use reinschrift_core::storage;
+
+#[test]
+fn created_is_parsed_and_kept_out_of_the_title() {
+    let task = data::parse_task_text("Renew passport created:2025-01-27 due:2025-03-01 +admin").expect("task parses");
+    assert_eq!(task.title, "Renew passport");
+    assert_eq!(task.created, NaiveDate::from_ymd_opt(2025, 1, 27));
+    assert_eq!(task.field_text("created").as_deref(), Some("2025-01-27"));
+}
+
+#[test]
+fn recreated_stays_in_the_title() {
+    let task = data::parse_task_text("recreated: index").expect("task parses");
+    assert_eq!(task.title, "recreated: index");
+    let dated = data::parse_task_text("Check recreated:2025-01-27 index").expect("task parses");
+    assert_eq!(dated.title, "Check recreated:2025-01-27 index");
+    assert_eq!(dated.created, None);
+}
+
+#[test]
+fn new_tasks_are_stamped_and_edits_keep_the_date() {
+    storage::set_memory_document("- [ ] Water plants created:2024-05-01\n- [ ] Renew passport created:2025-02-30\n".to_string());
+    data::set_backend_config(BackendConfig::Memory);
+    data::add_todo("Book room").unwrap();
+    let today = Local::now().date_naive();
+    let todos = data::load_todos().unwrap();
+    assert!(data::load_problems().iter().any(|problem| problem.line == 2), "invalid date is reported");
+    let added = todos.iter().find(|todo| todo.title == "Book room").expect("added task");
+    assert_eq!(added.created, Some(today));
+
+    let mut plants = todos.into_iter().find(|todo| todo.title == "Water plants").expect("existing task");
+    plants.due = Some(today);
+    data::update_todo_details(&plants).unwrap();
+    let document = storage::memory_document();
+    assert!(document.contains(&format!("- [ ] Water plants due:{} created:2024-05-01", today.format("%Y-%m-%d"))), "{document}");
+}
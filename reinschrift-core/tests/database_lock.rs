@@ -0,0 +1,43 @@
+//! Edits wait for each other through [`lock::acquire`].
+
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use reinschrift_core::data::BackendConfig;
+use reinschrift_core::lock;
+
+fn database(name: &str) -> BackendConfig {
+    let dir = std::env::temp_dir().join(format!("reinschrift-lock-{}-{name}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    BackendConfig::Local(dir.join("todo.md"))
+}
+
+#[test]
+fn a_second_edit_waits_for_the_first() {
+    let config = database("wait");
+    let (locked_tx, locked_rx) = mpsc::channel();
+    let holder = {
+        let config = config.clone();
+        thread::spawn(move || {
+            let _lock = lock::acquire(&config).unwrap();
+            locked_tx.send(()).unwrap();
+            thread::sleep(Duration::from_millis(300));
+        })
+    };
+    locked_rx.recv().unwrap();
+
+    let started = Instant::now();
+    let _lock = lock::acquire(&config).unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(200), "took the lock after {:?}", started.elapsed());
+    holder.join().unwrap();
+}
+
+#[test]
+fn nested_edits_do_not_wait_for_themselves() {
+    let config = database("nested");
+    let _outer = lock::acquire(&config).unwrap();
+    let started = Instant::now();
+    let _inner = lock::acquire(&config).unwrap();
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
@@ -0,0 +1,75 @@
+//! Timed `due:` values, read in the Europe/Berlin time zone.
+
+use std::sync::Once;
+
+use chrono::{Local, NaiveDate, NaiveTime, TimeZone, Utc};
+use reinschrift_core::data::{self, BackendConfig, TodoItem};
+use reinschrift_core::storage;
+
+fn in_berlin() {
+    static BERLIN: Once = Once::new();
+    // SAFETY: set before any test of this file reads the time zone.
+    BERLIN.call_once(|| unsafe { std::env::set_var("TZ", "Europe/Berlin") });
+}
+
+fn task(text: &str) -> TodoItem {
+    in_berlin();
+    data::parse_task_text(text).expect("task parses")
+}
+
+fn utc(text: &str) -> chrono::DateTime<Utc> {
+    text.parse().unwrap()
+}
+
+#[test]
+fn times_without_offset_are_local() {
+    let call = task("Call Anna due:2024-06-01T14:00");
+    assert_eq!(call.due, NaiveDate::from_ymd_opt(2024, 6, 1));
+    assert_eq!(call.due_time, NaiveTime::from_hms_opt(14, 0, 0));
+    assert_eq!(call.due_offset, None);
+    assert_eq!(call.due_at().map(|due| due.to_utc()), Some(utc("2024-06-01T12:00:00Z")));
+    assert_eq!(call.field_text("due").as_deref(), Some("2024-06-01T14:00"));
+
+    let all_day = task("Pay rent due:2024-06-01");
+    assert_eq!((all_day.due_time, all_day.due_at()), (None, None));
+}
+
+#[test]
+fn offsets_are_shown_locally_and_kept() {
+    let call = task("Call Tokyo due:2024-06-01T23:30Z");
+    assert_eq!(call.due, NaiveDate::from_ymd_opt(2024, 6, 2));
+    assert_eq!(call.due_time, NaiveTime::from_hms_opt(1, 30, 0));
+    assert_eq!(call.field_text("due").as_deref(), Some("2024-06-01T23:30Z"));
+
+    let meeting = task("Meeting due:2024-06-01T09:00-04:00");
+    assert_eq!(meeting.due_at(), Some(Local.with_ymd_and_hms(2024, 6, 1, 15, 0, 0).unwrap()));
+    assert_eq!(meeting.field_text("due").as_deref(), Some("2024-06-01T09:00-04:00"));
+}
+
+#[test]
+fn dst_gaps_and_repeats_resolve() {
+    // 02:30 does not exist on 31 March, the clock shows 03:30 then.
+    let skipped = task("Backup due:2024-03-31T02:30");
+    assert_eq!(skipped.due_at().map(|due| due.to_utc()), Some(utc("2024-03-31T01:30:00Z")));
+    // 02:30 happens twice on 27 October; the first, still in summer time, counts.
+    let repeated = task("Backup due:2024-10-27T02:30");
+    assert_eq!(repeated.due_at().map(|due| due.to_utc()), Some(utc("2024-10-27T00:30:00Z")));
+    assert_eq!(repeated.field_text("due").as_deref(), Some("2024-10-27T02:30"));
+}
+
+#[test]
+fn due_today_keeps_the_time() {
+    in_berlin();
+    storage::set_memory_document("- [ ] Stand-up due:2024-06-01T09:15\n".to_string());
+    data::set_backend_config(BackendConfig::Memory);
+    let item = data::load_todos().unwrap().remove(0);
+    let today = data::set_due_today(&item.key).unwrap();
+    assert_eq!(storage::memory_document(), format!("- [ ] Stand-up due:{}T09:15\n", today.format("%Y-%m-%d")));
+}
+
+#[test]
+fn overdue_in_a_title_is_no_due_date() {
+    let report = task("Check overdue:2024-01-01 report");
+    assert_eq!(report.title, "Check overdue:2024-01-01 report");
+    assert_eq!(report.due, None);
+}
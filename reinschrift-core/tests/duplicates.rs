@@ -0,0 +1,16 @@
+//! Merging duplicate tasks with [`duplicates::merged`].
+
+use reinschrift_core::data;
+use reinschrift_core::duplicates;
+
+#[test]
+fn merged_tasks_take_the_due_time_with_the_date() {
+    let keep = data::parse_task_text("Call Anna +family").expect("task parses");
+    let other = data::parse_task_text("Call Anna due:2025-03-01T14:00+01:00 created:2025-01-27").expect("task parses");
+    let merged = duplicates::merged(&keep, std::slice::from_ref(&other));
+    assert_eq!(other.due_offset, Some(3600));
+    assert!(other.due_time.is_some());
+    assert_eq!((merged.due, merged.due_time, merged.due_offset), (other.due, other.due_time, other.due_offset));
+    assert_eq!(merged.created, other.created);
+    assert_eq!(merged.project.as_deref(), Some("family"));
+}
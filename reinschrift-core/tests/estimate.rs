@@ -0,0 +1,13 @@
+//! Parsing `est:` estimates.
+
+use reinschrift_core::data;
+
+#[test]
+fn estimates_parse_and_reject_overflow() {
+    assert_eq!(data::parse_estimate("1h30m"), Some(90));
+    assert_eq!(data::parse_estimate("2h"), Some(120));
+    assert_eq!(data::parse_estimate("45m"), Some(45));
+    assert_eq!(data::parse_estimate("0m"), None);
+    assert_eq!(data::parse_estimate("100000000h"), None);
+    assert_eq!(data::parse_estimate("71582788h4294967295m"), None);
+}
@@ -0,0 +1,16 @@
+//! Languages without a catalog fall back along [`i18n::fallback_languages`].
+
+use reinschrift_core::i18n::{self, t};
+
+#[test]
+fn untranslated_language_uses_the_fallback_chain() {
+    i18n::set_language("it_IT.UTF-8".to_string());
+    assert_eq!(i18n::fallback_languages(), ["en", "de"]);
+    assert_eq!(t("save"), "Save");
+    assert_eq!(i18n::current_locale(), "en");
+
+    i18n::set_fallback_languages(vec!["tlh".to_string(), "de".to_string()]);
+    assert_eq!(t("save"), "Speichern");
+    assert_eq!(i18n::current_locale(), "de");
+    assert_eq!(t("no_such_key"), "no_such_key");
+}
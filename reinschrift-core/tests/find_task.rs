@@ -0,0 +1,19 @@
+//! Resolving task links and `reinschrift show` ids with [`data::find_task`].
+
+use reinschrift_core::data::{self, TodoItem};
+
+fn task(text: &str, done: bool) -> TodoItem {
+    TodoItem { done, ..data::parse_task_text(text).expect("task parses") }
+}
+
+#[test]
+fn titles_prefer_the_open_instance_of_a_routine() {
+    let items = [
+        task("Water plants rec:weekly", true),
+        task("Water plants rec:weekly due:2025-02-03", false),
+        task("Call Anna ^abc123", false),
+    ];
+    let found = data::find_task(&items, "water Plants").expect("found by title");
+    assert!(!found.done);
+    assert_eq!(data::find_task(&items, "^abc123").map(|item| item.title.as_str()), Some("Call Anna"));
+}
@@ -0,0 +1,18 @@
+//! `--i18n-debug` marks translations and reports the keys that lack one.
+
+use reinschrift_core::i18n::{self, t};
+
+#[test]
+fn marks_strings_and_reports_missing_keys_with_their_caller() {
+    i18n::set_language("en".to_string());
+    // Dropping the guard would write the report into the crate folder.
+    std::mem::forget(i18n::enable_debug());
+
+    assert_eq!(t("cancel"), "⟦Cancel⟧");
+    assert_eq!(t("no_such_key"), "⟦no_such_key⟧");
+
+    let report = i18n::debug_report();
+    let line = report.lines().find(|line| line.trim_start().starts_with("no_such_key")).expect("missing key reported");
+    assert!(line.contains("tests/i18n_debug.rs:"), "{line}");
+    assert!(!report.contains("  cancel"), "{report}");
+}
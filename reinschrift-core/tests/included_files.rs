@@ -0,0 +1,38 @@
+//! Tasks of `#include`d files keep to their file when compared.
+
+use std::path::PathBuf;
+
+use reinschrift_core::data::{self, ChangeKind, TodoItem, TodoKey};
+
+fn task(title: &str, file: Option<&str>, line_index: usize, marker: Option<&str>) -> TodoItem {
+    TodoItem {
+        title: title.to_string(),
+        key: TodoKey { line_index, marker: marker.map(str::to_string), file: file.map(PathBuf::from) },
+        ..TodoItem::default()
+    }
+}
+
+#[test]
+fn the_same_marker_in_two_files_is_no_duplicate() {
+    let items = [
+        task("Report", None, 2, Some("a1")),
+        task("Invoice", Some("/tmp/work.todo"), 2, Some("a1")),
+        task("Invoice copy", Some("/tmp/work.todo"), 5, Some("a1")),
+    ];
+    let duplicates = data::find_duplicate_keys(&items);
+    assert_eq!(duplicates.len(), 1);
+    assert_eq!(duplicates[0].file, Some(PathBuf::from("/tmp/work.todo")));
+    assert_eq!(duplicates[0].line_indices, [2, 5]);
+}
+
+#[test]
+fn tasks_on_the_same_line_of_other_files_are_not_edits() {
+    let old = [task("Report", None, 2, None)];
+    let new = [task("Invoice", Some("/tmp/work.todo"), 2, None)];
+    let events: Vec<ChangeKind> = data::diff_todos(&old, &new).into_iter().map(|change| change.event).collect();
+    assert_eq!(events, [ChangeKind::Added, ChangeKind::Removed]);
+
+    let renamed = [task("Write report", None, 2, None)];
+    let events: Vec<ChangeKind> = data::diff_todos(&old, &renamed).into_iter().map(|change| change.event).collect();
+    assert_eq!(events, [ChangeKind::Edited]);
+}
@@ -0,0 +1,32 @@
+//! Interrupted saves found through [`journal::pending`].
+
+use std::fs;
+
+use reinschrift_core::data::{self, BackendConfig};
+use reinschrift_core::journal;
+
+#[test]
+fn an_interrupted_write_to_an_included_file_is_replayed() {
+    let dir = std::env::temp_dir().join(format!("reinschrift-journal-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let database = dir.join("todo.md");
+    let work = dir.join("work.md");
+    fs::write(&database, "#include work.md\n- [ ] Call Anna\n").unwrap();
+    fs::write(&work, "- [ ] Write report\n").unwrap();
+    data::set_backend_config(BackendConfig::Local(database));
+    assert_eq!(data::load_todos().unwrap().len(), 2);
+    assert!(journal::pending().is_empty());
+
+    // What a crash between journal and real write leaves behind.
+    let included = data::included_files().into_iter().next().expect("work.md is included");
+    journal::record(&included, "- [ ] Write report\n- [ ] Book room\n").unwrap();
+    let pending = journal::pending();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].database, included);
+
+    journal::replay(&pending[0]).unwrap();
+    assert_eq!(fs::read_to_string(&work).unwrap(), "- [ ] Write report\n- [ ] Book room\n");
+    assert!(!journal::journal_path(&included).exists());
+    assert!(journal::pending().is_empty());
+    assert_eq!(data::load_todos().unwrap().len(), 3);
+}
@@ -0,0 +1,31 @@
+//! Pasted Markdown checklists read by [`import::markdown_checklist`].
+
+use reinschrift_core::i18n::t;
+use reinschrift_core::import;
+
+#[test]
+fn each_item_becomes_a_task_with_its_box() {
+    let pasted = "Für Samstag:\n\n- [ ] Milch kaufen +einkauf\n* [x] Anna anrufen\n  + [X] Brief einwerfen\n1. [ ] Fahrrad due:2025-05-03\n2) [ ] Blumen\n";
+    let items = import::markdown_checklist(pasted);
+    let titles: Vec<(&str, bool)> = items.iter().map(|item| (item.title.as_str(), item.done)).collect();
+    assert_eq!(
+        titles,
+        [("Milch kaufen", false), ("Anna anrufen", true), ("Brief einwerfen", true), ("Fahrrad", false), ("Blumen", false)]
+    );
+    assert_eq!(items[0].project.as_deref(), Some("einkauf"));
+    assert_eq!(items[3].field_text("due").as_deref(), Some("2025-05-03"));
+    assert!(items.iter().all(|item| item.section.as_ref() == t("no_section")));
+}
+
+#[test]
+fn headings_become_sections() {
+    let items = import::markdown_checklist("# Release\n- [ ] Tag\n## Docs\n- [x] Changelog\n");
+    let sections: Vec<&str> = items.iter().map(|item| item.section.as_ref()).collect();
+    assert_eq!(sections, ["Release", "Docs"]);
+}
+
+#[test]
+fn other_lists_and_empty_items_are_skipped() {
+    let items = import::markdown_checklist("- Milk\n- [ ]\n-[ ] Tight\n- [?] Maybe\n[ ] Bare\n12 [ ] Number\n");
+    assert!(items.is_empty(), "{items:?}");
+}
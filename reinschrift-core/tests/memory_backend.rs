@@ -0,0 +1,164 @@
+//! Add, edit, toggle and reload flows against the in-memory backend.
+//!
+//! The backend and its document are process-wide, so every test holds
+//! [`Session`] for its whole run.
+
+use std::sync::{Mutex, MutexGuard};
+
+use chrono::Local;
+use reinschrift_core::data::{self, BackendConfig, DataError, TodoItem};
+use reinschrift_core::storage;
+
+static LOCK: Mutex<()> = Mutex::new(());
+
+struct Session {
+    _guard: MutexGuard<'static, ()>,
+}
+
+impl Session {
+    fn new(document: &str) -> Self {
+        let guard = LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        storage::set_memory_document(document.to_string());
+        data::set_backend_config(BackendConfig::Memory);
+        Session { _guard: guard }
+    }
+
+    fn items(&self) -> Vec<TodoItem> {
+        data::load_todos().expect("memory document loads")
+    }
+
+    fn item(&self, title: &str) -> TodoItem {
+        self.items().into_iter().find(|item| item.title == title).unwrap_or_else(|| panic!("no task {title:?}"))
+    }
+
+    fn document(&self) -> String {
+        storage::memory_document()
+    }
+}
+
+const DOCUMENT: &str = "### Work\n\n- [ ] Write report +office\n- [ ] Call Anna\n\n---\n\n- [x] Old task\n";
+
+#[test]
+fn add_goes_before_the_separator() {
+    let session = Session::new(DOCUMENT);
+    data::add_todo("Book room").unwrap();
+    let today = Local::now().date_naive().format("%Y-%m-%d");
+    let document = session.document();
+    let added = document.find(&format!("- [ ] Book room due:{today}")).expect("added line");
+    assert!(added < document.find("---").unwrap());
+    assert_eq!(session.items().len(), 4);
+}
+
+#[test]
+fn toggle_round_trips() {
+    let session = Session::new(DOCUMENT);
+    data::complete_todo(&session.item("Call Anna"), true).unwrap();
+    assert!(session.item("Call Anna").done);
+    assert!(session.document().contains("- [x] Call Anna"));
+
+    data::complete_todo(&session.item("Call Anna"), false).unwrap();
+    assert!(!session.item("Call Anna").done);
+    assert_eq!(session.document(), DOCUMENT);
+}
+
+#[test]
+fn edit_keeps_the_line_in_place() {
+    let session = Session::new(DOCUMENT);
+    let mut report = session.item("Write report");
+    report.title = "Write the report".to_string();
+    report.priority = Some('A');
+    data::update_todo_details(&report).unwrap();
+
+    let titles: Vec<String> = session.items().into_iter().map(|item| item.title).collect();
+    assert_eq!(titles, ["Write the report", "Call Anna", "Old task"]);
+    assert_eq!(session.item("Write the report").priority, Some('A'));
+}
+
+#[test]
+fn outside_change_shows_in_the_fingerprint_and_merges() {
+    let session = Session::new(DOCUMENT);
+    let before = data::get_fingerprint().unwrap();
+    let base = session.item("Call Anna");
+
+    // Someone else renames the task while it is being edited here.
+    storage::set_memory_document(DOCUMENT.replace("Call Anna", "Call Anna back"));
+    assert_ne!(data::get_fingerprint().unwrap(), before);
+
+    let mut mine = base.clone();
+    mine.context = Some("phone".into());
+    let conflict = data::update_todo_merged(&base, &mine).unwrap();
+    assert!(conflict.is_none());
+    let merged = session.item("Call Anna back");
+    assert_eq!(merged.context.as_deref(), Some("phone"));
+}
+
+#[test]
+fn outside_aging_change_is_merged() {
+    let session = Session::new("- [ ] Pay rent pri:A aged:B\n");
+    let base = session.item("Pay rent");
+
+    // The raise is undone in another editor while the task is open here.
+    storage::set_memory_document("- [ ] Pay rent pri:B aged:off\n".to_string());
+    let mut mine = base.clone();
+    mine.context = Some("home".into());
+    let conflict = data::update_todo_merged(&base, &mine).unwrap();
+    assert!(conflict.is_none());
+    assert_eq!(session.document(), "- [ ] Pay rent @home pri:B aged:off\n");
+}
+
+#[test]
+fn completing_a_recurring_task_adds_the_next_one() {
+    let today = Local::now().date_naive();
+    let session = Session::new(&format!("- [ ] Water plants due:{} rec:weekly\n", today.format("%Y-%m-%d")));
+    data::complete_todo(&session.item("Water plants"), true).unwrap();
+
+    let items = session.items();
+    assert_eq!(items.len(), 2);
+    let next = items.iter().find(|item| !item.done).expect("next occurrence");
+    assert_eq!(next.due, Some(today + chrono::Duration::days(7)));
+}
+
+#[test]
+fn delete_removes_only_that_line() {
+    let session = Session::new(DOCUMENT);
+    data::delete_todo(&session.item("Write report")).unwrap();
+    assert_eq!(session.document(), DOCUMENT.replace("- [ ] Write report +office\n", ""));
+}
+
+#[test]
+fn errors_say_what_went_wrong() {
+    let session = Session::new(DOCUMENT);
+    let mut gone = session.item("Call Anna");
+    gone.key.line_index = 99;
+    let err = data::delete_todo(&gone).unwrap_err();
+    assert!(matches!(err.downcast_ref::<DataError>(), Some(DataError::TaskNotFound)));
+    let err = data::add_todo("  ").unwrap_err();
+    assert!(matches!(err.downcast_ref::<DataError>(), Some(DataError::EmptyTitle)));
+
+    data::set_backend_config(BackendConfig::Local("/nonexistent/reinschrift/todo.md".into()));
+    let missing = data::load_todos().unwrap_err();
+    assert!(matches!(missing.downcast_ref::<DataError>(), Some(DataError::NotFound { .. })));
+}
+
+#[test]
+fn deleted_database_is_recreated_as_last_loaded() {
+    let _session = Session::new(DOCUMENT);
+    let dir = std::env::temp_dir().join(format!("reinschrift-recreate-{}", std::process::id()));
+    let path = dir.join("branch").join("todo.md");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, DOCUMENT).unwrap();
+    data::set_backend_config(BackendConfig::Local(path.clone()));
+    assert_eq!(data::load_todos().unwrap().len(), 3);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+    let gone = data::load_todos().unwrap_err();
+    assert!(matches!(gone.downcast_ref::<DataError>(), Some(DataError::NotFound { .. })));
+    data::recreate_database().unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), DOCUMENT);
+
+    // A file that is back already stays as it is.
+    std::fs::write(&path, "- [ ] Restored elsewhere\n").unwrap();
+    data::recreate_database().unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "- [ ] Restored elsewhere\n");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
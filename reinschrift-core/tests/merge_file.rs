@@ -0,0 +1,33 @@
+//! Merging another database with [`import::merge_file`].
+
+use reinschrift_core::data::{self, BackendConfig};
+use reinschrift_core::{import, storage};
+
+#[test]
+fn changed_tasks_are_updated_instead_of_added_again() {
+    storage::set_memory_document(
+        "### Work\n\n- [ ] Write report ^abc123\n- [ ] Call Anna\n- [ ] Book room est:30m\n".to_string(),
+    );
+    data::set_backend_config(BackendConfig::Memory);
+    let other = std::env::temp_dir().join(format!("reinschrift-merge-{}.md", std::process::id()));
+    std::fs::write(
+        &other,
+        "### work\n\n- [x] Write report ✅ 2025-02-03 ^abc123\n- [ ] Call Anna +family\n- [ ] Book room est:30m\n- [ ] Renew passport\n",
+    )
+    .unwrap();
+
+    let report = import::merge_file(&other).unwrap();
+    std::fs::remove_file(&other).unwrap();
+    assert_eq!(report.added.iter().map(|item| item.title.as_str()).collect::<Vec<_>>(), ["Renew passport"]);
+    assert_eq!((report.updated, report.duplicates), (2, 1));
+
+    let items = data::load_todos().unwrap();
+    assert_eq!(items.len(), 4);
+    let report = items.iter().find(|item| item.title == "Write report").unwrap();
+    assert!(report.done);
+    assert_eq!(report.key.marker.as_deref(), Some("abc123"));
+    let call = items.iter().find(|item| item.title == "Call Anna").unwrap();
+    assert_eq!(call.project.as_deref(), Some("family"));
+    assert!(call.key.marker.is_some(), "the matched task keeps its identity from now on");
+    assert!(items.iter().all(|item| item.section == "Work"));
+}
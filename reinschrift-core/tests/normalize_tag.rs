@@ -0,0 +1,10 @@
+//! Cleaning up project and context names typed or sent by other programs.
+
+use reinschrift_core::data;
+
+#[test]
+fn tags_lose_sigils_and_whitespace() {
+    assert_eq!(data::normalize_tag("+read later").as_deref(), Some("readlater"));
+    assert_eq!(data::normalize_tag(" @home\noffice").as_deref(), Some("homeoffice"));
+    assert_eq!(data::normalize_tag("+ "), None);
+}
@@ -0,0 +1,33 @@
+//! Numbers follow the separators of [`i18n::current_locale`].
+
+use std::time::Duration;
+
+use reinschrift_core::i18n::{self, format_count, format_duration, format_number, format_percent};
+
+#[test]
+fn numbers_follow_the_locale() {
+    i18n::set_language("de_DE.UTF-8".to_string());
+    assert_eq!(format_number(1234.5, 1), "1.234,5");
+    assert_eq!(format_percent(0.42, 0), "42\u{a0}%");
+    assert_eq!(format_count(999), "999");
+
+    i18n::set_language("de_CH".to_string());
+    assert_eq!(format_number(1234.5, 1), "1’234.5");
+
+    i18n::set_language("en".to_string());
+    assert_eq!(format_number(-1234567.0, 0), "-1,234,567");
+    assert_eq!(format_percent(0.125, 1), "12.5%");
+    assert_eq!(format_number(-0.001, 1), "0.0");
+
+    i18n::set_language("es".to_string());
+    assert_eq!(format_count(1234), "1234");
+    assert_eq!(format_count(12345), "12.345");
+
+    i18n::set_language("fr".to_string());
+    assert_eq!(format_percent(0.5, 0), "50\u{202f}%");
+
+    i18n::set_language("sv".to_string());
+    assert_eq!(format_number(-2.5, 1), "\u{2212}2,5");
+    assert_eq!(format_duration(Duration::from_secs(65 * 60)), "1 h 05 min");
+    assert_eq!(format_duration(Duration::from_secs(90)), "2 min");
+}
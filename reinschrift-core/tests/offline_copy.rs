@@ -0,0 +1,17 @@
+//! File names of the offline copies of remote databases.
+
+use reinschrift_core::cache;
+
+#[test]
+fn locations_differing_in_punctuation_get_their_own_copy() {
+    assert_ne!(cache::copy_file_name("sftp://nas/a_b.md"), cache::copy_file_name("sftp://nas/a/b.md"));
+    assert_ne!(cache::copy_file_name("sftp://nas/a__2fb.md"), cache::copy_file_name("sftp://nas/a_/b.md"));
+    assert_eq!(cache::copy_file_name("sftp://nas/a_b.md"), cache::copy_file_name("sftp://nas/a_b.md"));
+    assert!(cache::copy_file_name("sftp://nas/a_b.md").starts_with("sftp___nas_a_b.md-"));
+}
+
+#[test]
+fn long_locations_fit_in_a_file_name() {
+    let location = format!("davs://cloud.example.com/{}/todo.md", "ördner/".repeat(60));
+    assert!(cache::copy_file_name(&location).len() < 80);
+}
@@ -0,0 +1,16 @@
+//! `--language x-pseudo` accents and pads the English text.
+
+use reinschrift_core::i18n::{self, pseudolocalize, t};
+
+#[test]
+fn pseudo_locale_accents_the_english_text() {
+    i18n::set_language(i18n::PSEUDO_LANGUAGE.to_string());
+    assert_eq!(t("save"), "[Šàṽé~~]");
+    assert_eq!(i18n::current_locale(), "en");
+}
+
+#[test]
+fn placeholders_and_accelerators_stay_readable() {
+    assert_eq!(pseudolocalize("Done: {}"), "[Đöñé: {}~~]");
+    assert_eq!(pseudolocalize("Press <Primary>r"), "[Þŕéšš <Primary>ŕ~~]");
+}
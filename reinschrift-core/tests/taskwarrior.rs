@@ -0,0 +1,62 @@
+//! Round trips through `task export` and `task import`.
+//!
+//! The backend and its document are process-wide, so every test holds
+//! [`LOCK`] for its whole run.
+
+use std::sync::{Mutex, MutexGuard};
+
+use reinschrift_core::data::{self, BackendConfig};
+use reinschrift_core::{export, import, storage};
+use serde_json::Value;
+
+static LOCK: Mutex<()> = Mutex::new(());
+
+fn session(document: &str) -> MutexGuard<'static, ()> {
+    let guard = LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    storage::set_memory_document(document.to_string());
+    data::set_backend_config(BackendConfig::Memory);
+    guard
+}
+
+#[test]
+fn exports_keep_their_identity_and_imports_update_in_place() {
+    let _session = session("### Work\n\n- [ ] Write report est:30m ^abc123\n- [ ] Call Anna\n");
+
+    let mut items = data::load_todos().unwrap();
+    data::ensure_markers(&mut items).unwrap();
+    let first = export::to_taskwarrior(&items);
+    let second = export::to_taskwarrior(&data::load_todos().unwrap());
+    assert_eq!(first, second, "the same tasks export the same way");
+    let tasks: Vec<Value> = serde_json::from_str(&first).unwrap();
+    let uuids: Vec<&str> = tasks.iter().filter_map(|task| task["uuid"].as_str()).collect();
+    assert_eq!(uuids.len(), 2);
+    assert_eq!(uuids[0], export::taskwarrior_uuid("abc123"));
+    assert_eq!(&uuids[0][14..15], "8");
+
+    // Taskwarrior hands the tasks back with one of them completed.
+    let edited = first.replacen("\"pending\"", "\"completed\"", 1);
+    let imported = data::import_todos(&import::taskwarrior(&edited).unwrap()).unwrap();
+    assert_eq!(imported, data::Imported { added: 0, updated: 2 });
+    let items = data::load_todos().unwrap();
+    assert_eq!(items.len(), 2);
+    let report = items.iter().find(|item| item.title == "Write report").unwrap();
+    assert!(report.done);
+    assert_eq!(report.estimate, Some(30), "fields Taskwarrior does not know stay");
+    assert_eq!(items[0].key.marker.as_deref(), Some("abc123"));
+}
+
+#[test]
+fn missing_markers_are_added_in_one_write() {
+    let _session = session("- [ ] Call Anna\n- [ ] Book room ^abc123\n- [ ] Water plants\n");
+    let writes = || storage::open(BackendConfig::Memory).fingerprint().unwrap().parse::<u64>().unwrap();
+
+    let mut items = data::load_todos().unwrap();
+    let before = writes();
+    data::ensure_markers(&mut items).unwrap();
+    assert_eq!(writes(), before + 1);
+    assert_eq!(data::load_todos().unwrap(), items);
+    assert_ne!(items[0].key.marker, items[2].key.marker);
+
+    data::ensure_markers(&mut items).unwrap();
+    assert_eq!(writes(), before + 1, "nothing to add, nothing written");
+}
@@ -0,0 +1,46 @@
+//! Field names inside a word belong to the title.
+
+use chrono::NaiveDate;
+use reinschrift_core::data::{self, Aging, BackendConfig};
+use reinschrift_core::storage;
+
+#[test]
+fn words_ending_in_est_keep_the_title_whole() {
+    let request = data::parse_task_text("Fix request: empty body est:1h").expect("task parses");
+    assert_eq!(request.title, "Fix request: empty body");
+    assert_eq!(request.estimate, Some(60));
+    let latest = data::parse_task_text("Read the latest:est:30m notes").expect("task parses");
+    assert_eq!(latest.title, "Read the latest:est:30m notes");
+    assert_eq!(latest.estimate, None);
+}
+
+#[test]
+fn edits_write_the_title_back_unchanged() {
+    storage::set_memory_document("- [ ] Write test: parser est:2h\n- [ ] Fix request: empty body\n".to_string());
+    data::set_backend_config(BackendConfig::Memory);
+    for mut todo in data::load_todos().unwrap() {
+        todo.estimate = Some(30);
+        data::update_todo_details(&todo).unwrap();
+    }
+    assert_eq!(storage::memory_document(), "- [ ] Write test: parser est:30m\n- [ ] Fix request: empty body est:30m\n");
+}
+
+#[test]
+fn restart_is_no_start_date() {
+    let restart = data::parse_task_text("restart: nginx").expect("task parses");
+    assert_eq!(restart.title, "restart: nginx");
+    let dated = data::parse_task_text("Plan restart:2026-01-01 start:2026-02-01").expect("task parses");
+    assert_eq!(dated.title, "Plan restart:2026-01-01");
+    assert_eq!(dated.start, NaiveDate::from_ymd_opt(2026, 2, 1));
+    let undated = data::parse_task_text("Plan restart:2026-01-01").expect("task parses");
+    assert_eq!(undated.start, None);
+}
+
+#[test]
+fn managed_is_no_aging_marker() {
+    let managed = data::parse_task_text("Ask managed: team").expect("task parses");
+    assert_eq!(managed.title, "Ask managed: team");
+    let staged = data::parse_task_text("Review staged:B changes aged:C").expect("task parses");
+    assert_eq!(staged.title, "Review staged:B changes");
+    assert_eq!(staged.aged, Some(Aging::Raised(Some('C'))));
+}
@@ -0,0 +1,62 @@
+//! The retry policy of [`storage::write_with_retry`].
+
+use std::cell::Cell;
+
+use anyhow::{bail, Result};
+use reinschrift_core::data::DataError;
+use reinschrift_core::storage::{self, Storage};
+
+/// Fails the first `failures` writes with `error`.
+struct Flaky {
+    failures: u32,
+    error: fn() -> DataError,
+    writes: Cell<u32>,
+}
+
+impl Storage for Flaky {
+    fn read(&self) -> Result<String> {
+        Ok(String::new())
+    }
+
+    fn write(&self, _content: &str) -> Result<()> {
+        self.writes.set(self.writes.get() + 1);
+        if self.writes.get() <= self.failures {
+            bail!((self.error)());
+        }
+        Ok(())
+    }
+
+    fn fingerprint(&self) -> Result<String> {
+        Ok(self.writes.get().to_string())
+    }
+}
+
+fn busy() -> DataError {
+    DataError::from_io("todo.md", true, std::io::Error::from(std::io::ErrorKind::ResourceBusy))
+}
+
+#[test]
+fn passing_failures_are_retried() {
+    let storage = Flaky { failures: 2, error: busy, writes: Cell::new(0) };
+    storage::write_with_retry(&storage, "- [ ] Task\n").unwrap();
+    assert_eq!(storage.writes.get(), 3);
+}
+
+#[test]
+fn lasting_failures_give_up() {
+    let storage = Flaky { failures: u32::MAX, error: busy, writes: Cell::new(0) };
+    let err = storage::write_with_retry(&storage, "- [ ] Task\n").unwrap_err();
+    assert!(matches!(err.downcast_ref::<DataError>(), Some(DataError::Io { writing: true, .. })));
+    assert_eq!(storage.writes.get(), 4);
+
+    let missing = || DataError::NotFound { location: "todo.md".to_string() };
+    let storage = Flaky { failures: u32::MAX, error: missing, writes: Cell::new(0) };
+    assert!(storage::write_with_retry(&storage, "- [ ] Task\n").is_err());
+    assert_eq!(storage.writes.get(), 1);
+
+    let read_only = || DataError::from_io("todo.md", true, std::io::Error::from(std::io::ErrorKind::PermissionDenied));
+    let storage = Flaky { failures: u32::MAX, error: read_only, writes: Cell::new(0) };
+    let err = storage::write_with_retry(&storage, "- [ ] Task\n").unwrap_err();
+    assert!(matches!(err.downcast_ref::<DataError>(), Some(DataError::PermissionDenied { .. })));
+    assert_eq!(storage.writes.get(), 1);
+}
@@ -0,0 +1,22 @@
+#!/bin/bash
+set -e
+
+# Without a display the window tests need a Broadway server to draw into.
+if [ -z "$WAYLAND_DISPLAY" ] && [ -z "$DISPLAY" ] && [ -z "$BROADWAY_DISPLAY" ]; then
+    if ! command -v gtk4-broadwayd >/dev/null; then
+        echo "No display and no gtk4-broadwayd; install GTK 4's Broadway backend to run the window tests." >&2
+        exit 1
+    fi
+    echo "Starting Broadway server..."
+    gtk4-broadwayd :5 >/dev/null 2>&1 &
+    BROADWAY_PID=$!
+    trap 'kill $BROADWAY_PID 2>/dev/null' EXIT
+    sleep 1
+    if ! kill -0 "$BROADWAY_PID" 2>/dev/null; then
+        echo "gtk4-broadwayd did not start, is display :5 taken?" >&2
+        exit 1
+    fi
+    export GDK_BACKEND=broadway BROADWAY_DISPLAY=:5
+fi
+
+cargo test --workspace "$@"
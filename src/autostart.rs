@@ -0,0 +1,101 @@
+//! Starting the app in service mode at login, so reminders fire without the
+//! window having been opened. Outside Flatpak this is an XDG autostart entry;
+//! inside the sandbox the Background portal writes it for us.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use gtk::gio;
+use gtk::gio::prelude::*;
+use gtk::glib;
+use reinschrift_core::i18n::t;
+
+use crate::sandbox::in_flatpak;
+
+const DESKTOP_FILE: &str = "me.dumke.Reinschrift.desktop";
+/// Command line passed to the portal; matches `command:` in the Flatpak manifest.
+const FLATPAK_COMMAND: &str = "reinschrift";
+
+fn desktop_entry_path() -> PathBuf {
+    let mut path = glib::user_config_dir();
+    path.push("autostart");
+    path.push(DESKTOP_FILE);
+    path
+}
+
+fn desktop_entry(exe: &Path) -> String {
+    format!(
+        "[Desktop Entry]
+Type=Application
+Name={}
+Exec=\"{}\" --service
+Icon=me.dumke.Reinschrift
+NoDisplay=true
+X-GNOME-Autostart-enabled=true
+",
+        t("app_title"),
+        exe.display(),
+    )
+}
+
+/// Installs or removes the login autostart of `reinschrift --service`.
+pub fn set_enabled(enabled: bool) -> Result<()> {
+    if in_flatpak() {
+        request_background(enabled);
+        return Ok(());
+    }
+
+    let path = desktop_entry_path();
+    if !enabled {
+        if path.exists() {
+            fs::remove_file(&path).with_context(|| t("write_error").replace("{}", &path.display().to_string()))?;
+        }
+        return Ok(());
+    }
+
+    let exe = std::env::current_exe()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, desktop_entry(&exe)).with_context(|| t("write_error").replace("{}", &path.display().to_string()))?;
+    tracing::info!(path = %path.display(), "autostart entry installed");
+    Ok(())
+}
+
+/// Asks `org.freedesktop.portal.Background` to (un)register the autostart.
+/// The portal answers asynchronously, so failures are only logged.
+fn request_background(enabled: bool) {
+    let connection = match gio::bus_get_sync(gio::BusType::Session, gio::Cancellable::NONE) {
+        Ok(connection) => connection,
+        Err(err) => {
+            tracing::warn!("No session bus for the Background portal: {err}");
+            return;
+        }
+    };
+
+    let options = glib::VariantDict::new(None);
+    options.insert_value("reason", &t("autostart_reason").to_variant());
+    options.insert_value("autostart", &enabled.to_variant());
+    options.insert_value(
+        "commandline",
+        &vec![FLATPAK_COMMAND.to_string(), "--service".to_string()].to_variant(),
+    );
+    let parameters = glib::Variant::tuple_from_iter([String::new().to_variant(), options.end()]);
+
+    connection.call(
+        Some("org.freedesktop.portal.Desktop"),
+        "/org/freedesktop/portal/desktop",
+        "org.freedesktop.portal.Background",
+        "RequestBackground",
+        Some(&parameters),
+        None,
+        gio::DBusCallFlags::NONE,
+        -1,
+        gio::Cancellable::NONE,
+        move |result| match result {
+            Ok(_) => tracing::info!(enabled, "Background portal request sent"),
+            Err(err) => tracing::warn!("Background portal request failed: {err}"),
+        },
+    );
+}
@@ -0,0 +1,316 @@
+use anyhow::{bail, Result};
+use reinschrift_core::i18n::LANGUAGES;
+
+const BIN_NAME: &str = "reinschrift";
+const SHELLS: [&str; 3] = ["bash", "zsh", "fish"];
+/// Keys of [`reinschrift_core::starter::StarterTemplate`].
+const TEMPLATES: [&str; 3] = ["simple", "gtd", "weekly"];
+const VIEWS: [&str; 9] = ["today", "list", "columns", "contexts", "focus", "matrix", "graph", "timeline", "week"];
+
+/// What kind of value follows a flag, used to drive shell completion.
+enum ValueKind {
+    None,
+    File,
+    /// Free-form value without completion candidates.
+    Text(&'static str),
+    Choice(&'static [&'static str]),
+    /// The `^marker` of a task, read from `list --json` while completing.
+    Task,
+}
+
+struct Flag {
+    name: &'static str,
+    help: &'static str,
+    value: ValueKind,
+}
+
+/// A command such as `reinschrift add`. All but `show` run without a window.
+struct Subcommand {
+    name: &'static str,
+    help: &'static str,
+    flags: &'static [Flag],
+    /// What its arguments other than flags are.
+    arguments: ValueKind,
+}
+
+/// Every command line flag understood by `main`. Keep in sync when adding new ones.
+const FLAGS: &[Flag] = &[
+    Flag { name: "--database", help: "Todo database file", value: ValueKind::File },
+    Flag { name: "--language", help: "User interface language", value: ValueKind::Choice(&LANGUAGES) },
+    Flag { name: "--new-task", help: "Open the window ready to add a task", value: ValueKind::None },
+    Flag { name: "--view", help: "Open the window in the given view", value: ValueKind::Choice(&VIEWS) },
+    Flag { name: "--serve", help: "Serve a local JSON API on ADDRESS:PORT instead of opening a window", value: ValueKind::Text("address") },
+    Flag { name: "--native-messaging", help: "Serve a browser extension over stdin and stdout instead of opening a window", value: ValueKind::None },
+    Flag { name: "--digest", help: "Mail the tasks due today or overdue, or write them to FILE", value: ValueKind::File },
+    Flag { name: "--service", help: "Start hidden and keep running for reminders", value: ValueKind::None },
+    Flag { name: "--demo", help: "Try the app with sample tasks kept in memory", value: ValueKind::None },
+    Flag { name: "--verbose", help: "Log debug output", value: ValueKind::None },
+    Flag { name: "--profile-startup", help: "Log how long each startup stage takes", value: ValueKind::None },
+    Flag { name: "--i18n-debug", help: "Mark translated strings and report missing translations on exit", value: ValueKind::None },
+    Flag { name: "--log-file", help: "Also write logs to a daily rotated file", value: ValueKind::File },
+    Flag { name: "--generate-completions", help: "Print a shell completion script", value: ValueKind::Choice(&SHELLS) },
+];
+
+/// Every subcommand handled by [`crate::commands`]. Keep in sync as well.
+const SUBCOMMANDS: &[Subcommand] = &[
+    Subcommand {
+        name: "add",
+        help: "Add a task, or one per line of stdin",
+        flags: &[
+            Flag { name: "--stdin", help: "Read tasks from stdin, as text or JSON lines", value: ValueKind::None },
+            Flag { name: "--section", help: "Add below this ### heading", value: ValueKind::Text("section") },
+        ],
+        arguments: ValueKind::Text("text"),
+    },
+    Subcommand {
+        name: "check",
+        help: "Validate the database or the given files",
+        flags: &[Flag { name: "--json", help: "Print a JSON array of findings", value: ValueKind::None }],
+        arguments: ValueKind::File,
+    },
+    Subcommand {
+        name: "init",
+        help: "Create a new database with example sections",
+        flags: &[Flag { name: "--template", help: "Layout of the new file", value: ValueKind::Choice(&TEMPLATES) }],
+        arguments: ValueKind::File,
+    },
+    Subcommand {
+        name: "list",
+        help: "Print the open tasks",
+        flags: &[
+            Flag { name: "--all", help: "Include completed tasks", value: ValueKind::None },
+            Flag { name: "--json", help: "Print a JSON array of tasks", value: ValueKind::None },
+            Flag { name: "--taskwarrior", help: "Print the tasks as Taskwarrior export JSON", value: ValueKind::None },
+        ],
+        arguments: ValueKind::None,
+    },
+    Subcommand {
+        name: "show",
+        help: "Show the task with this marker or title in the window",
+        flags: &[],
+        arguments: ValueKind::Task,
+    },
+    Subcommand {
+        name: "watch",
+        help: "Print a line per task change until interrupted",
+        flags: &[
+            Flag { name: "--json", help: "Print each change as a JSON object", value: ValueKind::None },
+            Flag { name: "--interval", help: "Seconds between checks (default 2)", value: ValueKind::Text("seconds") },
+        ],
+        arguments: ValueKind::None,
+    },
+];
+
+pub fn is_subcommand(name: &str) -> bool {
+    SUBCOMMANDS.iter().any(|command| command.name == name)
+}
+
+pub fn generate_completions(shell: &str) -> Result<String> {
+    match shell {
+        "bash" => Ok(bash_completions(FLAGS, SUBCOMMANDS)),
+        "zsh" => Ok(zsh_completions(FLAGS, SUBCOMMANDS)),
+        "fish" => Ok(fish_completions(FLAGS, SUBCOMMANDS)),
+        other => bail!("Unsupported shell '{other}', expected one of: {}", SHELLS.join(", ")),
+    }
+}
+
+/// Shell pipeline printing the markers of the open tasks, one per line, for
+/// the completion functions. `list --json` prints one `"marker": "…"` per line.
+fn markers_command() -> String {
+    format!(r#"{BIN_NAME} list --json 2>/dev/null | sed -n 's/.*"marker": *"\([^"]*\)".*/\1/p'"#)
+}
+
+fn bash_reply(flag: &Flag) -> Option<String> {
+    match &flag.value {
+        ValueKind::None => None,
+        ValueKind::File => Some("compgen -f -- \"$cur\"".to_string()),
+        ValueKind::Text(_) => Some(String::new()),
+        ValueKind::Choice(values) => Some(format!("compgen -W \"{}\" -- \"$cur\"", values.join(" "))),
+        ValueKind::Task => Some(format!("compgen -W \"$(_{BIN_NAME}_markers)\" -- \"$cur\"")),
+    }
+}
+
+fn bash_completions(flags: &[Flag], subcommands: &[Subcommand]) -> String {
+    let mut cases = String::new();
+    let all_flags = flags.iter().chain(subcommands.iter().flat_map(|command| command.flags));
+    for flag in all_flags {
+        let Some(reply) = bash_reply(flag) else {
+            continue;
+        };
+        cases.push_str(&format!("        {})\n            COMPREPLY=( $({reply}) )\n            return\n            ;;\n", flag.name));
+    }
+
+    let mut command_cases = String::new();
+    for command in subcommands {
+        let names: Vec<&str> = command.flags.iter().map(|f| f.name).collect();
+        let arguments = match &command.arguments {
+            ValueKind::File => " $(compgen -f -- \"$cur\")".to_string(),
+            ValueKind::Task => format!(" $(compgen -W \"$(_{BIN_NAME}_markers)\" -- \"$cur\")"),
+            ValueKind::Choice(values) => format!(" $(compgen -W \"{}\" -- \"$cur\")", values.join(" ")),
+            ValueKind::None | ValueKind::Text(_) => String::new(),
+        };
+        command_cases.push_str(&format!(
+            "            {})\n                COMPREPLY=( $(compgen -W \"{}\" -- \"$cur\"){arguments} )\n                return\n                ;;\n",
+            command.name,
+            names.join(" "),
+        ));
+    }
+
+    let names: Vec<&str> = flags
+        .iter()
+        .map(|f| f.name)
+        .chain(subcommands.iter().map(|c| c.name))
+        .collect();
+
+    format!(
+        "_{bin}_markers() {{
+    {markers}
+}}
+
+_{bin}() {{
+    local cur prev
+    cur=\"${{COMP_WORDS[COMP_CWORD]}}\"
+    prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"
+    case \"$prev\" in
+{cases}    esac
+    if [[ $COMP_CWORD -gt 1 ]]; then
+        case \"${{COMP_WORDS[1]}}\" in
+{command_cases}        esac
+    fi
+    COMPREPLY=( $(compgen -W \"{names}\" -- \"$cur\") )
+}}
+complete -o filenames -F _{bin} {bin}
+",
+        bin = BIN_NAME,
+        markers = markers_command(),
+        names = names.join(" "),
+    )
+}
+
+/// `text` for the `[description]` of a zsh `_arguments` or `_values` spec
+/// inside single quotes.
+fn zsh_description(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:")
+        .replace('\'', "'\\''")
+}
+
+fn zsh_spec(flag: &Flag) -> String {
+    let action = match &flag.value {
+        ValueKind::None => String::new(),
+        ValueKind::File => ":file:_files".to_string(),
+        ValueKind::Text(name) => format!(":{name}: "),
+        ValueKind::Choice(values) => format!(":value:({})", values.join(" ")),
+        ValueKind::Task => format!(":task:_{BIN_NAME}_markers"),
+    };
+    format!("'{}[{}]{}'", flag.name, zsh_description(flag.help), action)
+}
+
+/// The `_arguments` spec of the non-flag arguments of a subcommand.
+fn zsh_arguments(kind: &ValueKind) -> Option<String> {
+    match kind {
+        ValueKind::None => None,
+        ValueKind::File => Some("'*:file:_files'".to_string()),
+        ValueKind::Text(name) => Some(format!("'*:{name}: '")),
+        ValueKind::Choice(values) => Some(format!("'1:value:({})'", values.join(" "))),
+        ValueKind::Task => Some(format!("'1:task:_{BIN_NAME}_markers'")),
+    }
+}
+
+fn zsh_completions(flags: &[Flag], subcommands: &[Subcommand]) -> String {
+    let mut specs: Vec<String> = flags.iter().map(|flag| format!("    {}", zsh_spec(flag))).collect();
+    specs.push("    '1: :->command'".to_string());
+    specs.push("    '*:: :->args'".to_string());
+
+    let commands: Vec<String> = subcommands
+        .iter()
+        .map(|command| format!("'{}[{}]'", command.name, zsh_description(command.help)))
+        .collect();
+    let mut command_cases = String::new();
+    for command in subcommands {
+        let flags: Vec<String> = command.flags.iter().map(zsh_spec).chain(zsh_arguments(&command.arguments)).collect();
+        command_cases.push_str(&format!("                {}) _arguments {} ;;\n", command.name, flags.join(" ")));
+    }
+
+    format!(
+        "#compdef {bin}
+
+_{bin}_markers() {{
+    local -a markers
+    markers=(${{(f)\"$({markers})\"}})
+    _describe 'task' markers
+}}
+
+_{bin}() {{
+    local line state
+    _arguments -C \\
+{specs}
+    case $state in
+        command)
+            _values 'command' {commands}
+            ;;
+        args)
+            case $line[1] in
+{command_cases}            esac
+            ;;
+    esac
+}}
+
+_{bin} \"$@\"
+",
+        bin = BIN_NAME,
+        markers = markers_command(),
+        specs = specs.join(" \\\n"),
+        commands = commands.join(" "),
+    )
+}
+
+/// `text` inside fish single quotes.
+fn fish_quoted(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+fn fish_line(flag: &Flag, condition: &str) -> String {
+    let long = flag.name.trim_start_matches("--");
+    let value = match &flag.value {
+        ValueKind::None => String::new(),
+        ValueKind::File => "-r -F ".to_string(),
+        ValueKind::Text(_) => "-x ".to_string(),
+        ValueKind::Choice(values) => format!("-x -a '{}' ", values.join(" ")),
+        ValueKind::Task => format!("-x -a '(__{BIN_NAME}_markers)' "),
+    };
+    format!("complete -c {BIN_NAME}{condition} -l {long} {value}-d {}\n", fish_quoted(flag.help))
+}
+
+fn fish_completions(flags: &[Flag], subcommands: &[Subcommand]) -> String {
+    let mut out = format!("function __{BIN_NAME}_markers\n    {}\nend\n", markers_command());
+    for flag in flags {
+        out.push_str(&fish_line(flag, ""));
+    }
+    for command in subcommands {
+        out.push_str(&format!(
+            "complete -c {BIN_NAME} -n __fish_use_subcommand -f -a {} -d {}\n",
+            command.name,
+            fish_quoted(command.help)
+        ));
+        let condition = format!(" -n '__fish_seen_subcommand_from {}'", command.name);
+        for flag in command.flags {
+            out.push_str(&fish_line(flag, &condition));
+        }
+        let arguments = match &command.arguments {
+            ValueKind::File => Some("-F".to_string()),
+            ValueKind::Task => Some(format!("-f -a '(__{BIN_NAME}_markers)'")),
+            ValueKind::Choice(values) => Some(format!("-f -a '{}'", values.join(" "))),
+            ValueKind::None | ValueKind::Text(_) => None,
+        };
+        if let Some(arguments) = arguments {
+            out.push_str(&format!("complete -c {BIN_NAME}{condition} {arguments}\n"));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,59 @@
+//! Completion scripts with help texts that need quoting. Each script is also
+//! run through `<shell> -n` when that shell is installed.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use super::*;
+
+const QUOTED_FLAGS: &[Flag] = &[
+    Flag { name: "--digest", help: "Mail today's tasks [due]: or FILE", value: ValueKind::File },
+    Flag { name: "--view", help: "Open the window's view", value: ValueKind::Choice(&VIEWS) },
+];
+
+const QUOTED_SUBCOMMANDS: &[Subcommand] = &[Subcommand {
+    name: "show",
+    help: "Show a task's row",
+    flags: &[Flag { name: "--json", help: "Print the task's JSON", value: ValueKind::None }],
+    arguments: ValueKind::Task,
+}];
+
+/// Whether `shell -n` accepts `script`, or `None` without that shell.
+fn parses(shell: &str, script: &str) -> Option<bool> {
+    let mut child = Command::new(shell)
+        .arg("-n")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    child.stdin.take()?.write_all(script.as_bytes()).ok()?;
+    Some(child.wait().ok()?.success())
+}
+
+#[test]
+fn apostrophes_are_escaped_for_every_shell() {
+    let zsh = zsh_completions(QUOTED_FLAGS, QUOTED_SUBCOMMANDS);
+    assert!(zsh.contains(r"'--digest[Mail today'\''s tasks \[due\]\: or FILE]:file:_files'"), "{zsh}");
+    assert!(zsh.contains(r"'show[Show a task'\''s row]'"), "{zsh}");
+    let fish = fish_completions(QUOTED_FLAGS, QUOTED_SUBCOMMANDS);
+    assert!(fish.contains(r"-d 'Mail today\'s tasks [due]: or FILE'"), "{fish}");
+    assert!(fish.contains(r"-d 'Show a task\'s row'"), "{fish}");
+
+    let scripts = [
+        ("bash", bash_completions(QUOTED_FLAGS, QUOTED_SUBCOMMANDS)),
+        ("zsh", zsh),
+        ("fish", fish),
+    ];
+    for (shell, script) in scripts {
+        assert_ne!(parses(shell, &script), Some(false), "{shell} rejects:\n{script}");
+    }
+}
+
+#[test]
+fn generated_scripts_parse() {
+    for shell in SHELLS {
+        let script = generate_completions(shell).unwrap();
+        assert_ne!(parses(shell, &script), Some(false), "{shell} rejects:\n{script}");
+    }
+}
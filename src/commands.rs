@@ -0,0 +1,372 @@
+//! Subcommands that work on the database without opening a window.
+
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::process::{Command, ExitCode};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use chrono::Local;
+use gtk::gio;
+use gtk::glib;
+use reinschrift_core::check::{self as checks, Severity};
+use reinschrift_core::config;
+use reinschrift_core::data::{self, Name, TodoItem, TodoKey};
+use reinschrift_core::export;
+use reinschrift_core::i18n::t;
+use reinschrift_core::starter::{self, StarterTemplate};
+
+use crate::sandbox;
+
+/// Runs `args[0]` as subcommand with the remaining arguments and returns
+/// the status the process should exit with.
+pub fn run(args: &[String]) -> Result<ExitCode> {
+    let Some((name, rest)) = args.split_first() else {
+        bail!("No command given");
+    };
+    match name.as_str() {
+        "add" => add(rest)?,
+        "check" => return check(rest),
+        "init" => init(rest)?,
+        "list" => list(rest)?,
+        "watch" => watch(rest)?,
+        other => bail!("Unknown command '{other}'"),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// `add [TEXT…] [--stdin] [--section NAME]`: every stdin line is a task,
+/// either as text with inline tokens or as a JSON object in the format of
+/// [`TodoItem`]. All tasks are written at once.
+fn add(args: &[String]) -> Result<()> {
+    let mut from_stdin = false;
+    let mut section = None;
+    let mut words = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--stdin" => from_stdin = true,
+            "--section" => section = Some(args.next().context("--section requires a name")?.clone()),
+            other if other.starts_with("--") => bail!("Unknown option '{other}' for add"),
+            _ => words.push(arg.as_str()),
+        }
+    }
+
+    let mut items = Vec::new();
+    if !words.is_empty() {
+        items.push(task_from_text(&words.join(" "))?);
+    }
+    if from_stdin {
+        for (number, line) in io::stdin().lock().lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let item = if line.starts_with('{') {
+                let mut item = serde_json::from_str::<TodoItem>(line)
+                    .with_context(|| format!("Line {} of stdin is not a valid task object", number + 1))?;
+                // A `^marker` from `list --json` would name two tasks at once.
+                item.key = TodoKey::default();
+                item
+            } else {
+                task_from_text(line)?
+            };
+            items.push(item);
+        }
+    }
+    if items.is_empty() {
+        bail!(t("title_empty_error"));
+    }
+
+    if let Some(section) = &section {
+        for item in items.iter_mut().filter(|item| item.section.trim().is_empty()) {
+            item.section = Name::new(section);
+        }
+    }
+    let today = Local::now().date_naive();
+    for item in &mut items {
+        item.created.get_or_insert(today);
+    }
+    println!("{}", data::import_todos(&items)?.summary());
+    Ok(())
+}
+
+/// `check [FILE…] [--json]`: validates each file, or the configured
+/// database, and prints one `FILE:LINE: severity[code]: message` line per
+/// finding. Exits with 1 if there are errors and 2 if a file cannot be
+/// read, so it can run as a pre-commit hook; warnings alone pass.
+fn check(args: &[String]) -> Result<ExitCode> {
+    let mut json = false;
+    let mut files = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            other if other.starts_with("--") => bail!("Unknown option '{other}' for check"),
+            file => files.push(file),
+        }
+    }
+
+    let locations: Vec<Option<&str>> = if files.is_empty() {
+        vec![None]
+    } else {
+        files.into_iter().map(Some).collect()
+    };
+    let mut failed = false;
+    let mut unreadable = false;
+    let mut findings = Vec::new();
+    let mut out = io::stdout().lock();
+    for location in locations {
+        if let Some(file) = location {
+            data::set_todo_path(std::path::absolute(file).unwrap_or_else(|_| file.into()));
+        }
+        let database = location.map(str::to_string).unwrap_or_else(data::database_location);
+        let diagnostics = match checks::check_database() {
+            Ok(diagnostics) => diagnostics,
+            Err(err) => {
+                eprintln!("{database}: {err:#}");
+                unreadable = true;
+                continue;
+            }
+        };
+        failed |= diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error);
+        for diagnostic in diagnostics {
+            let file = diagnostic.file.as_ref().map(|file| file.display().to_string()).unwrap_or_else(|| database.clone());
+            if json {
+                findings.push(serde_json::json!({
+                    "file": file,
+                    "line": diagnostic.line,
+                    "severity": diagnostic.severity,
+                    "code": diagnostic.code,
+                    "message": diagnostic.message,
+                }));
+                continue;
+            }
+            let severity = serde_json::to_value(diagnostic.severity)?;
+            writeln!(
+                out,
+                "{file}:{}: {}[{}]: {}",
+                diagnostic.line,
+                severity.as_str().unwrap_or_default(),
+                diagnostic.code,
+                diagnostic.message
+            )?;
+        }
+    }
+    if json {
+        writeln!(out, "{}", serde_json::to_string_pretty(&findings)?)?;
+    } else if !failed && !unreadable {
+        eprintln!("{}", t("check_no_problems"));
+    }
+    out.flush()?;
+    Ok(match (unreadable, failed) {
+        (true, _) => ExitCode::from(2),
+        (false, true) => ExitCode::FAILURE,
+        (false, false) => ExitCode::SUCCESS,
+    })
+}
+
+/// `init [PATH] [--template gtd|simple|weekly]`: creates a database laid
+/// out as the template, `todo.md` in the current folder by default or in
+/// PATH if it is a folder. An existing file is never overwritten.
+fn init(args: &[String]) -> Result<()> {
+    let mut template = StarterTemplate::Simple;
+    let mut path = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--template" => {
+                let key = args.next().context("--template requires a name")?;
+                let Some(chosen) = StarterTemplate::from_key(key) else {
+                    let names: Vec<&str> = StarterTemplate::ALL.iter().map(|template| template.as_key()).collect();
+                    bail!("Unknown template '{key}', expected one of: {}", names.join(", "));
+                };
+                template = chosen;
+            }
+            other if other.starts_with("--") => bail!("Unknown option '{other}' for init"),
+            other if path.is_none() => path = Some(std::path::PathBuf::from(other)),
+            other => bail!("Unexpected argument '{other}' for init"),
+        }
+    }
+
+    let mut path = path.unwrap_or_else(|| "todo.md".into());
+    if path.is_dir() {
+        path.push("todo.md");
+    }
+    let path = std::path::absolute(&path).unwrap_or(path);
+    starter::create(&path, template)?;
+    println!("{}", t("init_created").replace("{}", &path.display().to_string()));
+    Ok(())
+}
+
+/// `list [--all] [--json | --taskwarrior]`: open tasks in file order, or all
+/// with `--all`. `--json` prints them as one array of [`TodoItem`] objects,
+/// `--taskwarrior` in the format of `task export` for piping to `task import`.
+fn list(args: &[String]) -> Result<()> {
+    let mut json = false;
+    let mut taskwarrior = false;
+    let mut all = false;
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--taskwarrior" => taskwarrior = true,
+            "--all" => all = true,
+            other => bail!("Unknown option '{other}' for list"),
+        }
+    }
+
+    let mut items: Vec<TodoItem> = data::load_todos()?
+        .into_iter()
+        .filter(|item| all || !item.done)
+        .collect();
+    // Write errors (e.g. a closed pipe to `head`) end the command instead of panicking.
+    let mut out = io::stdout().lock();
+    if json {
+        writeln!(out, "{}", serde_json::to_string_pretty(&items)?)?;
+        return Ok(());
+    }
+    if taskwarrior {
+        // UUIDs come from the markers, so `task import` recognizes the tasks next time.
+        data::ensure_markers(&mut items)?;
+        writeln!(out, "{}", export::to_taskwarrior(&items))?;
+        return Ok(());
+    }
+    for item in &items {
+        writeln!(out, "{}", format_list_line(item))?;
+    }
+    Ok(())
+}
+
+fn format_list_line(item: &TodoItem) -> String {
+    let checkbox = if item.done { "[x]" } else { "[ ]" };
+    let mut parts = vec![format!("{checkbox} {}", item.title)];
+    if let Some(project) = &item.project {
+        parts.push(format!("+{project}"));
+    }
+    if let Some(context) = &item.context {
+        parts.push(format!("@{context}"));
+    }
+    if let Some(priority) = item.priority {
+        parts.push(format!("pri:{priority}"));
+    }
+    if let Some(due) = item.due {
+        parts.push(config::format_date(due));
+    }
+    parts.join("  ")
+}
+
+/// `watch [--json] [--interval SECONDS]`: polls the database and prints one
+/// line per task change until interrupted. With `--json` every line is a
+/// [`data::TodoChange`] object, e.g. `{"event":"completed","task":{…}}`.
+fn watch(args: &[String]) -> Result<()> {
+    let mut json = false;
+    let mut interval = 2;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--interval" => {
+                interval = args
+                    .next()
+                    .and_then(|value| value.parse::<u64>().ok())
+                    .filter(|secs| *secs > 0)
+                    .context("--interval requires a number of seconds")?;
+            }
+            other => bail!("Unknown option '{other}' for watch"),
+        }
+    }
+
+    let mut items = data::load_todos()?;
+    let mut fingerprint = data::get_fingerprint().ok();
+    loop {
+        std::thread::sleep(Duration::from_secs(interval));
+        let current = match data::get_fingerprint() {
+            Ok(current) => current,
+            Err(err) => {
+                tracing::warn!("watch: {err}");
+                continue;
+            }
+        };
+        if fingerprint.as_ref() == Some(&current) {
+            continue;
+        }
+        let reloaded = match data::load_todos() {
+            Ok(reloaded) => reloaded,
+            Err(err) => {
+                tracing::warn!("watch: {err}");
+                continue;
+            }
+        };
+        let mut out = io::stdout().lock();
+        for change in data::diff_todos(&items, &reloaded) {
+            if json {
+                writeln!(out, "{}", serde_json::to_string(&change)?)?;
+            } else {
+                let event = serde_json::to_value(change.event)?;
+                writeln!(out, "{}: {}", event.as_str().unwrap_or_default(), change.task.title)?;
+            }
+        }
+        out.flush()?;
+        items = reloaded;
+        fingerprint = Some(current);
+    }
+}
+
+/// `--digest [FILE]`: today's and overdue tasks as a mail to write with
+/// `xdg-email`, or saved to `FILE` instead; HTML for `.html` files, text
+/// otherwise.
+pub fn digest(output: Option<&Path>) -> Result<()> {
+    let items = data::load_todos()?;
+    let today = Local::now().date_naive();
+    let Some(path) = output else {
+        return email_digest(&export::digest_subject(today), &export::digest_text(&items, today));
+    };
+    let html = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm"));
+    let content = if html {
+        export::digest_html(&items, today)
+    } else {
+        export::digest_text(&items, today)
+    };
+    fs::write(path, content).with_context(|| t("write_error").replace("{}", &path.display().to_string()))?;
+    println!("{}", t("exported_to").replace("{}", &path.display().to_string()));
+    Ok(())
+}
+
+/// Opens a new mail with `subject` and `body` in the default mail program.
+/// `xdg-email` is not part of the Flatpak runtime, so there a `mailto:` link
+/// goes through the OpenURI portal instead.
+pub fn email_digest(subject: &str, body: &str) -> Result<()> {
+    if sandbox::in_flatpak() {
+        return open_mail(subject, body);
+    }
+    let status = Command::new("xdg-email")
+        .args(["--utf8", "--subject", subject, "--body", body])
+        .status()
+        .with_context(|| t("digest_email_error"))?;
+    if !status.success() {
+        bail!("{} ({status})", t("digest_email_error"));
+    }
+    Ok(())
+}
+
+/// Hands a `mailto:` link for the new mail to the default handler without
+/// waiting for the mail program, so the window can call it too.
+pub fn open_mail(subject: &str, body: &str) -> Result<()> {
+    let uri = format!(
+        "mailto:?subject={}&body={}",
+        glib::Uri::escape_string(subject, None, false),
+        glib::Uri::escape_string(body, None, false),
+    );
+    gio::AppInfo::launch_default_for_uri(&uri, gio::AppLaunchContext::NONE).with_context(|| t("digest_email_error"))
+}
+
+/// Plain text gets the same defaults as the entry field in the window:
+/// open and due today unless it names a date itself.
+fn task_from_text(text: &str) -> Result<TodoItem> {
+    let mut item = data::parse_task_text(text).with_context(|| t("title_empty_error"))?;
+    item.due.get_or_insert(Local::now().date_naive());
+    Ok(item)
+}
@@ -0,0 +1,58 @@
+use std::path::Path;
+
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{InitError, RollingFileAppender, Rotation};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, EnvFilter};
+
+/// Rotated log files kept next to `--log-file`; older days are deleted.
+const KEPT_LOG_FILES: usize = 7;
+
+/// Installs the global subscriber. Logs go to stderr and, with `log_file`,
+/// additionally to a daily rotated file next to it (`<name>.YYYY-MM-DD`).
+/// Stderr only shows warnings unless `verbose` is set, so commands stay quiet
+/// for scripts; the file also gets info. `RUST_LOG` overrides both levels.
+///
+/// The returned guard flushes the file writer and must live until exit.
+pub fn init(verbose: bool, log_file: Option<&Path>) -> Option<WorkerGuard> {
+    let filter = |default_level: &str| {
+        let level = if verbose { "debug" } else { default_level };
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(level))
+    };
+
+    let stderr_layer = fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_target(false)
+        .with_filter(filter("warn"));
+
+    let (file_layer, guard) = match log_file.map(rolling_appender) {
+        Some(Ok(appender)) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            (Some(fmt::layer().with_writer(writer).with_ansi(false).with_filter(filter("info"))), Some(guard))
+        }
+        Some(Err(err)) => {
+            eprintln!("Cannot open the log file: {err}");
+            (None, None)
+        }
+        None => (None, None),
+    };
+
+    tracing_subscriber::registry()
+        .with(stderr_layer)
+        .with(file_layer)
+        .init();
+
+    guard
+}
+
+fn rolling_appender(path: &Path) -> Result<RollingFileAppender, InitError> {
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_else(|| "reinschrift.log".into());
+    // Created up front; the appender warns about a missing one when pruning.
+    let _ = std::fs::create_dir_all(dir);
+    RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(name)
+        .max_log_files(KEPT_LOG_FILES)
+        .build(dir)
+}
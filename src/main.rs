@@ -1,41 +1,237 @@
-mod data;
+mod autostart;
+mod cli;
+mod commands;
+mod logging;
+mod native_messaging;
+mod profile;
+mod sandbox;
+mod serve;
+mod settings;
+mod spelling;
+mod todo_object;
+mod todo_row;
 mod ui;
-mod i18n;
+
+use std::process::ExitCode;
 
 use anyhow::{bail, Context, Result};
 use adw::prelude::*;
-use gtk::glib;
+use gtk::{gio, glib};
+use reinschrift_core::{config, data, i18n};
 use i18n::t;
 
 const APP_ID: &str = "me.dumke.Reinschrift";
 
-fn main() -> Result<()> {
+fn main() -> Result<ExitCode> {
+    // Defaults first, so that every flag below can override them.
+    let config_error = config::load().err();
+    let config = config::get();
+
     let mut filtered_args: Vec<String> = std::env::args().collect();
+    if let Some(pos) = filtered_args.iter().position(|x| x == "--generate-completions") {
+        let Some(shell) = filtered_args.get(pos + 1) else {
+            bail!("--generate-completions requires a shell name (bash, zsh or fish)");
+        };
+        print!("{}", cli::generate_completions(shell)?);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some(pos) = filtered_args.iter().position(|x| x == "--profile-startup") {
+        filtered_args.remove(pos);
+        profile::enable();
+    }
+
+    let verbose = if let Some(pos) = filtered_args.iter().position(|x| x == "--verbose") {
+        filtered_args.remove(pos);
+        true
+    } else {
+        false
+    };
+
+    let service_mode = if let Some(pos) = filtered_args.iter().position(|x| x == "--service") {
+        filtered_args.remove(pos);
+        true
+    } else {
+        false
+    };
+
+    let mut log_file = None;
+    if let Some(pos) = filtered_args.iter().position(|x| x == "--log-file") {
+        filtered_args.remove(pos);
+        if pos < filtered_args.len() {
+            log_file = Some(std::path::PathBuf::from(filtered_args.remove(pos)));
+        }
+    }
+    let _log_guard = logging::init(verbose, log_file.as_deref());
+    if let Some(err) = config_error {
+        tracing::warn!("{err:#}");
+    }
+    // Kept until main returns, when it writes the report.
+    let _i18n_report = if let Some(pos) = filtered_args.iter().position(|x| x == "--i18n-debug") {
+        filtered_args.remove(pos);
+        Some(i18n::enable_debug())
+    } else {
+        None
+    };
+
+    // config.toml < environment < command line.
+    let mut database = config.database.map(|location| (location, "config.toml", ui::DatabaseOrigin::Configured));
+    if let Some(location) = env_override("REINSCHRIFT_DATABASE") {
+        database = Some((location, "REINSCHRIFT_DATABASE", ui::DatabaseOrigin::Configured));
+    }
     if let Some(pos) = filtered_args.iter().position(|x| x == "--database") {
         filtered_args.remove(pos);
         if pos < filtered_args.len() {
-            let db_path = filtered_args.remove(pos);
-            let absolute_path = std::fs::canonicalize(&db_path).unwrap_or_else(|_| std::path::PathBuf::from(db_path));
-            data::set_todo_path(absolute_path);
+            database = Some((filtered_args.remove(pos), "command line", ui::DatabaseOrigin::CommandLine));
         }
     }
+    if let Some((location, source, origin)) = database {
+        use_database(location, source);
+        ui::set_database_origin(origin);
+    }
 
+    if let Some(chain) = config.fallback_languages {
+        i18n::set_fallback_languages(chain);
+    }
+    let mut language = env_override("REINSCHRIFT_LANGUAGE").or(config.language);
     if let Some(pos) = filtered_args.iter().position(|x| x == "--language") {
         filtered_args.remove(pos);
         if pos < filtered_args.len() {
-            let lang = filtered_args.remove(pos);
-            i18n::set_language(lang);
+            language = Some(filtered_args.remove(pos));
         }
     }
+    if let Some(lang) = language {
+        i18n::set_language(lang);
+    }
 
-    gtk::glib::set_application_name(&t("app_title"));
-    adw::init().context(t("init_adw_error"))?;
+    // After `--language`, the sample tasks are written in it.
+    let demo = if let Some(pos) = filtered_args.iter().position(|x| x == "--demo") {
+        filtered_args.remove(pos);
+        reinschrift_core::demo::start()?;
+        true
+    } else {
+        false
+    };
 
-    let app = adw::Application::builder().application_id(APP_ID).build();
+    let native_messaging = if let Some(pos) = filtered_args.iter().position(|x| x == "--native-messaging") {
+        filtered_args.remove(pos);
+        true
+    } else {
+        native_messaging::is_browser_launch(&filtered_args)
+    };
+    if native_messaging {
+        ui::load_saved_backend();
+        return native_messaging::run().map(|()| ExitCode::SUCCESS);
+    }
+
+    // `show` needs the window, so unlike the other subcommands it goes through
+    // the startup action below instead of commands::run.
+    let mut show_task = None;
+    if filtered_args.get(1).is_some_and(|arg| arg == "show") {
+        let Some(id) = filtered_args.get(2).cloned() else {
+            bail!("show requires a task marker or title");
+        };
+        filtered_args.drain(1..3);
+        show_task = Some(id);
+    }
 
-    app.connect_activate(|app| {
-        if let Err(err) = ui::build_ui(app, false) {
-            eprintln!("{}: {err:?}", t("build_ui_error"));
+    if filtered_args.get(1).is_some_and(|arg| cli::is_subcommand(arg)) {
+        // `init` makes its own file; don't create the default one on the way.
+        if filtered_args[1] != "init" {
+            ui::load_saved_backend();
+        }
+        return commands::run(&filtered_args[1..]);
+    }
+
+    if let Some(pos) = filtered_args.iter().position(|x| x == "--digest") {
+        let output = filtered_args.get(pos + 1).filter(|arg| !arg.starts_with("--")).map(std::path::PathBuf::from);
+        ui::load_saved_backend();
+        return commands::digest(output.as_deref()).map(|()| ExitCode::SUCCESS);
+    }
+
+    if let Some(pos) = filtered_args.iter().position(|x| x == "--serve") {
+        let Some(addr) = filtered_args.get(pos + 1) else {
+            bail!("--serve requires an address such as 127.0.0.1:8377");
+        };
+        ui::load_saved_backend();
+        return serve::run(addr).map(|()| ExitCode::SUCCESS);
+    }
+
+    // Desktop-file actions and dock menus start us with one of these; they are
+    // run as application actions, in the already running instance if any.
+    let mut startup_action: Option<(&str, Option<glib::Variant>)> = None;
+    if let Some(pos) = filtered_args.iter().position(|x| x == "--new-task") {
+        filtered_args.remove(pos);
+        startup_action = Some(("new-task", None));
+    }
+    if let Some(pos) = filtered_args.iter().position(|x| x == "--view") {
+        filtered_args.remove(pos);
+        if pos < filtered_args.len() {
+            startup_action = match filtered_args.remove(pos).as_str() {
+                "today" => Some(("show-today", None)),
+                view => Some(("show-view", Some(view.to_variant()))),
+            };
+        }
+    }
+
+    if let Some(id) = show_task {
+        startup_action = Some(("show-task", Some(id.to_variant())));
+    }
+
+    // The first lookup parses the catalog of the current language.
+    let title = profile::measure("translation loading", || t("app_title"));
+    gtk::glib::set_application_name(&title);
+    profile::measure("adw init", adw::init).context(t("init_adw_error"))?;
+
+    // A demo runs beside the real instance instead of being handed to it.
+    let mut flags = gio::ApplicationFlags::HANDLES_OPEN;
+    if demo {
+        flags |= gio::ApplicationFlags::NON_UNIQUE;
+    }
+    let app = adw::Application::builder().application_id(APP_ID).flags(flags).build();
+
+    if let Some((name, parameter)) = &startup_action {
+        app.register(gio::Cancellable::NONE)?;
+        if app.is_remote() {
+            app.activate_action(name, parameter.as_ref());
+            return Ok(ExitCode::SUCCESS);
+        }
+    }
+
+    let startup_action = std::cell::RefCell::new(startup_action);
+    app.connect_activate(move |app| {
+        // A running instance (e.g. the login service) only needs its window shown.
+        if let Some(window) = app.windows().first() {
+            window.present();
+        } else if let Err(err) = profile::measure("window setup", || ui::build_ui(app, false, service_mode)) {
+            tracing::error!("{}: {err:?}", t("build_ui_error"));
+            return;
+        }
+        if let Some((name, parameter)) = startup_action.borrow_mut().take() {
+            app.activate_action(name, parameter.as_ref());
+        }
+    });
+
+    // `reinschrift FILE`, "Open With", file associations and task links end
+    // up here, forwarded to the primary instance if one is already running.
+    app.connect_open(move |app, files, _hint| {
+        if let Some(link) = files.first().filter(|file| file.has_uri_scheme(data::TASK_URI_SCHEME)) {
+            if let Err(err) = ui::open_task_link(app, &link.uri(), service_mode) {
+                tracing::error!("{err:?}");
+            }
+            return;
+        }
+        let Some(file) = files.first() else {
+            return;
+        };
+        let Some(path) = file.path().filter(|_| file.has_uri_scheme("file")) else {
+            if let Err(err) = ui::open_location(app, &file.uri(), service_mode) {
+                tracing::error!("{err:?}");
+            }
+            return;
+        };
+        if let Err(err) = ui::open_database(app, path, service_mode) {
+            tracing::error!("{}: {err:?}", t("build_ui_error"));
         }
     });
 
@@ -44,5 +240,24 @@ fn main() -> Result<()> {
         bail!("{}: {:?}", t("app_exit_status"), status);
     }
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Points the data layer at `location`, a path or URI given in `source`.
+fn use_database(location: String, source: &str) {
+    if data::is_remote_uri(&location) {
+        tracing::info!(uri = %location, "using database location from {source}");
+        data::set_database(&location);
+    } else {
+        // No canonicalize: it fails on paths the sandbox can't see and would
+        // resolve Document portal paths we want to keep.
+        let absolute_path = std::path::absolute(&location).unwrap_or_else(|_| std::path::PathBuf::from(location));
+        tracing::info!(path = %absolute_path.display(), "using database from {source}");
+        data::set_todo_path(absolute_path);
+    }
+}
+
+/// The non-empty value of the environment variable `name`.
+fn env_override(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.trim().is_empty())
 }
@@ -0,0 +1,161 @@
+//! `--native-messaging`: the host side of the WebExtensions Native Messaging
+//! protocol of Chrome and Firefox, so a browser extension can add the open
+//! page as a task.
+//!
+//! The browser starts the host itself as named in a manifest, installed as
+//! `me.dumke.reinschrift.json` from `native-messaging/chrome.json` or
+//! `firefox.json`, and passes either the calling origin
+//! (`chrome-extension://…/`, Chrome) or the manifest path and extension ID
+//! (Firefox) instead of the flag. Each message, in both
+//! directions, is a JSON object preceded by its length as a 32-bit integer
+//! in native byte order. The host serves messages until the browser closes
+//! stdin; stdout carries nothing but replies.
+//!
+//! Messages:
+//! - `{"action": "ping"}` – answers with the version
+//! - `{"action": "add", "title": "...", "url": "...", "kind": "read-later"}`
+//!   – adds the page with its URL as reference. `kind` is `read-later`
+//!   (the default, due "sometime") or `follow-up` (due tomorrow); `due`,
+//!   `project`, `context` and `section` override what the kind sets.
+//!
+//! Replies are `{"ok": true, ...}` or `{"ok": false, "error": "..."}`.
+
+use std::io::{self, Read, Write};
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{Duration, Local, NaiveDate};
+use reinschrift_core::data::{self, Name, TodoItem};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// The largest reply browsers accept from a host.
+const MAX_REPLY: usize = 1024 * 1024;
+/// Limit for incoming messages, far above any page title and URL.
+const MAX_MESSAGE: usize = 4 * 1024 * 1024;
+
+/// Name of the host in the manifests, as extensions address it.
+pub const HOST_NAME: &str = "me.dumke.reinschrift";
+
+/// Whether the browser started us: Chrome passes the caller's origin,
+/// Firefox our manifest's path and the extension ID.
+pub fn is_browser_launch(args: &[String]) -> bool {
+    match args.get(1) {
+        Some(origin) if origin.starts_with("chrome-extension://") => true,
+        Some(manifest) => args.len() == 3 && manifest.ends_with(&format!("{HOST_NAME}.json")),
+        None => false,
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+enum Message {
+    Ping,
+    Add(NewPage),
+}
+
+#[derive(Deserialize)]
+struct NewPage {
+    #[serde(default)]
+    title: String,
+    url: Option<String>,
+    #[serde(default)]
+    kind: PageKind,
+    due: Option<NaiveDate>,
+    project: Option<String>,
+    context: Option<String>,
+    section: Option<String>,
+}
+
+#[derive(Copy, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum PageKind {
+    #[default]
+    ReadLater,
+    FollowUp,
+}
+
+pub fn run() -> Result<()> {
+    tracing::info!("serving native messaging on stdin");
+    let mut stdin = io::stdin().lock();
+    let mut stdout = io::stdout().lock();
+    while let Some(message) = read_message(&mut stdin)? {
+        let reply = match handle(message) {
+            Ok(reply) => reply,
+            Err(err) => {
+                tracing::warn!("native message failed: {err:#}");
+                json!({ "ok": false, "error": format!("{err:#}") })
+            }
+        };
+        write_message(&mut stdout, &reply)?;
+    }
+    Ok(())
+}
+
+/// The next message, `None` once the browser has closed the pipe.
+fn read_message(input: &mut impl Read) -> Result<Option<Vec<u8>>> {
+    let mut length = [0; 4];
+    match input.read_exact(&mut length) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err.into()),
+    }
+    let length = u32::from_ne_bytes(length) as usize;
+    if length > MAX_MESSAGE {
+        bail!("message of {length} bytes is too long");
+    }
+    let mut message = vec![0; length];
+    input.read_exact(&mut message)?;
+    Ok(Some(message))
+}
+
+fn write_message(output: &mut impl Write, reply: &Value) -> Result<()> {
+    let body = serde_json::to_vec(reply)?;
+    if body.len() > MAX_REPLY {
+        bail!("reply of {} bytes is too long for the browser", body.len());
+    }
+    output.write_all(&(body.len() as u32).to_ne_bytes())?;
+    output.write_all(&body)?;
+    output.flush()?;
+    Ok(())
+}
+
+fn handle(message: Vec<u8>) -> Result<Value> {
+    match serde_json::from_slice(&message)? {
+        Message::Ping => Ok(json!({ "ok": true, "version": env!("CARGO_PKG_VERSION") })),
+        Message::Add(page) => {
+            let item = page_task(page, Local::now().date_naive())?;
+            data::import_todos(std::slice::from_ref(&item))?;
+            Ok(json!({ "ok": true, "task": item }))
+        }
+    }
+}
+
+/// The task for `page`, titled after the page or else its URL.
+fn page_task(page: NewPage, today: NaiveDate) -> Result<TodoItem> {
+    // Brackets would end the `[[reference]]` early.
+    let url = page
+        .url
+        .map(|url| url.trim().replace('[', "%5B").replace(']', "%5D"))
+        .filter(|url| !url.is_empty());
+    let title = match page.title.split_whitespace().collect::<Vec<_>>().join(" ") {
+        title if !title.is_empty() => title,
+        _ => url.clone().ok_or_else(|| anyhow!("the page has neither title nor URL"))?,
+    };
+    let due = page.due.or(match page.kind {
+        PageKind::ReadLater => NaiveDate::from_ymd_opt(9999, 12, 31),
+        PageKind::FollowUp => Some(today + Duration::days(1)),
+    });
+    let name = |value: Option<String>| data::normalize_tag(&value?).map(Name::from);
+    // A line break would end the `### section` heading and start new lines.
+    let section: String = page.section.unwrap_or_default().chars().filter(|c| !c.is_control()).collect();
+    Ok(TodoItem {
+        title,
+        reference: url,
+        due,
+        project: name(page.project),
+        context: name(page.context),
+        section: section.trim().into(),
+        created: Some(today),
+        ..TodoItem::default()
+    })
+}
@@ -0,0 +1,63 @@
+//! `--profile-startup`: logs how long each stage of the startup takes.
+//!
+//! Stages are measured with [`measure`] and logged with the time since the
+//! process started, up to the first frame drawn by the main window.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use gtk::prelude::*;
+
+static STARTED: OnceLock<Instant> = OnceLock::new();
+
+pub fn enable() {
+    STARTED.get_or_init(Instant::now);
+}
+
+fn enabled() -> bool {
+    STARTED.get().is_some()
+}
+
+fn log(stage: &str, took: Option<std::time::Duration>) {
+    let Some(started) = STARTED.get() else {
+        return;
+    };
+    let at = started.elapsed().as_secs_f64() * 1000.0;
+    match took {
+        Some(took) => tracing::info!("startup: {stage} took {:.1} ms, done at {at:.1} ms", took.as_secs_f64() * 1000.0),
+        None => tracing::info!("startup: {stage} at {at:.1} ms"),
+    }
+}
+
+/// Runs `stage` and logs its duration when profiling.
+pub fn measure<T>(stage: &str, stage_fn: impl FnOnce() -> T) -> T {
+    if !enabled() {
+        return stage_fn();
+    }
+    let started = Instant::now();
+    let value = stage_fn();
+    log(stage, Some(started.elapsed()));
+    value
+}
+
+/// Logs when `window` has drawn its first frame.
+pub fn watch_first_frame(window: &impl IsA<gtk::Widget>) {
+    if !enabled() {
+        return;
+    }
+    window.connect_map(|window| {
+        let Some(clock) = window.frame_clock() else {
+            return;
+        };
+        let handler = Rc::new(RefCell::new(None));
+        let own_handler = Rc::clone(&handler);
+        *handler.borrow_mut() = Some(clock.connect_after_paint(move |clock| {
+            log("first frame", None);
+            if let Some(id) = own_handler.borrow_mut().take() {
+                clock.disconnect(id);
+            }
+        }));
+    });
+}
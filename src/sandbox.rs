@@ -0,0 +1,33 @@
+//! Living inside the Flatpak sandbox. Files the user picks are only visible
+//! through the Document portal, mounted at `$XDG_RUNTIME_DIR/doc/<id>/<name>`;
+//! the id stays valid across restarts while the mount point may not.
+
+use std::path::{Component, Path, PathBuf};
+
+use gtk::glib;
+
+pub fn in_flatpak() -> bool {
+    Path::new("/.flatpak-info").exists()
+}
+
+fn documents_root() -> PathBuf {
+    glib::user_runtime_dir().join("doc")
+}
+
+/// `<id>/<name>` of a file exported through the Document portal.
+pub fn document_id(path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(documents_root()).ok()?;
+    let mut parts = relative.components().map(|part| match part {
+        Component::Normal(name) => name.to_str(),
+        _ => None,
+    });
+    let (Some(Some(id)), Some(Some(name)), None) = (parts.next(), parts.next(), parts.next()) else {
+        return None;
+    };
+    Some(format!("{id}/{name}"))
+}
+
+/// Where the portal currently exposes the document with `id`.
+pub fn document_path(id: &str) -> PathBuf {
+    documents_root().join(id)
+}
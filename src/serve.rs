@@ -0,0 +1,148 @@
+//! `--serve ADDR`: a small JSON HTTP API over the todo database, so scripts,
+//! browser extensions and home automation can drive the list.
+//!
+//! There is no authentication, so bind it to a loopback address. State
+//! changing requests must be sent as `application/json`, which browsers
+//! cannot do cross-origin without a CORS preflight this server never grants.
+//! A page that rebinds its own host name to 127.0.0.1 passes as same-origin,
+//! so requests must also name the server itself in `Host`: `localhost`,
+//! `127.0.0.1`, `[::1]` or the bound address, with the bound port.
+//!
+//! Routes (`ID` is a task's `^marker`, or its 0-based `line_index`):
+//! - `GET /todos` – all tasks as a JSON array
+//! - `POST /todos` with `{"title": "..."}` – add a task due today
+//! - `POST /todos/ID/complete` – mark a task done
+//! - `DELETE /todos/ID` – remove a task
+
+use std::net::SocketAddr;
+
+use anyhow::{anyhow, Result};
+use reinschrift_core::data::{self, TodoItem};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+struct ApiError {
+    status: u16,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: u16, message: impl Into<String>) -> Self {
+        Self { status, message: message.into() }
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(err: anyhow::Error) -> Self {
+        Self::new(500, err.to_string())
+    }
+}
+
+#[derive(Deserialize)]
+struct NewTask {
+    title: String,
+}
+
+pub fn run(addr: &str) -> Result<()> {
+    let socket: SocketAddr = addr.parse().map_err(|err| anyhow!("Invalid address '{addr}': {err}"))?;
+    if !socket.ip().is_loopback() {
+        tracing::warn!(%socket, "API is reachable from the network and has no authentication");
+    }
+    let server = Server::http(socket).map_err(|err| anyhow!("Could not listen on {socket}: {err}"))?;
+    tracing::info!(%socket, "serving JSON API");
+
+    for mut request in server.incoming_requests() {
+        let (status, body) = match check_host(&request, socket).and_then(|()| handle(&mut request)) {
+            Ok((status, body)) => (status, body),
+            Err(err) => (err.status, json!({ "error": err.message })),
+        };
+        tracing::debug!(method = %request.method(), url = request.url(), status, "API request");
+        let response = Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(Header::from_bytes("Content-Type", "application/json").expect("static header"));
+        if let Err(err) = request.respond(response) {
+            tracing::warn!("Could not send API response: {err}");
+        }
+    }
+    Ok(())
+}
+
+fn handle(request: &mut Request) -> Result<(u16, Value), ApiError> {
+    let path = request.url().split('?').next().unwrap_or("").trim_end_matches('/').to_string();
+    let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+
+    match (request.method(), segments.as_slice()) {
+        (Method::Get, ["todos"]) => Ok((200, json!(data::load_todos()?))),
+        (Method::Post, ["todos"]) => {
+            let task: NewTask = read_json(request)?;
+            data::add_todo(&task.title)?;
+            Ok((201, json!({ "status": "ok" })))
+        }
+        (Method::Post, ["todos", id, "complete"]) => {
+            require_json(request)?;
+            let todo = find_todo(id)?;
+            data::complete_todo(&todo, true)?;
+            Ok((200, json!({ "status": "ok" })))
+        }
+        (Method::Delete, ["todos", id]) => {
+            let todo = find_todo(id)?;
+            data::delete_todo(&todo)?;
+            Ok((200, json!({ "status": "ok" })))
+        }
+        _ => Err(ApiError::new(404, format!("No route for {} {}", request.method(), path))),
+    }
+}
+
+fn check_host(request: &Request, socket: SocketAddr) -> Result<(), ApiError> {
+    let host = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Host"))
+        .map(|header| header.value.as_str());
+    if host.is_some_and(|host| host_allowed(host, socket)) {
+        Ok(())
+    } else {
+        Err(ApiError::new(421, "Host must be this server's loopback address"))
+    }
+}
+
+/// Whether `host` names this server. Host names other than `localhost` can
+/// be pointed at it by anyone, so only IP literals and `localhost` pass.
+fn host_allowed(host: &str, socket: SocketAddr) -> bool {
+    let port = socket.port();
+    ["localhost", "127.0.0.1", "[::1]"].iter().any(|name| host.eq_ignore_ascii_case(&format!("{name}:{port}")))
+        || host == socket.to_string()
+}
+
+fn require_json(request: &Request) -> Result<(), ApiError> {
+    let is_json = request.headers().iter().any(|header| {
+        header.field.equiv("Content-Type") && header.value.as_str().starts_with("application/json")
+    });
+    if is_json {
+        Ok(())
+    } else {
+        Err(ApiError::new(415, "Content-Type must be application/json"))
+    }
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(request: &mut Request) -> Result<T, ApiError> {
+    require_json(request)?;
+    let mut body = String::new();
+    request
+        .as_reader()
+        .read_to_string(&mut body)
+        .map_err(|err| ApiError::new(400, err.to_string()))?;
+    serde_json::from_str(&body).map_err(|err| ApiError::new(400, err.to_string()))
+}
+
+fn find_todo(id: &str) -> Result<TodoItem, ApiError> {
+    let line_index = id.parse::<usize>().ok();
+    data::load_todos()?
+        .into_iter()
+        .find(|todo| todo.key.marker.as_deref() == Some(id) || Some(todo.key.line_index) == line_index)
+        .ok_or_else(|| ApiError::new(404, format!("No task with id '{id}'")))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,18 @@
+//! Which `Host` headers the API answers.
+
+use super::*;
+
+#[test]
+fn only_the_server_itself_may_be_named_as_host() {
+    let socket: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+    assert!(host_allowed("localhost:8080", socket));
+    assert!(host_allowed("127.0.0.1:8080", socket));
+    assert!(host_allowed("[::1]:8080", socket));
+    assert!(!host_allowed("attacker.example:8080", socket), "rebound host name");
+    assert!(!host_allowed("localhost", socket), "another port");
+    assert!(!host_allowed("localhost:9090", socket));
+
+    let network: SocketAddr = "192.168.1.5:8080".parse().unwrap();
+    assert!(host_allowed("192.168.1.5:8080", network));
+    assert!(!host_allowed("nas.example:8080", network));
+}
@@ -0,0 +1,160 @@
+//! Preferences in GSettings (`me.dumke.Reinschrift.gschema.xml`).
+//!
+//! The schema only exists once installed, e.g. in the Flatpak; a plain
+//! `cargo run` keeps using `preferences.json`. Schema keys mirror the
+//! preference fields (`show_done` is `show-done`), so values are moved
+//! between both generically through serde instead of field by field.
+
+use anyhow::{bail, Context, Result};
+use gtk::gio;
+use gtk::gio::prelude::*;
+use gtk::glib::{Variant, VariantClass, VariantTy};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+pub const SCHEMA_ID: &str = "me.dumke.Reinschrift";
+/// Set once the old `preferences.json` has been copied over.
+const MIGRATED_KEY: &str = "json-migrated";
+/// Keys bound directly to window properties rather than preferences.
+pub const WINDOW_KEYS: [(&str, &str); 3] = [
+    ("window-width", "default-width"),
+    ("window-height", "default-height"),
+    ("window-maximized", "maximized"),
+];
+
+/// The app's settings, or `None` when the schema is not installed.
+pub fn open() -> Option<gio::Settings> {
+    let schema = gio::SettingsSchemaSource::default()?.lookup(SCHEMA_ID, true)?;
+    Some(gio::Settings::new_full(&schema, None::<&gio::SettingsBackend>, None))
+}
+
+fn schema(settings: &gio::Settings) -> Result<gio::SettingsSchema> {
+    settings
+        .settings_schema()
+        .context("settings without schema")
+}
+
+fn is_preference_key(key: &str) -> bool {
+    key != MIGRATED_KEY && !WINDOW_KEYS.iter().any(|(name, _)| *name == key)
+}
+
+pub fn load<T: DeserializeOwned>(settings: &gio::Settings) -> Result<T> {
+    let mut object = Map::new();
+    for key in schema(settings)?.list_keys() {
+        if is_preference_key(&key) {
+            object.insert(key.replace('-', "_"), variant_to_json(&settings.value(&key)));
+        }
+    }
+    Ok(serde_json::from_value(Value::Object(object))?)
+}
+
+/// Writes all fields of `prefs`, emitting `changed` only for keys whose value
+/// actually differs.
+pub fn store<T: Serialize>(settings: &gio::Settings, prefs: &T) -> Result<()> {
+    let Value::Object(object) = serde_json::to_value(prefs)? else {
+        bail!("preferences must serialize to an object");
+    };
+    let schema = schema(settings)?;
+    settings.delay();
+    for (field, value) in object {
+        let key = field.replace('_', "-");
+        if !schema.has_key(&key) {
+            tracing::warn!(%key, "preference missing from the GSettings schema, not saved");
+            continue;
+        }
+        let ty = schema.key(&key).value_type();
+        let Some(variant) = json_to_variant(&value, &ty) else {
+            tracing::warn!(%key, %value, "preference does not match its schema type");
+            continue;
+        };
+        if settings.value(&key) != variant {
+            settings.set_value(&key, &variant)?;
+        }
+    }
+    settings.apply();
+    Ok(())
+}
+
+/// Copies the preferences read by `legacy` into GSettings the first time the
+/// schema is available.
+pub fn migrate<T: Serialize>(settings: &gio::Settings, legacy: impl FnOnce() -> Option<T>) {
+    if settings.boolean(MIGRATED_KEY) {
+        return;
+    }
+    if let Some(prefs) = legacy() {
+        match store(settings, &prefs) {
+            Ok(_) => tracing::info!("preferences.json migrated to GSettings"),
+            Err(err) => {
+                tracing::warn!("Could not migrate preferences.json: {err}");
+                return;
+            }
+        }
+    }
+    if let Err(err) = settings.set_boolean(MIGRATED_KEY, true) {
+        tracing::warn!("Could not mark preferences as migrated: {err}");
+    }
+}
+
+fn json_to_variant(value: &Value, ty: &VariantTy) -> Option<Variant> {
+    if ty.is_maybe() {
+        return match value {
+            Value::Null => Some(Variant::from_none(ty.element())),
+            value => json_to_variant(value, ty.element()).map(|child| Variant::from_some(&child)),
+        };
+    }
+    if ty.is_array() {
+        let element = ty.element();
+        let children = if element.is_dict_entry() {
+            value
+                .as_object()?
+                .iter()
+                .map(|(key, value)| {
+                    json_to_variant(value, element.value()).map(|value| Variant::from_dict_entry(&key.to_variant(), &value))
+                })
+                .collect::<Option<Vec<_>>>()?
+        } else {
+            value
+                .as_array()?
+                .iter()
+                .map(|value| json_to_variant(value, element))
+                .collect::<Option<Vec<_>>>()?
+        };
+        return Some(Variant::array_from_iter_with_type(element, children));
+    }
+    match ty.as_str() {
+        "b" => value.as_bool().map(|b| b.to_variant()),
+        "s" => value.as_str().map(|s| s.to_variant()),
+        "i" => value.as_i64().and_then(|n| i32::try_from(n).ok()).map(|n| n.to_variant()),
+        "u" => value.as_u64().and_then(|n| u32::try_from(n).ok()).map(|n| n.to_variant()),
+        "v" => match value {
+            Value::Bool(b) => Some(b.to_variant()),
+            Value::String(s) => Some(s.to_variant()),
+            _ => None,
+        }
+        .map(|inner| Variant::from_variant(&inner)),
+        _ => None,
+    }
+}
+
+fn variant_to_json(variant: &Variant) -> Value {
+    match variant.classify() {
+        VariantClass::Boolean => Value::Bool(variant.get::<bool>().unwrap_or_default()),
+        VariantClass::String => Value::String(variant.str().unwrap_or_default().to_string()),
+        VariantClass::Int32 => variant.get::<i32>().map_or(Value::Null, Value::from),
+        VariantClass::Uint32 => variant.get::<u32>().map_or(Value::Null, Value::from),
+        VariantClass::Maybe => variant.as_maybe().map_or(Value::Null, |child| variant_to_json(&child)),
+        VariantClass::Variant => variant.as_variant().map_or(Value::Null, |inner| variant_to_json(&inner)),
+        VariantClass::Array if variant.type_().element().is_dict_entry() => Value::Object(
+            variant
+                .iter()
+                .map(|entry| {
+                    let key = entry.child_value(0).str().unwrap_or_default().to_string();
+                    (key, variant_to_json(&entry.child_value(1)))
+                })
+                .collect(),
+        ),
+        VariantClass::Array => Value::Array(variant.iter().map(|child| variant_to_json(&child)).collect()),
+        _ => Value::Null,
+    }
+}
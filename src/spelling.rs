@@ -0,0 +1,298 @@
+//! Spell checking for the task title entries.
+//!
+//! GTK 4 has no spell checker of its own, so words are looked up through
+//! `hunspell -a` (or `aspell -a`), kept running in ispell pipe mode with the
+//! dictionary of the interface language. Misspelled words get the usual red
+//! underline and the context menu offers suggestions. Without either tool,
+//! or without a dictionary for the language, the entries stay unchecked.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::rc::Rc;
+use std::time::Duration;
+
+use gtk::prelude::*;
+use gtk::{gio, glib, pango};
+use reinschrift_core::i18n::{self, t};
+
+/// Pause after typing before the text is checked again.
+const RECHECK_DELAY: Duration = Duration::from_millis(300);
+const MAX_SUGGESTIONS: usize = 5;
+
+/// A running `hunspell -a`/`aspell -a` and what it said so far.
+struct Checker {
+    process: Child,
+    input: ChildStdin,
+    output: BufReader<ChildStdout>,
+    /// Suggestions per misspelled word, `None` for correct ones.
+    known: HashMap<String, Option<Vec<String>>>,
+}
+
+impl Drop for Checker {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+thread_local! {
+    /// Started on first use; `Some(None)` once starting failed.
+    static CHECKER: RefCell<Option<Option<Checker>>> = const { RefCell::new(None) };
+}
+
+/// Dictionary names to try for the interface language, e.g. `de_AT`, `de_DE`.
+fn dictionaries() -> Vec<String> {
+    let locale = i18n::current_locale();
+    let region = match locale.split('_').next().unwrap_or_default() {
+        "de" => "de_DE",
+        "en" => "en_US",
+        "es" => "es_ES",
+        "fr" => "fr_FR",
+        "sv" => "sv_SE",
+        // Japanese is not written with spaces between words.
+        _ => return Vec::new(),
+    };
+    let mut names = vec![locale.clone()];
+    if locale != region {
+        names.push(region.to_string());
+    }
+    names.retain(|name| name.contains('_'));
+    names
+}
+
+impl Checker {
+    fn start() -> Option<Checker> {
+        for dictionary in dictionaries() {
+            let commands = [
+                ("hunspell", vec!["-a".to_string(), "-i".to_string(), "utf-8".to_string(), "-d".to_string(), dictionary.clone()]),
+                ("aspell", vec!["-a".to_string(), "--encoding=utf-8".to_string(), format!("--lang={dictionary}")]),
+            ];
+            for (program, args) in commands {
+                let Ok(mut process) = Command::new(program)
+                    .args(&args)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::null())
+                    .spawn()
+                else {
+                    continue;
+                };
+                let (Some(input), Some(output)) = (process.stdin.take(), process.stdout.take()) else {
+                    continue;
+                };
+                let mut checker = Checker { process, input, output: BufReader::new(output), known: HashMap::new() };
+                // Both print an `@(#) International Ispell …` banner when the
+                // dictionary loaded and exit otherwise.
+                let mut banner = String::new();
+                if checker.output.read_line(&mut banner).is_ok() && banner.starts_with("@(#)") {
+                    tracing::info!(%program, %dictionary, "spell checking enabled");
+                    return Some(checker);
+                }
+            }
+        }
+        tracing::debug!("no spell checker available");
+        None
+    }
+
+    /// Suggestions if `word` is misspelled, `None` if it is correct.
+    fn check(&mut self, word: &str) -> std::io::Result<Option<Vec<String>>> {
+        if let Some(known) = self.known.get(word) {
+            return Ok(known.clone());
+        }
+        // `^` keeps a word starting with a command character from being read as one.
+        writeln!(self.input, "^{word}")?;
+        self.input.flush()?;
+        let mut result = None;
+        loop {
+            let mut line = String::new();
+            if self.output.read_line(&mut line)? == 0 {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if line.starts_with('&') {
+                // `& word count offset: first, second, …`
+                let suggestions = line.split_once(": ").map(|(_, list)| list).unwrap_or_default();
+                result = Some(suggestions.split(", ").take(MAX_SUGGESTIONS).map(str::to_string).collect());
+            } else if line.starts_with('#') {
+                result = Some(Vec::new());
+            }
+        }
+        self.known.insert(word.to_string(), result.clone());
+        Ok(result)
+    }
+
+    /// Adds `word` to the personal dictionary of the checker.
+    fn learn(&mut self, word: &str) -> std::io::Result<()> {
+        writeln!(self.input, "*{word}\n#")?;
+        self.input.flush()?;
+        self.known.insert(word.to_string(), None);
+        Ok(())
+    }
+}
+
+/// Runs `f` with the checker, starting it if needed. `None` without one.
+fn with_checker<T>(f: impl FnOnce(&mut Checker) -> std::io::Result<T>) -> Option<T> {
+    CHECKER.with(|cell| {
+        let mut cell = cell.borrow_mut();
+        let checker = cell.get_or_insert_with(Checker::start).as_mut()?;
+        match f(checker) {
+            Ok(value) => Some(value),
+            Err(err) => {
+                tracing::warn!("spell checker stopped: {err}");
+                *cell = Some(None);
+                None
+            }
+        }
+    })
+}
+
+fn suggestions(word: &str) -> Option<Vec<String>> {
+    with_checker(|checker| checker.check(word)).flatten()
+}
+
+/// Byte ranges of the words worth checking: tokens such as `+project`,
+/// `@context`, `^marker`, `due:…`, links and anything with digits are skipped.
+fn words(text: &str) -> Vec<(usize, usize)> {
+    let mut words = Vec::new();
+    let mut offset = 0;
+    for token in text.split_inclusive(char::is_whitespace) {
+        let start = offset;
+        offset += token.len();
+        let token = token.trim_end();
+        let skipped = token.starts_with(['+', '@', '^', '#', '['])
+            || token.contains([':', '/', '_'])
+            || token.chars().any(|c| c.is_ascii_digit());
+        if skipped {
+            continue;
+        }
+        let is_letter = |c: char| c.is_alphabetic();
+        let (Some(first), Some(last)) = (token.find(is_letter), token.rfind(is_letter)) else {
+            continue;
+        };
+        let last = last + token[last..].chars().next().map_or(0, char::len_utf8);
+        words.push((start + first, start + last));
+    }
+    words
+}
+
+/// Character range and text of the word the context menu works on.
+type Target = Rc<RefCell<Option<(i32, i32, String)>>>;
+
+/// Checks the text of `editable` as it is typed. Entries and entry rows
+/// both edit through a [`gtk::Text`], which carries the underlines and the
+/// suggestion menu.
+pub fn attach(editable: &impl IsA<gtk::Editable>) {
+    let Some(text) = editable.delegate().and_downcast::<gtk::Text>() else {
+        return;
+    };
+    if with_checker(|_| Ok(())).is_none() {
+        return;
+    }
+
+    if !text.text().is_empty() {
+        underline(&text);
+    }
+    let pending: Rc<Cell<Option<glib::SourceId>>> = Rc::new(Cell::new(None));
+    text.connect_changed(move |text| {
+        if let Some(source) = pending.take() {
+            source.remove();
+        }
+        let pending_for_check = Rc::clone(&pending);
+        let text = text.clone();
+        pending.set(Some(glib::timeout_add_local_once(RECHECK_DELAY, move || {
+            pending_for_check.set(None);
+            underline(&text);
+        })));
+    });
+
+    let menu = gio::Menu::new();
+    text.set_extra_menu(Some(&menu));
+    let target: Target = Rc::new(RefCell::new(None));
+    let actions = gio::SimpleActionGroup::new();
+    let replace = gio::SimpleAction::new("replace", Some(glib::VariantTy::STRING));
+    replace.connect_activate(glib::clone!(@weak text, @strong target => move |_, parameter| {
+        let (Some((start, end, _)), Some(word)) = (target.take(), parameter.and_then(|p| p.get::<String>())) else {
+            return;
+        };
+        text.delete_text(start, end);
+        let mut position = start;
+        text.insert_text(&word, &mut position);
+        text.set_position(position);
+    }));
+    actions.add_action(&replace);
+    let learn = gio::SimpleAction::new("learn", None);
+    learn.connect_activate(glib::clone!(@weak text, @strong target => move |_, _| {
+        if let Some((_, _, word)) = target.take() {
+            with_checker(|checker| checker.learn(&word));
+            underline(&text);
+        }
+    }));
+    actions.add_action(&learn);
+    text.insert_action_group("spelling", Some(&actions));
+
+    // Fill the menu before the context menu opens for the word under the pointer.
+    let click = gtk::GestureClick::builder().button(gtk::gdk::BUTTON_SECONDARY).build();
+    click.set_propagation_phase(gtk::PropagationPhase::Capture);
+    click.connect_pressed(glib::clone!(@weak text => move |_, _, x, _| {
+        fill_menu(&text, &menu, &target, x);
+    }));
+    text.add_controller(click);
+}
+
+fn underline(text: &gtk::Text) {
+    let content = text.text();
+    let attributes = pango::AttrList::new();
+    for (start, end) in words(&content) {
+        if suggestions(&content[start..end]).is_none() {
+            continue;
+        }
+        let mut attribute = pango::AttrInt::new_underline(pango::Underline::Error);
+        attribute.set_start_index(start as u32);
+        attribute.set_end_index(end as u32);
+        attributes.insert(attribute);
+    }
+    text.set_attributes(Some(&attributes));
+}
+
+fn fill_menu(text: &gtk::Text, menu: &gio::Menu, target: &Target, x: f64) {
+    menu.remove_all();
+    target.replace(None);
+    let content = text.text();
+    let chars = content.chars().count();
+    // The character under the pointer: the last cursor position left of it.
+    let pointer = (0..=chars)
+        .take_while(|position| text.compute_cursor_extents(*position).0.x() as f64 <= x)
+        .last()
+        .unwrap_or(0);
+    let char_index = |byte: usize| content[..byte].chars().count();
+    let misspelled = words(&content).into_iter().find_map(|(start, end)| {
+        let (first, last) = (char_index(start), char_index(end));
+        if pointer < first || pointer > last {
+            return None;
+        }
+        let word = &content[start..end];
+        suggestions(word).map(|list| (first, last, word.to_string(), list))
+    });
+    let Some((first, last, word, list)) = misspelled else {
+        return;
+    };
+    let section = gio::Menu::new();
+    for suggestion in &list {
+        let item = gio::MenuItem::new(Some(suggestion), None);
+        item.set_action_and_target_value(Some("spelling.replace"), Some(&suggestion.to_variant()));
+        section.append_item(&item);
+    }
+    if list.is_empty() {
+        // No such action, so the item shows greyed out.
+        let item = gio::MenuItem::new(Some(&t("spelling_no_suggestions")), Some("spelling.none"));
+        section.append_item(&item);
+    }
+    section.append(Some(&t("spelling_learn").replace("{}", &word)), Some("spelling.learn"));
+    menu.append_section(None, &section);
+    target.replace(Some((first as i32, last as i32, word)));
+}
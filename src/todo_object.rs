@@ -0,0 +1,164 @@
+//! `TodoObject`, the item type of the task list model.
+//!
+//! Every entry of the list is one of these: either a task, whose fields are
+//! mirrored as properties for bindings and expressions, or a section header.
+//! [`TodoObject::set_todo`] updates an entry in place, which notifies only
+//! the properties that changed. `position`, `group` and `collapsed` are set
+//! by the list pipeline for sorting and filtering.
+
+use std::cell::{Cell, RefCell};
+use std::cmp::Ordering;
+
+use gtk::glib;
+use gtk::glib::prelude::*;
+use gtk::glib::subclass::prelude::*;
+use reinschrift_core::collation::{Collator, OptionalKey};
+use reinschrift_core::data::{TodoItem, TodoKey};
+
+/// A text of the task that the table can be sorted by.
+#[derive(Clone, Copy)]
+pub enum SortText {
+    Title,
+    Project,
+    Context,
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, glib::Properties)]
+    #[properties(wrapper_type = super::TodoObject)]
+    pub struct TodoObject {
+        #[property(get, set)]
+        title: RefCell<String>,
+        #[property(get, set)]
+        done: Cell<bool>,
+        /// `YYYY-MM-DD`, empty without due date.
+        #[property(get, set)]
+        due: RefCell<String>,
+        #[property(get, set)]
+        project: RefCell<String>,
+        #[property(get, set)]
+        context: RefCell<String>,
+        /// `A` to `Z`, empty without priority.
+        #[property(get, set)]
+        priority: RefCell<String>,
+        #[property(get, set)]
+        pinned: Cell<bool>,
+        /// The label of a section header, empty for tasks.
+        #[property(get)]
+        pub(super) header: RefCell<String>,
+        /// Rank in the list, headers come right before their first task.
+        #[property(get, set)]
+        position: Cell<u32>,
+        /// Label of the section the task is listed under.
+        #[property(get, set)]
+        group: RefCell<String>,
+        /// Whether the tasks below this header are hidden.
+        #[property(get, set)]
+        collapsed: Cell<bool>,
+        pub(super) item: RefCell<Option<TodoItem>>,
+        /// Collation keys by [`super::SortText`], built on first use.
+        pub(super) sort_keys: RefCell<[Option<OptionalKey>; 3]>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for TodoObject {
+        const NAME: &'static str = "ReinschriftTodoObject";
+        type Type = super::TodoObject;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for TodoObject {}
+}
+
+glib::wrapper! {
+    pub struct TodoObject(ObjectSubclass<imp::TodoObject>);
+}
+
+impl TodoObject {
+    pub fn new(item: &TodoItem) -> Self {
+        let object: Self = glib::Object::new();
+        object.set_todo(item);
+        object
+    }
+
+    pub fn new_header(label: &str) -> Self {
+        let object: Self = glib::Object::new();
+        object.imp().header.replace(label.to_string());
+        object
+    }
+
+    /// The task, `None` for section headers.
+    pub fn todo(&self) -> Option<TodoItem> {
+        self.imp().item.borrow().clone()
+    }
+
+    /// The key of the task, `None` for section headers.
+    pub fn key(&self) -> Option<TodoKey> {
+        self.imp().item.borrow().as_ref().map(|item| item.key.clone())
+    }
+
+    pub fn is_header(&self) -> bool {
+        self.imp().item.borrow().is_none()
+    }
+
+    /// The header label, `None` for tasks.
+    pub fn header_label(&self) -> Option<String> {
+        self.is_header().then(|| self.header())
+    }
+
+    /// Orders by `text`, tasks without it last. The keys are kept until the
+    /// task changes, so sorting does not rebuild them for every comparison.
+    pub fn compare_text(&self, other: &Self, text: SortText) -> Ordering {
+        self.build_sort_key(text);
+        other.build_sort_key(text);
+        let (keys, other_keys) = (self.imp().sort_keys.borrow(), other.imp().sort_keys.borrow());
+        keys[text as usize].cmp(&other_keys[text as usize])
+    }
+
+    fn build_sort_key(&self, text: SortText) {
+        let mut keys = self.imp().sort_keys.borrow_mut();
+        if keys[text as usize].is_some() {
+            return;
+        }
+        let item = self.imp().item.borrow();
+        let value = item.as_ref().and_then(|item| match text {
+            SortText::Title => Some(item.title.as_str()),
+            SortText::Project => item.project.as_deref(),
+            SortText::Context => item.context.as_deref(),
+        });
+        keys[text as usize] = Some(Collator::current().optional_key(value));
+    }
+
+    /// Replaces the task and notifies the properties that changed.
+    pub fn set_todo(&self, item: &TodoItem) {
+        self.imp().item.replace(Some(item.clone()));
+        self.imp().sort_keys.take();
+        let text = |value: Option<&str>| value.unwrap_or_default().to_string();
+        let due = item.due.map(|due| due.format("%Y-%m-%d").to_string()).unwrap_or_default();
+        let priority = item.priority.map(String::from).unwrap_or_default();
+        let _notify = self.freeze_notify();
+        if self.title() != item.title {
+            self.set_title(item.title.as_str());
+        }
+        if self.done() != item.done {
+            self.set_done(item.done);
+        }
+        if self.due() != due {
+            self.set_due(due);
+        }
+        if self.project() != text(item.project.as_deref()) {
+            self.set_project(text(item.project.as_deref()));
+        }
+        if self.context() != text(item.context.as_deref()) {
+            self.set_context(text(item.context.as_deref()));
+        }
+        if self.priority() != priority {
+            self.set_priority(priority);
+        }
+        if self.pinned() != item.pinned {
+            self.set_pinned(item.pinned);
+        }
+    }
+}
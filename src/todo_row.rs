@@ -0,0 +1,471 @@
+//! `TodoRow`, the row widget of the task list.
+//!
+//! The widget tree of a row is built once per recycled list item from the
+//! template below and shows either a section header or a task. Binding only
+//! updates texts, icons and style classes through [`TodoRow::show_header`]
+//! and [`TodoRow::show_task`]; whatever did not change is left alone, so
+//! scrolling through long lists does not allocate per row.
+
+use std::cell::{Cell, RefCell};
+use std::ops::Range;
+
+use gtk::glib;
+use gtk::pango;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use reinschrift_core::data::{Aging, TodoItem};
+use reinschrift_core::i18n::t;
+
+/// Style classes for the due state of a task, see `due_urgency_class`.
+const URGENCY_CLASSES: [&str; 3] = ["todo-done", "todo-overdue", "todo-due-today"];
+
+/// Background of search matches, the yellow of the GNOME palette at half
+/// opacity so the text stays readable in light and dark styles.
+const MATCH_BACKGROUND: (u16, u16, u16) = (0xf6f6, 0xd3d3, 0x2d2d);
+const MATCH_ALPHA: u16 = 0x8000;
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, gtk::CompositeTemplate)]
+    #[template(string = r#"
+<interface>
+  <template class="ReinschriftTodoRow" parent="GtkBox">
+    <child>
+      <object class="GtkStack" id="stack">
+        <property name="hexpand">true</property>
+        <property name="transition-type">none</property>
+        <child>
+          <object class="GtkStackPage">
+            <property name="name">header</property>
+            <property name="child">
+              <object class="GtkBox" id="header_box">
+                <property name="margin-start">12</property>
+                <property name="margin-end">12</property>
+                <property name="margin-top">8</property>
+                <property name="margin-bottom">4</property>
+                <style><class name="todo-header"/></style>
+                <child>
+                  <object class="GtkImage" id="header_icon">
+                    <property name="icon-name">pan-down-symbolic</property>
+                    <property name="margin-end">6</property>
+                    <style><class name="dim-label"/></style>
+                  </object>
+                </child>
+                <child>
+                  <object class="GtkLabel" id="header_label">
+                    <property name="xalign">0</property>
+                    <property name="hexpand">true</property>
+                    <style>
+                      <class name="heading"/>
+                      <class name="dim-label"/>
+                    </style>
+                  </object>
+                </child>
+                <child>
+                  <object class="GtkButton" id="rename_button">
+                    <property name="icon-name">document-edit-symbolic</property>
+                    <property name="valign">center</property>
+                    <style><class name="flat"/></style>
+                  </object>
+                </child>
+              </object>
+            </property>
+          </object>
+        </child>
+        <child>
+          <object class="GtkStackPage">
+            <property name="name">item</property>
+            <property name="child">
+              <object class="GtkBox" id="task_box">
+                <property name="spacing">12</property>
+                <property name="margin-start">12</property>
+                <property name="margin-end">12</property>
+                <property name="margin-top">6</property>
+                <property name="margin-bottom">6</property>
+                <style><class name="todo-row"/></style>
+                <child>
+                  <object class="GtkCheckButton" id="check">
+                    <property name="valign">center</property>
+                  </object>
+                </child>
+                <child>
+                  <object class="GtkBox">
+                    <property name="orientation">vertical</property>
+                    <property name="spacing">4</property>
+                    <child>
+                      <object class="GtkLabel" id="title">
+                        <property name="xalign">0</property>
+                        <property name="ellipsize">end</property>
+                        <property name="wrap">true</property>
+                        <property name="wrap-mode">word-char</property>
+                        <style>
+                          <class name="title-4"/>
+                          <class name="todo-title"/>
+                        </style>
+                      </object>
+                    </child>
+                    <child>
+                      <object class="GtkLabel" id="meta">
+                        <property name="xalign">0</property>
+                        <property name="wrap">true</property>
+                        <property name="wrap-mode">word-char</property>
+                        <style>
+                          <class name="dim-label"/>
+                          <class name="todo-meta"/>
+                        </style>
+                      </object>
+                    </child>
+                  </object>
+                </child>
+                <child>
+                  <object class="GtkBox">
+                    <property name="hexpand">true</property>
+                  </object>
+                </child>
+                <child>
+                  <object class="GtkLabel" id="priority">
+                    <property name="visible">false</property>
+                    <property name="valign">center</property>
+                    <style><class name="priority-badge"/></style>
+                  </object>
+                </child>
+                <child>
+                  <object class="GtkButton" id="open_button">
+                    <property name="icon-name">document-open-symbolic</property>
+                    <property name="visible">false</property>
+                    <property name="valign">center</property>
+                    <style><class name="flat"/></style>
+                  </object>
+                </child>
+                <child>
+                  <object class="GtkButton" id="star_button">
+                    <property name="icon-name">non-starred-symbolic</property>
+                    <property name="valign">center</property>
+                    <style><class name="flat"/></style>
+                  </object>
+                </child>
+                <child>
+                  <object class="GtkButton" id="today_button">
+                    <property name="icon-name">x-office-calendar-symbolic</property>
+                    <property name="valign">center</property>
+                    <style><class name="flat"/></style>
+                  </object>
+                </child>
+                <child>
+                  <object class="GtkButton" id="postpone_button">
+                    <property name="icon-name">go-next-symbolic</property>
+                    <property name="valign">center</property>
+                    <style><class name="flat"/></style>
+                  </object>
+                </child>
+                <child>
+                  <object class="GtkButton" id="sometimes_button">
+                    <property name="icon-name">clock-symbolic</property>
+                    <property name="valign">center</property>
+                    <style><class name="flat"/></style>
+                  </object>
+                </child>
+              </object>
+            </property>
+          </object>
+        </child>
+      </object>
+    </child>
+  </template>
+</interface>
+"#)]
+    pub struct TodoRow {
+        #[template_child]
+        pub stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub header_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub header_icon: TemplateChild<gtk::Image>,
+        #[template_child]
+        pub header_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub rename_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub task_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub check: TemplateChild<gtk::CheckButton>,
+        #[template_child]
+        pub title: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub meta: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub priority: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub open_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub star_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub today_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub postpone_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub sometimes_button: TemplateChild<gtk::Button>,
+        /// The search whose matches are marked, see [`super::TodoRow::highlight`].
+        pub search: RefCell<String>,
+        /// Priority whose `todo-priority-*` class the task box carries.
+        pub priority_class: Cell<Option<char>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for TodoRow {
+        const NAME: &'static str = "ReinschriftTodoRow";
+        type Type = super::TodoRow;
+        type ParentType = gtk::Box;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for TodoRow {
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.rename_button.set_tooltip_text(Some(&t("rename_tag")));
+            self.open_button.set_tooltip_text(Some(&t("open_linked_file")));
+            self.star_button.set_tooltip_text(Some(&t("pin")));
+            self.today_button.set_tooltip_text(Some(&t("set_due_today")));
+            self.postpone_button.set_tooltip_text(Some(&t("postpone_tomorrow")));
+            self.sometimes_button.set_tooltip_text(Some(&t("postpone_sometimes")));
+            // The title is bound to the task object and may change without a new bind.
+            let row = self.obj().downgrade();
+            self.title.connect_label_notify(move |_| {
+                if let Some(row) = row.upgrade() {
+                    row.imp().mark_matches();
+                }
+            });
+        }
+    }
+
+    impl TodoRow {
+        pub fn mark_matches(&self) {
+            let search = self.search.borrow();
+            for label in [&*self.title, &*self.meta] {
+                label.set_attributes(match_attributes(&label.text(), &search).as_ref());
+            }
+        }
+    }
+
+    impl WidgetImpl for TodoRow {}
+    impl BoxImpl for TodoRow {}
+}
+
+glib::wrapper! {
+    pub struct TodoRow(ObjectSubclass<imp::TodoRow>)
+        @extends gtk::Box, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Orientable;
+}
+
+impl Default for TodoRow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TodoRow {
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+
+    /// Shows the section header `label`. `collapsed` is `None` for sections
+    /// that cannot be collapsed in the current sort order.
+    pub fn show_header(&self, label: &str, collapsed: Option<bool>, renamable: bool) {
+        let imp = self.imp();
+        imp.stack.set_visible_child_name("header");
+        if imp.header_label.text() != label {
+            imp.header_label.set_text(label);
+        }
+        imp.header_icon.set_visible(collapsed.is_some());
+        let icon = if collapsed == Some(true) { "pan-end-symbolic" } else { "pan-down-symbolic" };
+        imp.header_icon.set_icon_name(Some(icon));
+        let tooltip = collapsed.map(|collapsed| t(if collapsed { "expand_section" } else { "collapse_section" }));
+        imp.stack.set_tooltip_text(tooltip.as_deref());
+        imp.rename_button.set_visible(renamable);
+    }
+
+    /// Shows `todo` with the given due state class and metadata line. The
+    /// title and check button are bound to the task object instead.
+    pub fn show_task(&self, todo: &TodoItem, urgency: Option<&str>, metadata: &str) {
+        let imp = self.imp();
+        imp.stack.set_visible_child_name("item");
+        imp.stack.set_tooltip_text(None);
+        for class in URGENCY_CLASSES {
+            set_class(&*imp.task_box, class, Some(class) == urgency);
+        }
+        set_class(&*imp.task_box, "todo-aged", matches!(todo.aged, Some(Aging::Raised(_))) && !todo.done);
+        set_class(&*imp.task_box, "todo-compact", metadata.is_empty());
+        let open_priority = todo.priority.filter(|_| !todo.done);
+        let shown = imp.priority_class.replace(open_priority);
+        if shown != open_priority {
+            if let Some(old) = shown {
+                imp.task_box.remove_css_class(&priority_class(old));
+            }
+            if let Some(new) = open_priority {
+                imp.task_box.add_css_class(&priority_class(new));
+            }
+        }
+        set_class(&*imp.title, "dim-label", todo.done);
+        let priority = open_priority.map(String::from).unwrap_or_default();
+        if imp.priority.text() != priority {
+            imp.priority.set_text(&priority);
+            imp.priority.set_tooltip_text(Some(&t("priority_badge").replace("{}", &priority)));
+        }
+        imp.priority.set_visible(!priority.is_empty());
+        imp.open_button.set_visible(todo.file_link().is_some());
+        let (icon, tooltip) = if todo.pinned {
+            ("starred-symbolic", "unpin")
+        } else {
+            ("non-starred-symbolic", "pin")
+        };
+        if imp.star_button.icon_name().as_deref() != Some(icon) {
+            imp.star_button.set_icon_name(icon);
+            imp.star_button.set_tooltip_text(Some(&t(tooltip)));
+        }
+        if imp.meta.text() != metadata {
+            imp.meta.set_text(metadata);
+            imp.mark_matches();
+        }
+    }
+
+    /// Marks where `search` occurs in the title and metadata line, ignoring
+    /// case; an empty search clears the marks.
+    pub fn highlight(&self, search: &str) {
+        let imp = self.imp();
+        if *imp.search.borrow() != search {
+            imp.search.replace(search.to_string());
+            imp.mark_matches();
+        }
+    }
+
+    /// The label of the header shown, empty for tasks.
+    pub fn header_text(&self) -> String {
+        self.imp().header_label.text().to_string()
+    }
+
+    pub fn header_box(&self) -> gtk::Box {
+        self.imp().header_box.get()
+    }
+
+    pub fn task_box(&self) -> gtk::Box {
+        self.imp().task_box.get()
+    }
+
+    pub fn check_button(&self) -> gtk::CheckButton {
+        self.imp().check.get()
+    }
+
+    pub fn title_label(&self) -> gtk::Label {
+        self.imp().title.get()
+    }
+
+    pub fn rename_button(&self) -> gtk::Button {
+        self.imp().rename_button.get()
+    }
+
+    pub fn open_button(&self) -> gtk::Button {
+        self.imp().open_button.get()
+    }
+
+    pub fn star_button(&self) -> gtk::Button {
+        self.imp().star_button.get()
+    }
+
+    pub fn today_button(&self) -> gtk::Button {
+        self.imp().today_button.get()
+    }
+
+    pub fn postpone_button(&self) -> gtk::Button {
+        self.imp().postpone_button.get()
+    }
+
+    pub fn sometimes_button(&self) -> gtk::Button {
+        self.imp().sometimes_button.get()
+    }
+}
+
+/// `todo-priority-a` for `pri:A` and so on.
+fn priority_class(priority: char) -> String {
+    format!("todo-priority-{}", priority.to_ascii_lowercase())
+}
+
+fn set_class(widget: &impl IsA<gtk::Widget>, class: &str, enabled: bool) {
+    if widget.has_css_class(class) == enabled {
+        return;
+    }
+    if enabled {
+        widget.add_css_class(class);
+    } else {
+        widget.remove_css_class(class);
+    }
+}
+
+/// Byte ranges of the non-overlapping matches of `search` in `text`,
+/// compared in lower case.
+pub fn match_ranges(text: &str, search: &str) -> Vec<Range<usize>> {
+    let needle: Vec<char> = search.chars().flat_map(char::to_lowercase).collect();
+    let mut ranges = Vec::new();
+    if needle.is_empty() {
+        return ranges;
+    }
+    let mut from = 0;
+    for (start, _) in text.char_indices() {
+        if start < from {
+            continue;
+        }
+        if let Some(length) = match_length(&text[start..], &needle) {
+            ranges.push(start..start + length);
+            from = start + length;
+        }
+    }
+    ranges
+}
+
+/// Length in bytes of the start of `text` that reads `needle` in lower case.
+fn match_length(text: &str, needle: &[char]) -> Option<usize> {
+    let mut wanted = needle.iter();
+    for (index, c) in text.char_indices() {
+        for lower in c.to_lowercase() {
+            if wanted.next() != Some(&lower) {
+                return None;
+            }
+        }
+        if wanted.as_slice().is_empty() {
+            return Some(index + c.len_utf8());
+        }
+    }
+    None
+}
+
+fn match_attributes(text: &str, search: &str) -> Option<pango::AttrList> {
+    let ranges = match_ranges(text, search);
+    if ranges.is_empty() {
+        return None;
+    }
+    let list = pango::AttrList::new();
+    let (red, green, blue) = MATCH_BACKGROUND;
+    for range in ranges {
+        let (start, end) = (range.start as u32, range.end as u32);
+        let mut background = pango::AttrColor::new_background(red, green, blue);
+        let mut alpha = pango::AttrInt::new_background_alpha(MATCH_ALPHA);
+        let mut weight = pango::AttrInt::new_weight(pango::Weight::Bold);
+        background.set_start_index(start);
+        background.set_end_index(end);
+        alpha.set_start_index(start);
+        alpha.set_end_index(end);
+        weight.set_start_index(start);
+        weight.set_end_index(end);
+        list.insert(background);
+        list.insert(alpha);
+        list.insert(weight);
+    }
+    Some(list)
+}
@@ -1,19 +1,21 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
 use std::cmp::Ordering;
 use std::fs;
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
 use std::sync::Mutex;
+use std::time::Instant;
 
 use adw::prelude::*;
 use adw::{self, Application};
-use anyhow::Result;
-use chrono::{Datelike, Duration, Local, NaiveDate};
+use anyhow::{bail, Result};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use glib::{clone, BoxedAnyObject};
+use glib::clone;
 use gtk::gdk;
 use gtk::gio;
 use gtk::{AlertDialog, FileDialog, FileFilter};
@@ -25,8 +27,34 @@ use serde::{Deserialize, Serialize};
 use serde_json;
 use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
 
-use crate::data::{self, TodoItem};
-use crate::i18n::t;
+use crate::autostart;
+use crate::commands;
+use crate::profile;
+use crate::sandbox;
+use crate::settings;
+use crate::spelling;
+use crate::todo_object::{SortText, TodoObject};
+use crate::todo_row::TodoRow;
+use reinschrift_core::aging;
+use reinschrift_core::cache::{self, SyncStatus};
+use reinschrift_core::calendar::{self, Event};
+use reinschrift_core::collation::{self, CollationKey, Collator, OptionalKey};
+use reinschrift_core::config;
+use reinschrift_core::data::{self, Aging, DataError, Name, TodoItem};
+use reinschrift_core::demo;
+use reinschrift_core::drafts::{self, EditDraft, EditFields};
+use reinschrift_core::duplicates;
+use reinschrift_core::export;
+use reinschrift_core::history::{self, HistoryEntry, HistoryEvent};
+use reinschrift_core::import;
+use reinschrift_core::i18n::{self, t};
+use reinschrift_core::journal;
+use reinschrift_core::repair::{self, RepairAction};
+use reinschrift_core::starter::{self, StarterTemplate};
+use reinschrift_core::stats;
+use reinschrift_core::suggest;
+use reinschrift_core::templates;
+use reinschrift_core::storage;
 
 enum VoiceMsg {
     Error(String),
@@ -35,12 +63,6 @@ enum VoiceMsg {
     Finished,
 }
 
-#[derive(Clone)]
-enum ListEntry {
-    Header(String),
-    Item(TodoItem),
-}
-
 #[derive(Copy, Clone, PartialEq, Eq)]
 enum SortMode {
     Topic,
@@ -82,6 +104,303 @@ impl SortMode {
     }
 }
 
+/// How the task list is presented.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum ViewMode {
+    List,
+    Columns,
+    Contexts,
+    Focus,
+    Matrix,
+    Graph,
+    Timeline,
+    Week,
+}
+
+impl ViewMode {
+    fn from_index(index: u32) -> Self {
+        match index {
+            1 => ViewMode::Columns,
+            2 => ViewMode::Contexts,
+            3 => ViewMode::Focus,
+            4 => ViewMode::Matrix,
+            5 => ViewMode::Graph,
+            6 => ViewMode::Timeline,
+            7 => ViewMode::Week,
+            _ => ViewMode::List,
+        }
+    }
+
+    fn to_index(self) -> u32 {
+        match self {
+            ViewMode::List => 0,
+            ViewMode::Columns => 1,
+            ViewMode::Contexts => 2,
+            ViewMode::Focus => 3,
+            ViewMode::Matrix => 4,
+            ViewMode::Graph => 5,
+            ViewMode::Timeline => 6,
+            ViewMode::Week => 7,
+        }
+    }
+
+    fn from_key(key: &str) -> Self {
+        match key {
+            "columns" => ViewMode::Columns,
+            "contexts" => ViewMode::Contexts,
+            "focus" => ViewMode::Focus,
+            "matrix" => ViewMode::Matrix,
+            "graph" => ViewMode::Graph,
+            "timeline" => ViewMode::Timeline,
+            "week" => ViewMode::Week,
+            _ => ViewMode::List,
+        }
+    }
+
+    fn as_key(self) -> &'static str {
+        match self {
+            ViewMode::List => "list",
+            ViewMode::Columns => "columns",
+            ViewMode::Contexts => "contexts",
+            ViewMode::Focus => "focus",
+            ViewMode::Matrix => "matrix",
+            ViewMode::Graph => "graph",
+            ViewMode::Timeline => "timeline",
+            ViewMode::Week => "week",
+        }
+    }
+}
+
+/// Tasks with a due date at most this many days ahead count as urgent.
+const URGENT_DAYS: i64 = 2;
+
+/// A cell of the Eisenhower matrix, derived from due date (urgent) and
+/// priority `A` or `B` (important).
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Quadrant {
+    Do,
+    Schedule,
+    Delegate,
+    Eliminate,
+}
+
+impl Quadrant {
+    /// In grid order: important row first, urgent column first.
+    const ALL: [Quadrant; 4] = [Quadrant::Do, Quadrant::Schedule, Quadrant::Delegate, Quadrant::Eliminate];
+
+    fn new(urgent: bool, important: bool) -> Self {
+        match (urgent, important) {
+            (true, true) => Quadrant::Do,
+            (false, true) => Quadrant::Schedule,
+            (true, false) => Quadrant::Delegate,
+            (false, false) => Quadrant::Eliminate,
+        }
+    }
+
+    fn of(todo: &TodoItem, today: NaiveDate) -> Self {
+        Quadrant::new(is_urgent(todo, today), is_important(todo))
+    }
+
+    fn urgent(self) -> bool {
+        matches!(self, Quadrant::Do | Quadrant::Delegate)
+    }
+
+    fn important(self) -> bool {
+        matches!(self, Quadrant::Do | Quadrant::Schedule)
+    }
+
+    fn title_key(self) -> &'static str {
+        match self {
+            Quadrant::Do => "matrix_do",
+            Quadrant::Schedule => "matrix_schedule",
+            Quadrant::Delegate => "matrix_delegate",
+            Quadrant::Eliminate => "matrix_eliminate",
+        }
+    }
+
+    /// `todo` with priority and due date changed just enough to land here:
+    /// important tasks get `pri:A`, urgent ones are due today, and moving out
+    /// drops the priority or pushes the due date a week out.
+    fn apply(self, todo: &TodoItem, today: NaiveDate) -> TodoItem {
+        let mut updated = todo.clone();
+        if self.important() != is_important(todo) {
+            updated.priority = self.important().then_some('A');
+        }
+        if self.urgent() != is_urgent(todo, today) {
+            let days = if self.urgent() { 0 } else { 7 };
+            updated.due = Some(today + Duration::days(days));
+        }
+        updated
+    }
+}
+
+fn is_urgent(todo: &TodoItem, today: NaiveDate) -> bool {
+    todo.due
+        .is_some_and(|due| due.year() != 9999 && due <= today + Duration::days(URGENT_DAYS))
+}
+
+fn is_important(todo: &TodoItem) -> bool {
+    todo.priority.is_some_and(|priority| priority <= 'B')
+}
+
+/// Foreign formats offered under "Import from".
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum ImportFormat {
+    MicrosoftTodo,
+    GoogleTasks,
+    Taskwarrior,
+}
+
+impl ImportFormat {
+    const ALL: [ImportFormat; 3] = [ImportFormat::MicrosoftTodo, ImportFormat::GoogleTasks, ImportFormat::Taskwarrior];
+
+    fn as_key(self) -> &'static str {
+        match self {
+            ImportFormat::MicrosoftTodo => "microsoft",
+            ImportFormat::GoogleTasks => "google",
+            ImportFormat::Taskwarrior => "taskwarrior",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.as_key() == key)
+    }
+
+    fn label(self) -> String {
+        match self {
+            ImportFormat::MicrosoftTodo => t("import_microsoft"),
+            ImportFormat::GoogleTasks => t("import_google"),
+            ImportFormat::Taskwarrior => t("import_taskwarrior"),
+        }
+    }
+
+    fn suffixes(self) -> &'static [&'static str] {
+        match self {
+            ImportFormat::MicrosoftTodo => &["json", "csv"],
+            ImportFormat::GoogleTasks | ImportFormat::Taskwarrior => &["json"],
+        }
+    }
+
+    fn parse(self, content: &str) -> Result<Vec<TodoItem>> {
+        match self {
+            ImportFormat::MicrosoftTodo => import::microsoft_todo(content),
+            ImportFormat::GoogleTasks => import::google_tasks(content),
+            ImportFormat::Taskwarrior => import::taskwarrior(content),
+        }
+    }
+}
+
+/// What the header bar status indicator reports about the database.
+#[derive(Clone, PartialEq)]
+enum Persistence {
+    Loaded,
+    Saving,
+    Saved,
+    ExternalChange,
+    Syncing,
+    /// The remote database is unreachable; saves wait in the offline copy.
+    Offline(String),
+    Error(String),
+}
+
+impl Persistence {
+    fn icon_name(&self) -> &'static str {
+        match self {
+            Persistence::Loaded | Persistence::Saved => "emblem-ok-symbolic",
+            Persistence::Saving => "document-save-symbolic",
+            Persistence::ExternalChange => "document-edit-symbolic",
+            Persistence::Syncing => "emblem-synchronizing-symbolic",
+            Persistence::Offline(_) => "network-offline-symbolic",
+            Persistence::Error(_) => "dialog-warning-symbolic",
+        }
+    }
+
+    fn summary(&self, at: DateTime<Local>) -> String {
+        let time = at.format("%H:%M:%S").to_string();
+        match self {
+            Persistence::Loaded => t("status_loaded").replace("{}", &time),
+            Persistence::Saving => t("status_saving"),
+            Persistence::Saved => t("status_saved").replace("{}", &time),
+            Persistence::ExternalChange => t("status_external").replace("{}", &time),
+            Persistence::Syncing => t("status_syncing"),
+            Persistence::Offline(_) => t("status_offline").replace("{}", &time),
+            Persistence::Error(_) => t("status_error").replace("{}", &time),
+        }
+    }
+}
+
+/// A piece of information that can be shown in a row's metadata line.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum MetaField {
+    Section,
+    Project,
+    Context,
+    Due,
+    Estimate,
+    Recurrence,
+    Reference,
+    Created,
+}
+
+impl MetaField {
+    const ALL: [MetaField; 8] = [
+        MetaField::Section,
+        MetaField::Project,
+        MetaField::Context,
+        MetaField::Due,
+        MetaField::Estimate,
+        MetaField::Recurrence,
+        MetaField::Reference,
+        MetaField::Created,
+    ];
+
+    /// Fields shown until the user picks their own; every new task carries a
+    /// creation date, so that one is opt-in.
+    fn defaults() -> Vec<MetaField> {
+        Self::ALL.into_iter().filter(|field| *field != MetaField::Created).collect()
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "section" => Some(MetaField::Section),
+            "project" => Some(MetaField::Project),
+            "context" => Some(MetaField::Context),
+            "due" => Some(MetaField::Due),
+            "estimate" => Some(MetaField::Estimate),
+            "recurrence" => Some(MetaField::Recurrence),
+            "reference" => Some(MetaField::Reference),
+            "created" => Some(MetaField::Created),
+            _ => None,
+        }
+    }
+
+    fn as_key(self) -> &'static str {
+        match self {
+            MetaField::Section => "section",
+            MetaField::Project => "project",
+            MetaField::Context => "context",
+            MetaField::Due => "due",
+            MetaField::Estimate => "estimate",
+            MetaField::Recurrence => "recurrence",
+            MetaField::Reference => "reference",
+            MetaField::Created => "created",
+        }
+    }
+
+    fn label(self) -> String {
+        match self {
+            MetaField::Section => t("section"),
+            MetaField::Project => t("project_plus"),
+            MetaField::Context => t("location_at"),
+            MetaField::Due => t("due_date"),
+            MetaField::Estimate => t("estimate"),
+            MetaField::Recurrence => t("recurrence"),
+            MetaField::Reference => t("reference"),
+            MetaField::Created => t("created_date"),
+        }
+    }
+}
+
 #[derive(Clone, Default, Serialize, Deserialize)]
 struct Preferences {
     sort_mode: Option<String>,
@@ -89,6 +408,9 @@ struct Preferences {
     show_done: bool,
     #[serde(default)]
     db_path: Option<String>,
+    /// Document portal id (`<id>/<name>`) of the database inside Flatpak.
+    #[serde(default)]
+    db_document: Option<String>,
     #[serde(default)]
     show_due_only: bool,
     #[serde(default)]
@@ -105,18 +427,238 @@ struct Preferences {
     use_whisper: bool,
     #[serde(default = "default_whisper_language")]
     whisper_language: String,
+    /// Visible metadata fields in display order; `None` shows all.
+    #[serde(default)]
+    metadata_fields: Option<Vec<String>>,
+    #[serde(default)]
+    view_mode: Option<String>,
+    /// Last active context tab, keyed by database location.
+    #[serde(default)]
+    context_tabs: HashMap<String, String>,
+    #[serde(default)]
+    daily_agenda: bool,
+    /// Local time of the agenda notification as `HH:MM`.
+    #[serde(default)]
+    agenda_time: Option<String>,
+    #[serde(default)]
+    agenda_last_sent: Option<NaiveDate>,
+    /// `remind:` times up to this moment have already been notified.
+    #[serde(default)]
+    reminders_checked: Option<NaiveDateTime>,
+    #[serde(default)]
+    autostart_service: bool,
+    /// Remapped accelerators per action, e.g. `"app.reload": ["F5"]`.
+    #[serde(default)]
+    shortcuts: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    vim_keys: bool,
+    /// Turn animations off in this app even where the system has them on.
+    #[serde(default)]
+    reduce_motion: bool,
+    /// Delete tasks without asking, set by "Don't ask again".
+    #[serde(default)]
+    skip_delete_confirmation: bool,
+    /// Seconds between checks when file change events are unreliable.
+    #[serde(default = "default_poll_interval")]
+    poll_interval: u32,
+    /// How often "What should I do now?" was skipped per task (`^marker` or title).
+    #[serde(default)]
+    suggestion_skips: HashMap<String, u32>,
+    /// Move open tasks of past `### YYYY-MM-DD` sections to today's section.
+    #[serde(default)]
+    daily_rollover: bool,
+    #[serde(default)]
+    rollover_last: Option<NaiveDate>,
+    /// Raise the priority of tasks overdue by more than this many days; 0 is off.
+    #[serde(default)]
+    priority_aging_days: u32,
+    #[serde(default)]
+    aging_last: Option<NaiveDate>,
+    /// `.ics` file or URL whose events are shown in the Today and Week views.
+    #[serde(default)]
+    calendar_source: Option<String>,
+    /// Collapsed section headers, keyed by database location.
+    #[serde(default)]
+    collapsed_sections: HashMap<String, Vec<String>>,
+    /// Sort, filters and view of each database, keyed by its location.
+    #[serde(default)]
+    view_states: HashMap<String, ViewState>,
+    /// Accent colour pinned for a database, keyed by its location, from
+    /// [`ACCENT_COLORS`]; the system accent applies elsewhere.
+    #[serde(default)]
+    accents: HashMap<String, String>,
+}
+
+/// How a database was last looked at, restored when it is opened again.
+/// Collapsed sections and the context tab are kept per database on their own.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct ViewState {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sort_mode: Option<String>,
+    show_done: bool,
+    show_due_only: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    view_mode: Option<String>,
+}
+
+impl ViewState {
+    fn of(prefs: &Preferences) -> Self {
+        ViewState {
+            sort_mode: prefs.sort_mode.clone(),
+            show_done: prefs.show_done,
+            show_due_only: prefs.show_due_only,
+            view_mode: prefs.view_mode.clone(),
+        }
+    }
+
+    fn apply(&self, prefs: &mut Preferences) {
+        prefs.sort_mode = self.sort_mode.clone();
+        prefs.show_done = self.show_done;
+        prefs.show_due_only = self.show_due_only;
+        prefs.view_mode = self.view_mode.clone();
+    }
 }
 
 fn default_whisper_language() -> String {
     "auto".to_string()
 }
 
+fn default_poll_interval() -> u32 {
+    2
+}
+
+/// Poll interval while file monitoring delivers events, mostly for WebDAV.
+const MONITORED_POLL_INTERVAL: u32 = 10;
+
+/// Poll interval while the database file is gone, to find it again soon
+/// where the file monitor misses its return.
+const MISSING_POLL_INTERVAL: u32 = 2;
+
+const DEFAULT_AGENDA_TIME: &str = "08:00";
+
+/// Pause after typing before unsaved input is written as a draft.
+const DRAFT_SAVE_DELAY_SECONDS: u32 = 2;
+
+fn reminder_notification_id(task_id: &str) -> String {
+    format!("reminder-{task_id}")
+}
+/// Seconds between reads of the calendar while it is configured.
+const CALENDAR_REFRESH_SECONDS: u32 = 900;
+
+/// Accent colours a database can be pinned to, those libadwaita offers in
+/// the system settings.
+const ACCENT_COLORS: [(&str, &str); 9] = [
+    ("blue", "#3584e4"),
+    ("teal", "#2190a4"),
+    ("green", "#3a944a"),
+    ("yellow", "#c88800"),
+    ("orange", "#ed5b00"),
+    ("red", "#e62d42"),
+    ("pink", "#d56199"),
+    ("purple", "#9141ac"),
+    ("slate", "#6f8396"),
+];
+
+/// Weeks shown in the completion heatmap of the statistics page.
+const HEATMAP_WEEKS: i64 = 52;
+
+fn parse_agenda_time(text: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(text.trim(), "%H:%M").ok()
+}
+
+/// App actions whose accelerators can be remapped in the settings, with the
+/// label key used there and in the cheatsheet and the built-in default.
+const SHORTCUT_ACTIONS: &[(&str, &str, &[&str])] = &[
+    ("app.new-task", "key_new", &["<Primary>n"]),
+    ("app.search", "key_search", &["<Primary>f"]),
+    ("app.add-from-selection", "key_add_from_selection", &["<Primary><Shift>v"]),
+    ("app.reload", "key_reload", &["<Primary>r"]),
+    ("app.export-html", "key_export", &["<Primary>e"]),
+    ("app.show-today", "key_show_today", &[]),
+    ("app.open-settings", "settings", &["<Primary>comma"]),
+    ("app.close-window", "key_quit", &["<Primary>w", "<Primary>q", "<Alt>F4"]),
+];
+
+fn default_shortcuts(action: &str) -> &'static [&'static str] {
+    SHORTCUT_ACTIONS
+        .iter()
+        .find(|(name, _, _)| *name == action)
+        .map(|(_, _, defaults)| *defaults)
+        .unwrap_or(&[])
+}
+
+/// Installs the accelerators of all remappable actions, preferring the
+/// user's choice over the default.
+fn apply_shortcuts(app: &impl IsA<gtk::Application>, custom: &HashMap<String, Vec<String>>) {
+    for (action, _, defaults) in SHORTCUT_ACTIONS {
+        match custom.get(*action) {
+            Some(accels) => {
+                let accels: Vec<&str> = accels.iter().map(String::as_str).collect();
+                app.set_accels_for_action(action, &accels);
+            }
+            None => app.set_accels_for_action(action, defaults),
+        }
+    }
+}
+
+/// Parses a space separated list like `<Primary>n <Alt>n` into canonical
+/// accelerator names; `None` if any of them is invalid.
+fn parse_shortcuts(text: &str) -> Option<Vec<String>> {
+    text.split_whitespace()
+        .map(|accel| gtk::accelerator_parse(accel).map(|(key, mods)| gtk::accelerator_name(key, mods).to_string()))
+        .collect()
+}
+
+/// Human readable form of an accelerator, e.g. `Ctrl+R`.
+fn shortcut_label(accel: &str) -> String {
+    gtk::accelerator_parse(accel)
+        .map(|(key, mods)| gtk::accelerator_get_label(key, mods).to_string())
+        .unwrap_or_else(|| accel.to_string())
+}
+
+/// Checks once a minute whether the morning agenda or a task reminder is due.
+/// Polling instead of long timers keeps it correct across suspend and setting
+/// changes.
+fn schedule_daily_agenda(state: Rc<AppState>) {
+    glib::timeout_add_seconds_local(60, clone!(@weak state => @default-return glib::ControlFlow::Break, move || {
+        state.send_agenda_if_due();
+        state.send_due_reminders();
+        glib::ControlFlow::Continue
+    }));
+}
+
+fn schedule_calendar_refresh(state: Rc<AppState>) {
+    state.refresh_calendar(false);
+    glib::timeout_add_seconds_local(CALENDAR_REFRESH_SECONDS, clone!(@weak state => @default-return glib::ControlFlow::Break, move || {
+        state.refresh_calendar(false);
+        glib::ControlFlow::Continue
+    }));
+}
+
+/// Picks up saves reported by the data layer for the status indicator.
+fn schedule_status_refresh(state: Rc<AppState>) {
+    glib::timeout_add_seconds_local(1, clone!(@weak state => @default-return glib::ControlFlow::Break, move || {
+        state.sync_save_status();
+        state.sync_offline_status();
+        glib::ControlFlow::Continue
+    }));
+}
+
 fn schedule_poll(state: Rc<AppState>, interval: u32) {
     glib::timeout_add_seconds_local(interval, clone!(@weak state => @default-return glib::ControlFlow::Break, move || {
+        let was_missing = state.database_missing.get();
         let next_interval = match state.check_for_updates() {
-            Ok(_) => 10,
+            Ok(_) => {
+                if was_missing && !state.database_missing.get() {
+                    state.install_monitor();
+                }
+                state.poll_interval()
+            }
             Err(e) => {
-                eprintln!("{}", t("auto_reload_error").replace("{}", &e.to_string()));
+                let message = t("auto_reload_error").replace("{}", &e.to_string());
+                tracing::warn!("{message}");
+                state.set_persistence(Persistence::Error(message));
                 std::cmp::min(interval * 2, 300)
             }
         };
@@ -125,7 +667,60 @@ fn schedule_poll(state: Rc<AppState>, interval: u32) {
     }));
 }
 
-pub fn build_ui(app: &Application, debug_mode: bool) -> Result<()> {
+/// The database as a GIO file, for monitoring and mounting.
+fn database_file() -> gio::File {
+    match data::get_backend_config() {
+        data::BackendConfig::Uri(uri) => gio::File::for_uri(&uri),
+        _ => gio::File::for_path(data::todo_path()),
+    }
+}
+
+/// The remote database if its share still has to be mounted.
+fn unmounted_database() -> Option<gio::File> {
+    if !matches!(data::get_backend_config(), data::BackendConfig::Uri(_)) {
+        return None;
+    }
+    let file = database_file();
+    match file.find_enclosing_mount(gio::Cancellable::NONE) {
+        Err(err) if err.matches(gio::IOErrorEnum::NotMounted) => Some(file),
+        _ => None,
+    }
+}
+
+/// False where a monitor exists but misses changes made elsewhere: network
+/// file systems, GIO's own polling monitor and Document portal mounts.
+fn file_events_reliable(file: &gio::File, monitor: &gio::FileMonitor) -> bool {
+    if monitor.type_().name() == "GPollFileMonitor" {
+        return false;
+    }
+    if file.path().is_some_and(|path| sandbox::document_id(&path).is_some()) {
+        return false;
+    }
+    let remote = file
+        .query_filesystem_info(gio::FILE_ATTRIBUTE_FILESYSTEM_REMOTE, gio::Cancellable::NONE)
+        .map(|info| info.boolean(gio::FILE_ATTRIBUTE_FILESYSTEM_REMOTE))
+        .unwrap_or(false);
+    !remote
+}
+
+/// Rebinds all rows right after midnight so overdue/due-today styling follows the date.
+fn schedule_midnight_refresh(state: Rc<AppState>) {
+    let now = Local::now();
+    let next_midnight = (now.date_naive() + Duration::days(1))
+        .and_hms_opt(0, 0, 1)
+        .expect("valid time");
+    let wait = (next_midnight - now.naive_local()).num_seconds().max(1) as u32;
+    glib::timeout_add_seconds_local(wait, clone!(@weak state => @default-return glib::ControlFlow::Break, move || {
+        state.repopulate_store();
+        state.run_daily_passes();
+        schedule_midnight_refresh(state);
+        glib::ControlFlow::Break
+    }));
+}
+
+/// Builds the main window. In `service_mode` it stays hidden and closing it
+/// only hides it again, so scheduled reminders keep running.
+pub fn build_ui(app: &Application, debug_mode: bool, service_mode: bool) -> Result<()> {
     let provider = gtk::CssProvider::new();
     provider.load_from_string(
         "@keyframes pulse {
@@ -135,6 +730,59 @@ pub fn build_ui(app: &Application, debug_mode: bool) -> Result<()> {
         }
         .pulse {
             animation: pulse 1s infinite;
+            color: @error_color;
+        }
+        .todo-overdue .todo-meta {
+            color: @error_color;
+        }
+        .todo-due-today .todo-title {
+            font-weight: bold;
+            color: @accent_color;
+        }
+        .todo-done .todo-title {
+            text-decoration: line-through;
+        }
+        .todo-aged .todo-meta {
+            color: @warning_color;
+        }
+        .heat-cell {
+            min-width: 10px;
+            min-height: 10px;
+            border-radius: 2px;
+            background-color: alpha(@view_fg_color, 0.08);
+        }
+        .heat-1 { background-color: alpha(@accent_bg_color, 0.3); }
+        .heat-2 { background-color: alpha(@accent_bg_color, 0.55); }
+        .heat-3 { background-color: alpha(@accent_bg_color, 0.8); }
+        .heat-4 { background-color: @accent_bg_color; }
+        .matrix-quadrant {
+            border-radius: 12px;
+            background-color: alpha(@view_fg_color, 0.04);
+        }
+        .graph-node {
+            padding: 4px 10px;
+        }
+        .calendar-event {
+            opacity: 0.7;
+        }
+        .timeline-bar {
+            padding: 0 6px;
+            border-radius: 6px;
+            background-color: alpha(@accent_bg_color, 0.3);
+        }
+        .timeline-bar:hover {
+            background-color: alpha(@accent_bg_color, 0.45);
+        }
+        .matrix-quadrant:drop(active) {
+            background-color: alpha(@accent_bg_color, 0.2);
+        }
+        .priority-badge {
+            padding: 0 7px;
+            border-radius: 99px;
+            font-size: smaller;
+            font-weight: bold;
+            color: @accent_fg_color;
+            background-color: @accent_bg_color;
         }",
     );
     gtk::style_context_add_provider_for_display(
@@ -143,6 +791,8 @@ pub fn build_ui(app: &Application, debug_mode: bool) -> Result<()> {
         gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
     );
 
+    let user_css = install_user_css();
+
     let window = adw::ApplicationWindow::builder()
         .application(app)
         .title(&t("app_title"))
@@ -150,9 +800,8 @@ pub fn build_ui(app: &Application, debug_mode: bool) -> Result<()> {
         .default_height(780)
         .build();
 
-    let header = adw::HeaderBar::builder()
-        .title_widget(&gtk::Label::builder().label(&t("app_title")).build())
-        .build();
+    let window_title = adw::WindowTitle::new(&t("app_title"), "");
+    let header = adw::HeaderBar::builder().title_widget(&window_title).build();
 
     let search_entry = gtk::SearchEntry::builder()
         .placeholder_text(&t("search_placeholder"))
@@ -175,6 +824,58 @@ pub fn build_ui(app: &Application, debug_mode: bool) -> Result<()> {
     settings_btn.add_css_class("flat");
     header.pack_start(&settings_btn);
 
+    let app_menu = gio::Menu::new();
+    app_menu.append(Some(&t("open_database")), Some("app.open-database"));
+    app_menu.append(Some(&t("open_location")), Some("app.open-location"));
+    app_menu.append(Some(&t("statistics")), Some("app.statistics"));
+    app_menu.append(Some(&t("starter_new_database")), Some("app.new-database"));
+    app_menu.append(Some(&t("template_new_from")), Some("app.use-template"));
+    app_menu.append(Some(&t("template_save")), Some("app.save-template"));
+    app_menu.append(Some(&t("find_duplicates")), Some("app.find-duplicates"));
+    app_menu.append(Some(&t("merge_file")), Some("app.merge-file"));
+    let import_menu = gio::Menu::new();
+    for format in ImportFormat::ALL {
+        import_menu.append(Some(&format.label()), Some(&format!("app.import::{}", format.as_key())));
+    }
+    import_menu.append(Some(&t("import_checklist")), Some("app.import-checklist"));
+    app_menu.append_submenu(Some(&t("import_from")), &import_menu);
+    app_menu.append(Some(&t("export_html")), Some("app.export-html"));
+    app_menu.append(Some(&t("export_taskwarrior")), Some("app.export-taskwarrior"));
+    app_menu.append(Some(&t("digest_email")), Some("app.email-digest"));
+    let menu_btn = gtk::MenuButton::builder()
+        .icon_name("view-more-symbolic")
+        .tooltip_text(t("more_actions"))
+        .menu_model(&app_menu)
+        .build();
+    menu_btn.add_css_class("flat");
+    header.pack_end(&menu_btn);
+
+    let status_details = gtk::Label::builder()
+        .xalign(0.0)
+        .wrap(true)
+        .max_width_chars(40)
+        .selectable(true)
+        .build();
+    let status_reload_btn = gtk::Button::builder()
+        .label(t("reload"))
+        .action_name("app.reload")
+        .halign(gtk::Align::End)
+        .build();
+    let status_box = gtk::Box::new(gtk::Orientation::Vertical, 12);
+    status_box.set_margin_top(6);
+    status_box.set_margin_bottom(6);
+    status_box.set_margin_start(6);
+    status_box.set_margin_end(6);
+    status_box.append(&status_details);
+    status_box.append(&status_reload_btn);
+    let status_btn = gtk::MenuButton::builder()
+        .icon_name(Persistence::Loaded.icon_name())
+        .popover(&gtk::Popover::builder().child(&status_box).build())
+        .build();
+    status_btn.add_css_class("flat");
+    status_btn.add_css_class("dim-label");
+    header.pack_start(&status_btn);
+
     let add_task_btn = gtk::ToggleButton::builder()
         .icon_name("list-add-symbolic")
         .tooltip_text(&t("add"))
@@ -196,11 +897,26 @@ pub fn build_ui(app: &Application, debug_mode: bool) -> Result<()> {
     refresh_btn.add_css_class("flat");
     header.pack_end(&refresh_btn);
 
+    let suggest_btn = gtk::Button::builder()
+        .icon_name("dialog-question-symbolic")
+        .tooltip_text(t("suggest_next"))
+        .action_name("app.suggest-next")
+        .build();
+    suggest_btn.add_css_class("flat");
+    header.pack_end(&suggest_btn);
+
     let overlay = adw::ToastOverlay::new();
     overlay.set_hexpand(true);
     overlay.set_vexpand(true);
-    let store = gio::ListStore::new::<BoxedAnyObject>();
-    let state = Rc::new(AppState::new(&window, &overlay, &store, debug_mode));
+    let state = Rc::new(AppState::new(&window, &overlay, debug_mode));
+    *state.css_monitor.borrow_mut() = user_css;
+    // Above the app styles, below the user's style.css.
+    if let Some(display) = gdk::Display::default() {
+        gtk::style_context_add_provider_for_display(&display, &state.accent_provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION + 1);
+    }
+    *state.status_button.borrow_mut() = Some(status_btn);
+    *state.status_details.borrow_mut() = Some(status_details);
+    *state.window_title.borrow_mut() = Some(window_title);
 
     // Neue To-do Eingabezeile unter den Filtereinstellungen
     let new_row = gtk::Box::new(gtk::Orientation::Horizontal, 6);
@@ -212,7 +928,15 @@ pub fn build_ui(app: &Application, debug_mode: bool) -> Result<()> {
     let new_entry = gtk::Entry::new();
     new_entry.set_placeholder_text(Some(&t("new_todo_placeholder")));
     new_entry.set_hexpand(true);
+    // Ctrl+. opens the same chooser.
+    new_entry.set_show_emoji_icon(true);
     new_row.append(&new_entry);
+    install_completion(Rc::downgrade(&state), &new_entry, Completion::Inline);
+    spelling::attach(&new_entry);
+    *state.quick_add_entry.borrow_mut() = Some(new_entry.clone());
+    new_entry.connect_changed(clone!(@weak state => move |_| {
+        state.schedule_draft_save();
+    }));
 
     let search_btn_for_stop = search_btn.clone();
     search_entry.connect_stop_search(move |_| {
@@ -277,6 +1001,18 @@ pub fn build_ui(app: &Application, debug_mode: bool) -> Result<()> {
     due_filter.set_active(state.show_due_only());
     controls.append(&due_filter);
 
+    let view_spacer = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+    view_spacer.set_hexpand(true);
+    controls.append(&view_spacer);
+
+    let view_selector = gtk::DropDown::from_strings(&[&t("view_list"), &t("view_columns"), &t("view_contexts"), &t("view_focus"), &t("view_matrix"), &t("view_graph"), &t("view_timeline"), &t("view_week")]);
+    view_selector.set_tooltip_text(Some(&t("view")));
+    view_selector.set_selected(state.view_mode().to_index());
+    controls.append(&view_selector);
+    *state.sort_selector.borrow_mut() = Some(sort_selector.clone());
+    *state.due_filter.borrow_mut() = Some(due_filter.clone());
+    *state.view_selector.borrow_mut() = Some(view_selector.clone());
+
     let add_revealer = gtk::Revealer::builder()
         .child(&new_row)
         .transition_type(gtk::RevealerTransitionType::SlideDown)
@@ -294,6 +1030,21 @@ pub fn build_ui(app: &Application, debug_mode: bool) -> Result<()> {
         }
     });
 
+    let add_task_btn_for_search = add_task_btn.clone();
+    search_btn.connect_toggled(clone!(@weak search_revealer, @weak search_entry => move |btn| {
+        let active = btn.is_active();
+        search_revealer.set_reveal_child(active);
+        if active {
+            search_entry.grab_focus();
+            add_task_btn_for_search.set_active(false);
+        } else {
+            search_entry.set_text("");
+        }
+    }));
+    search_entry.connect_search_changed(clone!(@weak state => move |entry| {
+        state.set_search_term(entry.text().trim());
+    }));
+
     let state_for_add = Rc::clone(&state);
     let new_entry_for_add = new_entry.clone();
     add_btn.connect_clicked(move |_| {
@@ -307,13 +1058,13 @@ pub fn build_ui(app: &Application, debug_mode: bool) -> Result<()> {
             Ok(_) => {
                 new_entry_for_add.set_text("");
                 if let Err(err) = state_for_add.reload() {
-                    state_for_add.show_error(&t("reload_error").replace("{}", &err.to_string()));
+                    state_for_add.show_error(&t("reload_error").replace("{}", &describe_error(&err)));
                 } else {
                     state_for_add.show_info(&t("task_added"));
                 }
             }
             Err(err) => {
-                state_for_add.show_error(&t("create_error").replace("{}", &err.to_string()));
+                state_for_add.show_error(&t("create_error").replace("{}", &describe_error(&err)));
             }
         }
     });
@@ -332,25 +1083,37 @@ pub fn build_ui(app: &Application, debug_mode: bool) -> Result<()> {
             Ok(_) => {
                 new_entry_for_add2.set_text("");
                 if let Err(err) = state_for_add2.reload() {
-                    state_for_add2.show_error(&t("reload_error").replace("{}", &err.to_string()));
+                    state_for_add2.show_error(&t("reload_error").replace("{}", &describe_error(&err)));
                 } else {
                     state_for_add2.show_info(&t("task_added"));
                 }
             }
             Err(err) => {
-                state_for_add2.show_error(&t("create_error").replace("{}", &err.to_string()));
+                state_for_add2.show_error(&t("create_error").replace("{}", &describe_error(&err)));
             }
         }
     });
 
     // Erzeuge das vertikale Content-Layout noch vor dem Einfügen der neuen Zeile
     let content = gtk::Box::new(gtk::Orientation::Vertical, 0);
+    let file_banner = adw::Banner::builder().use_markup(false).build();
+    file_banner.connect_button_clicked(clone!(@weak state => move |_| state.resolve_file_problem()));
+    content.append(&file_banner);
+    *state.file_banner.borrow_mut() = Some(file_banner);
+    let duplicates_banner = adw::Banner::new("");
+    content.append(&duplicates_banner);
+    *state.duplicates_banner.borrow_mut() = Some(duplicates_banner);
+    let problems = create_problems_panel();
+    problems.repair_button.connect_clicked(clone!(@weak state => move |_| state.show_repair_dialog()));
+    content.append(&problems.banner);
+    content.append(&problems.revealer);
+    *state.problems_panel.borrow_mut() = Some(problems);
     content.append(&controls);
     content.append(&search_revealer);
     content.append(&add_revealer);
     content.append(&overlay);
 
-    let list_view = create_list_view(&state);
+    let list_view = create_list_view(&state, &state.store());
     *state.list_view.borrow_mut() = Some(list_view.clone());
     let scrolled = gtk::ScrolledWindow::builder()
         .child(&list_view)
@@ -358,7 +1121,117 @@ pub fn build_ui(app: &Application, debug_mode: bool) -> Result<()> {
         .hexpand(true)
         .build();
     *state.scrolled_window.borrow_mut() = Some(scrolled.clone());
-    overlay.set_child(Some(&scrolled));
+    // Lists coasting on after a flick are motion too.
+    state.apply_reduce_motion();
+    if let Some(settings) = gtk::Settings::default() {
+        scrolled.set_kinetic_scrolling(settings.is_gtk_enable_animations());
+        settings.connect_gtk_enable_animations_notify(clone!(@weak scrolled => move |settings| {
+            scrolled.set_kinetic_scrolling(settings.is_gtk_enable_animations());
+        }));
+    }
+    // A middle click on the list pastes the primary selection into quick add.
+    let paste_selection = gtk::GestureClick::builder().button(gdk::BUTTON_MIDDLE).build();
+    paste_selection.connect_released(clone!(@weak add_task_btn, @weak new_entry => move |gesture, _, _, _| {
+        gesture.set_state(gtk::EventSequenceState::Claimed);
+        new_entry.primary_clipboard().read_text_async(gio::Cancellable::NONE, clone!(@weak add_task_btn, @weak new_entry => move |text| {
+            let Some(title) = text.ok().flatten().and_then(|text| selection_title(&text)) else {
+                return;
+            };
+            add_task_btn.set_active(true);
+            new_entry.set_text(&title);
+            new_entry.set_position(-1);
+        }));
+    }));
+    scrolled.add_controller(paste_selection);
+    let today_events = gtk::Box::new(gtk::Orientation::Vertical, 2);
+    today_events.set_margin_top(6);
+    today_events.set_margin_start(12);
+    today_events.set_margin_end(12);
+    today_events.set_visible(false);
+    let list_page = gtk::Box::new(gtk::Orientation::Vertical, 0);
+    list_page.append(&today_events);
+    list_page.append(&scrolled);
+    *state.today_events.borrow_mut() = Some(today_events);
+
+    let column_view = create_column_view(&state);
+    let column_scrolled = gtk::ScrolledWindow::builder()
+        .child(&column_view)
+        .vexpand(true)
+        .hexpand(true)
+        .build();
+
+    let view_stack = gtk::Stack::new();
+    view_stack.add_named(&list_page, Some(ViewMode::List.as_key()));
+    view_stack.add_named(&column_scrolled, Some(ViewMode::Columns.as_key()));
+
+    let context_stack = adw::ViewStack::new();
+    context_stack.set_vexpand(true);
+    let context_switcher = adw::ViewSwitcher::builder()
+        .stack(&context_stack)
+        .policy(adw::ViewSwitcherPolicy::Wide)
+        .build();
+    let switcher_scrolled = gtk::ScrolledWindow::builder()
+        .child(&context_switcher)
+        .vscrollbar_policy(gtk::PolicyType::Never)
+        .propagate_natural_height(true)
+        .build();
+    let contexts_box = gtk::Box::new(gtk::Orientation::Vertical, 0);
+    contexts_box.append(&switcher_scrolled);
+    contexts_box.append(&context_stack);
+    view_stack.add_named(&contexts_box, Some(ViewMode::Contexts.as_key()));
+    context_stack.connect_visible_child_name_notify(clone!(@weak state => move |stack| {
+        if state.syncing_tabs.get() {
+            return;
+        }
+        if let Some(name) = stack.visible_child_name() {
+            state.remember_context_tab(&name);
+        }
+    }));
+    *state.context_stack.borrow_mut() = Some(context_stack);
+
+    let focus_done_btn = gtk::Button::with_label(&t("done"));
+    focus_done_btn.add_css_class("suggested-action");
+    focus_done_btn.add_css_class("pill");
+    let focus_skip_btn = gtk::Button::with_label(&t("focus_skip"));
+    focus_skip_btn.add_css_class("pill");
+    let focus_postpone_btn = gtk::Button::with_label(&t("postpone_tomorrow"));
+    focus_postpone_btn.add_css_class("pill");
+    let focus_buttons = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+    focus_buttons.set_halign(gtk::Align::Center);
+    focus_buttons.append(&focus_skip_btn);
+    focus_buttons.append(&focus_postpone_btn);
+    focus_buttons.append(&focus_done_btn);
+    let focus_page = adw::StatusPage::builder()
+        .child(&focus_buttons)
+        .vexpand(true)
+        .build();
+    focus_page.add_css_class("todo-focus");
+    view_stack.add_named(&focus_page, Some(ViewMode::Focus.as_key()));
+    view_stack.add_named(&create_matrix_view(&state), Some(ViewMode::Matrix.as_key()));
+    view_stack.add_named(&create_graph_view(&state), Some(ViewMode::Graph.as_key()));
+    view_stack.add_named(&create_timeline_view(&state), Some(ViewMode::Timeline.as_key()));
+    view_stack.add_named(&create_week_view(&state), Some(ViewMode::Week.as_key()));
+    *state.focus_page.borrow_mut() = Some(focus_page);
+    focus_done_btn.connect_clicked(clone!(@weak state => move |_| {
+        state.focus_complete();
+    }));
+    focus_skip_btn.connect_clicked(clone!(@weak state => move |_| {
+        state.focus_skip();
+    }));
+    focus_postpone_btn.connect_clicked(clone!(@weak state => move |_| {
+        state.focus_postpone();
+    }));
+
+    state.store().connect_items_changed(clone!(@weak state => move |_, _, _, _| {
+        state.schedule_view_sync();
+    }));
+    view_stack.set_visible_child_name(state.view_mode().as_key());
+    *state.view_stack.borrow_mut() = Some(view_stack.clone());
+    let database_stack = gtk::Stack::new();
+    database_stack.add_named(&view_stack, Some("views"));
+    database_stack.add_named(&create_missing_page(&state), Some("missing"));
+    *state.database_stack.borrow_mut() = Some(database_stack.clone());
+    overlay.set_child(Some(&database_stack));
 
     let toolbar_view = adw::ToolbarView::new();
     toolbar_view.add_top_bar(&header);
@@ -366,7 +1239,7 @@ pub fn build_ui(app: &Application, debug_mode: bool) -> Result<()> {
 
     window.set_content(Some(&toolbar_view));
 
-    // ESC-Taste zum Schließen der Revealer, ? für Hilfe, Ctrl+N/F für Aktionen
+    // ESC-Taste zum Schließen der Revealer, ? für Hilfe
     let key_controller = gtk::EventControllerKey::new();
     let search_btn_esc = search_btn.clone();
     let add_task_btn_esc = add_task_btn.clone();
@@ -381,12 +1254,6 @@ pub fn build_ui(app: &Application, debug_mode: bool) -> Result<()> {
         } else if key == gdk::Key::question && !has_ctrl {
             state_for_keys.show_cheatsheet();
             glib::Propagation::Stop
-        } else if has_ctrl && (key == gdk::Key::n || key == gdk::Key::N) {
-            add_task_btn_esc.set_active(!add_task_btn_esc.is_active());
-            glib::Propagation::Stop
-        } else if has_ctrl && (key == gdk::Key::f || key == gdk::Key::F) {
-            search_btn_esc.set_active(!search_btn_esc.is_active());
-            glib::Propagation::Stop
         } else {
             glib::Propagation::Proceed
         }
@@ -407,11 +1274,16 @@ pub fn build_ui(app: &Application, debug_mode: bool) -> Result<()> {
     let refresh_action = gio::SimpleAction::new("reload", None);
     refresh_action.connect_activate(clone!(@weak state => move |_, _| {
         if let Err(err) = state.reload() {
-            state.show_error(&t("load_error").replace("{}", &err.to_string()));
+            state.show_error(&t("load_error").replace("{}", &describe_error(&err)));
         }
     }));
     app.add_action(&refresh_action);
-    app.set_accels_for_action("app.reload", &["<Primary>r"]);
+
+    let retry_save_action = gio::SimpleAction::new("retry-save", None);
+    retry_save_action.connect_activate(clone!(@weak state => move |_, _| {
+        state.retry_failed_save();
+    }));
+    app.add_action(&retry_save_action);
 
     let settings_action = gio::SimpleAction::new("open-settings", None);
     let state_for_settings_action = Rc::clone(&state);
@@ -426,28 +1298,227 @@ pub fn build_ui(app: &Application, debug_mode: bool) -> Result<()> {
         window_for_close.close();
     });
     app.add_action(&close_action);
-    app.set_accels_for_action("app.close-window", &["<Primary>w", "<Primary>q", "<Alt>F4"]);
 
-    refresh_btn.connect_clicked(clone!(@weak app => move |_| {
-        let _ = app.activate_action("app.reload", None);
+    let today_action = gio::SimpleAction::new("show-today", None);
+    today_action.connect_activate(clone!(@weak window, @weak due_filter, @weak view_selector => move |_, _| {
+        view_selector.set_selected(ViewMode::List.to_index());
+        due_filter.set_active(true);
+        window.present();
     }));
+    app.add_action(&today_action);
 
-    // Keep state alive for the window lifetime so weak references can upgrade.
-    unsafe {
+    let view_action = gio::SimpleAction::new("show-view", Some(glib::VariantTy::STRING));
+    view_action.connect_activate(clone!(@weak window, @weak view_selector => move |_, parameter| {
+        let Some(key) = parameter.and_then(|p| p.get::<String>()) else {
+            return;
+        };
+        view_selector.set_selected(ViewMode::from_key(&key).to_index());
+        window.present();
+    }));
+    app.add_action(&view_action);
+
+    // Target of task links and `reinschrift show`; the parameter is a marker or title.
+    let show_task_action = gio::SimpleAction::new("show-task", Some(glib::VariantTy::STRING));
+    show_task_action.connect_activate(clone!(@weak window, @weak state, @weak view_selector => move |_, parameter| {
+        let Some(id) = parameter.and_then(|p| p.get::<String>()) else {
+            return;
+        };
+        window.present();
+        view_selector.set_selected(ViewMode::List.to_index());
+        state.reveal_task(&id);
+    }));
+    app.add_action(&show_task_action);
+
+    // Buttons of reminder notifications, so they work without the window.
+    let complete_task_action = gio::SimpleAction::new("complete-task", Some(glib::VariantTy::STRING));
+    complete_task_action.connect_activate(clone!(@weak state => move |_, parameter| {
+        if let Some(id) = parameter.and_then(|p| p.get::<String>()) {
+            state.complete_reminded_task(&id);
+        }
+    }));
+    app.add_action(&complete_task_action);
+
+    let snooze_task_action = gio::SimpleAction::new("snooze-task", Some(glib::VariantTy::STRING));
+    snooze_task_action.connect_activate(clone!(@weak state => move |_, parameter| {
+        if let Some(id) = parameter.and_then(|p| p.get::<String>()) {
+            state.snooze_reminded_task(&id);
+        }
+    }));
+    app.add_action(&snooze_task_action);
+
+    let open_database_action = gio::SimpleAction::new("open-database", None);
+    open_database_action.connect_activate(clone!(@weak state => move |_, _| {
+        state.choose_database();
+    }));
+    app.add_action(&open_database_action);
+
+    let open_location_action = gio::SimpleAction::new("open-location", None);
+    open_location_action.connect_activate(clone!(@weak state => move |_, _| {
+        state.show_open_location_dialog();
+    }));
+    app.add_action(&open_location_action);
+
+    let new_database_action = gio::SimpleAction::new("new-database", None);
+    new_database_action.connect_activate(clone!(@weak state => move |_, _| {
+        state.show_new_database_dialog();
+    }));
+    app.add_action(&new_database_action);
+
+    let duplicates_action = gio::SimpleAction::new("find-duplicates", None);
+    duplicates_action.connect_activate(clone!(@weak state => move |_, _| {
+        state.show_duplicates_dialog();
+    }));
+    app.add_action(&duplicates_action);
+
+    let merge_action = gio::SimpleAction::new("merge-file", None);
+    merge_action.connect_activate(clone!(@weak state => move |_, _| {
+        state.merge_file();
+    }));
+    app.add_action(&merge_action);
+
+    let suggest_action = gio::SimpleAction::new("suggest-next", None);
+    suggest_action.connect_activate(clone!(@weak state => move |_, _| {
+        state.show_suggestion();
+    }));
+    app.add_action(&suggest_action);
+
+    let save_template_action = gio::SimpleAction::new("save-template", None);
+    save_template_action.connect_activate(clone!(@weak state => move |_, _| {
+        state.show_save_template_dialog();
+    }));
+    app.add_action(&save_template_action);
+
+    let use_template_action = gio::SimpleAction::new("use-template", None);
+    use_template_action.connect_activate(clone!(@weak state => move |_, _| {
+        state.show_use_template_dialog();
+    }));
+    app.add_action(&use_template_action);
+
+    let statistics_action = gio::SimpleAction::new("statistics", None);
+    statistics_action.connect_activate(clone!(@weak state => move |_, _| {
+        state.show_statistics();
+    }));
+    app.add_action(&statistics_action);
+
+    let import_action = gio::SimpleAction::new("import", Some(glib::VariantTy::STRING));
+    import_action.connect_activate(clone!(@weak state => move |_, parameter| {
+        let format = parameter.and_then(|p| p.get::<String>()).and_then(|key| ImportFormat::from_key(&key));
+        if let Some(format) = format {
+            state.import_from(format);
+        }
+    }));
+    app.add_action(&import_action);
+
+    let import_checklist_action = gio::SimpleAction::new("import-checklist", None);
+    import_checklist_action.connect_activate(clone!(@weak state => move |_, _| {
+        state.show_checklist_import();
+    }));
+    app.add_action(&import_checklist_action);
+
+    let export_action = gio::SimpleAction::new("export-html", None);
+    export_action.connect_activate(clone!(@weak state => move |_, _| {
+        state.export_html();
+    }));
+    app.add_action(&export_action);
+
+    let export_taskwarrior_action = gio::SimpleAction::new("export-taskwarrior", None);
+    export_taskwarrior_action.connect_activate(clone!(@weak state => move |_, _| {
+        state.export_taskwarrior();
+    }));
+    app.add_action(&export_taskwarrior_action);
+
+    let digest_action = gio::SimpleAction::new("email-digest", None);
+    digest_action.connect_activate(clone!(@weak state => move |_, _| {
+        state.email_digest();
+    }));
+    app.add_action(&digest_action);
+
+    let new_task_action = gio::SimpleAction::new("new-task", None);
+    new_task_action.connect_activate(clone!(@weak window, @weak add_task_btn => move |_, _| {
+        window.present();
+        add_task_btn.set_active(true);
+    }));
+    app.add_action(&new_task_action);
+
+    let selection_action = gio::SimpleAction::new("add-from-selection", None);
+    selection_action.connect_activate(clone!(@weak state => move |_, _| {
+        state.add_from_selection();
+    }));
+    app.add_action(&selection_action);
+
+    let search_action = gio::SimpleAction::new("search", None);
+    search_action.connect_activate(clone!(@weak search_btn => move |_, _| {
+        search_btn.set_active(!search_btn.is_active());
+    }));
+    app.add_action(&search_action);
+
+    apply_shortcuts(app, &state.preferences.borrow().shortcuts);
+
+    refresh_btn.connect_clicked(clone!(@weak app => move |_| {
+        let _ = app.activate_action("app.reload", None);
+    }));
+
+    // Closing the window also destroys an open task dialog.
+    window.connect_close_request(clone!(@weak state => @default-return glib::Propagation::Proceed, move |_| {
+        state.save_draft();
+        glib::Propagation::Proceed
+    }));
+
+    // Keep state alive for the window lifetime so weak references can upgrade.
+    unsafe {
         window.set_data("app-state", state.clone());
     }
 
-    window.present();
+    if service_mode {
+        window.set_hide_on_close(true);
+        tracing::info!("running as background service");
+    } else {
+        profile::watch_first_frame(&window);
+        window.present();
+    }
 
-    if let Err(err) = state.reload() {
-        let err_msg = err.to_string();
-        let msg = if err_msg == t("no_database_configured") {
-            err_msg
+    if unmounted_database().is_some() {
+        state.mount_and_reload();
+    } else if let Err(err) = profile::measure("file parse", || state.reload()) {
+        let no_database = matches!(err.downcast_ref::<DataError>(), Some(DataError::NoDatabase));
+        if no_database && !service_mode {
+            // First launch: no database on the command line or from before.
+            state.show_welcome_dialog();
+        } else if state.database_missing.get() {
+            // The missing page offers the ways out.
         } else {
-            format!("{}\n{}", t("load_error").replace("{}", &err_msg), t("select_valid_file"))
-        };
-        state.show_error(&msg);
-        state.show_settings_dialog(None);
+            let msg = if no_database {
+                describe_error(&err)
+            } else if err.downcast_ref::<DataError>().is_some() {
+                t("load_error").replace("{}", &describe_error(&err))
+            } else {
+                format!("{}\n{}", t("load_error").replace("{}", &describe_error(&err)), t("select_valid_file"))
+            };
+            state.show_error(&msg);
+            if service_mode {
+                // Nothing to show a dialog in.
+            } else if repair::scan().is_ok_and(|repairs| !repairs.is_empty()) {
+                state.show_repair_dialog();
+            } else {
+                state.show_settings_dialog(None);
+            }
+        }
+    } else {
+        state.run_daily_passes();
+        if service_mode {
+            // Nothing to show a dialog in.
+        } else if demo::is_active() {
+            state.show_info(&t("demo_active"));
+        } else if state.first_run {
+            // The new list is already open; the assistant offers the other ways in.
+            state.show_welcome_dialog();
+        } else {
+            state.offer_draft_recovery();
+        }
+    }
+    if !service_mode {
+        // After the first load, which finds the included files to check too.
+        state.offer_journal_recovery();
     }
 
     sort_selector.connect_selected_notify(clone!(@weak state => move |dropdown| {
@@ -459,127 +1530,206 @@ pub fn build_ui(app: &Application, debug_mode: bool) -> Result<()> {
         state.set_show_due_only(btn.is_active());
     }));
 
-    if let Err(err) = state.install_monitor() {
-        state.show_error(&t("monitor_error").replace("{}", &err.to_string()));
+    if let Some(settings) = &state.settings {
+        for (key, property) in settings::WINDOW_KEYS {
+            settings.bind(key, &window, property).build();
+        }
+        settings.bind("show-due-only", &due_filter, "active").build();
+        // Picks up edits made with dconf/gsettings while the app runs.
+        settings.connect_changed(None, clone!(@weak state => move |_, _| {
+            state.sync_preferences_from_settings();
+        }));
     }
 
-    schedule_poll(state, 10);
-
-    Ok(())
-}
+    view_selector.connect_selected_notify(clone!(@weak state => move |dropdown| {
+        state.set_view_mode(ViewMode::from_index(dropdown.selected()));
+    }));
 
-fn create_list_view(state: &Rc<AppState>) -> gtk::ListView {
-    let factory = gtk::SignalListItemFactory::new();
-    let state_weak = Rc::downgrade(state);
-    let factory_state = state_weak.clone();
+    state.install_monitor();
 
-    factory.connect_setup(move |_, list_item_obj| {
-        let Some(list_item) = list_item_obj.downcast_ref::<gtk::ListItem>() else {
-            return;
-        };
+    schedule_midnight_refresh(Rc::clone(&state));
+    schedule_daily_agenda(Rc::clone(&state));
+    schedule_calendar_refresh(Rc::clone(&state));
+    schedule_status_refresh(Rc::clone(&state));
+    schedule_poll(state, MONITORED_POLL_INTERVAL);
 
-        let stack = gtk::Stack::new();
-        stack.set_transition_type(gtk::StackTransitionType::None);
-        stack.set_hexpand(true);
+    Ok(())
+}
 
-        // Header row
-        let header_box = gtk::Box::new(gtk::Orientation::Horizontal, 0);
-        header_box.set_margin_start(12);
-        header_box.set_margin_end(12);
-        header_box.set_margin_top(8);
-        header_box.set_margin_bottom(4);
-        let header_label = gtk::Label::builder()
-            .xalign(0.0)
-            .label("")
-            .build();
-        header_label.add_css_class("heading");
-        header_label.add_css_class("dim-label");
-        header_box.append(&header_label);
-        stack.add_named(&header_box, Some("header"));
+/// Handles `GApplication::open`: switches the running window to `path`,
+/// building the window first when the app was launched just for this file.
+/// Handles a `reinschrift://task/<id>` link: shows the window and the task.
+pub fn open_task_link(app: &Application, uri: &str, service_mode: bool) -> Result<()> {
+    let prefix = format!("{}://task/", data::TASK_URI_SCHEME);
+    let Some(id) = uri.strip_prefix(&prefix).and_then(|id| glib::Uri::unescape_string(id, None::<&str>)) else {
+        bail!(t("task_link_invalid").replace("{}", uri));
+    };
+    if app.windows().is_empty() {
+        build_ui(app, false, service_mode)?;
+    }
+    app.activate_action("show-task", Some(&id.to_variant()));
+    Ok(())
+}
 
-        // Todo row
-        let container = gtk::Box::new(gtk::Orientation::Horizontal, 12);
-        container.set_homogeneous(false);
-        container.set_margin_start(12);
-        container.set_margin_end(12);
-        container.set_margin_top(6);
-        container.set_margin_bottom(6);
+/// Like [`open_database`], for a GIO location such as `sftp://nas/todo.md`.
+pub fn open_location(app: &Application, uri: &str, service_mode: bool) -> Result<()> {
+    if app.windows().is_empty() {
+        data::set_database(uri);
+        build_ui(app, false, service_mode)?;
+    }
+    let Some(window) = app.windows().first().cloned() else {
+        return Ok(());
+    };
+    let state = unsafe { window.data::<Rc<AppState>>("app-state") }.map(|ptr| unsafe { ptr.as_ref() }.clone());
+    if let Some(state) = state {
+        state.open_location(uri);
+    }
+    window.present();
+    Ok(())
+}
 
-        let check = gtk::CheckButton::new();
-        check.set_valign(gtk::Align::Center);
-        container.append(&check);
+pub fn open_database(app: &Application, path: PathBuf, service_mode: bool) -> Result<()> {
+    if app.windows().is_empty() {
+        data::set_todo_path(path.clone());
+        build_ui(app, false, service_mode)?;
+    }
+    let Some(window) = app.windows().first().cloned() else {
+        return Ok(());
+    };
+    let state = unsafe { window.data::<Rc<AppState>>("app-state") }.map(|ptr| unsafe { ptr.as_ref() }.clone());
+    if let Some(state) = state {
+        state.open_database(path);
+    }
+    window.present();
+    Ok(())
+}
 
-        let column = gtk::Box::new(gtk::Orientation::Vertical, 4);
-        let title = gtk::Label::builder()
-            .xalign(0.0)
-            .ellipsize(pango::EllipsizeMode::End)
-            .wrap(true)
-            .wrap_mode(pango::WrapMode::WordChar)
-            .build();
-        title.add_css_class("title-4");
-        column.append(&title);
+/// Stylesheet replacing the system accent with the pinned colour `key`,
+/// `None` for unknown keys. Sets the variables of libadwaita 1.6 and the
+/// named colours before it.
+fn accent_css(key: &str) -> Option<String> {
+    let (_, color) = ACCENT_COLORS.iter().find(|(name, _)| *name == key)?;
+    Some(format!(
+        ":root {{ --accent-bg-color: {color}; }}\n@define-color accent_bg_color {color};\n@define-color accent_color {color};"
+    ))
+}
 
-        let meta = gtk::Label::builder()
-            .xalign(0.0)
-            .wrap(true)
-            .wrap_mode(pango::WrapMode::WordChar)
-            .build();
-        meta.add_css_class("dim-label");
-        column.append(&meta);
+fn user_css_path() -> PathBuf {
+    let mut path = glib::user_config_dir();
+    path.push("reinschrift");
+    path.push("style.css");
+    path
+}
 
-        container.append(&column);
+/// Loads the user's `style.css` above the app styles and reloads it whenever
+/// it changes. The returned monitor has to be kept alive.
+fn install_user_css() -> Option<gio::FileMonitor> {
+    let display = gdk::Display::default()?;
+    let provider = gtk::CssProvider::new();
+    provider.connect_parsing_error(|_, section, error| {
+        tracing::warn!("style.css {}: {}", section.to_str(), error);
+    });
+    gtk::style_context_add_provider_for_display(&display, &provider, gtk::STYLE_PROVIDER_PRIORITY_USER);
 
-        let spacer = gtk::Box::new(gtk::Orientation::Horizontal, 0);
-        spacer.set_hexpand(true);
-        container.append(&spacer);
+    let path = user_css_path();
+    let load = clone!(@weak provider, @strong path => move || {
+        if path.exists() {
+            provider.load_from_path(&path);
+            tracing::info!(path = %path.display(), "user stylesheet loaded");
+        } else {
+            provider.load_from_string("");
+        }
+    });
+    load();
+
+    let monitor = gio::File::for_path(&path)
+        .monitor_file(gio::FileMonitorFlags::NONE, Option::<&gio::Cancellable>::None)
+        .map_err(|err| tracing::warn!("Cannot watch {}: {err}", path.display()))
+        .ok()?;
+    monitor.connect_changed(move |_, _, _, event| {
+        if matches!(event, gio::FileMonitorEvent::ChangesDoneHint | gio::FileMonitorEvent::Created | gio::FileMonitorEvent::Deleted) {
+            load();
+        }
+    });
+    Some(monitor)
+}
 
-        let today_btn = gtk::Button::builder()
-            .icon_name("x-office-calendar-symbolic")
-            .tooltip_text(&t("set_due_today"))
-            .build();
-        today_btn.set_valign(gtk::Align::Center);
-        today_btn.add_css_class("flat");
-        container.append(&today_btn);
+fn create_list_view(state: &Rc<AppState>, entries: &impl IsA<gio::ListModel>) -> gtk::ListView {
+    let factory = gtk::SignalListItemFactory::new();
+    let state_weak = Rc::downgrade(state);
+    let factory_state = state_weak.clone();
 
-        let postpone_btn = gtk::Button::builder()
-            .icon_name("go-next-symbolic")
-            .tooltip_text(&t("postpone_tomorrow"))
-            .build();
-        postpone_btn.set_valign(gtk::Align::Center);
-        postpone_btn.add_css_class("flat");
-        container.append(&postpone_btn);
+    factory.connect_setup(move |_, list_item_obj| {
+        let Some(list_item) = list_item_obj.downcast_ref::<gtk::ListItem>() else {
+            return;
+        };
 
-        let sometimes_btn = gtk::Button::builder()
-            .icon_name("clock-symbolic")
-            .tooltip_text(&t("postpone_sometimes"))
-            .build();
-        sometimes_btn.set_valign(gtk::Align::Center);
-        sometimes_btn.add_css_class("flat");
-        container.append(&sometimes_btn);
+        let row = TodoRow::new();
+        list_item.set_child(Some(&row));
+        if let Some(state) = factory_state.upgrade() {
+            state.rows.borrow_mut().push(row.downgrade());
+        }
 
-        stack.add_named(&container, Some("item"));
-        list_item.set_child(Some(&stack));
+        let header_box = row.header_box();
+        let rename_btn = row.rename_button();
+        let rename_state = factory_state.clone();
+        rename_btn.connect_clicked(clone!(@weak row => move |_| {
+            if let Some(state) = rename_state.upgrade() {
+                state.show_rename_tag_dialog(&row.header_text());
+            }
+        }));
+        let header_click = gtk::GestureClick::new();
+        let header_state = factory_state.clone();
+        header_click.connect_released(clone!(@weak row => move |_, _, _, _| {
+            if let Some(state) = header_state.upgrade() {
+                state.toggle_section(&row.header_text());
+            }
+        }));
+        header_box.add_controller(header_click);
+
+        let container = row.task_box();
+        let check = row.check_button();
+        let title = row.title_label();
+        let open_btn = row.open_button();
+        let star_btn = row.star_button();
+        let today_btn = row.today_button();
+        let postpone_btn = row.postpone_button();
+        let sometimes_btn = row.sometimes_button();
+
+        // Title and checkbox follow the task object; the rest is set on bind.
+        let item_expression = list_item.property_expression("item");
+        item_expression.chain_property::<TodoObject>("title").bind(&title, "label", gtk::Widget::NONE);
+        item_expression.chain_property::<TodoObject>("done").bind(&check, "active", gtk::Widget::NONE);
 
         // Keyboard shortcuts for list items
         let key_controller = gtk::EventControllerKey::new();
         let state_item_key = factory_state.clone();
         let weak_list_item = list_item.downgrade();
         
-        key_controller.connect_key_pressed(move |_, keyval, _, _| {
+        key_controller.connect_key_pressed(move |_, keyval, _, modifiers| {
             let Some(list_item) = weak_list_item.upgrade() else { return glib::Propagation::Proceed; };
             let Some(obj) = list_item.item() else { return glib::Propagation::Proceed; };
-            let Ok(todo_obj) = obj.downcast::<BoxedAnyObject>() else { return glib::Propagation::Proceed; };
-            let entry = todo_obj.borrow::<ListEntry>();
-            let todo = match &*entry {
-                ListEntry::Item(todo) => todo.clone(),
-                ListEntry::Header(_) => return glib::Propagation::Proceed,
-            };
-            
+            let Ok(todo_obj) = obj.downcast::<TodoObject>() else { return glib::Propagation::Proceed; };
             let Some(state) = state_item_key.upgrade() else { return glib::Propagation::Proceed; };
+            let Some(todo) = todo_obj.todo() else {
+                if !matches!(keyval, gdk::Key::space | gdk::Key::Return | gdk::Key::KP_Enter) {
+                    return glib::Propagation::Proceed;
+                }
+                state.toggle_section(&todo_obj.header());
+                return glib::Propagation::Stop;
+            };
             
-            let unicode = keyval.to_unicode();
+            if let Some(days) = due_nudge_days(keyval, modifiers) {
+                if let Err(err) = state.nudge_due(&todo, days) {
+                    state.show_error(&describe_error(&err));
+                }
+                return glib::Propagation::Stop;
+            }
+            // While a type-ahead is under way, keys belong to its text.
+            let typing = state.type_ahead_pending();
+            let unicode = keyval.to_unicode().filter(|_| !typing);
             match keyval {
-                gdk::Key::space => {
+                gdk::Key::space if !typing => {
                     let _ = state.toggle_item(&todo, !todo.done);
                     glib::Propagation::Stop
                 }
@@ -587,9 +1737,7 @@ fn create_list_view(state: &Rc<AppState>) -> gtk::ListView {
                     let _ = state.set_due_today(&todo);
                     glib::Propagation::Stop
                 }
-                _ if unicode == Some('+') || unicode == Some('*') || unicode == Some('=') || 
-                     keyval == gdk::Key::plus || keyval == gdk::Key::KP_Add || 
-                     keyval == gdk::Key::asterisk || keyval == gdk::Key::KP_Multiply => {
+                _ if unicode.is_some() && matches!(keyval, gdk::Key::plus | gdk::Key::equal | gdk::Key::KP_Add | gdk::Key::asterisk | gdk::Key::KP_Multiply) => {
                     let _ = state.set_due_in_days(&todo, 1);
                     glib::Propagation::Stop
                 }
@@ -597,19 +1745,50 @@ fn create_list_view(state: &Rc<AppState>) -> gtk::ListView {
                     let _ = state.set_due_sometimes(&todo);
                     glib::Propagation::Stop
                 }
+                _ if (unicode == Some('o') || unicode == Some('O')) && todo.file_link().is_some() => {
+                    state.open_linked_file(&todo);
+                    glib::Propagation::Stop
+                }
                 _ => glib::Propagation::Proceed,
             }
         });
-        stack.add_controller(key_controller);
+        row.add_controller(key_controller);
+
+        // Tasks can be dragged onto the quadrants of the matrix view.
+        let drag_source = gtk::DragSource::new();
+        drag_source.set_actions(gdk::DragAction::MOVE);
+        let drag_list_item = list_item.downgrade();
+        drag_source.connect_prepare(move |_, _, _| {
+            let obj = drag_list_item.upgrade()?.item()?;
+            list_entry_todo(&obj)?;
+            Some(gdk::ContentProvider::for_value(&obj.to_value()))
+        });
+        drag_source.connect_drag_begin(clone!(@weak container => move |source, _| {
+            source.set_icon(Some(&gtk::WidgetPaintable::new(Some(&container))), 0, 0);
+        }));
+        row.add_controller(drag_source);
 
-        unsafe {
-            list_item.set_data("stack", stack.downgrade());
-            list_item.set_data("header-label", header_label.downgrade());
-            list_item.set_data("todo-check", check.downgrade());
-            list_item.set_data("todo-title", title.downgrade());
-            list_item.set_data("todo-meta", meta.downgrade());
-            list_item.set_data("todo-button", postpone_btn.downgrade());
-        }
+        let open_list = list_item.downgrade();
+        let open_state = factory_state.clone();
+        open_btn.connect_clicked(move |_| {
+            let Some(todo) = open_list.upgrade().and_then(|item| item.item()).and_then(|obj| list_entry_todo(&obj)) else {
+                return;
+            };
+            if let Some(state) = open_state.upgrade() {
+                state.open_linked_file(&todo);
+            }
+        });
+
+        let star_list = list_item.downgrade();
+        let star_state = factory_state.clone();
+        star_btn.connect_clicked(move |_| {
+            let Some(todo) = star_list.upgrade().and_then(|item| item.item()).and_then(|obj| list_entry_todo(&obj)) else {
+                return;
+            };
+            if let Some(state) = star_state.upgrade() {
+                state.toggle_pinned(&todo);
+            }
+        });
 
         let weak_list = list_item.downgrade();
         let state_for_handler = factory_state.clone();
@@ -620,21 +1799,16 @@ fn create_list_view(state: &Rc<AppState>) -> gtk::ListView {
             let Some(obj) = list_item.item() else {
                 return;
             };
-            let Ok(todo_obj) = obj.downcast::<BoxedAnyObject>() else {
+            let Some(todo) = list_entry_todo(&obj) else {
                 return;
             };
-            let entry = todo_obj.borrow::<ListEntry>();
-            let todo = match &*entry {
-                ListEntry::Item(todo) => todo.clone(),
-                ListEntry::Header(_) => return,
-            };
             if btn.is_active() == todo.done {
                 return;
             }
 
             if let Some(state) = state_for_handler.upgrade() {
                 if let Err(err) = state.toggle_item(&todo, btn.is_active()) {
-                    state.show_error(&t("update_error").replace("{}", &err.to_string()));
+                    state.show_error(&t("update_error").replace("{}", &describe_error(&err)));
                 }
             }
         });
@@ -648,14 +1822,9 @@ fn create_list_view(state: &Rc<AppState>) -> gtk::ListView {
             let Some(obj) = list_item.item() else {
                 return;
             };
-            let Ok(todo_obj) = obj.downcast::<BoxedAnyObject>() else {
+            let Some(todo) = list_entry_todo(&obj) else {
                 return;
             };
-            let entry = todo_obj.borrow::<ListEntry>();
-            let todo = match &*entry {
-                ListEntry::Item(todo) => todo.clone(),
-                ListEntry::Header(_) => return,
-            };
 
             if let Some(state) = postpone_state.upgrade() {
                 state.show_due_shortcuts(&todo);
@@ -671,18 +1840,13 @@ fn create_list_view(state: &Rc<AppState>) -> gtk::ListView {
             let Some(obj) = list_item.item() else {
                 return;
             };
-            let Ok(todo_obj) = obj.downcast::<BoxedAnyObject>() else {
+            let Some(todo) = list_entry_todo(&obj) else {
                 return;
             };
-            let entry = todo_obj.borrow::<ListEntry>();
-            let todo = match &*entry {
-                ListEntry::Item(todo) => todo.clone(),
-                ListEntry::Header(_) => return,
-            };
 
             if let Some(state) = today_state.upgrade() {
                 if let Err(err) = state.set_due_today(&todo) {
-                    state.show_error(&t("set_due_error").replace("{}", &err.to_string()));
+                    state.show_error(&t("set_due_error").replace("{}", &describe_error(&err)));
                 }
             }
         });
@@ -696,1787 +1860,7525 @@ fn create_list_view(state: &Rc<AppState>) -> gtk::ListView {
             let Some(obj) = list_item.item() else {
                 return;
             };
-            let Ok(todo_obj) = obj.downcast::<BoxedAnyObject>() else {
+            let Some(todo) = list_entry_todo(&obj) else {
                 return;
             };
-            let entry = todo_obj.borrow::<ListEntry>();
-            let todo = match &*entry {
-                ListEntry::Item(todo) => todo.clone(),
-                ListEntry::Header(_) => return,
-            };
 
             if let Some(state) = sometimes_state.upgrade() {
                 if let Err(err) = state.set_due_sometimes(&todo) {
-                    state.show_error(&t("set_due_error").replace("{}", &err.to_string()));
+                    state.show_error(&t("set_due_error").replace("{}", &describe_error(&err)));
                 }
             }
         });
 
     });
 
-    factory.connect_bind(|_, list_item_obj| {
+    let bind_state = state_weak.clone();
+    factory.connect_bind(move |_, list_item_obj| {
         let Some(list_item) = list_item_obj.downcast_ref::<gtk::ListItem>() else {
             return;
         };
-        let Some(obj) = list_item.item() else {
-            return;
-        };
-        let Ok(todo_obj) = obj.downcast::<BoxedAnyObject>() else {
+        let Some(todo_obj) = list_item.item().and_then(|obj| obj.downcast::<TodoObject>().ok()) else {
             return;
         };
-        let entry = todo_obj.borrow::<ListEntry>();
-        let Some(stack_ref_ptr) = (unsafe { list_item.data::<glib::WeakRef<gtk::Stack>>("stack") }) else {
+        bind_list_item(list_item, &todo_obj, &bind_state);
+        // Changes made in place, such as a toggle, show without rebinding.
+        let weak_item = list_item.downgrade();
+        let notify_state = bind_state.clone();
+        let handler = todo_obj.connect_notify_local(None, move |todo_obj, _| {
+            if let Some(list_item) = weak_item.upgrade() {
+                bind_list_item(&list_item, todo_obj, &notify_state);
+            }
+        });
+        unsafe {
+            list_item.set_data("todo-notify", (todo_obj.downgrade(), handler));
+        }
+    });
+    factory.connect_unbind(|_, list_item_obj| {
+        let Some(list_item) = list_item_obj.downcast_ref::<gtk::ListItem>() else {
             return;
         };
-        let Some(stack) = unsafe { stack_ref_ptr.as_ref() }.upgrade() else {
+        let notify = unsafe { list_item.steal_data::<(glib::WeakRef<TodoObject>, glib::SignalHandlerId)>("todo-notify") };
+        let Some((todo_obj, handler)) = notify else {
             return;
         };
-
-        match &*entry {
-            ListEntry::Header(label) => {
-                stack.set_visible_child_name("header");
-                if let Some(header_ref_ptr) = unsafe {
-                    list_item.data::<glib::WeakRef<gtk::Label>>("header-label")
-                } {
-                    if let Some(header_label) = unsafe { header_ref_ptr.as_ref() }.upgrade() {
-                        header_label.set_text(label);
-                    }
-                }
-            }
-            ListEntry::Item(todo) => {
-                stack.set_visible_child_name("item");
-                if let Some(check_ref_ptr) = unsafe {
-                    list_item.data::<glib::WeakRef<gtk::CheckButton>>("todo-check")
-                } {
-                    if let Some(check_widget) = unsafe { check_ref_ptr.as_ref() }.upgrade() {
-                        if check_widget.is_active() != todo.done {
-                            check_widget.set_active(todo.done);
-                        }
-                    }
-                }
-                if let Some(title_ref_ptr) = unsafe {
-                    list_item.data::<glib::WeakRef<gtk::Label>>("todo-title")
-                } {
-                    if let Some(title_widget) = unsafe { title_ref_ptr.as_ref() }.upgrade() {
-                        title_widget.set_text(&todo.title);
-                        if todo.done {
-                            title_widget.add_css_class("dim-label");
-                        } else {
-                            title_widget.remove_css_class("dim-label");
-                        }
-                    }
-                }
-                if let Some(meta_ref_ptr) = unsafe {
-                    list_item.data::<glib::WeakRef<gtk::Label>>("todo-meta")
-                } {
-                    if let Some(meta_widget) = unsafe { meta_ref_ptr.as_ref() }.upgrade() {
-                        meta_widget.set_text(&format_metadata(todo));
-                    }
-                }
-            }
+        if let Some(todo_obj) = todo_obj.upgrade() {
+            todo_obj.disconnect(handler);
         }
     });
 
-    let model = gtk::SingleSelection::new(Some(state.store()));
+    let model = gtk::SingleSelection::new(Some(entries.clone()));
     model.set_autoselect(false);
     model.set_can_unselect(true);
     let list_view = gtk::ListView::new(Some(model), Some(factory));
     list_view.set_single_click_activate(true);
     let activate_state = state_weak.clone();
-    list_view.connect_activate(move |_, position| {
-        if let Some(state) = activate_state.upgrade() {
-            state.open_entry_at(position);
+    list_view.connect_activate(move |view, position| {
+        let Some(state) = activate_state.upgrade() else {
+            return;
+        };
+        let todo = view
+            .model()
+            .and_then(|model| model.item(position))
+            .and_then(|obj| list_entry_todo(&obj));
+        if let Some(todo) = todo {
+            state.show_details_dialog(&todo);
         }
     });
+
+    // Vim profile: one-key commands plus the two-key `dd` and `gg`, whose
+    // first key is remembered here.
+    let vim_controller = gtk::EventControllerKey::new();
+    let vim_state = state_weak.clone();
+    let pending = Rc::new(Cell::new(None::<char>));
+    vim_controller.connect_key_pressed(clone!(@weak list_view => @default-return glib::Propagation::Proceed, move |_, keyval, _, modifiers| {
+        let Some(state) = vim_state.upgrade() else {
+            return glib::Propagation::Proceed;
+        };
+        if !state.vim_keys() || modifiers.intersects(gdk::ModifierType::CONTROL_MASK | gdk::ModifierType::ALT_MASK) {
+            return glib::Propagation::Proceed;
+        }
+        let Some(key) = keyval.to_unicode() else {
+            return glib::Propagation::Proceed;
+        };
+        let prefix = pending.replace(None);
+        match (prefix, key) {
+            (_, 'j') => vim_move(&list_view, 1),
+            (_, 'k') => vim_move(&list_view, -1),
+            (Some('g'), 'g') => vim_move(&list_view, i32::MIN),
+            (_, 'G') => vim_move(&list_view, i32::MAX),
+            (_, 'x') => {
+                let result = vim_selected(&list_view).map(|todo| state.toggle_item(&todo, !todo.done));
+                if let Some(Err(err)) = result {
+                    state.show_error(&t("update_error").replace("{}", &describe_error(&err)));
+                }
+            }
+            (Some('d'), 'd') => {
+                let window = list_view.root().and_downcast::<gtk::Window>();
+                if let (Some(todo), Some(window)) = (vim_selected(&list_view), window) {
+                    state.request_delete(&window, &todo, || {});
+                }
+            }
+            (_, 'd' | 'g') => pending.set(Some(key)),
+            (_, '/') => {
+                let _ = list_view.activate_action("app.search", None);
+            }
+            _ => return glib::Propagation::Proceed,
+        }
+        glib::Propagation::Stop
+    }));
+    list_view.add_controller(vim_controller);
+
+    // Pasting a Markdown checklist onto the list offers to import it.
+    let paste_state = state_weak.clone();
+    let paste = gtk::ShortcutController::new();
+    paste.add_shortcut(gtk::Shortcut::new(
+        gtk::ShortcutTrigger::parse_string("<Primary>v"),
+        Some(gtk::CallbackAction::new(move |_, _| {
+            if let Some(state) = paste_state.upgrade() {
+                state.paste_checklist();
+            }
+            glib::Propagation::Stop
+        })),
+    ));
+    list_view.add_controller(paste);
+    install_type_ahead(state_weak, &list_view);
     list_view
 }
 
-struct AppState {
-    store: gio::ListStore,
-    overlay: adw::ToastOverlay,
-    monitor: RefCell<Option<gio::FileMonitor>>,
-    cached_items: RefCell<Vec<TodoItem>>,
-    last_fingerprint: RefCell<Option<String>>,
-    sort_mode: RefCell<SortMode>,
-    window: glib::WeakRef<adw::ApplicationWindow>,
-    preferences: RefCell<Preferences>,
-    search_term: RefCell<String>,
-    list_view: RefCell<Option<gtk::ListView>>,
-    scrolled_window: RefCell<Option<gtk::ScrolledWindow>>,
-    is_recording: Arc<AtomicBool>,
-    _debug_mode: bool,
+/// Shows `todo_obj` in the row of `list_item`.
+fn bind_list_item(list_item: &gtk::ListItem, todo_obj: &TodoObject, state: &std::rc::Weak<AppState>) {
+    let Some(row) = list_item.child().and_downcast::<TodoRow>() else {
+        return;
+    };
+    let state = state.upgrade();
+    match todo_obj.todo() {
+        None => {
+            let label = todo_obj.header();
+            let collapsed = state
+                .as_ref()
+                .filter(|state| state.sections_collapsible())
+                .map(|state| state.is_section_collapsed(&label));
+            let renamable = state.as_ref().is_some_and(|state| state.header_tag(&label).is_some());
+            row.show_header(&label, collapsed, renamable);
+        }
+        Some(todo) => {
+            let fields = state
+                .as_ref()
+                .map(|state| state.metadata_fields())
+                .unwrap_or_else(MetaField::defaults);
+            let urgency = due_urgency_class(&todo, Local::now().date_naive());
+            row.show_task(&todo, urgency, &format_metadata(&todo, &fields));
+            row.highlight(&state.as_ref().map(|state| state.search_term.borrow().to_lowercase()).unwrap_or_default());
+        }
+    }
 }
 
-impl AppState {
-    fn new(window: &adw::ApplicationWindow, overlay: &adw::ToastOverlay, store: &gio::ListStore, debug_mode: bool) -> Self {
-        let current_at_start = data::todo_path();
-        let mut prefs = load_preferences();
-        let sort_mode = prefs
-            .sort_mode
-            .as_deref()
-            .map(SortMode::from_key)
-            .unwrap_or(SortMode::Topic);
-        prefs.sort_mode = Some(sort_mode.as_key().to_string());
+/// Pause after which the next key starts a new type-ahead search.
+const TYPE_AHEAD_RESET_MS: u128 = 1000;
+
+/// Keys of the single-key row actions, which never start a type-ahead.
+const ROW_ACTION_KEYS: [char; 9] = ['t', 'T', 's', 'S', 'o', 'O', '+', '*', '='];
+
+/// Nautilus-style type-ahead: typing while the list has focus selects the
+/// first task whose title starts with (or else contains) the typed text. The
+/// text is shown in a small bubble and forgotten after a short pause. It
+/// starts with a key that is no row action, so `t`, `s`, `o` and `+` keep
+/// working on the selected task.
+fn install_type_ahead(state: std::rc::Weak<AppState>, list_view: &gtk::ListView) {
+    let label = gtk::Label::new(None);
+    let bubble = gtk::Popover::builder()
+        .autohide(false)
+        .has_arrow(false)
+        .position(gtk::PositionType::Top)
+        .child(&label)
+        .build();
+    bubble.set_parent(list_view);
+    list_view.connect_destroy(clone!(@weak bubble => move |_| {
+        bubble.unparent();
+    }));
 
-        if prefs.use_webdav {
-             if let Some(url) = &prefs.webdav_url {
-                 data::set_backend_config(data::BackendConfig::WebDav {
-                     url: url.clone(),
-                     path: prefs.webdav_path.clone(),
-                     username: prefs.webdav_username.clone(),
-                     password: prefs.webdav_password.clone(),
-                 });
-             }
-        } else {
-            let default_path = data::default_todo_path();
-            
-            if !current_at_start.as_os_str().is_empty() && current_at_start != default_path {
-                // Command line argument was used
-                prefs.db_path = Some(current_at_start.to_string_lossy().into_owned());
-            } else if let Some(db_path) = prefs.db_path.clone() {
-                // No command line argument, use saved preference
-                data::set_todo_path(PathBuf::from(db_path));
-            } else if !current_at_start.as_os_str().is_empty() {
-                // No command line and no preference, use default
-                prefs.db_path = Some(current_at_start.to_string_lossy().into_owned());
-            }
+    let typed = Rc::new(RefCell::new(String::new()));
+    let generation = Rc::new(Cell::new(0u32));
+    let controller = gtk::EventControllerKey::new();
+    controller.connect_key_pressed(clone!(@weak list_view => @default-return glib::Propagation::Proceed, move |_, keyval, _, modifiers| {
+        let Some(state) = state.upgrade() else {
+            return glib::Propagation::Proceed;
+        };
+        if state.vim_keys() || modifiers.intersects(gdk::ModifierType::CONTROL_MASK | gdk::ModifierType::ALT_MASK) {
+            return glib::Propagation::Proceed;
         }
-
-        if !prefs.use_whisper {
-            let mut model_path = glib::user_cache_dir();
-            model_path.push("reinschrift_todo");
-            model_path.push("ggml-small.bin");
-            if model_path.exists() {
-                let _ = fs::remove_file(model_path);
+        if !state.type_ahead_pending() {
+            typed.borrow_mut().clear();
+        }
+        match keyval {
+            gdk::Key::BackSpace if !typed.borrow().is_empty() => {
+                typed.borrow_mut().pop();
             }
+            _ => match keyval.to_unicode() {
+                // A leading space or row action key keeps working on the
+                // selected task.
+                Some(c) if !c.is_control() && (!typed.borrow().is_empty() || (c != ' ' && !ROW_ACTION_KEYS.contains(&c))) => {
+                    typed.borrow_mut().push(c);
+                }
+                _ => return glib::Propagation::Proceed,
+            },
         }
 
-        Self {
-            store: store.clone(),
-            overlay: overlay.clone(),
-            monitor: RefCell::new(None),
-            cached_items: RefCell::new(Vec::new()),
-            sort_mode: RefCell::new(sort_mode),
-            window: window.downgrade(),
-            preferences: RefCell::new(prefs),
-            search_term: RefCell::new(String::new()),
-            list_view: RefCell::new(None),
-            scrolled_window: RefCell::new(None),
-            is_recording: Arc::new(AtomicBool::new(false)),
-            _debug_mode: debug_mode,
-            last_fingerprint: RefCell::new(None),
+        let text = typed.borrow().clone();
+        if text.is_empty() {
+            state.type_ahead_key.set(None);
+            bubble.popdown();
+            return glib::Propagation::Stop;
         }
-    }
-
-    fn store(&self) -> gio::ListStore {
-        self.store.clone()
-    }
+        state.type_ahead_key.set(Some(Instant::now()));
+        if let Some(position) = type_ahead_match(&list_view, &text) {
+            list_view.scroll_to(position, gtk::ListScrollFlags::FOCUS | gtk::ListScrollFlags::SELECT, None);
+        }
+        label.set_text(&text);
+        let width = list_view.width();
+        let height = list_view.height();
+        bubble.set_pointing_to(Some(&gdk::Rectangle::new(width / 2, height - 12, 1, 1)));
+        bubble.popup();
+
+        let current = generation.get().wrapping_add(1);
+        generation.set(current);
+        let generation = Rc::clone(&generation);
+        glib::timeout_add_local_once(
+            std::time::Duration::from_millis(TYPE_AHEAD_RESET_MS as u64 * 2),
+            clone!(@weak bubble => move || {
+                if generation.get() == current {
+                    bubble.popdown();
+                }
+            }),
+        );
+        glib::Propagation::Stop
+    }));
+    list_view.add_controller(controller);
+}
 
-    fn sort_mode(&self) -> SortMode {
-        *self.sort_mode.borrow()
-    }
+fn type_ahead_match(view: &gtk::ListView, text: &str) -> Option<u32> {
+    let model = view.model()?;
+    let needle = text.to_lowercase();
+    let titles: Vec<(u32, String)> = (0..model.n_items())
+        .filter_map(|i| {
+            let todo = model.item(i).and_then(|obj| list_entry_todo(&obj))?;
+            Some((i, todo.title.to_lowercase()))
+        })
+        .collect();
+    titles
+        .iter()
+        .find(|(_, title)| title.starts_with(&needle))
+        .or_else(|| titles.iter().find(|(_, title)| title.contains(&needle)))
+        .map(|(position, _)| *position)
+}
 
-    fn show_completed(&self) -> bool {
-        self.preferences.borrow().show_done
-    }
+/// Days the due date of the selected task moves for a key press: `Ctrl++` /
+/// `Ctrl+-` or `]` / `[` shift by a day, with `Shift` by a week.
+fn due_nudge_days(keyval: gdk::Key, modifiers: gdk::ModifierType) -> Option<i64> {
+    let ctrl = modifiers.contains(gdk::ModifierType::CONTROL_MASK);
+    let direction = match keyval {
+        gdk::Key::bracketright | gdk::Key::braceright => 1,
+        gdk::Key::bracketleft | gdk::Key::braceleft => -1,
+        gdk::Key::plus | gdk::Key::equal | gdk::Key::KP_Add | gdk::Key::asterisk if ctrl => 1,
+        gdk::Key::minus | gdk::Key::underscore | gdk::Key::KP_Subtract if ctrl => -1,
+        _ => return None,
+    };
+    let step = if modifiers.contains(gdk::ModifierType::SHIFT_MASK) { 7 } else { 1 };
+    Some(direction * step)
+}
 
-    fn show_due_only(&self) -> bool {
-        self.preferences.borrow().show_due_only
-    }
+/// What an entry completes: the `+project` / `@context` token being typed in
+/// a task line, or its whole text as the value of one field.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Completion {
+    Inline,
+    Project,
+    Context,
+    Section,
+}
 
-    fn use_whisper(&self) -> bool {
-        self.preferences.borrow().use_whisper
-    }
+/// Part of the entry text that an accepted suggestion replaces.
+#[derive(Copy, Clone, Default)]
+struct CompletionTarget {
+    start: i32,
+    end: i32,
+    sigil: Option<char>,
+}
 
-    fn whisper_language(&self) -> String {
-        self.preferences.borrow().whisper_language.clone()
-    }
+const COMPLETION_LIMIT: usize = 8;
+
+/// Suggests known values for the text typed into `entry` in a popover below
+/// it. Up/Down select a suggestion, Tab or Enter take it, Escape hides them.
+fn install_completion(state: std::rc::Weak<AppState>, entry: &impl IsA<gtk::Editable>, kind: Completion) {
+    let entry = entry.clone().upcast::<gtk::Editable>();
+    let list = gtk::ListBox::new();
+    list.set_selection_mode(gtk::SelectionMode::Single);
+    list.set_activate_on_single_click(true);
+    list.set_focusable(false);
+    let popover = gtk::Popover::builder()
+        .autohide(false)
+        .has_arrow(false)
+        .position(gtk::PositionType::Bottom)
+        .halign(gtk::Align::Start)
+        .child(&list)
+        .build();
+    popover.set_parent(&entry);
+    entry.connect_destroy(clone!(@weak popover => move |_| {
+        popover.unparent();
+    }));
 
-    fn whisper_model_path(&self) -> PathBuf {
-        let mut dir = glib::user_cache_dir();
-        dir.push("reinschrift_todo");
-        dir.push("ggml-small.bin");
-        dir
-    }
+    let target = Rc::new(Cell::new(CompletionTarget::default()));
+    let applying = Rc::new(Cell::new(false));
+    let accept = Rc::new(clone!(@weak entry, @weak popover, @strong target, @strong applying => move |value: &str| {
+        let target = target.get();
+        let replacement = match target.sigil {
+            Some(sigil) => format!("{sigil}{value} "),
+            None => value.to_string(),
+        };
+        applying.set(true);
+        entry.delete_text(target.start, target.end);
+        let mut position = target.start;
+        entry.insert_text(&replacement, &mut position);
+        entry.set_position(position);
+        applying.set(false);
+        popover.popdown();
+    }));
 
-    fn reload(&self) -> Result<()> {
-        let items = data::load_todos()?;
-        *self.cached_items.borrow_mut() = items;
-        if let Ok(fp) = data::get_fingerprint() {
-            *self.last_fingerprint.borrow_mut() = Some(fp);
+    list.connect_row_activated(clone!(@strong accept => move |_, row| {
+        if let Some(label) = row.child().and_downcast::<gtk::Label>() {
+            accept(&label.text());
         }
-        self.repopulate_store();
-        Ok(())
-    }
+    }));
 
-    fn check_for_updates(&self) -> Result<()> {
-        let current_fp = data::get_fingerprint()?;
-        let last_fp = self.last_fingerprint.borrow().clone();
+    entry.connect_changed(clone!(@weak popover, @weak list, @strong target, @strong applying => move |entry| {
+        if applying.get() {
+            return;
+        }
+        let Some(state) = state.upgrade() else {
+            return;
+        };
+        let text = entry.text();
+        let cursor = entry.position().max(0) as usize;
+        let before: String = text.chars().take(cursor).collect();
+        let (start, sigil, prefix) = match kind {
+            Completion::Inline => {
+                let word = before.rsplit(char::is_whitespace).next().unwrap_or_default();
+                let mut chars = word.chars();
+                let sigil = chars.next();
+                let source = match sigil {
+                    Some('+') => Completion::Project,
+                    Some('@') => Completion::Context,
+                    _ => {
+                        popover.popdown();
+                        return;
+                    }
+                };
+                let start = cursor - word.chars().count();
+                ((start, cursor), sigil.map(|sigil| (sigil, source)), chars.as_str().to_string())
+            }
+            _ if text.trim().is_empty() => {
+                popover.popdown();
+                return;
+            }
+            _ => ((0, text.chars().count()), None, text.trim().to_string()),
+        };
+        let source = sigil.map_or(kind, |(_, source)| source);
+        let needle = prefix.to_lowercase();
+        let suggestions: Vec<String> = state
+            .known_values(source)
+            .into_iter()
+            .filter(|value| value.to_lowercase().starts_with(&needle) && *value != prefix)
+            .take(COMPLETION_LIMIT)
+            .collect();
+        if suggestions.is_empty() {
+            popover.popdown();
+            return;
+        }
 
-        if Some(current_fp) != last_fp {
-            self.reload()?;
+        target.set(CompletionTarget {
+            start: start.0 as i32,
+            end: start.1 as i32,
+            sigil: sigil.map(|(sigil, _)| sigil),
+        });
+        list.remove_all();
+        for value in &suggestions {
+            let label = gtk::Label::builder().label(value).xalign(0.0).build();
+            let row = gtk::ListBoxRow::builder().child(&label).focusable(false).build();
+            list.append(&row);
         }
-        Ok(())
-    }
+        list.unselect_all();
+        popover.popup();
+    }));
 
-    fn toggle_item(&self, todo: &TodoItem, done: bool) -> Result<()> {
-        let today = Local::now().date_naive();
-        let is_historic = todo.due.map(|d| d < today).unwrap_or(false);
-        let is_recurring = todo.recurrence.is_some();
-
-        if done && is_historic && is_recurring {
-            let mut updated = todo.clone();
-            updated.due = Some(today);
-            updated.done = true;
-            data::update_todo_details(&updated)?;
-        } else {
-            data::toggle_todo(&todo.key, done)?;
-        }
-
-        if done {
-            if let Some(rule) = todo.recurrence.as_deref() {
-                if let Some(next_due) = data::next_due_date(todo.due, rule) {
-                    let mut next_item = todo.clone();
-                    next_item.key = data::TodoKey { line_index: 0, marker: None };
-                    next_item.done = false;
-                    next_item.due = Some(next_due);
-                    if let Err(err) = data::add_todo_full(&next_item) {
-                        eprintln!("Failed to add recurring task: {err}");
-                    }
+    let controller = gtk::EventControllerKey::new();
+    controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+    controller.connect_key_pressed(clone!(@weak popover, @weak list => @default-return glib::Propagation::Proceed, move |_, keyval, _, _| {
+        if !popover.is_visible() {
+            return glib::Propagation::Proceed;
+        }
+        let selected = list.selected_row().map(|row| row.index());
+        let count = list.observe_children().n_items() as i32;
+        match keyval {
+            gdk::Key::Down | gdk::Key::Up => {
+                let next = match (selected, keyval == gdk::Key::Down) {
+                    (None, true) => 0,
+                    (None, false) => count - 1,
+                    (Some(index), true) => (index + 1).min(count - 1),
+                    (Some(index), false) => (index - 1).max(0),
+                };
+                list.select_row(list.row_at_index(next).as_ref());
+                glib::Propagation::Stop
+            }
+            gdk::Key::Tab | gdk::Key::Return | gdk::Key::KP_Enter => {
+                let row = match selected {
+                    Some(index) => list.row_at_index(index),
+                    None if keyval == gdk::Key::Tab => list.row_at_index(0),
+                    None => return glib::Propagation::Proceed,
+                };
+                if let Some(label) = row.and_then(|row| row.child()).and_downcast::<gtk::Label>() {
+                    accept(&label.text());
                 }
+                glib::Propagation::Stop
+            }
+            gdk::Key::Escape => {
+                popover.popdown();
+                glib::Propagation::Stop
             }
+            _ => glib::Propagation::Proceed,
         }
+    }));
+    entry.add_controller(controller);
 
-        self.reload()?;
-        let message = if done {
-            format!("Erledigt: {}", todo.title)
-        } else {
-            format!("Reaktiviert: {}", todo.title)
-        };
-        self.show_info(&message);
-        Ok(())
-    }
+    let focus = gtk::EventControllerFocus::new();
+    focus.connect_leave(clone!(@weak popover => move |_| {
+        popover.popdown();
+    }));
+    entry.add_controller(focus);
+}
 
-    fn set_due_today(&self, todo: &TodoItem) -> Result<()> {
-        let today = data::set_due_today(&todo.key)?;
-        self.reload()?;
-        self.show_info(&format!("Fällig heute ({})", today));
-        Ok(())
-    }
+/// Positions of the task rows in `view`, skipping section headers.
+fn item_positions(view: &gtk::ListView) -> Vec<u32> {
+    let Some(model) = view.model() else {
+        return Vec::new();
+    };
+    (0..model.n_items())
+        .filter(|&i| model.item(i).and_then(|obj| list_entry_todo(&obj)).is_some())
+        .collect()
+}
 
-    fn set_due_in_days(&self, todo: &TodoItem, days: i64) -> Result<()> {
-        let mut updated = todo.clone();
-        let target = Local::now().date_naive() + Duration::days(days);
-        updated.due = Some(target);
-        self.save_item(&updated)
+/// Moves the selection `delta` tasks down (negative: up); `i32::MIN` and
+/// `i32::MAX` jump to the first and last task.
+fn vim_move(view: &gtk::ListView, delta: i32) {
+    let Some(selection) = view.model().and_then(|m| m.downcast::<gtk::SingleSelection>().ok()) else {
+        return;
+    };
+    let positions = item_positions(view);
+    if positions.is_empty() {
+        return;
     }
+    let current = positions.iter().position(|&p| p == selection.selected());
+    let last = positions.len() as i64 - 1;
+    let target = match (delta, current) {
+        (i32::MIN, _) => 0,
+        (i32::MAX, _) => last,
+        (_, None) => if delta > 0 { 0 } else { last },
+        (_, Some(index)) => (index as i64 + delta as i64).clamp(0, last),
+    };
+    let position = positions[target as usize];
+    view.scroll_to(position, gtk::ListScrollFlags::FOCUS | gtk::ListScrollFlags::SELECT, None);
+}
 
-    fn set_due_sometimes(&self, todo: &TodoItem) -> Result<()> {
-        let mut updated = todo.clone();
-        updated.due = Some(NaiveDate::from_ymd_opt(9999, 12, 31).unwrap());
-        self.save_item(&updated)
+fn vim_selected(view: &gtk::ListView) -> Option<TodoItem> {
+    let selection = view.model()?.downcast::<gtk::SingleSelection>().ok()?;
+    selection.selected_item().and_then(|obj| list_entry_todo(&obj))
+}
+
+/// Localized name of one of [`TodoItem::FIELDS`] or `section`.
+fn field_label(field: &str) -> String {
+    match field {
+        "title" => t("title"),
+        "section" => t("section"),
+        "project" => t("column_project"),
+        "context" => t("column_context"),
+        "due" => t("column_due"),
+        "remind" => t("reminder"),
+        "reference" => t("reference"),
+        "recurrence" => t("recurrence"),
+        "priority" => t("column_priority"),
+        "aged" => t("aged_field"),
+        "estimate" => t("estimate"),
+        "start" => t("start_date"),
+        "depends_on" => t("depends_on"),
+        "pinned" => t("pinned"),
+        "done" => t("done"),
+        "created" => t("created_date"),
+        other => other.to_string(),
     }
+}
 
-    fn show_due_shortcuts(self: &Rc<Self>, todo: &TodoItem) {
-        let Some(parent) = self.window.upgrade() else {
-            self.show_error("Kein Fenster verfügbar");
-            return;
+/// Collapsed change log for the detail dialog, newest entry first. The label
+/// already tells how often the task was pushed back.
+fn history_expander(entries: &[HistoryEntry]) -> gtk::Expander {
+    let postponed = entries.iter().filter(|e| e.event == HistoryEvent::Postponed).count();
+    let label = if postponed > 0 {
+        t("history_postponed_count").replace("{}", &postponed.to_string())
+    } else {
+        t("history")
+    };
+    let list = gtk::Box::new(gtk::Orientation::Vertical, 6);
+    list.set_margin_top(6);
+    for entry in entries.iter().rev() {
+        let event = match entry.event {
+            HistoryEvent::Added => t("history_added"),
+            HistoryEvent::Edited => t("history_edited"),
+            HistoryEvent::Postponed => t("history_postponed"),
+            HistoryEvent::Completed => t("history_completed"),
+            HistoryEvent::Reopened => t("history_reopened"),
+            HistoryEvent::Deleted => t("history_deleted"),
         };
-
-        let dialog = AlertDialog::builder()
-            .modal(true)
-            .build();
-        dialog.set_message("Fälligkeit verschieben");
-        dialog.set_detail("Bitte Ziel wählen");
-        dialog.set_buttons(&["Morgen", "In 3 Tagen", "In 7 Tagen", "In einem Monat", "Irgendwann", "Abbrechen"]);
-        dialog.set_default_button(0);
-        dialog.set_cancel_button(5);
-
-        let state = Rc::clone(self);
-        let base_todo = todo.clone();
-        dialog.choose(
-            Some(&parent),
-            Option::<&gio::Cancellable>::None,
-            clone!(@strong state, @strong base_todo => move |result| {
-                match result {
-                    Ok(index) => {
-                        let action = match index {
-                            0 => Some(1),
-                            1 => Some(3),
-                            2 => Some(7),
-                            3 => Some(30),
-                            4 => None,
-                            _ => return,
-                        };
-
-                        let outcome = match action {
-                            Some(days) => state.set_due_in_days(&base_todo, days),
-                            None => state.set_due_sometimes(&base_todo),
-                        };
-
-                        if let Err(err) = outcome {
-                            state.show_error(&format!("Konnte verschieben: {err}"));
-                        }
-                    }
-                    Err(err) => {
-                        state.show_error(&format!("Konnte Dialog nicht anzeigen: {err}"));
-                    }
-                }
-            }),
-        );
+        let mut text = format!("{} · {}", entry.time.format("%Y-%m-%d %H:%M"), event);
+        for change in &entry.changes {
+            let field = field_label(&change.field);
+            let from = change.from.as_deref().unwrap_or("–");
+            let to = change.to.as_deref().unwrap_or("–");
+            text.push_str(&format!("\n    {field}: {from} → {to}"));
+        }
+        let row = gtk::Label::builder().label(text.as_str()).xalign(0.0).wrap(true).build();
+        row.add_css_class("dim-label");
+        list.append(&row);
     }
+    let scrolled = gtk::ScrolledWindow::builder()
+        .child(&list)
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .propagate_natural_height(true)
+        .max_content_height(160)
+        .build();
+    gtk::Expander::builder().label(label.as_str()).child(&scrolled).build()
+}
 
-    fn show_cheatsheet(self: &Rc<Self>) {
-        let Some(parent) = self.window.upgrade() else {
-            self.show_error(&t("no_window"));
-            return;
-        };
+/// The task registered for a store entry, or `None` for section headers.
+fn list_entry_todo(obj: &glib::Object) -> Option<TodoItem> {
+    obj.downcast_ref::<TodoObject>()?.todo()
+}
 
-        let dialog = adw::Window::builder()
-            .title(&t("cheatsheet"))
-            .transient_for(&parent)
-            .modal(true)
-            .default_width(400)
+/// The Eisenhower matrix: the open tasks of the store in a 2×2 grid. Dropping
+/// a task on another quadrant rewrites its priority and due date to match.
+fn create_matrix_view(state: &Rc<AppState>) -> gtk::Grid {
+    let grid = gtk::Grid::builder()
+        .row_homogeneous(true)
+        .column_homogeneous(true)
+        .row_spacing(6)
+        .column_spacing(6)
+        .margin_top(6)
+        .margin_bottom(6)
+        .margin_start(6)
+        .margin_end(6)
+        .build();
+    for (index, quadrant) in Quadrant::ALL.into_iter().enumerate() {
+        let filtered = gtk::FilterListModel::new(
+            Some(state.store()),
+            Some(gtk::CustomFilter::new(move |obj| {
+                let today = Local::now().date_naive();
+                list_entry_todo(obj).is_some_and(|todo| !todo.done && Quadrant::of(&todo, today) == quadrant)
+            })),
+        );
+        let title = gtk::Label::builder()
+            .label(t(quadrant.title_key()))
+            .xalign(0.0)
+            .margin_top(8)
+            .margin_start(12)
             .build();
-        dialog.set_destroy_with_parent(true);
-
-        let key_controller = gtk::EventControllerKey::new();
-        let dialog_clone = dialog.clone();
-        key_controller.connect_key_pressed(move |_, keyval, _, _| {
-            if keyval == gdk::Key::Escape {
-                dialog_clone.close();
-                glib::Propagation::Stop
-            } else {
-                glib::Propagation::Proceed
+        title.add_css_class("heading");
+        let scrolled = gtk::ScrolledWindow::builder()
+            .child(&create_list_view(state, &filtered))
+            .vexpand(true)
+            .hexpand(true)
+            .build();
+        let cell = gtk::Box::new(gtk::Orientation::Vertical, 4);
+        cell.add_css_class("matrix-quadrant");
+        cell.set_overflow(gtk::Overflow::Hidden);
+        cell.append(&title);
+        cell.append(&scrolled);
+
+        let drop_target = gtk::DropTarget::new(TodoObject::static_type(), gdk::DragAction::MOVE);
+        let drop_state = Rc::downgrade(state);
+        drop_target.connect_drop(move |_, value, _, _| {
+            let todo = value.get::<glib::Object>().ok().and_then(|obj| list_entry_todo(&obj));
+            match (todo, drop_state.upgrade()) {
+                (Some(todo), Some(state)) => {
+                    state.move_to_quadrant(&todo, quadrant);
+                    true
+                }
+                _ => false,
             }
         });
-        dialog.add_controller(key_controller);
+        cell.add_controller(drop_target);
 
-        let content = gtk::Box::new(gtk::Orientation::Vertical, 12);
-        content.set_margin_top(24);
-        content.set_margin_bottom(24);
-        content.set_margin_start(24);
-        content.set_margin_end(24);
+        grid.attach(&cell, (index % 2) as i32, (index / 2) as i32, 1, 1);
+    }
+    grid
+}
 
-        let grid = gtk::Grid::builder()
-            .column_spacing(24)
-            .row_spacing(8)
+/// Shows `events` as plain, non-interactive lines in `container`, hiding it
+/// when there are none.
+fn fill_event_rows(container: &gtk::Box, events: &[&Event]) {
+    while let Some(child) = container.first_child() {
+        container.remove(&child);
+    }
+    for event in events {
+        let time = match (event.all_day, event.end) {
+            (true, _) => t("calendar_all_day"),
+            (false, Some(end)) if end.date() == event.start.date() => {
+                format!("{}–{}", event.start.format("%H:%M"), end.format("%H:%M"))
+            }
+            (false, _) => event.start.format("%H:%M").to_string(),
+        };
+        let row = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+        row.add_css_class("calendar-event");
+        row.append(&gtk::Image::from_icon_name("x-office-calendar-symbolic"));
+        let time_label = gtk::Label::new(Some(&time));
+        time_label.add_css_class("numeric");
+        row.append(&time_label);
+        let summary = gtk::Label::builder()
+            .label(&event.summary)
+            .ellipsize(pango::EllipsizeMode::End)
+            .xalign(0.0)
+            .hexpand(true)
             .build();
+        row.append(&summary);
+        row.set_tooltip_text(Some(&format!("{time} {}", event.summary)));
+        container.append(&row);
+    }
+    container.set_visible(!events.is_empty());
+}
 
-        let shortcuts = [
-            ("key_help", "?"),
-            ("key_new", "Ctrl + N"),
-            ("key_search", "Ctrl + F"),
-            ("key_reload", "Ctrl + R"),
-            ("key_quit", "Ctrl + Q"),
-            ("key_nav", "↑ / ↓"),
-            ("key_toggle", "Space"),
-            ("key_edit", "Enter"),
-            ("key_today", "t"),
-            ("key_tomorrow", "+"),
-            ("key_sometimes", "s"),
-        ];
+/// Monday of the week `today` is in.
+fn week_start(today: NaiveDate) -> NaiveDate {
+    today - Duration::days(i64::from(today.weekday().num_days_from_monday()))
+}
 
-        for (i, (key, shortcut)) in shortcuts.iter().enumerate() {
-            let key_label = gtk::Label::builder()
-                .label(*shortcut)
-                .xalign(1.0)
-                .build();
-            key_label.add_css_class("dim-label");
-            
-            let desc_label = gtk::Label::builder()
-                .label(&t(key))
-                .xalign(0.0)
-                .build();
-            
-            grid.attach(&key_label, 0, i as i32, 1, 1);
-            grid.attach(&desc_label, 1, i as i32, 1, 1);
+/// Widgets of the week planner that follow the shown week.
+struct WeekView {
+    /// Weeks before (negative) or after the current one.
+    offset: Rc<Cell<i64>>,
+    /// Monday the titles and filters were last set up for.
+    shown: Cell<Option<NaiveDate>>,
+    range_label: gtk::Label,
+    titles: Vec<gtk::Label>,
+    /// Calendar events of each day, above its tasks.
+    events: Vec<gtk::Box>,
+    filters: Vec<gtk::CustomFilter>,
+}
+
+/// A column of the week planner: one day, or the backlog of undated tasks.
+#[derive(Clone, Copy)]
+enum PlannerColumn {
+    Backlog,
+    Day(i64),
+}
+
+impl PlannerColumn {
+    /// The due date this column stands for, `None` for the backlog.
+    fn due(self, offset: i64, today: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            PlannerColumn::Backlog => None,
+            PlannerColumn::Day(index) => Some(week_start(today) + Duration::days(offset * 7 + index)),
         }
+    }
+}
 
-        content.append(&grid);
+/// Seven day columns next to a backlog of undated tasks. Dropping a task on
+/// a day makes it due then, dropping it on the backlog removes the date.
+fn create_week_view(state: &Rc<AppState>) -> gtk::Box {
+    let offset = Rc::new(Cell::new(0));
+    let columns = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+    columns.set_margin_start(6);
+    columns.set_margin_end(6);
+    columns.set_margin_bottom(6);
+    let mut titles = Vec::new();
+    let mut day_events = Vec::new();
+    let mut filters = Vec::new();
+    let kinds = std::iter::once(PlannerColumn::Backlog).chain((0..7).map(PlannerColumn::Day));
+    for column in kinds {
+        let filter_offset = Rc::clone(&offset);
+        let filter = gtk::CustomFilter::new(move |obj| {
+            let today = Local::now().date_naive();
+            list_entry_todo(obj).is_some_and(|todo| !todo.done && todo.due == column.due(filter_offset.get(), today))
+        });
+        let filtered = gtk::FilterListModel::new(Some(state.store()), Some(filter.clone()));
+        filters.push(filter);
+        let ordered = gtk::SortListModel::new(Some(filtered), Some(item_sorter(compare_within_day)));
 
-        let close_btn = gtk::Button::with_label(&t("close"));
-        close_btn.set_halign(gtk::Align::End);
-        close_btn.set_margin_top(12);
-        let dialog_close = dialog.clone();
-        close_btn.connect_clicked(move |_| {
-            dialog_close.close();
+        let title = gtk::Label::builder()
+            .label(t("week_backlog"))
+            .xalign(0.0)
+            .margin_top(8)
+            .margin_start(12)
+            .build();
+        title.add_css_class("heading");
+        let scrolled = gtk::ScrolledWindow::builder()
+            .child(&create_list_view(state, &ordered))
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .vexpand(true)
+            .build();
+        let cell = gtk::Box::new(gtk::Orientation::Vertical, 4);
+        cell.add_css_class("matrix-quadrant");
+        cell.set_overflow(gtk::Overflow::Hidden);
+        cell.set_size_request(if matches!(column, PlannerColumn::Backlog) { 240 } else { 180 }, -1);
+        cell.set_hexpand(!matches!(column, PlannerColumn::Backlog));
+        cell.append(&title);
+        if matches!(column, PlannerColumn::Day(_)) {
+            let events = gtk::Box::new(gtk::Orientation::Vertical, 2);
+            events.set_margin_start(12);
+            events.set_margin_end(12);
+            events.set_visible(false);
+            cell.append(&events);
+            titles.push(title);
+            day_events.push(events);
+        }
+        cell.append(&scrolled);
+
+        let drop_target = gtk::DropTarget::new(TodoObject::static_type(), gdk::DragAction::MOVE);
+        let drop_state = Rc::downgrade(state);
+        let drop_offset = Rc::clone(&offset);
+        drop_target.connect_drop(move |_, value, _, _| {
+            let todo = value.get::<glib::Object>().ok().and_then(|obj| list_entry_todo(&obj));
+            match (todo, drop_state.upgrade()) {
+                (Some(todo), Some(state)) => {
+                    state.plan_for_day(&todo, column.due(drop_offset.get(), Local::now().date_naive()));
+                    true
+                }
+                _ => false,
+            }
         });
-        content.append(&close_btn);
+        cell.add_controller(drop_target);
+        columns.append(&cell);
+    }
 
-        dialog.set_content(Some(&content));
-        dialog.present();
+    let previous_btn = gtk::Button::from_icon_name("go-previous-symbolic");
+    previous_btn.set_tooltip_text(Some(&t("week_previous")));
+    let current_btn = gtk::Button::with_label(&t("week_current"));
+    let next_btn = gtk::Button::from_icon_name("go-next-symbolic");
+    next_btn.set_tooltip_text(Some(&t("week_next")));
+    let range_label = gtk::Label::new(None);
+    range_label.add_css_class("heading");
+    range_label.set_margin_start(6);
+    let navigation = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+    navigation.set_margin_top(6);
+    navigation.set_margin_start(12);
+    navigation.set_margin_end(12);
+    for widget in [previous_btn.upcast_ref::<gtk::Widget>(), current_btn.upcast_ref(), next_btn.upcast_ref(), range_label.upcast_ref()] {
+        navigation.append(widget);
+    }
+    for (button, step) in [(&previous_btn, Some(-1)), (&current_btn, None), (&next_btn, Some(1))] {
+        let offset = Rc::clone(&offset);
+        button.connect_clicked(clone!(@weak state => move |_| {
+            offset.set(step.map_or(0, |step| offset.get() + step));
+            state.refresh_week_view();
+        }));
     }
 
-    fn show_settings_dialog(self: &Rc<Self>, voice_btn: Option<gtk::Button>) {
-        let Some(parent) = self.window.upgrade() else {
-            self.show_error(&t("no_window"));
-            return;
-        };
+    let scrolled = gtk::ScrolledWindow::builder()
+        .child(&columns)
+        .vscrollbar_policy(gtk::PolicyType::Never)
+        .vexpand(true)
+        .build();
+    let page = gtk::Box::new(gtk::Orientation::Vertical, 6);
+    page.append(&navigation);
+    page.append(&scrolled);
+
+    *state.week_view.borrow_mut() = Some(Rc::new(WeekView {
+        offset,
+        shown: Cell::new(None),
+        range_label,
+        titles,
+        events: day_events,
+        filters,
+    }));
+    state.refresh_week_view();
+    page
+}
 
-        // Enforce WebDAV mode
-        self.set_use_webdav(true);
+const GRAPH_NODE_WIDTH: i32 = 180;
+const GRAPH_NODE_HEIGHT: i32 = 36;
+const GRAPH_GAP_X: i32 = 56;
+const GRAPH_GAP_Y: i32 = 16;
+const GRAPH_MARGIN: i32 = 16;
+
+/// Start and end point of an arrow in the graph view.
+type GraphEdge = ((f64, f64), (f64, f64));
+
+/// The banner for lines of the file the parser skipped, with the list of
+/// them revealed below it; filled by [`AppState::update_problems_panel`].
+struct ProblemsPanel {
+    banner: adw::Banner,
+    revealer: gtk::Revealer,
+    list: gtk::ListBox,
+    /// Opens [`AppState::show_repair_dialog`].
+    repair_button: gtk::Button,
+}
 
-        let dialog = adw::PreferencesWindow::builder()
-            .title(&t("settings"))
-            .transient_for(&parent)
-            .modal(true)
-            .default_width(480)
-            .build();
+fn create_problems_panel() -> ProblemsPanel {
+    let banner = adw::Banner::builder().button_label(t("problems_show")).build();
+    let list = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .margin_top(6)
+        .margin_bottom(6)
+        .margin_start(12)
+        .margin_end(12)
+        .build();
+    list.add_css_class("boxed-list");
+    let scrolled = gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .propagate_natural_height(true)
+        .max_content_height(200)
+        .child(&list)
+        .build();
+    let repair_button = gtk::Button::builder()
+        .label(t("repair_button"))
+        .halign(gtk::Align::End)
+        .margin_end(12)
+        .margin_bottom(6)
+        .build();
+    let panel = gtk::Box::new(gtk::Orientation::Vertical, 0);
+    panel.append(&scrolled);
+    panel.append(&repair_button);
+    let revealer = gtk::Revealer::builder().child(&panel).build();
+    banner.connect_button_clicked(clone!(@weak revealer => move |banner| {
+        let reveal = !revealer.reveals_child();
+        revealer.set_reveal_child(reveal);
+        banner.set_button_label(Some(&t(if reveal { "problems_hide" } else { "problems_show" })));
+    }));
+    ProblemsPanel { banner, revealer, list, repair_button }
+}
 
-        // --- General Page ---
-        let general_page = adw::PreferencesPage::builder()
-            .title(&t("general"))
-            .icon_name("preferences-system-symbolic")
-            .build();
-        dialog.add(&general_page);
+/// Shown in place of the views while the database file is gone, e.g. after
+/// a `git checkout` or a sync tool removed it.
+fn create_missing_page(state: &Rc<AppState>) -> adw::StatusPage {
+    let recreate_btn = gtk::Button::with_label(&t("database_recreate"));
+    recreate_btn.add_css_class("suggested-action");
+    recreate_btn.add_css_class("pill");
+    let choose_btn = gtk::Button::with_label(&t("database_choose_other"));
+    choose_btn.add_css_class("pill");
+    let buttons = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+    buttons.set_halign(gtk::Align::Center);
+    buttons.append(&choose_btn);
+    buttons.append(&recreate_btn);
+    let waiting = gtk::Label::new(Some(&t("database_waiting")));
+    waiting.add_css_class("dim-label");
+    waiting.set_wrap(true);
+    waiting.set_justify(gtk::Justification::Center);
+    let child = gtk::Box::new(gtk::Orientation::Vertical, 18);
+    child.append(&buttons);
+    child.append(&waiting);
+    let page = adw::StatusPage::builder()
+        .icon_name("dialog-warning-symbolic")
+        .title(t("database_missing_title"))
+        .child(&child)
+        .vexpand(true)
+        .build();
+    recreate_btn.connect_clicked(clone!(@weak state => move |_| {
+        state.recreate_database();
+    }));
+    choose_btn.connect_clicked(clone!(@weak state => move |_| {
+        state.choose_database();
+    }));
+    *state.missing_page.borrow_mut() = Some(page.clone());
+    page
+}
 
-        let general_group = adw::PreferencesGroup::builder()
-            .title(&t("general"))
-            .build();
-        general_page.add(&general_group);
+/// Widgets of the dependency graph view, filled by
+/// [`AppState::refresh_graph_view`].
+struct GraphView {
+    projects: gtk::StringList,
+    selector: gtk::DropDown,
+    nodes: gtk::Fixed,
+    area: gtk::DrawingArea,
+    placeholder: gtk::Label,
+    /// Arrows of the current drawing, from blocker to blocked task.
+    edges: Rc<RefCell<Vec<GraphEdge>>>,
+    syncing: Cell<bool>,
+}
 
-        let show_done_row = adw::SwitchRow::builder()
-            .title(&t("show_completed"))
-            .active(self.show_completed())
-            .build();
-        show_done_row.add_prefix(&gtk::Image::from_icon_name("view-list-symbolic"));
-        let state_done = Rc::clone(self);
-        show_done_row.connect_active_notify(move |row| {
-            state_done.set_show_completed(row.is_active());
-        });
-        general_group.add(&show_done_row);
+/// The blocked-by graph: task buttons laid out in columns with the blocking
+/// tasks left of the ones waiting for them, arrows drawn with cairo below.
+fn create_graph_view(state: &Rc<AppState>) -> gtk::Box {
+    let projects = gtk::StringList::new(&[&t("graph_all_projects")]);
+    let selector = gtk::DropDown::builder().model(&projects).halign(gtk::Align::Start).build();
+    selector.set_margin_top(6);
+    selector.set_margin_start(12);
+
+    let edges: Rc<RefCell<Vec<GraphEdge>>> = Rc::new(RefCell::new(Vec::new()));
+    let area = gtk::DrawingArea::new();
+    let draw_edges = Rc::clone(&edges);
+    area.set_draw_func(move |area, cr, _, _| {
+        let color = area.color();
+        cr.set_source_rgba(color.red().into(), color.green().into(), color.blue().into(), 0.5);
+        cr.set_line_width(1.5);
+        for &((x1, y1), (x2, y2)) in draw_edges.borrow().iter() {
+            let bend = (x2 - x1).abs() / 2.0;
+            cr.move_to(x1, y1);
+            cr.curve_to(x1 + bend, y1, x2 - bend, y2, x2, y2);
+            let _ = cr.stroke();
+            cr.move_to(x2, y2);
+            cr.line_to(x2 - 7.0, y2 - 4.0);
+            cr.line_to(x2 - 7.0, y2 + 4.0);
+            cr.close_path();
+            let _ = cr.fill();
+        }
+    });
+    let nodes = gtk::Fixed::new();
+    let canvas = gtk::Overlay::builder().child(&area).build();
+    canvas.add_overlay(&nodes);
+    canvas.set_halign(gtk::Align::Start);
+    canvas.set_valign(gtk::Align::Start);
+
+    let placeholder = gtk::Label::builder()
+        .label(t("graph_empty"))
+        .wrap(true)
+        .vexpand(true)
+        .build();
+    placeholder.add_css_class("dim-label");
 
-        let show_due_row = adw::SwitchRow::builder()
-            .title(&t("show_due_only_mode"))
-            .active(self.show_due_only())
-            .build();
-        show_due_row.add_prefix(&gtk::Image::from_icon_name("appointment-soon-symbolic"));
-        let state_due = Rc::clone(self);
-        show_due_row.connect_active_notify(move |row| {
-            state_due.set_show_due_only(row.is_active());
-        });
-        general_group.add(&show_due_row);
+    let scrolled = gtk::ScrolledWindow::builder()
+        .child(&canvas)
+        .vexpand(true)
+        .hexpand(true)
+        .build();
+    let page = gtk::Box::new(gtk::Orientation::Vertical, 6);
+    page.append(&selector);
+    page.append(&placeholder);
+    page.append(&scrolled);
+
+    selector.connect_selected_notify(clone!(@weak state => move |_| {
+        let syncing = state.graph_view.borrow().as_ref().is_some_and(|view| view.syncing.get());
+        if !syncing {
+            state.refresh_graph_view();
+        }
+    }));
+    *state.graph_view.borrow_mut() = Some(Rc::new(GraphView {
+        projects,
+        selector,
+        nodes,
+        area,
+        placeholder,
+        edges,
+        syncing: Cell::new(false),
+    }));
+    page
+}
 
-        // --- WebDAV Page ---
-        let webdav_page = adw::PreferencesPage::builder()
-            .title(&t("webdav"))
-            .icon_name("network-server-symbolic")
-            .build();
-        dialog.add(&webdav_page);
+const TIMELINE_DAY_WIDTH: i32 = 40;
+const TIMELINE_ROW_HEIGHT: i32 = 30;
+const TIMELINE_HEADER_HEIGHT: i32 = 40;
+/// Days shown after the last bar, room to drag tasks later.
+const TIMELINE_TRAILING_DAYS: i64 = 14;
+
+/// Widgets of the timeline view, filled by
+/// [`AppState::refresh_timeline_view`].
+struct TimelineView {
+    bars: gtk::Fixed,
+    area: gtk::DrawingArea,
+    placeholder: gtk::Label,
+    /// First day shown and the number of days, for the grid drawing.
+    range: Rc<Cell<(NaiveDate, i64)>>,
+}
 
-        let webdav_group = adw::PreferencesGroup::builder()
-            .title(&t("webdav"))
-            .build();
-        webdav_page.add(&webdav_group);
+/// Tasks with dates as bars from `start:` to `due:` over a day grid, one row
+/// per task grouped by project. Dragging a bar sideways moves both dates.
+fn create_timeline_view(state: &Rc<AppState>) -> gtk::Box {
+    let today = Local::now().date_naive();
+    let range = Rc::new(Cell::new((today, 0)));
+    let area = gtk::DrawingArea::new();
+    let draw_range = Rc::clone(&range);
+    area.set_draw_func(move |area, cr, _, height| {
+        let (first, days) = draw_range.get();
+        let color = area.color();
+        let (red, green, blue) = (color.red().into(), color.green().into(), color.blue().into());
+        let today = Local::now().date_naive();
+        for offset in 0..days {
+            let day = first + Duration::days(offset);
+            let x = f64::from(TIMELINE_DAY_WIDTH) * offset as f64;
+            if day.weekday().number_from_monday() > 5 {
+                cr.set_source_rgba(red, green, blue, 0.04);
+                cr.rectangle(x, 0.0, f64::from(TIMELINE_DAY_WIDTH), f64::from(height));
+                let _ = cr.fill();
+            }
+            cr.set_source_rgba(red, green, blue, if day.day() == 1 { 0.3 } else { 0.1 });
+            cr.move_to(x + 0.5, f64::from(TIMELINE_HEADER_HEIGHT) / 2.0);
+            cr.line_to(x + 0.5, f64::from(height));
+            let _ = cr.stroke();
+        }
+        let today_offset = (today - first).num_days();
+        if (0..days).contains(&today_offset) {
+            let x = f64::from(TIMELINE_DAY_WIDTH) * (today_offset as f64 + 0.5);
+            cr.set_source_rgba(0.2, 0.5, 0.9, 0.8);
+            cr.set_line_width(2.0);
+            cr.move_to(x, f64::from(TIMELINE_HEADER_HEIGHT));
+            cr.line_to(x, f64::from(height));
+            let _ = cr.stroke();
+        }
+    });
+    let bars = gtk::Fixed::new();
+    let canvas = gtk::Overlay::builder().child(&area).build();
+    canvas.add_overlay(&bars);
+    canvas.set_halign(gtk::Align::Start);
+    canvas.set_valign(gtk::Align::Start);
+
+    let placeholder = gtk::Label::builder()
+        .label(t("timeline_empty"))
+        .wrap(true)
+        .vexpand(true)
+        .build();
+    placeholder.add_css_class("dim-label");
+    let scrolled = gtk::ScrolledWindow::builder()
+        .child(&canvas)
+        .vexpand(true)
+        .hexpand(true)
+        .build();
+    let page = gtk::Box::new(gtk::Orientation::Vertical, 0);
+    page.append(&placeholder);
+    page.append(&scrolled);
+
+    *state.timeline_view.borrow_mut() = Some(Rc::new(TimelineView {
+        bars,
+        area,
+        placeholder,
+        range,
+    }));
+    page
+}
 
-        let (_, _, wd_path, wd_user, wd_pass) = self.get_webdav_prefs();
-        // Note: wd_url is fetched inside the closure below or we can get it here if needed, 
-        // but we need to bind it to the row.
-        // Let's get the current values again to populate the fields.
-        let (_, wd_url, _, _, _) = self.get_webdav_prefs();
+/// First and last day `todo` covers on the timeline: `start:` to `due:`, a
+/// single day when only one is set, `None` without dates or for "sometime".
+fn timeline_span(todo: &TodoItem) -> Option<(NaiveDate, NaiveDate)> {
+    let due = todo.due.filter(|due| due.year() != 9999);
+    match (todo.start, due) {
+        (Some(start), Some(due)) => Some((start.min(due), due.max(start))),
+        (Some(day), None) | (None, Some(day)) => Some((day, day)),
+        (None, None) => None,
+    }
+}
 
-        let url_row = adw::EntryRow::builder()
-            .title(&t("webdav_url"))
-            .text(wd_url.unwrap_or_default())
-            .build();
-        let state_url = Rc::clone(self);
-        url_row.connect_changed(move |row| {
-            state_url.set_webdav_url(row.text().to_string());
-        });
-        webdav_group.add(&url_row);
+/// Blocked-by edges between `items` as `(blocker, blocked)` indices.
+/// Markers that match no task are skipped.
+fn dependency_edges(items: &[TodoItem]) -> Vec<(usize, usize)> {
+    let mut edges = Vec::new();
+    for (blocked, item) in items.iter().enumerate() {
+        for marker in &item.depends_on {
+            let blocker = items.iter().position(|other| other.key.marker.as_deref() == Some(marker.as_str()));
+            if let Some(blocker) = blocker.filter(|&blocker| blocker != blocked) {
+                edges.push((blocker, blocked));
+            }
+        }
+    }
+    edges
+}
 
-        let path_row = adw::EntryRow::builder()
-            .title(&t("path_relative"))
-            .text(wd_path.unwrap_or_default())
-            .build();
-        let state_path = Rc::clone(self);
-        path_row.connect_changed(move |row| {
-            state_path.set_webdav_path(row.text().to_string());
-        });
-        webdav_group.add(&path_row);
+/// Column of each of `count` nodes: one right of its rightmost blocker.
+/// Cycles stop growing after `count` rounds.
+fn graph_layers(count: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+    let mut layers = vec![0; count];
+    for _ in 0..count {
+        let mut changed = false;
+        for &(blocker, blocked) in edges {
+            if layers[blocked] < layers[blocker] + 1 {
+                layers[blocked] = layers[blocker] + 1;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    layers
+}
 
-        let user_row = adw::EntryRow::builder()
-            .title(&t("username"))
-            .text(wd_user.unwrap_or_default())
-            .build();
-        let state_user = Rc::clone(self);
-        user_row.connect_changed(move |row| {
-            state_user.set_webdav_username(row.text().to_string());
-        });
-        webdav_group.add(&user_row);
+/// Table view over the same store as the list: headers are filtered out and
+/// sorting follows the clicked column header.
+fn create_column_view(state: &Rc<AppState>) -> gtk::ColumnView {
+    let items_only = gtk::FilterListModel::new(
+        Some(state.store()),
+        Some(gtk::CustomFilter::new(|obj| list_entry_todo(obj).is_some())),
+    );
+    let sorted = gtk::SortListModel::new(Some(items_only), None::<gtk::Sorter>);
+    let selection = gtk::SingleSelection::new(Some(sorted.clone()));
+    selection.set_autoselect(false);
+    selection.set_can_unselect(true);
+
+    let column_view = gtk::ColumnView::new(Some(selection));
+    column_view.set_show_column_separators(true);
+    // Header clicks sort within the pinned and the unpinned tasks.
+    let sorter = gtk::MultiSorter::new();
+    sorter.append(item_sorter(compare_pinned));
+    if let Some(column_sorter) = column_view.sorter() {
+        sorter.append(column_sorter);
+    }
+    sorted.set_sorter(Some(&sorter));
+
+    column_view.append_column(&done_column(state));
+    let title = text_column(&t("title"), |todo| todo.title.clone(), text_sorter(SortText::Title));
+    title.set_expand(true);
+    column_view.append_column(&title);
+    column_view.append_column(&text_column(
+        &t("column_project"),
+        |todo| todo.project.as_deref().unwrap_or_default().to_string(),
+        text_sorter(SortText::Project),
+    ));
+    column_view.append_column(&text_column(
+        &t("column_context"),
+        |todo| todo.context.as_deref().unwrap_or_default().to_string(),
+        text_sorter(SortText::Context),
+    ));
+    column_view.append_column(&text_column(
+        &t("column_due"),
+        format_due_cell,
+        item_sorter(|a, b| compare_optional(&a.due, &b.due)),
+    ));
+    column_view.append_column(&text_column(
+        &t("column_priority"),
+        |todo| todo.priority.map(String::from).unwrap_or_default(),
+        item_sorter(|a, b| compare_optional(&a.priority, &b.priority)),
+    ));
+
+    let activate_state = Rc::downgrade(state);
+    column_view.connect_activate(move |view, position| {
+        let Some(state) = activate_state.upgrade() else {
+            return;
+        };
+        let todo = view
+            .model()
+            .and_then(|model| model.item(position))
+            .and_then(|obj| list_entry_todo(&obj));
+        if let Some(todo) = todo {
+            state.show_details_dialog(&todo);
+        }
+    });
+    column_view
+}
 
-        let pass_row = adw::PasswordEntryRow::builder()
-            .title(&t("password"))
-            .text(wd_pass.unwrap_or_default())
-            .build();
-        let state_pass = Rc::clone(self);
-        pass_row.connect_changed(move |row| {
-            state_pass.set_webdav_password(row.text().to_string());
-        });
-        webdav_group.add(&pass_row);
+fn context_tab_name(context: Option<&str>) -> String {
+    match context {
+        Some(c) => format!("@{c}"),
+        None => "none".to_string(),
+    }
+}
 
-        let check_row = adw::ActionRow::builder()
-            .title(&t("check_connection"))
-            .build();
-        let check_button = gtk::Button::builder()
-            .label(&t("check_connection"))
-            .valign(gtk::Align::Center)
+/// Orders present values first so empty cells collect at the bottom.
+fn compare_optional<T: Ord>(a: &Option<T>, b: &Option<T>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp(b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// The due date, with the time of day for timed tasks.
+fn format_due_cell(todo: &TodoItem) -> String {
+    match (todo.due, todo.due_time) {
+        (Some(date), _) if date.year() == 9999 => t("sometimes"),
+        (Some(date), Some(time)) => format!("{} {}", config::format_date(date), time.format("%H:%M")),
+        (Some(date), None) => config::format_date(date),
+        (None, _) => String::new(),
+    }
+}
+
+/// Whether `todo` is open and due at a set time on `day`.
+fn is_timed_on(todo: &TodoItem, day: NaiveDate) -> bool {
+    !todo.done && todo.due == Some(day) && todo.due_time.is_some()
+}
+
+/// Selected text as a task title, on one line. `None` for blank text.
+fn selection_title(text: &str) -> Option<String> {
+    let title = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+/// How the task dialog shows reminders; `T` in place of the space is
+/// accepted too.
+const REMIND_ENTRY_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+fn parse_reminder(text: &str) -> Option<NaiveDateTime> {
+    let text = text.split_whitespace().collect::<Vec<_>>().join("T");
+    NaiveDateTime::parse_from_str(&text, data::REMIND_FORMAT).ok()
+}
+
+/// Quick picks of the reminder row: in an hour, tomorrow morning and,
+/// for a task due later, the mornings one and two days before.
+fn reminder_presets(due: Option<NaiveDate>, now: NaiveDateTime) -> Vec<(String, NaiveDateTime)> {
+    let morning = NaiveTime::from_hms_opt(9, 0, 0).unwrap_or_default();
+    let in_an_hour = now.with_second(0).unwrap_or(now) + Duration::hours(1);
+    let mut presets = vec![
+        (t("reminder_in_an_hour"), in_an_hour),
+        (t("reminder_tomorrow_morning"), (now.date() + Duration::days(1)).and_time(morning)),
+    ];
+    let before_due = [(1, "reminder_day_before_due"), (2, "reminder_two_days_before_due")];
+    for (days, key) in before_due {
+        if let Some(at) = due.map(|due| (due - Duration::days(days)).and_time(morning)).filter(|at| *at > now) {
+            presets.push((t(key), at));
+        }
+    }
+    presets
+}
+
+/// All-day tasks of one day first, then the timed ones by time.
+fn compare_within_day(a: &TodoItem, b: &TodoItem) -> Ordering {
+    a.due_at().is_some().cmp(&b.due_at().is_some()).then_with(|| a.due_at().cmp(&b.due_at()))
+}
+
+fn item_sorter(compare: fn(&TodoItem, &TodoItem) -> Ordering) -> gtk::CustomSorter {
+    gtk::CustomSorter::new(move |a, b| match (list_entry_todo(a), list_entry_todo(b)) {
+        (Some(a), Some(b)) => compare(&a, &b).into(),
+        _ => gtk::Ordering::Equal,
+    })
+}
+
+/// Sorts tasks by one of their texts, with the keys cached on the objects.
+fn text_sorter(text: SortText) -> gtk::CustomSorter {
+    gtk::CustomSorter::new(move |a, b| match (a.downcast_ref::<TodoObject>(), b.downcast_ref::<TodoObject>()) {
+        (Some(a), Some(b)) => a.compare_text(b, text).into(),
+        _ => gtk::Ordering::Equal,
+    })
+}
+
+fn text_column(title: &str, text: fn(&TodoItem) -> String, sorter: gtk::CustomSorter) -> gtk::ColumnViewColumn {
+    let factory = gtk::SignalListItemFactory::new();
+    factory.connect_setup(|_, list_item_obj| {
+        let Some(list_item) = list_item_obj.downcast_ref::<gtk::ListItem>() else {
+            return;
+        };
+        let label = gtk::Label::builder()
+            .xalign(0.0)
+            .ellipsize(pango::EllipsizeMode::End)
             .build();
-        check_button.add_css_class("flat");
-        check_row.add_suffix(&check_button);
-        
-        let state_for_check = Rc::clone(self);
-        check_button.connect_clicked(move |_| {
-            let (_, url, path, user, pass) = state_for_check.get_webdav_prefs();
-            
-            let Some(u) = url else {
-                state_for_check.show_error(&t("no_url_error"));
+        list_item.set_child(Some(&label));
+    });
+    factory.connect_bind(move |_, list_item_obj| {
+        let Some(list_item) = list_item_obj.downcast_ref::<gtk::ListItem>() else {
+            return;
+        };
+        let Some(todo) = list_item.item().and_then(|obj| list_entry_todo(&obj)) else {
+            return;
+        };
+        let Some(label) = list_item.child().and_downcast::<gtk::Label>() else {
+            return;
+        };
+        label.set_text(&text(&todo));
+        label.set_tooltip_text(Some(&todo.title));
+    });
+
+    let column = gtk::ColumnViewColumn::new(Some(title), Some(factory));
+    column.set_sorter(Some(&sorter));
+    column.set_resizable(true);
+    column
+}
+
+fn done_column(state: &Rc<AppState>) -> gtk::ColumnViewColumn {
+    let factory = gtk::SignalListItemFactory::new();
+    let factory_state = Rc::downgrade(state);
+    factory.connect_setup(move |_, list_item_obj| {
+        let Some(list_item) = list_item_obj.downcast_ref::<gtk::ListItem>() else {
+            return;
+        };
+        let check = gtk::CheckButton::new();
+        check.set_halign(gtk::Align::Center);
+        list_item.set_child(Some(&check));
+
+        let weak_list = list_item.downgrade();
+        let state_for_handler = factory_state.clone();
+        check.connect_toggled(move |btn| {
+            let Some(todo) = weak_list
+                .upgrade()
+                .and_then(|list_item| list_item.item())
+                .and_then(|obj| list_entry_todo(&obj))
+            else {
                 return;
             };
-            if u.trim().is_empty() {
-                state_for_check.show_error(&t("no_url_error"));
+            if btn.is_active() == todo.done {
+                return;
+            }
+            let Some(state) = state_for_handler.upgrade() else {
                 return;
+            };
+            if let Err(err) = state.toggle_item(&todo, btn.is_active()) {
+                state.show_error(&t("update_error").replace("{}", &describe_error(&err)));
             }
+        });
+    });
+    factory.connect_bind(|_, list_item_obj| {
+        let Some(list_item) = list_item_obj.downcast_ref::<gtk::ListItem>() else {
+            return;
+        };
+        let Some(todo) = list_item.item().and_then(|obj| list_entry_todo(&obj)) else {
+            return;
+        };
+        let Some(check) = list_item.child().and_downcast::<gtk::CheckButton>() else {
+            return;
+        };
+        if check.is_active() != todo.done {
+            check.set_active(todo.done);
+        }
+    });
 
-            let state_bg = state_for_check.clone();
-            let (sender, receiver) = std::sync::mpsc::channel();
-            
-            let u_clone = u.clone();
-            let path_clone = path.clone();
-            let user_clone = user.clone();
-            let pass_clone = pass.clone();
+    let column = gtk::ColumnViewColumn::new(Some(&t("done")), Some(factory));
+    column.set_sorter(Some(&item_sorter(|a, b| a.done.cmp(&b.done))));
+    column.set_resizable(true);
+    column
+}
 
-            std::thread::spawn(move || {
-                let result = data::test_webdav_connection(&u_clone, path_clone.as_deref(), user_clone.as_deref(), pass_clone.as_deref());
-                let _ = sender.send(result);
-            });
+/// What the main list shows; read by the filters of [`TaskList`].
+#[derive(Default)]
+struct ListCriteria {
+    include_done: bool,
+    due_only: bool,
+    /// While searching, every matching task is listed regardless of the
+    /// other filters and sections stay expanded.
+    searching: bool,
+    collapsed: HashSet<String>,
+    /// Headers of the sections that have tasks to show.
+    headers: HashSet<String>,
+}
 
-            glib::timeout_add_local(std::time::Duration::from_millis(100), move || {
-                match receiver.try_recv() {
-                    Ok(result) => {
+impl ListCriteria {
+    fn accepts(&self, todo: &TodoItem, today: NaiveDate) -> bool {
+        (self.include_done || !todo.done) && (!self.due_only || todo.due.is_none_or(|due| due <= today))
+    }
+}
+
+/// The main list as a model pipeline. `items` holds a [`TodoObject`] for
+/// every task and section header and only changes when tasks are loaded or
+/// the sections change; filters decide what is shown and a sorter on the
+/// `position` property puts each header above its tasks.
+struct TaskList {
+    items: gio::ListStore,
+    criteria: Rc<RefCell<ListCriteria>>,
+    /// Tasks passing the done and due filters, outside collapsed sections.
+    task_filter: gtk::CustomFilter,
+    search_filter: gtk::StringFilter,
+    header_filter: gtk::CustomFilter,
+    sorter: gtk::NumericSorter,
+    model: gtk::SortListModel,
+    headers: RefCell<HashMap<String, TodoObject>>,
+}
+
+impl TaskList {
+    fn new() -> Self {
+        let items = gio::ListStore::new::<TodoObject>();
+        let criteria = Rc::new(RefCell::new(ListCriteria::default()));
+        let task_filter = gtk::CustomFilter::new(clone!(@strong criteria => move |obj| {
+            let Some(object) = obj.downcast_ref::<TodoObject>() else {
+                return false;
+            };
+            let Some(todo) = object.todo() else {
+                return false;
+            };
+            let criteria = criteria.borrow();
+            criteria.searching
+                || (criteria.accepts(&todo, Local::now().date_naive()) && !criteria.collapsed.contains(&object.group()))
+        }));
+        let header_filter = gtk::CustomFilter::new(clone!(@strong criteria => move |obj| {
+            obj.downcast_ref::<TodoObject>()
+                .and_then(|object| object.header_label())
+                .is_some_and(|label| criteria.borrow().headers.contains(&label))
+        }));
+        let search_filter = gtk::StringFilter::builder()
+            .expression(gtk::PropertyExpression::new(TodoObject::static_type(), gtk::Expression::NONE, "title"))
+            .match_mode(gtk::StringFilterMatchMode::Substring)
+            .ignore_case(true)
+            .build();
+        let tasks = gtk::EveryFilter::new();
+        tasks.append(task_filter.clone());
+        tasks.append(search_filter.clone());
+        let filter = gtk::AnyFilter::new();
+        filter.append(header_filter.clone());
+        filter.append(tasks);
+        let filtered = gtk::FilterListModel::new(Some(items.clone()), Some(filter));
+        let sorter = gtk::NumericSorter::new(Some(gtk::PropertyExpression::new(
+            TodoObject::static_type(),
+            gtk::Expression::NONE,
+            "position",
+        )));
+        let model = gtk::SortListModel::new(Some(filtered), Some(sorter.clone()));
+        Self {
+            items,
+            criteria,
+            task_filter,
+            search_filter,
+            header_filter,
+            sorter,
+            model,
+            headers: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// The listed task objects by task line, to be reused on reload.
+    fn task_objects(&self) -> HashMap<data::TodoKey, TodoObject> {
+        (0..self.items.n_items())
+            .filter_map(|i| self.items.item(i).and_downcast::<TodoObject>())
+            .filter_map(|object| Some((object.key()?, object)))
+            .collect()
+    }
+
+    /// The header object for `label`, created on first use.
+    fn header(&self, label: &str) -> TodoObject {
+        self.headers
+            .borrow_mut()
+            .entry(label.to_string())
+            .or_insert_with(|| TodoObject::new_header(label))
+            .clone()
+    }
+
+    /// Puts `entries`, in display order, into the pipeline and filters them
+    /// by `criteria` and the title search `search`.
+    fn arrange(&self, entries: Vec<TodoObject>, criteria: ListCriteria, search: &str) {
+        for (position, object) in (0u32..).zip(&entries) {
+            if object.position() != position {
+                object.set_position(position);
+            }
+        }
+        let unchanged = entries.len() == self.items.n_items() as usize
+            && (0u32..).zip(&entries).all(|(i, object)| self.items.item(i).as_ref() == Some(object.upcast_ref()));
+        if !unchanged {
+            self.items.splice(0, self.items.n_items(), &entries);
+        }
+        *self.criteria.borrow_mut() = criteria;
+        self.task_filter.changed(gtk::FilterChange::Different);
+        self.header_filter.changed(gtk::FilterChange::Different);
+        let search = (!search.is_empty()).then_some(search);
+        if self.search_filter.search().as_deref() != search {
+            self.search_filter.set_search(search);
+        }
+        self.sorter.changed(gtk::SorterChange::Different);
+    }
+
+    /// Changes the title search while the search results are listed; the
+    /// entries stay, only the filters change. `search` is lowercase.
+    fn refine_search(&self, search: &str) {
+        let headers = (0..self.items.n_items())
+            .filter_map(|i| self.items.item(i).and_downcast::<TodoObject>())
+            .filter(|object| !object.is_header() && object.title().to_lowercase().contains(search))
+            .map(|object| object.group())
+            .collect();
+        {
+            let mut criteria = self.criteria.borrow_mut();
+            criteria.searching = true;
+            criteria.headers = headers;
+        }
+        self.header_filter.changed(gtk::FilterChange::Different);
+        self.search_filter.set_search(Some(search));
+    }
+}
+
+/// Reads the unsaved state of the task dialog, see [`AppState::save_draft`].
+type DraftReader = Box<dyn Fn() -> Option<EditDraft>>;
+
+struct AppState {
+    task_list: TaskList,
+    overlay: adw::ToastOverlay,
+    monitor: RefCell<Option<gio::FileMonitor>>,
+    include_monitors: RefCell<Vec<(PathBuf, gio::FileMonitor)>>,
+    cached_items: RefCell<Vec<TodoItem>>,
+    last_fingerprint: RefCell<Option<String>>,
+    /// Whether the file monitor can be trusted to report outside changes.
+    file_events: Cell<bool>,
+    /// Set while the offline conflict dialog is open.
+    offline_conflict_open: Cell<bool>,
+    /// When the type-ahead last took a key, while it holds typed text.
+    type_ahead_key: Cell<Option<Instant>>,
+    sort_mode: RefCell<SortMode>,
+    window: glib::WeakRef<adw::ApplicationWindow>,
+    preferences: RefCell<Preferences>,
+    /// Source of `preferences` when the GSettings schema is installed.
+    settings: Option<gio::Settings>,
+    search_term: RefCell<String>,
+    /// Rows of the list, for marking the matches of a new search in those
+    /// already shown.
+    rows: RefCell<Vec<glib::WeakRef<TodoRow>>>,
+    list_view: RefCell<Option<gtk::ListView>>,
+    scrolled_window: RefCell<Option<gtk::ScrolledWindow>>,
+    duplicates_banner: RefCell<Option<adw::Banner>>,
+    /// Shows the first of `file_problems` until it is resolved.
+    file_banner: RefCell<Option<adw::Banner>>,
+    /// What went wrong loading and watching the database, see [`FileSlot`].
+    file_problems: RefCell<[Option<FileProblem>; 2]>,
+    /// Holds the views, or [`AppState::missing_page`] instead while the
+    /// database file is gone.
+    database_stack: RefCell<Option<gtk::Stack>>,
+    missing_page: RefCell<Option<adw::StatusPage>>,
+    database_missing: Cell<bool>,
+    problems_panel: RefCell<Option<ProblemsPanel>>,
+    css_monitor: RefCell<Option<gio::FileMonitor>>,
+    /// The accent pinned for the open database, empty without one.
+    accent_provider: gtk::CssProvider,
+    view_stack: RefCell<Option<gtk::Stack>>,
+    context_stack: RefCell<Option<adw::ViewStack>>,
+    syncing_tabs: Cell<bool>,
+    views_sync_pending: Cell<bool>,
+    focus_page: RefCell<Option<adw::StatusPage>>,
+    graph_view: RefCell<Option<Rc<GraphView>>>,
+    timeline_view: RefCell<Option<Rc<TimelineView>>>,
+    week_view: RefCell<Option<Rc<WeekView>>>,
+    /// Events of the configured calendar, see [`AppState::refresh_calendar`].
+    calendar_events: RefCell<Vec<Event>>,
+    today_events: RefCell<Option<gtk::Box>>,
+    focus_current: RefCell<Option<TodoItem>>,
+    focus_skipped: RefCell<Vec<data::TodoKey>>,
+    persistence: RefCell<(Persistence, DateTime<Local>)>,
+    status_button: RefCell<Option<gtk::MenuButton>>,
+    status_details: RefCell<Option<gtk::Label>>,
+    window_title: RefCell<Option<adw::WindowTitle>>,
+    /// The quick-add entry, saved as a draft while it has text.
+    quick_add_entry: RefCell<Option<gtk::Entry>>,
+    /// Reads the open task dialog; returns `None` while nothing changed.
+    edit_draft: RefCell<Option<DraftReader>>,
+    draft_save_pending: Cell<Option<glib::SourceId>>,
+    /// The default database was created at this start.
+    first_run: bool,
+    /// Database whose [`ViewState`] the preferences show; empty before the
+    /// first load.
+    view_state_owner: RefCell<String>,
+    sort_selector: RefCell<Option<gtk::DropDown>>,
+    due_filter: RefCell<Option<gtk::CheckButton>>,
+    view_selector: RefCell<Option<gtk::DropDown>>,
+    is_recording: Arc<AtomicBool>,
+    _debug_mode: bool,
+}
+
+impl AppState {
+    fn new(window: &adw::ApplicationWindow, overlay: &adw::ToastOverlay, debug_mode: bool) -> Self {
+        let mut prefs = load_preferences();
+        // config.toml sets the start state; the switch still works from there.
+        if let Some(hide) = config::get().hide_completed {
+            prefs.show_done = !hide;
+        }
+        let sort_mode = prefs
+            .sort_mode
+            .as_deref()
+            .map(SortMode::from_key)
+            .unwrap_or(SortMode::Topic);
+        prefs.sort_mode = Some(sort_mode.as_key().to_string());
+        let first_run = apply_backend_preferences(&mut prefs);
+
+        if !prefs.use_whisper {
+            let mut model_path = glib::user_cache_dir();
+            model_path.push("reinschrift_todo");
+            model_path.push("ggml-small.bin");
+            if model_path.exists() {
+                let _ = fs::remove_file(model_path);
+            }
+        }
+
+        Self {
+            task_list: TaskList::new(),
+            overlay: overlay.clone(),
+            monitor: RefCell::new(None),
+            include_monitors: RefCell::new(Vec::new()),
+            cached_items: RefCell::new(Vec::new()),
+            sort_mode: RefCell::new(sort_mode),
+            window: window.downgrade(),
+            preferences: RefCell::new(prefs),
+            // Bound window keys would be written back; the demo keeps them.
+            settings: settings::open().filter(|_| !demo::is_active()),
+            search_term: RefCell::new(String::new()),
+            rows: RefCell::new(Vec::new()),
+            list_view: RefCell::new(None),
+            scrolled_window: RefCell::new(None),
+            duplicates_banner: RefCell::new(None),
+            file_banner: RefCell::new(None),
+            file_problems: RefCell::new([None, None]),
+            database_stack: RefCell::new(None),
+            missing_page: RefCell::new(None),
+            database_missing: Cell::new(false),
+            problems_panel: RefCell::new(None),
+            css_monitor: RefCell::new(None),
+            accent_provider: gtk::CssProvider::new(),
+            view_stack: RefCell::new(None),
+            context_stack: RefCell::new(None),
+            syncing_tabs: Cell::new(false),
+            views_sync_pending: Cell::new(false),
+            focus_page: RefCell::new(None),
+            graph_view: RefCell::new(None),
+            timeline_view: RefCell::new(None),
+            week_view: RefCell::new(None),
+            calendar_events: RefCell::new(Vec::new()),
+            today_events: RefCell::new(None),
+            focus_current: RefCell::new(None),
+            focus_skipped: RefCell::new(Vec::new()),
+            persistence: RefCell::new((Persistence::Loaded, Local::now())),
+            status_button: RefCell::new(None),
+            status_details: RefCell::new(None),
+            window_title: RefCell::new(None),
+            quick_add_entry: RefCell::new(None),
+            edit_draft: RefCell::new(None),
+            draft_save_pending: Cell::new(None),
+            first_run,
+            is_recording: Arc::new(AtomicBool::new(false)),
+            view_state_owner: RefCell::new(String::new()),
+            sort_selector: RefCell::new(None),
+            due_filter: RefCell::new(None),
+            view_selector: RefCell::new(None),
+            _debug_mode: debug_mode,
+            last_fingerprint: RefCell::new(None),
+            file_events: Cell::new(false),
+            offline_conflict_open: Cell::new(false),
+            type_ahead_key: Cell::new(None),
+        }
+    }
+
+    /// The filtered and sorted entries of the main list.
+    fn store(&self) -> gtk::SortListModel {
+        self.task_list.model.clone()
+    }
+
+    fn sort_mode(&self) -> SortMode {
+        *self.sort_mode.borrow()
+    }
+
+    /// Filters the list by `search` in the titles and marks the matches.
+    fn set_search_term(&self, search: &str) {
+        if *self.search_term.borrow() == search {
+            return;
+        }
+        let was_searching = !self.search_term.borrow().is_empty();
+        self.search_term.replace(search.to_string());
+        let search = search.to_lowercase();
+        // Entering or leaving the search rearranges the list into result
+        // sections and back; typing on only filters those.
+        if was_searching && !search.is_empty() {
+            self.task_list.refine_search(&search);
+        } else {
+            self.repopulate_store();
+        }
+        self.rows.borrow_mut().retain(|row| match row.upgrade() {
+            Some(row) => {
+                row.highlight(&search);
+                true
+            }
+            None => false,
+        });
+    }
+
+    fn show_completed(&self) -> bool {
+        self.preferences.borrow().show_done
+    }
+
+    fn show_due_only(&self) -> bool {
+        self.preferences.borrow().show_due_only
+    }
+
+    fn metadata_fields(&self) -> Vec<MetaField> {
+        match &self.preferences.borrow().metadata_fields {
+            Some(keys) => keys.iter().filter_map(|key| MetaField::from_key(key)).collect(),
+            None => MetaField::defaults(),
+        }
+    }
+
+    fn set_metadata_fields(&self, fields: &[MetaField]) {
+        {
+            let mut prefs = self.preferences.borrow_mut();
+            prefs.metadata_fields = Some(fields.iter().map(|f| f.as_key().to_string()).collect());
+        }
+        self.persist_preferences();
+        self.repopulate_store();
+    }
+
+    fn view_mode(&self) -> ViewMode {
+        self.preferences
+            .borrow()
+            .view_mode
+            .as_deref()
+            .map(ViewMode::from_key)
+            .unwrap_or(ViewMode::List)
+    }
+
+    fn set_view_mode(&self, mode: ViewMode) {
+        {
+            let mut prefs = self.preferences.borrow_mut();
+            prefs.view_mode = Some(mode.as_key().to_string());
+        }
+        self.persist_preferences();
+        if let Some(stack) = self.view_stack.borrow().as_ref() {
+            stack.set_visible_child_name(mode.as_key());
+        }
+    }
+
+    /// Updates the context tabs and focus page once per main loop iteration
+    /// instead of on every single store change during a repopulate.
+    fn schedule_view_sync(self: &Rc<Self>) {
+        if self.views_sync_pending.replace(true) {
+            return;
+        }
+        let state = Rc::downgrade(self);
+        glib::idle_add_local_once(move || {
+            if let Some(state) = state.upgrade() {
+                state.views_sync_pending.set(false);
+                state.sync_context_tabs();
+                state.refresh_focus_view();
+                state.refresh_graph_view();
+                state.refresh_timeline_view();
+                state.refresh_week_view();
+                state.show_calendar_events();
+            }
+        });
+    }
+
+    /// Open tasks that are due by today (or undated), most important first:
+    /// by priority, then by due date, then by position in the file.
+    fn focus_queue(&self) -> Vec<TodoItem> {
+        let today = Local::now().date_naive();
+        let mut queue: Vec<TodoItem> = self
+            .cached_items
+            .borrow()
+            .iter()
+            .filter(|todo| !todo.done && todo.due.map(|d| d <= today).unwrap_or(true))
+            .cloned()
+            .collect();
+        queue.sort_by(|a, b| {
+            compare_pinned(a, b)
+                .then_with(|| compare_optional(&a.priority, &b.priority))
+                .then_with(|| compare_optional(&a.due, &b.due))
+                .then_with(|| a.key.line_index.cmp(&b.key.line_index))
+        });
+        queue
+    }
+
+    fn refresh_focus_view(&self) {
+        let Some(page) = self.focus_page.borrow().clone() else {
+            return;
+        };
+        let queue = self.focus_queue();
+        let mut pending: Vec<TodoItem> = {
+            let skipped = self.focus_skipped.borrow();
+            queue.iter().filter(|todo| !skipped.contains(&todo.key)).cloned().collect()
+        };
+        if pending.is_empty() && !queue.is_empty() {
+            // Everything was skipped once: start over from the top.
+            self.focus_skipped.borrow_mut().clear();
+            pending = queue;
+        }
+
+        let current = pending.first().cloned();
+        if let Some(buttons) = page.child() {
+            buttons.set_visible(current.is_some());
+        }
+        match &current {
+            Some(todo) => {
+                page.set_icon_name(None);
+                page.set_title(&todo.title);
+                let mut description = format_metadata(todo, &self.metadata_fields());
+                if pending.len() > 1 {
+                    if !description.is_empty() {
+                        description.push('\n');
+                    }
+                    description.push_str(&t("focus_remaining").replace("{}", &(pending.len() - 1).to_string()));
+                }
+                page.set_description((!description.is_empty()).then_some(description.as_str()));
+            }
+            None => {
+                page.set_icon_name(Some("emblem-ok-symbolic"));
+                page.set_title(&t("focus_empty"));
+                page.set_description(None);
+            }
+        }
+        *self.focus_current.borrow_mut() = current;
+    }
+
+    fn refresh_graph_view(&self) {
+        let Some(view) = self.graph_view.borrow().clone() else {
+            return;
+        };
+        let items = self.cached_items.borrow().clone();
+        let include_done = self.show_completed();
+        let edges: Vec<(usize, usize)> = dependency_edges(&items)
+            .into_iter()
+            .filter(|&(_, blocked)| include_done || !items[blocked].done)
+            .collect();
+
+        let mut projects: Vec<Name> = edges
+            .iter()
+            .flat_map(|&(blocker, blocked)| [blocker, blocked])
+            .filter_map(|index| items[index].project.clone().filter(|p| !p.is_empty()))
+            .collect();
+        collation::sort(&mut projects, |project| project);
+        projects.dedup();
+        let mut names = vec![t("graph_all_projects")];
+        names.extend(projects.iter().map(|project| format!("+{project}")));
+        let listed: Vec<String> = (0..view.projects.n_items())
+            .filter_map(|i| view.projects.string(i).map(|s| s.to_string()))
+            .collect();
+        if listed != names {
+            let previous = listed.get(view.selector.selected() as usize).cloned();
+            view.syncing.set(true);
+            let refs: Vec<&str> = names.iter().map(String::as_str).collect();
+            view.projects.splice(0, view.projects.n_items(), &refs);
+            let index = previous.and_then(|name| names.iter().position(|n| *n == name)).unwrap_or(0);
+            view.selector.set_selected(index as u32);
+            view.syncing.set(false);
+        }
+        let project = match view.selector.selected() as usize {
+            0 => None,
+            index => projects.get(index - 1).cloned(),
+        };
+
+        let in_project = |index: usize| project.is_none() || items[index].project == project;
+        let edges: Vec<(usize, usize)> = edges
+            .into_iter()
+            .filter(|&(blocker, blocked)| in_project(blocker) || in_project(blocked))
+            .collect();
+        let mut members: Vec<usize> = edges.iter().flat_map(|&(blocker, blocked)| [blocker, blocked]).collect();
+        members.sort_unstable();
+        members.dedup();
+        let local = |index: usize| members.binary_search(&index).unwrap_or_default();
+        let local_edges: Vec<(usize, usize)> = edges.iter().map(|&(a, b)| (local(a), local(b))).collect();
+        let layers = graph_layers(members.len(), &local_edges);
+
+        while let Some(child) = view.nodes.first_child() {
+            view.nodes.remove(&child);
+        }
+        let mut rows_used = vec![0; layers.iter().max().map_or(0, |max| max + 1)];
+        let mut positions = Vec::with_capacity(members.len());
+        for (node, &index) in members.iter().enumerate() {
+            let todo = &items[index];
+            let layer = layers[node];
+            let row = rows_used[layer];
+            rows_used[layer] += 1;
+            let x = GRAPH_MARGIN + layer as i32 * (GRAPH_NODE_WIDTH + GRAPH_GAP_X);
+            let y = GRAPH_MARGIN + row * (GRAPH_NODE_HEIGHT + GRAPH_GAP_Y);
+            positions.push((f64::from(x), f64::from(y)));
+
+            let label = gtk::Label::builder()
+                .label(&todo.title)
+                .ellipsize(pango::EllipsizeMode::End)
+                .xalign(0.0)
+                .build();
+            let button = gtk::Button::builder().child(&label).tooltip_text(&todo.title).build();
+            button.add_css_class("graph-node");
+            if todo.done {
+                button.add_css_class("dim-label");
+            }
+            button.set_size_request(GRAPH_NODE_WIDTH, GRAPH_NODE_HEIGHT);
+            let id = todo.key.marker.clone().unwrap_or_else(|| todo.title.clone());
+            button.connect_clicked(move |button| {
+                let _ = button.activate_action("app.show-task", Some(&id.to_variant()));
+            });
+            view.nodes.put(&button, f64::from(x), f64::from(y));
+        }
+
+        let (width, height) = (GRAPH_NODE_WIDTH as f64, GRAPH_NODE_HEIGHT as f64);
+        *view.edges.borrow_mut() = local_edges
+            .iter()
+            .map(|&(a, b)| {
+                let (ax, ay) = positions[a];
+                let (bx, by) = positions[b];
+                ((ax + width, ay + height / 2.0), (bx, by + height / 2.0))
+            })
+            .collect();
+        let columns = rows_used.len() as i32;
+        let rows = rows_used.iter().copied().max().unwrap_or(0);
+        let total_width = 2 * GRAPH_MARGIN + columns * GRAPH_NODE_WIDTH + (columns - 1).max(0) * GRAPH_GAP_X;
+        let total_height = 2 * GRAPH_MARGIN + rows * GRAPH_NODE_HEIGHT + (rows - 1).max(0) * GRAPH_GAP_Y;
+        view.area.set_content_width(total_width);
+        view.area.set_content_height(total_height);
+        view.area.queue_draw();
+        view.placeholder.set_visible(members.is_empty());
+    }
+
+    fn refresh_timeline_view(self: &Rc<Self>) {
+        let Some(view) = self.timeline_view.borrow().clone() else {
+            return;
+        };
+        let include_done = self.show_completed();
+        let mut tasks: Vec<(TodoItem, (NaiveDate, NaiveDate))> = self
+            .cached_items
+            .borrow()
+            .iter()
+            .filter(|todo| include_done || !todo.done)
+            .filter_map(|todo| timeline_span(todo).map(|span| (todo.clone(), span)))
+            .collect();
+        let collator = Collator::current();
+        tasks.sort_by_cached_key(|(todo, span)| (collator.optional_key(todo.project.as_deref()), *span));
+
+        while let Some(child) = view.bars.first_child() {
+            view.bars.remove(&child);
+        }
+        view.placeholder.set_visible(tasks.is_empty());
+        let today = Local::now().date_naive();
+        let first = tasks.iter().map(|(_, (start, _))| *start).min().unwrap_or(today).min(today) - Duration::days(1);
+        let last = tasks.iter().map(|(_, (_, end))| *end).max().unwrap_or(today).max(today);
+        let days = (last - first).num_days() + 1 + TIMELINE_TRAILING_DAYS;
+        view.range.set((first, days));
+
+        for offset in 0..days {
+            let day = first + Duration::days(offset);
+            let x = f64::from(TIMELINE_DAY_WIDTH) * offset as f64;
+            if offset == 0 || day.day() == 1 {
+                let month = gtk::Label::new(Some(&day.format("%Y-%m").to_string()));
+                month.add_css_class("caption-heading");
+                view.bars.put(&month, x + 4.0, 0.0);
+            }
+            let number = gtk::Label::new(Some(&day.day().to_string()));
+            number.add_css_class("caption");
+            if day == today {
+                number.add_css_class("accent");
+            } else {
+                number.add_css_class("dim-label");
+            }
+            number.set_size_request(TIMELINE_DAY_WIDTH, -1);
+            view.bars.put(&number, x, f64::from(TIMELINE_HEADER_HEIGHT) / 2.0);
+        }
+
+        let mut y = f64::from(TIMELINE_HEADER_HEIGHT);
+        let mut group: Option<Option<Name>> = None;
+        for (todo, (start, end)) in tasks {
+            if group.as_ref() != Some(&todo.project) {
+                let heading = gtk::Label::new(Some(&match &todo.project {
+                    Some(project) => format!("+{project}"),
+                    None => t("no_project"),
+                }));
+                heading.add_css_class("heading");
+                view.bars.put(&heading, 6.0, y + 6.0);
+                y += f64::from(TIMELINE_ROW_HEIGHT);
+                group = Some(todo.project.clone());
+            }
+            let x = f64::from(TIMELINE_DAY_WIDTH) * (start - first).num_days() as f64;
+            let span_days = (end - start).num_days() as i32 + 1;
+            let label = gtk::Label::builder()
+                .label(&todo.title)
+                .ellipsize(pango::EllipsizeMode::End)
+                .xalign(0.0)
+                .build();
+            let bar = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+            bar.append(&label);
+            bar.add_css_class("timeline-bar");
+            if todo.done {
+                bar.add_css_class("dim-label");
+            }
+            bar.set_size_request(span_days * TIMELINE_DAY_WIDTH - 4, TIMELINE_ROW_HEIGHT - 6);
+            bar.set_tooltip_text(Some(&format!(
+                "{}\n{} – {}",
+                todo.title,
+                start.format("%Y-%m-%d"),
+                end.format("%Y-%m-%d")
+            )));
+            bar.set_cursor_from_name(Some("grab"));
+            let (bar_x, bar_y) = (x + 2.0, y + 3.0);
+            view.bars.put(&bar, bar_x, bar_y);
+
+            let drag = gtk::GestureDrag::new();
+            let bars = view.bars.clone();
+            drag.connect_drag_update(clone!(@weak bar => move |_, offset_x, _| {
+                bars.move_(&bar, bar_x + offset_x, bar_y);
+            }));
+            let bars = view.bars.clone();
+            let state = Rc::downgrade(self);
+            drag.connect_drag_end(clone!(@weak bar => move |_, offset_x, offset_y| {
+                let days = (offset_x / f64::from(TIMELINE_DAY_WIDTH)).round() as i64;
+                bars.move_(&bar, bar_x, bar_y);
+                let Some(state) = state.upgrade() else {
+                    return;
+                };
+                if days != 0 {
+                    state.shift_schedule(&todo, days);
+                } else if offset_x.abs() < 4.0 && offset_y.abs() < 4.0 {
+                    let id = todo.key.marker.clone().unwrap_or_else(|| todo.title.clone());
+                    let _ = bar.activate_action("app.show-task", Some(&id.to_variant()));
+                }
+            }));
+            bar.add_controller(drag);
+            y += f64::from(TIMELINE_ROW_HEIGHT);
+        }
+
+        view.area.set_content_width(TIMELINE_DAY_WIDTH * days as i32);
+        view.area.set_content_height(y as i32 + TIMELINE_ROW_HEIGHT);
+        view.area.queue_draw();
+    }
+
+    /// Day titles and filters of the week planner, redone only when the shown
+    /// week changed, e.g. after navigating or past midnight on a Sunday.
+    fn refresh_week_view(&self) {
+        let Some(view) = self.week_view.borrow().clone() else {
+            return;
+        };
+        let today = Local::now().date_naive();
+        let monday = week_start(today) + Duration::weeks(view.offset.get());
+        if view.shown.get() == Some(monday) {
+            return;
+        }
+        view.shown.set(Some(monday));
+        let names = t("weekday_names");
+        let names: Vec<&str> = names.split(',').collect();
+        for (index, title) in view.titles.iter().enumerate() {
+            let day = monday + Duration::days(index as i64);
+            let name = names.get(index).copied().unwrap_or_default();
+            title.set_label(&format!("{name} {}", day.day()));
+            if day == today {
+                title.add_css_class("accent");
+            } else {
+                title.remove_css_class("accent");
+            }
+        }
+        view.range_label.set_label(
+            &t("week_range")
+                .replacen("{}", &monday.iso_week().week().to_string(), 1)
+                .replacen("{}", &monday.format("%Y-%m-%d").to_string(), 1)
+                .replacen("{}", &(monday + Duration::days(6)).format("%Y-%m-%d").to_string(), 1),
+        );
+        for filter in &view.filters {
+            filter.changed(gtk::FilterChange::Different);
+        }
+        self.show_calendar_events();
+    }
+
+    fn calendar_source(&self) -> String {
+        self.preferences.borrow().calendar_source.clone().unwrap_or_default()
+    }
+
+    fn set_calendar_source(self: &Rc<Self>, text: &str) {
+        let source = Some(text.trim().to_string()).filter(|source| !source.is_empty());
+        self.preferences.borrow_mut().calendar_source = source;
+        self.persist_preferences();
+        self.refresh_calendar(true);
+    }
+
+    /// Reads the configured calendar on a background thread and shows its
+    /// events once loaded. Failures are only reported when `report` is set,
+    /// so a periodic refresh while offline stays quiet.
+    fn refresh_calendar(self: &Rc<Self>, report: bool) {
+        let Some(source) = self.preferences.borrow().calendar_source.clone() else {
+            self.calendar_events.borrow_mut().clear();
+            self.show_calendar_events();
+            return;
+        };
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(calendar::load(&source));
+        });
+        let state = Rc::downgrade(self);
+        glib::timeout_add_local(std::time::Duration::from_millis(100), move || {
+            let result = match receiver.try_recv() {
+                Ok(result) => result,
+                Err(std::sync::mpsc::TryRecvError::Empty) => return glib::ControlFlow::Continue,
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => return glib::ControlFlow::Break,
+            };
+            let Some(state) = state.upgrade() else {
+                return glib::ControlFlow::Break;
+            };
+            match result {
+                Ok(events) => {
+                    *state.calendar_events.borrow_mut() = events;
+                    state.show_calendar_events();
+                }
+                Err(err) if report => state.show_error(&describe_error(&err)),
+                Err(err) => tracing::warn!("Could not read calendar: {err:#}"),
+            }
+            glib::ControlFlow::Break
+        });
+    }
+
+    /// Puts the calendar events above today's tasks and into the day columns
+    /// of the week planner.
+    fn show_calendar_events(&self) {
+        let events = self.calendar_events.borrow();
+        let today = Local::now().date_naive();
+        if let Some(container) = self.today_events.borrow().as_ref() {
+            let shown = if self.show_due_only() { calendar::on_day(&events, today) } else { Vec::new() };
+            fill_event_rows(container, &shown);
+        }
+        let Some(view) = self.week_view.borrow().clone() else {
+            return;
+        };
+        let Some(monday) = view.shown.get() else {
+            return;
+        };
+        for (index, container) in view.events.iter().enumerate() {
+            fill_event_rows(container, &calendar::on_day(&events, monday + Duration::days(index as i64)));
+        }
+    }
+
+    /// Makes `todo` due on `day`, or undated for `None`, from the week planner.
+    fn plan_for_day(self: &Rc<Self>, todo: &TodoItem, day: Option<NaiveDate>) {
+        if todo.due == day {
+            return;
+        }
+        let mut updated = todo.clone();
+        updated.due = day;
+        if let Err(err) = self.save_item(todo, &updated) {
+            self.show_error(&describe_error(&err));
+        }
+    }
+
+    /// Moves the start and due date of `todo` by `days`, as a drag on the
+    /// timeline does.
+    fn shift_schedule(self: &Rc<Self>, todo: &TodoItem, days: i64) {
+        let mut updated = todo.clone();
+        updated.start = todo.start.map(|start| start + Duration::days(days));
+        updated.due = todo
+            .due
+            .map(|due| if due.year() == 9999 { due } else { due + Duration::days(days) });
+        if let Err(err) = self.save_item(todo, &updated) {
+            self.show_error(&describe_error(&err));
+        }
+    }
+
+    fn focus_complete(self: &Rc<Self>) {
+        let Some(todo) = self.focus_current.borrow().clone() else {
+            return;
+        };
+        if let Err(err) = self.toggle_item(&todo, true) {
+            self.show_error(&t("update_error").replace("{}", &describe_error(&err)));
+        }
+    }
+
+    fn focus_skip(&self) {
+        let Some(todo) = self.focus_current.borrow().clone() else {
+            return;
+        };
+        self.focus_skipped.borrow_mut().push(todo.key);
+        self.refresh_focus_view();
+    }
+
+    fn focus_postpone(self: &Rc<Self>) {
+        let Some(todo) = self.focus_current.borrow().clone() else {
+            return;
+        };
+        if let Err(err) = self.set_due_in_days(&todo, 1) {
+            self.show_error(&t("set_due_error").replace("{}", &describe_error(&err)));
+        }
+    }
+
+    /// One tab per `@context` of the visible tasks, plus one for tasks without.
+    fn sync_context_tabs(self: &Rc<Self>) {
+        let Some(stack) = self.context_stack.borrow().clone() else {
+            return;
+        };
+        let include_done = self.show_completed();
+        let mut contexts: Vec<Option<Name>> = self
+            .cached_items
+            .borrow()
+            .iter()
+            .filter(|todo| include_done || !todo.done)
+            .map(|todo| todo.context.clone().filter(|c| !c.is_empty()))
+            .collect();
+        let collator = Collator::current();
+        contexts.sort_by_cached_key(|context| collator.optional_key(context.as_deref()));
+        contexts.dedup();
+
+        let names: Vec<String> = contexts.iter().map(|c| context_tab_name(c.as_deref())).collect();
+        let pages = stack.pages();
+        let existing: Vec<String> = (0..pages.n_items())
+            .filter_map(|i| pages.item(i).and_downcast::<adw::ViewStackPage>())
+            .filter_map(|page| page.name().map(|n| n.to_string()))
+            .collect();
+        if existing == names {
+            return;
+        }
+
+        self.syncing_tabs.set(true);
+        while let Some(child) = stack.first_child() {
+            stack.remove(&child);
+        }
+        for (context, name) in contexts.into_iter().zip(&names) {
+            let filter_context = context.clone();
+            let filtered = gtk::FilterListModel::new(
+                Some(self.store()),
+                Some(gtk::CustomFilter::new(move |obj| {
+                    list_entry_todo(obj)
+                        .is_some_and(|todo| todo.context.filter(|c| !c.is_empty()) == filter_context)
+                })),
+            );
+            let scrolled = gtk::ScrolledWindow::builder()
+                .child(&create_list_view(self, &filtered))
+                .vexpand(true)
+                .hexpand(true)
+                .build();
+            let title = match &context {
+                Some(c) => format!("@{c}"),
+                None => t("no_location"),
+            };
+            stack.add_titled(&scrolled, Some(name), &title);
+        }
+        let saved = self.preferences.borrow().context_tabs.get(&data::database_location()).cloned();
+        if let Some(saved) = saved.filter(|name| names.contains(name)) {
+            stack.set_visible_child_name(&saved);
+        }
+        self.syncing_tabs.set(false);
+    }
+
+    fn remember_context_tab(&self, name: &str) {
+        {
+            let mut prefs = self.preferences.borrow_mut();
+            prefs.context_tabs.insert(data::database_location(), name.to_string());
+        }
+        self.persist_preferences();
+    }
+
+    fn collapsed_sections(&self) -> Vec<String> {
+        self.preferences
+            .borrow()
+            .collapsed_sections
+            .get(&data::database_location())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Search results are grouped by fixed headers that always stay open.
+    fn sections_collapsible(&self) -> bool {
+        self.search_term.borrow().is_empty()
+    }
+
+    fn is_section_collapsed(&self, label: &str) -> bool {
+        self.collapsed_sections().iter().any(|section| section == label)
+    }
+
+    fn toggle_section(&self, label: &str) {
+        if !self.sections_collapsible() {
+            return;
+        }
+        {
+            let mut prefs = self.preferences.borrow_mut();
+            let sections = prefs.collapsed_sections.entry(data::database_location()).or_default();
+            match sections.iter().position(|section| section == label) {
+                Some(index) => {
+                    sections.remove(index);
+                }
+                None => sections.push(label.to_string()),
+            }
+            if sections.is_empty() {
+                prefs.collapsed_sections.remove(&data::database_location());
+            }
+        }
+        self.persist_preferences();
+        self.repopulate_store();
+    }
+
+    /// The `+project` or `@context` a group header stands for, if any.
+    fn header_tag(&self, label: &str) -> Option<(data::Tag, String)> {
+        if !self.sections_collapsible() {
+            return None;
+        }
+        let mode = *self.sort_mode.borrow();
+        let items = self.cached_items.borrow();
+        let item = items
+            .iter()
+            .find(|item| self.group_label(mode, item).as_deref() == Some(label))?;
+        let (tag, value) = match mode {
+            SortMode::Topic => (data::Tag::Project, item.project.clone()),
+            SortMode::Location => (data::Tag::Context, item.context.clone()),
+            SortMode::Date => return None,
+        };
+        value
+            .filter(|value| !value.is_empty() && !item.pinned)
+            .map(|value| (tag, value.to_string()))
+    }
+
+    fn show_rename_tag_dialog(self: &Rc<Self>, label: &str) {
+        let Some((tag, old)) = self.header_tag(label) else {
+            return;
+        };
+        let Some(parent) = self.window.upgrade() else {
+            self.show_error(&t("no_window"));
+            return;
+        };
+
+        let name_row = adw::EntryRow::builder().title(t("tag_new_name")).text(&old).build();
+        let name_list = gtk::ListBox::new();
+        name_list.add_css_class("boxed-list");
+        name_list.set_selection_mode(gtk::SelectionMode::None);
+        name_list.append(&name_row);
+
+        let hint = gtk::Label::builder()
+            .label(t("rename_tag_desc"))
+            .wrap(true)
+            .xalign(0.0)
+            .build();
+        hint.add_css_class("dim-label");
+
+        let rename_btn = gtk::Button::with_label(&t("rename"));
+        rename_btn.add_css_class("suggested-action");
+        rename_btn.set_halign(gtk::Align::End);
+
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 12);
+        content.set_margin_top(16);
+        content.set_margin_bottom(16);
+        content.set_margin_start(20);
+        content.set_margin_end(20);
+        content.append(&name_list);
+        content.append(&hint);
+        content.append(&rename_btn);
+
+        let toolbar = adw::ToolbarView::new();
+        toolbar.add_top_bar(&adw::HeaderBar::new());
+        toolbar.set_content(Some(&content));
+        let dialog = adw::Window::builder()
+            .title(t("rename_tag_title").replace("{}", &format!("{}{old}", tag.sigil())))
+            .transient_for(&parent)
+            .modal(true)
+            .default_width(400)
+            .content(&toolbar)
+            .build();
+        dialog.set_destroy_with_parent(true);
+
+        let state = Rc::clone(self);
+        let old_label = label.to_string();
+        let submit = clone!(@weak dialog, @weak name_row => move || {
+            let Some(new) = data::normalize_tag(&name_row.text()) else {
+                state.show_error(&t("tag_name_empty"));
+                return;
+            };
+            if new == old {
+                dialog.close();
+                return;
+            }
+            match data::rename_tag(tag, &old, &new) {
+                Ok(count) => {
+                    state.rename_tag_references(tag, &old, &new, &old_label);
+                    if let Err(err) = state.reload() {
+                        state.show_error(&describe_error(&err));
+                    }
+                    state.show_info(&t("tag_renamed").replacen("{}", &count.to_string(), 1).replacen("{}", &format!("{}{new}", tag.sigil()), 1));
+                    dialog.close();
+                }
+                Err(err) => state.show_error(&describe_error(&err)),
+            }
+        });
+        let submit = Rc::new(submit);
+        name_row.connect_entry_activated(clone!(@strong submit => move |_| submit()));
+        rename_btn.connect_clicked(move |_| submit());
+        dialog.present();
+        name_row.grab_focus();
+    }
+
+    /// After switching databases, brings back the sort, filters and view the
+    /// newly opened one was left with. A database opened for the first time
+    /// keeps the current ones.
+    fn follow_view_state(&self) {
+        let location = data::database_location();
+        if *self.view_state_owner.borrow() == location {
+            return;
+        }
+        let previous = self.view_state_owner.replace(location.clone());
+        let starting = previous.is_empty();
+        self.apply_accent();
+        if !starting {
+            let mut prefs = self.preferences.borrow_mut();
+            let current = ViewState::of(&prefs);
+            prefs.view_states.insert(previous, current);
+        }
+        let saved = self.preferences.borrow().view_states.get(&location).cloned();
+        let Some(saved) = saved.filter(|saved| *saved != ViewState::of(&self.preferences.borrow())) else {
+            self.persist_preferences();
+            return;
+        };
+        tracing::debug!(%location, ?saved, "restoring view state");
+        {
+            let mut prefs = self.preferences.borrow_mut();
+            saved.apply(&mut prefs);
+            // config.toml still decides at startup.
+            if let Some(hide) = config::get().hide_completed.filter(|_| starting) {
+                prefs.show_done = !hide;
+            }
+        }
+        *self.sort_mode.borrow_mut() = saved.sort_mode.as_deref().map(SortMode::from_key).unwrap_or(SortMode::Topic);
+        // The handlers of these find the preferences set already.
+        if let Some(selector) = self.sort_selector.borrow().as_ref() {
+            selector.set_selected(self.sort_mode().to_index());
+        }
+        if let Some(button) = self.due_filter.borrow().as_ref() {
+            button.set_active(saved.show_due_only);
+        }
+        if let Some(selector) = self.view_selector.borrow().as_ref() {
+            selector.set_selected(self.view_mode().to_index());
+        }
+        if let Some(stack) = self.view_stack.borrow().as_ref() {
+            stack.set_visible_child_name(self.view_mode().as_key());
+        }
+        self.persist_preferences();
+    }
+
+    /// The accent colour pinned for the open database, `None` following the
+    /// system.
+    fn accent(&self) -> Option<String> {
+        self.preferences.borrow().accents.get(&data::database_location()).cloned()
+    }
+
+    fn set_accent(&self, accent: Option<&str>) {
+        {
+            let mut prefs = self.preferences.borrow_mut();
+            match accent {
+                Some(accent) => prefs.accents.insert(data::database_location(), accent.to_string()),
+                None => prefs.accents.remove(&data::database_location()),
+            };
+        }
+        self.persist_preferences();
+        self.apply_accent();
+    }
+
+    fn apply_accent(&self) {
+        let css = self.accent().and_then(|accent| accent_css(&accent));
+        self.accent_provider.load_from_string(css.as_deref().unwrap_or_default());
+    }
+
+    /// Points the remembered context tab and collapsed headers of the current
+    /// database at the renamed tag.
+    fn rename_tag_references(&self, tag: data::Tag, old: &str, new: &str, old_label: &str) {
+        let location = data::database_location();
+        let new_label = match tag {
+            data::Tag::Project => t("topic_group").replace("{}", new),
+            data::Tag::Context => t("location_group").replace("{}", new),
+        };
+        {
+            let mut prefs = self.preferences.borrow_mut();
+            if let Some(sections) = prefs.collapsed_sections.get_mut(&location) {
+                for section in sections.iter_mut().filter(|section| *section == old_label) {
+                    *section = new_label.clone();
+                }
+                sections.dedup();
+            }
+            if tag == data::Tag::Context {
+                let old_tab = context_tab_name(Some(old));
+                if let Some(tab) = prefs.context_tabs.get_mut(&location).filter(|tab| **tab == old_tab) {
+                    *tab = context_tab_name(Some(new));
+                }
+            }
+        }
+        self.persist_preferences();
+    }
+
+    /// Distinct projects, contexts or sections of the loaded tasks, sorted.
+    fn known_values(&self, kind: Completion) -> Vec<String> {
+        let no_section = t("no_section");
+        let mut values: Vec<String> = self
+            .cached_items
+            .borrow()
+            .iter()
+            .filter_map(|todo| match kind {
+                Completion::Project => todo.project.as_deref().map(str::to_string),
+                Completion::Context => todo.context.as_deref().map(str::to_string),
+                Completion::Section => Some(todo.section.to_string()).filter(|section| *section != no_section),
+                Completion::Inline => None,
+            })
+            .filter(|value| !value.is_empty())
+            .collect();
+        values.sort();
+        values.dedup();
+        collation::sort(&mut values, |value| value);
+        values
+    }
+
+    fn daily_agenda(&self) -> bool {
+        self.preferences.borrow().daily_agenda
+    }
+
+    fn set_daily_agenda(&self, enabled: bool) {
+        self.preferences.borrow_mut().daily_agenda = enabled;
+        self.persist_preferences();
+    }
+
+    fn agenda_time(&self) -> String {
+        self.preferences
+            .borrow()
+            .agenda_time
+            .clone()
+            .unwrap_or_else(|| DEFAULT_AGENDA_TIME.to_string())
+    }
+
+    fn set_agenda_time(&self, text: &str) -> bool {
+        let Some(time) = parse_agenda_time(text) else {
+            return false;
+        };
+        self.preferences.borrow_mut().agenda_time = Some(time.format("%H:%M").to_string());
+        self.persist_preferences();
+        true
+    }
+
+    /// Accelerators currently bound to `action`.
+    fn shortcuts(&self, action: &str) -> Vec<String> {
+        let custom = self.preferences.borrow().shortcuts.get(action).cloned();
+        custom.unwrap_or_else(|| default_shortcuts(action).iter().map(|accel| accel.to_string()).collect())
+    }
+
+    /// Rebinds `action` to the space separated accelerators in `text`. An
+    /// empty text removes all shortcuts of the action.
+    fn set_shortcuts(&self, action: &str, text: &str) -> Result<()> {
+        let Some(accels) = parse_shortcuts(text) else {
+            bail!(t("invalid_shortcut_error").replace("{}", text.trim()));
+        };
+        for accel in &accels {
+            let taken = SHORTCUT_ACTIONS
+                .iter()
+                .find(|(other, _, _)| *other != action && self.shortcuts(other).contains(accel));
+            if let Some((_, label, _)) = taken {
+                bail!(t("shortcut_in_use_error").replacen("{}", &shortcut_label(accel), 1).replacen("{}", &t(label), 1));
+            }
+        }
+        self.preferences.borrow_mut().shortcuts.insert(action.to_string(), accels);
+        self.persist_preferences();
+        self.apply_shortcuts();
+        Ok(())
+    }
+
+    fn reset_shortcuts(&self, action: &str) {
+        self.preferences.borrow_mut().shortcuts.remove(action);
+        self.persist_preferences();
+        self.apply_shortcuts();
+    }
+
+    fn apply_shortcuts(&self) {
+        if let Some(app) = self.window.upgrade().and_then(|w| w.application()) {
+            apply_shortcuts(&app, &self.preferences.borrow().shortcuts);
+        }
+    }
+
+    /// Seconds until the next change check: short while the file monitor is
+    /// unreliable, so auto-refresh keeps working on NFS, SMB and the like.
+    fn poll_interval(&self) -> u32 {
+        let local = matches!(data::get_backend_config(), data::BackendConfig::Local(_));
+        if self.database_missing.get() {
+            MISSING_POLL_INTERVAL
+        } else if !local || self.file_events.get() {
+            MONITORED_POLL_INTERVAL
+        } else {
+            self.preferences.borrow().poll_interval.max(1)
+        }
+    }
+
+    fn set_poll_interval(&self, seconds: u32) {
+        self.preferences.borrow_mut().poll_interval = seconds;
+        self.persist_preferences();
+    }
+
+    fn set_daily_rollover(&self, enabled: bool) {
+        {
+            let mut prefs = self.preferences.borrow_mut();
+            prefs.daily_rollover = enabled;
+            // Rolls over on the next change of day, not right away.
+            prefs.rollover_last = enabled.then(|| Local::now().date_naive());
+        }
+        self.persist_preferences();
+    }
+
+    /// The once-a-day rewrites of the database, run at startup and midnight.
+    fn run_daily_passes(&self) {
+        self.roll_over_daily_sections();
+        self.age_priorities();
+    }
+
+    fn set_priority_aging_days(&self, days: u32) {
+        {
+            let mut prefs = self.preferences.borrow_mut();
+            prefs.priority_aging_days = days;
+            // A new threshold takes effect with the next daily pass.
+            prefs.aging_last = None;
+        }
+        self.persist_preferences();
+    }
+
+    fn age_priorities(&self) {
+        let today = Local::now().date_naive();
+        let days = {
+            let prefs = self.preferences.borrow();
+            if prefs.priority_aging_days == 0 || prefs.aging_last == Some(today) {
+                return;
+            }
+            prefs.priority_aging_days
+        };
+        match aging::age_priorities(days, today) {
+            Ok(aged) => {
+                self.preferences.borrow_mut().aging_last = Some(today);
+                self.persist_preferences();
+                if aged == 0 {
+                    return;
+                }
+                tracing::info!(aged, "raised priorities of overdue tasks");
+                if let Err(err) = self.reload() {
+                    self.show_error(&describe_error(&err));
+                }
+                self.show_info(&t("priority_aged").replace("{}", &aged.to_string()));
+            }
+            Err(err) => self.show_error(&t("priority_aging_error").replace("{}", &describe_error(&err))),
+        }
+    }
+
+    fn revert_aging(&self, todo: &TodoItem) {
+        match aging::revert(todo).and_then(|_| self.reload()) {
+            Ok(()) => self.show_info(&t("aging_reverted").replace("{}", &todo.title)),
+            Err(err) => self.show_error(&describe_error(&err)),
+        }
+    }
+
+    /// Once per day, moves unfinished tasks of past daily sections to today's.
+    fn roll_over_daily_sections(&self) {
+        let today = Local::now().date_naive();
+        {
+            let prefs = self.preferences.borrow();
+            if !prefs.daily_rollover || prefs.rollover_last == Some(today) {
+                return;
+            }
+        }
+        match data::roll_over_daily_sections(today) {
+            Ok(moved) => {
+                self.preferences.borrow_mut().rollover_last = Some(today);
+                self.persist_preferences();
+                if moved == 0 {
+                    return;
+                }
+                tracing::info!(moved, "rolled over daily sections");
+                if let Err(err) = self.reload() {
+                    self.show_error(&describe_error(&err));
+                }
+                self.show_info(&t("rollover_done").replace("{}", &moved.to_string()));
+            }
+            Err(err) => self.show_error(&t("rollover_error").replace("{}", &describe_error(&err))),
+        }
+    }
+
+    /// Whether the last key went to a type-ahead that is still going on.
+    fn type_ahead_pending(&self) -> bool {
+        self.type_ahead_key.get().is_some_and(|at| at.elapsed().as_millis() <= TYPE_AHEAD_RESET_MS)
+    }
+
+    fn vim_keys(&self) -> bool {
+        self.preferences.borrow().vim_keys
+    }
+
+    fn set_vim_keys(&self, enabled: bool) {
+        self.preferences.borrow_mut().vim_keys = enabled;
+        self.persist_preferences();
+    }
+
+    fn reduce_motion(&self) -> bool {
+        self.preferences.borrow().reduce_motion
+    }
+
+    fn set_reduce_motion(&self, enabled: bool) {
+        self.preferences.borrow_mut().reduce_motion = enabled;
+        self.persist_preferences();
+        self.apply_reduce_motion();
+    }
+
+    /// Switches GTK's and libadwaita's animations off while motion is
+    /// reduced, or back to the system's `gtk-enable-animations`.
+    fn apply_reduce_motion(&self) {
+        let Some(settings) = gtk::Settings::default() else {
+            return;
+        };
+        if self.reduce_motion() {
+            settings.set_gtk_enable_animations(false);
+        } else {
+            settings.reset_property("gtk-enable-animations");
+        }
+    }
+
+    fn set_skip_delete_confirmation(&self, skip: bool) {
+        self.preferences.borrow_mut().skip_delete_confirmation = skip;
+        self.persist_preferences();
+    }
+
+    fn autostart_service(&self) -> bool {
+        self.preferences.borrow().autostart_service
+    }
+
+    fn set_autostart_service(&self, enabled: bool) -> Result<()> {
+        autostart::set_enabled(enabled)?;
+        self.preferences.borrow_mut().autostart_service = enabled;
+        self.persist_preferences();
+        Ok(())
+    }
+
+    /// Sends one summary notification per day once the configured time passed.
+    fn send_agenda_if_due(&self) {
+        if !self.daily_agenda() {
+            return;
+        }
+        let now = Local::now();
+        let today = now.date_naive();
+        let time = parse_agenda_time(&self.agenda_time()).unwrap_or(NaiveTime::MIN);
+        if now.time() < time || self.preferences.borrow().agenda_last_sent == Some(today) {
+            return;
+        }
+
+        let (due_today, overdue) = self
+            .cached_items
+            .borrow()
+            .iter()
+            .filter(|todo| !todo.done)
+            .fold((0, 0), |(due_today, overdue), todo| match todo.due {
+                Some(d) if d == today => (due_today + 1, overdue),
+                Some(d) if d < today => (due_today, overdue + 1),
+                _ => (due_today, overdue),
+            });
+        self.preferences.borrow_mut().agenda_last_sent = Some(today);
+        self.persist_preferences();
+        if due_today == 0 && overdue == 0 {
+            return;
+        }
+
+        let Some(app) = self.window.upgrade().and_then(|w| w.application()) else {
+            return;
+        };
+        let notification = gio::Notification::new(&t("agenda_title"));
+        notification.set_body(Some(
+            &t("agenda_summary")
+                .replacen("{}", &due_today.to_string(), 1)
+                .replacen("{}", &overdue.to_string(), 1),
+        ));
+        notification.set_default_action("app.show-today");
+        app.send_notification(Some("daily-agenda"), &notification);
+        tracing::info!(due_today, overdue, "daily agenda sent");
+    }
+
+    /// Sends a notification with "Done" and "Snooze 1h" buttons for every open
+    /// task whose `remind:` time or due time passed since the last check.
+    fn send_due_reminders(&self) {
+        let now = Local::now().naive_local();
+        let checked = self.preferences.borrow().reminders_checked;
+        self.preferences.borrow_mut().reminders_checked = Some(now);
+        // The first run only starts the clock instead of replaying old reminders.
+        let Some(checked) = checked else {
+            self.persist_preferences();
+            return;
+        };
+        let Some(app) = self.window.upgrade().and_then(|w| w.application()) else {
+            return;
+        };
+
+        let items = self.cached_items.borrow();
+        // Due times compare as moments, so the hour repeated when DST ends
+        // does not notify twice.
+        let (since, until) = (data::local_datetime(checked), data::local_datetime(now));
+        let due = items.iter().filter(|todo| {
+            let reminded = todo.remind.is_some_and(|remind| remind > checked && remind <= now);
+            let due_now = todo.due_at().is_some_and(|due| due > since && due <= until);
+            !todo.done && (reminded || due_now)
+        });
+        let mut sent = false;
+        for todo in due {
+            let id = history::task_id(todo);
+            let notification = gio::Notification::new(&todo.title);
+            let details = format_metadata(todo, &MetaField::ALL);
+            if !details.is_empty() {
+                notification.set_body(Some(&details));
+            }
+            notification.set_default_action_and_target_value("app.show-task", Some(&id.to_variant()));
+            notification.add_button_with_target_value(&t("reminder_done"), "app.complete-task", Some(&id.to_variant()));
+            notification.add_button_with_target_value(&t("reminder_snooze"), "app.snooze-task", Some(&id.to_variant()));
+            app.send_notification(Some(&reminder_notification_id(&id)), &notification);
+            tracing::info!(task = %id, "reminder sent");
+            sent = true;
+        }
+        // Saved only when a reminder went out rather than every minute. After
+        // a restart the check resumes from there, which repeats none of them.
+        drop(items);
+        if sent {
+            self.persist_preferences();
+        }
+    }
+
+    fn complete_reminded_task(self: &Rc<Self>, id: &str) {
+        self.withdraw_reminder(id);
+        let todo = data::find_task(&self.cached_items.borrow(), id).cloned();
+        let Some(todo) = todo else {
+            self.show_error(&t("task_link_not_found").replace("{}", id));
+            return;
+        };
+        if let Err(err) = self.toggle_item(&todo, true) {
+            self.show_error(&t("update_error").replace("{}", &describe_error(&err)));
+        }
+    }
+
+    /// Moves the reminder of task `id` to an hour from now.
+    fn snooze_reminded_task(self: &Rc<Self>, id: &str) {
+        self.withdraw_reminder(id);
+        let todo = data::find_task(&self.cached_items.borrow(), id).cloned();
+        let Some(todo) = todo else {
+            self.show_error(&t("task_link_not_found").replace("{}", id));
+            return;
+        };
+        let mut updated = todo.clone();
+        let later = Local::now().naive_local() + Duration::hours(1);
+        updated.remind = later.with_second(0).and_then(|time| time.with_nanosecond(0));
+        if let Err(err) = self.save_item(&todo, &updated) {
+            self.show_error(&t("save_task_error").replace("{}", &describe_error(&err)));
+        }
+    }
+
+    fn withdraw_reminder(&self, id: &str) {
+        if let Some(app) = self.window.upgrade().and_then(|w| w.application()) {
+            app.withdraw_notification(&reminder_notification_id(id));
+        }
+    }
+
+    fn use_whisper(&self) -> bool {
+        self.preferences.borrow().use_whisper
+    }
+
+    fn whisper_language(&self) -> String {
+        self.preferences.borrow().whisper_language.clone()
+    }
+
+    fn whisper_model_path(&self) -> PathBuf {
+        let mut dir = glib::user_cache_dir();
+        dir.push("reinschrift_todo");
+        dir.push("ggml-small.bin");
+        dir
+    }
+
+    fn reload(&self) -> Result<()> {
+        let started = std::time::Instant::now();
+        if matches!(data::get_backend_config(), data::BackendConfig::WebDav { .. }) {
+            self.set_persistence(Persistence::Syncing);
+        }
+        let items = match data::load_todos() {
+            Ok(items) => items,
+            Err(err) => {
+                self.set_persistence(Persistence::Error(describe_error(&err)));
+                self.set_database_missing(matches!(err.downcast_ref::<DataError>(), Some(DataError::NotFound { .. })));
+                self.set_file_problem(FileSlot::Load, FileProblem::from_load_error(&err));
+                return Err(err);
+            }
+        };
+        self.set_database_missing(false);
+        self.follow_view_state();
+        self.set_file_problem(FileSlot::Load, database_read_only().then_some(FileProblem::ReadOnly));
+        self.update_duplicates_banner(&items);
+        self.update_problems_panel();
+        *self.cached_items.borrow_mut() = items;
+        if let Ok(fp) = data::get_fingerprint() {
+            *self.last_fingerprint.borrow_mut() = Some(fp);
+        }
+        self.repopulate_store();
+        self.update_launcher_badge();
+        tracing::debug!(elapsed = ?started.elapsed(), "reload finished");
+        if matches!(self.persistence.borrow().0, Persistence::Syncing | Persistence::Error(_)) {
+            self.set_persistence(Persistence::Loaded);
+        }
+        self.sync_save_status();
+        Ok(())
+    }
+
+    /// Shows `persistence` in the header bar indicator and its popover.
+    fn set_persistence(&self, persistence: Persistence) {
+        self.set_persistence_at(persistence, Local::now());
+    }
+
+    fn set_persistence_at(&self, persistence: Persistence, at: DateTime<Local>) {
+        let summary = persistence.summary(at);
+        let mut details = format!("{summary}\n{}", t("status_database").replace("{}", &data::database_location()));
+        if let Persistence::Error(message) | Persistence::Offline(message) = &persistence {
+            details.push_str(&format!("\n\n{message}"));
+        }
+        if let Some(button) = self.status_button.borrow().as_ref() {
+            button.set_icon_name(persistence.icon_name());
+            button.set_tooltip_text(Some(&summary));
+            if matches!(persistence, Persistence::Error(_)) {
+                button.remove_css_class("dim-label");
+                button.add_css_class("error");
+            } else {
+                button.remove_css_class("error");
+                button.add_css_class("dim-label");
+            }
+        }
+        if let Some(label) = self.status_details.borrow().as_ref() {
+            label.set_text(&details);
+        }
+        *self.persistence.borrow_mut() = (persistence, at);
+    }
+
+    /// Adopts the outcome of the latest write if it is newer than what the
+    /// indicator shows.
+    fn sync_save_status(&self) {
+        let Some(status) = data::save_status() else {
+            return;
+        };
+        if status.time() <= self.persistence.borrow().1 {
+            return;
+        }
+        let persistence = match &status {
+            data::SaveStatus::Saving(_) => Persistence::Saving,
+            data::SaveStatus::Saved(_) => Persistence::Saved,
+            data::SaveStatus::Failed(_, message) => Persistence::Error(message.clone()),
+        };
+        self.set_persistence_at(persistence, status.time());
+    }
+
+    /// Follows the offline copy of a remote database: shows when it cannot
+    /// reach the remote and asks which version to keep after a conflict.
+    fn sync_offline_status(self: &Rc<Self>) {
+        let offline = matches!(self.persistence.borrow().0, Persistence::Offline(_));
+        match cache::status() {
+            Some(SyncStatus::Offline(message)) if !offline => {
+                tracing::info!("working offline: {message}");
+                self.set_persistence(Persistence::Offline(message));
+            }
+            Some(SyncStatus::Conflict) if !self.offline_conflict_open.get() => self.offer_conflict_resolution(),
+            Some(SyncStatus::Synced | SyncStatus::Pending) if offline => self.set_persistence(Persistence::Saved),
+            _ => {}
+        }
+    }
+
+    fn offer_conflict_resolution(self: &Rc<Self>) {
+        let (Some(conflict), Some(parent)) = (cache::conflict(), self.window.upgrade()) else {
+            return;
+        };
+        self.offline_conflict_open.set(true);
+        let changed = conflict.mine.lines().zip(conflict.theirs.lines()).filter(|(mine, theirs)| mine != theirs).count()
+            + conflict.mine.lines().count().abs_diff(conflict.theirs.lines().count());
+
+        let dialog = AlertDialog::builder()
+            .modal(true)
+            .build();
+        dialog.set_message(&t("offline_conflict_title"));
+        dialog.set_detail(&t("offline_conflict_detail").replace("{}", &changed.to_string()));
+        dialog.set_buttons(&[&t("offline_use_theirs"), &t("offline_keep_mine")]);
+        dialog.set_default_button(1);
+
+        let state = Rc::clone(self);
+        dialog.choose(Some(&parent), gio::Cancellable::NONE, move |result| {
+            state.offline_conflict_open.set(false);
+            let keep_mine = match result {
+                Ok(0) => false,
+                Ok(1) => true,
+                // Asked again on the next status check.
+                _ => return,
+            };
+            if let Err(err) = cache::resolve(keep_mine).and_then(|_| state.reload()) {
+                state.show_error(&describe_error(&err));
+            }
+        });
+    }
+
+    fn update_duplicates_banner(&self, items: &[TodoItem]) {
+        let Some(banner) = self.duplicates_banner.borrow().clone() else {
+            return;
+        };
+        let duplicates = data::find_duplicate_keys(items);
+        if duplicates.is_empty() {
+            banner.set_revealed(false);
+            return;
+        }
+
+        let listing: Vec<String> = duplicates
+            .iter()
+            .map(|dup| {
+                let lines: Vec<String> = dup.line_indices.iter().map(|i| (i + 1).to_string()).collect();
+                let mut lines = lines.join(", ");
+                if let Some(name) = dup.file.as_deref().and_then(Path::file_name) {
+                    lines = format!("{}: {lines}", name.to_string_lossy());
+                }
+                t("duplicate_key_entry")
+                    .replacen("{}", &dup.marker, 1)
+                    .replacen("{}", &lines, 1)
+            })
+            .collect();
+        banner.set_title(&t("duplicate_keys_warning").replace("{}", &listing.join("; ")));
+        banner.set_revealed(true);
+    }
+
+    /// Records or clears the problem of `slot` and updates the banner.
+    fn set_file_problem(&self, slot: FileSlot, problem: Option<FileProblem>) {
+        if self.file_problems.borrow()[slot as usize] == problem {
+            return;
+        }
+        self.file_problems.borrow_mut()[slot as usize] = problem;
+        let Some(banner) = self.file_banner.borrow().clone() else {
+            return;
+        };
+        match self.file_problems.borrow().iter().flatten().next() {
+            Some(problem) => {
+                banner.set_title(&problem.title());
+                banner.set_button_label(Some(&problem.button_label()));
+                banner.set_revealed(true);
+            }
+            None => banner.set_revealed(false),
+        }
+    }
+
+    /// Shows the missing page instead of the views while the database file
+    /// is gone.
+    fn set_database_missing(&self, missing: bool) {
+        if self.database_missing.replace(missing) == missing {
+            return;
+        }
+        if missing {
+            tracing::warn!(location = %data::database_location(), "database file is gone");
+            if let Some(page) = self.missing_page.borrow().as_ref() {
+                let location = glib::markup_escape_text(&data::database_location());
+                page.set_description(Some(&t("database_missing_desc").replace("{}", &location)));
+            }
+        }
+        if let Some(stack) = self.database_stack.borrow().as_ref() {
+            stack.set_visible_child_name(if missing { "missing" } else { "views" });
+        }
+    }
+
+    /// Writes the deleted database back with the tasks last loaded.
+    fn recreate_database(self: &Rc<Self>) {
+        if let Err(err) = data::recreate_database() {
+            self.show_error(&t("database_recreate_error").replace("{}", &describe_error(&err)));
+            return;
+        }
+        self.database_back();
+        self.show_info(&t("database_recreated"));
+    }
+
+    /// Loads the database again once it reappeared, and watches the new
+    /// file: a monitor may still follow the deleted one.
+    fn database_back(self: &Rc<Self>) {
+        match self.reload() {
+            Ok(()) => self.install_monitor(),
+            Err(err) => self.show_error(&t("reload_error").replace("{}", &describe_error(&err))),
+        }
+    }
+
+    /// The banner button: the way out of the problem shown.
+    fn resolve_file_problem(self: &Rc<Self>) {
+        let problem = self.file_problems.borrow().iter().flatten().next().cloned();
+        match problem {
+            Some(FileProblem::Missing(_)) => self.show_settings_dialog(None),
+            Some(FileProblem::Unreadable(_) | FileProblem::ReadOnly) => {
+                if let Err(err) = self.reload() {
+                    self.show_error(&t("reload_error").replace("{}", &describe_error(&err)));
+                }
+            }
+            Some(FileProblem::Unwatched(_)) => self.install_monitor(),
+            None => {}
+        }
+    }
+
+    /// Lists what the last load could not read, so the file can be fixed
+    /// while the rest of the tasks stay usable.
+    fn update_problems_panel(&self) {
+        let panel = self.problems_panel.borrow();
+        let Some(panel) = panel.as_ref() else {
+            return;
+        };
+        // Warnings such as `note:` in a title are for `reinschrift check`.
+        let problems: Vec<data::Problem> = data::load_problems()
+            .into_iter()
+            .filter(|problem| !problem.kind.is_warning())
+            .collect();
+        panel.list.remove_all();
+        if problems.is_empty() {
+            panel.banner.set_revealed(false);
+            panel.revealer.set_reveal_child(false);
+            panel.banner.set_button_label(Some(&t("problems_show")));
+            return;
+        }
+        for problem in &problems {
+            let mut place = t("problem_line").replace("{}", &problem.line.to_string());
+            if let Some(file) = problem.file.as_deref().and_then(|file| file.file_name()) {
+                place = format!("{} · {place}", file.to_string_lossy());
+            }
+            let row = adw::ActionRow::builder()
+                .title(problem.message())
+                .subtitle(place)
+                .use_markup(false)
+                .build();
+            panel.list.append(&row);
+        }
+        panel.banner.set_title(&t("problems_banner").replace("{}", &problems.len().to_string()));
+        panel.banner.set_revealed(true);
+    }
+
+    /// Publishes the number of open tasks due today or earlier as a count
+    /// badge through the Unity LauncherEntry D-Bus API (Dash to Dock, Plank,
+    /// KDE task manager, …). Docks without support just ignore the signal.
+    fn update_launcher_badge(&self) {
+        let Some(app) = self.window.upgrade().and_then(|w| w.application()) else {
+            return;
+        };
+        let (Some(connection), Some(path), Some(app_id)) = (app.dbus_connection(), app.dbus_object_path(), app.application_id()) else {
+            return;
+        };
+        let today = Local::now().date_naive();
+        let count = self
+            .cached_items
+            .borrow()
+            .iter()
+            .filter(|todo| !todo.done && todo.due.is_some_and(|due| due <= today))
+            .count() as i64;
+
+        let properties = glib::VariantDict::new(None);
+        properties.insert_value("count", &count.to_variant());
+        properties.insert_value("count-visible", &(count > 0).to_variant());
+        let parameters = glib::Variant::tuple_from_iter([
+            format!("application://{app_id}.desktop").to_variant(),
+            properties.end(),
+        ]);
+        if let Err(err) = connection.emit_signal(None, &path, "com.canonical.Unity.LauncherEntry", "Update", Some(&parameters)) {
+            tracing::debug!("launcher badge update failed: {err}");
+        }
+    }
+
+    fn check_for_updates(&self) -> Result<()> {
+        let current_fp = match data::get_fingerprint() {
+            Ok(fingerprint) => fingerprint,
+            // Still gone; the missing page is showing already.
+            Err(err) if self.database_missing.get() && matches!(err.downcast_ref::<DataError>(), Some(DataError::NotFound { .. })) => {
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        };
+        let last_fp = self.last_fingerprint.borrow().clone();
+
+        if Some(current_fp) != last_fp {
+            tracing::info!("database changed externally, reloading");
+            self.reload()?;
+            self.set_persistence(Persistence::ExternalChange);
+        }
+        Ok(())
+    }
+
+    /// Adds the text selected in another window, the primary selection, as
+    /// a task.
+    fn add_from_selection(self: &Rc<Self>) {
+        let Some(window) = self.window.upgrade() else {
+            return;
+        };
+        window.primary_clipboard().read_text_async(gio::Cancellable::NONE, clone!(@weak self as state => move |text| {
+            let Some(title) = text.ok().flatten().and_then(|text| selection_title(&text)) else {
+                state.show_info(&t("selection_empty"));
+                return;
+            };
+            match data::add_todo(&title) {
+                Ok(_) => match state.reload() {
+                    Ok(()) => state.show_info(&t("task_added_from_selection").replace("{}", &title)),
+                    Err(err) => state.show_error(&t("reload_error").replace("{}", &describe_error(&err))),
+                },
+                Err(err) => state.show_error(&t("create_error").replace("{}", &describe_error(&err))),
+            }
+        }));
+    }
+
+    fn toggle_item(self: &Rc<Self>, todo: &TodoItem, done: bool) -> Result<()> {
+        data::complete_todo(todo, done)?;
+        // The next occurrence of a recurring task needs the full reload.
+        if todo.recurrence.is_some() || !self.update_in_place(todo, done) {
+            self.reload()?;
+        }
+        let message = if done {
+            t("task_completed").replace("{}", &todo.title)
+        } else {
+            t("task_reopened").replace("{}", &todo.title)
+        };
+        self.show_info(&message);
+        Ok(())
+    }
+
+    /// Marks the loaded `todo` done or open and re-filters the list without
+    /// reading the file again. Returns false if it is not loaded.
+    fn update_in_place(self: &Rc<Self>, todo: &TodoItem, done: bool) -> bool {
+        let Some(object) = self.task_list.task_objects().remove(&todo.key) else {
+            return false;
+        };
+        let mut updated = todo.clone();
+        updated.done = done;
+        updated.completed = done.then(|| Local::now().date_naive());
+        object.set_todo(&updated);
+        if let Some(cached) = self.cached_items.borrow_mut().iter_mut().find(|item| item.key == todo.key) {
+            *cached = updated;
+        }
+        if let Ok(fp) = data::get_fingerprint() {
+            *self.last_fingerprint.borrow_mut() = Some(fp);
+        }
+        self.repopulate_store();
+        self.schedule_view_sync();
+        self.update_launcher_badge();
+        self.sync_save_status();
+        true
+    }
+
+    fn delete_item(&self, todo: &TodoItem) {
+        let result = data::delete_todo(todo).and_then(|_| self.reload());
+        if let Err(err) = result {
+            self.show_error(&t("delete_error").replace("{}", &describe_error(&err)));
+        }
+    }
+
+    /// Deletes `todo` once confirmed over `parent`, or right away if asking
+    /// was turned off. `deleted` runs after the task is gone.
+    fn request_delete(self: &Rc<Self>, parent: &impl IsA<gtk::Window>, todo: &TodoItem, deleted: impl Fn() + 'static) {
+        if self.preferences.borrow().skip_delete_confirmation {
+            self.delete_item(todo);
+            deleted();
+            return;
+        }
+
+        let dialog = adw::MessageDialog::new(Some(parent), Some(&t("confirm_delete_title")), Some(&todo.title));
+        dialog.add_responses(&[("cancel", &t("cancel")), ("delete", &t("delete"))]);
+        dialog.set_response_appearance("delete", adw::ResponseAppearance::Destructive);
+        dialog.set_default_response(Some("cancel"));
+        dialog.set_close_response("cancel");
+        let dont_ask = gtk::CheckButton::with_label(&t("dont_ask_again"));
+        dialog.set_extra_child(Some(&dont_ask));
+
+        let state = Rc::clone(self);
+        let todo = todo.clone();
+        dialog.connect_response(None, move |_, response| {
+            if response != "delete" {
+                return;
+            }
+            if dont_ask.is_active() {
+                state.set_skip_delete_confirmation(true);
+            }
+            state.delete_item(&todo);
+            deleted();
+        });
+        dialog.present();
+    }
+
+    fn set_due_today(&self, todo: &TodoItem) -> Result<()> {
+        let today = data::set_due_today(&todo.key)?;
+        self.reload()?;
+        self.show_info(&t("due_set_today").replace("{}", &config::format_date(today)));
+        Ok(())
+    }
+
+    fn set_due_in_days(self: &Rc<Self>, todo: &TodoItem, days: i64) -> Result<()> {
+        let mut updated = todo.clone();
+        let target = Local::now().date_naive() + Duration::days(days);
+        updated.due = Some(target);
+        self.save_item(todo, &updated)
+    }
+
+    /// Moves the due date by `days`, counting from today for tasks without
+    /// one (or due "sometime").
+    fn nudge_due(self: &Rc<Self>, todo: &TodoItem, days: i64) -> Result<()> {
+        let today = Local::now().date_naive();
+        let base = todo.due.filter(|due| due.year() != 9999).unwrap_or(today);
+        let mut updated = todo.clone();
+        updated.due = Some(base + Duration::days(days));
+        let conflict = data::update_todo_merged(todo, &updated)?;
+        self.reload()?;
+        match conflict {
+            Some(conflict) => self.show_merge_dialog(vec![conflict]),
+            None => self.show_info(
+                &t("due_nudged")
+                    .replacen("{}", &updated.title, 1)
+                    .replacen("{}", &format_due_cell(&updated), 1),
+            ),
+        }
+        Ok(())
+    }
+
+    fn move_to_quadrant(self: &Rc<Self>, todo: &TodoItem, quadrant: Quadrant) {
+        let updated = quadrant.apply(todo, Local::now().date_naive());
+        if updated == *todo {
+            return;
+        }
+        if let Err(err) = self.save_item(todo, &updated) {
+            self.show_error(&describe_error(&err));
+        }
+    }
+
+    fn set_due_sometimes(self: &Rc<Self>, todo: &TodoItem) -> Result<()> {
+        let mut updated = todo.clone();
+        updated.due = Some(NaiveDate::from_ymd_opt(9999, 12, 31).unwrap());
+        self.save_item(todo, &updated)
+    }
+
+    fn show_due_shortcuts(self: &Rc<Self>, todo: &TodoItem) {
+        let Some(parent) = self.window.upgrade() else {
+            self.show_error(&t("no_window"));
+            return;
+        };
+
+        let dialog = AlertDialog::builder()
+            .modal(true)
+            .build();
+        dialog.set_message(&t("due_shortcuts_title"));
+        dialog.set_detail(&t("due_shortcuts_detail"));
+        let buttons = [
+            t("due_shortcut_tomorrow"),
+            t("due_shortcut_3_days"),
+            t("due_shortcut_7_days"),
+            t("due_shortcut_month"),
+            t("due_shortcut_sometime"),
+            t("cancel"),
+        ];
+        dialog.set_buttons(&buttons.iter().map(String::as_str).collect::<Vec<_>>());
+        dialog.set_default_button(0);
+        dialog.set_cancel_button(5);
+
+        let state = Rc::clone(self);
+        let base_todo = todo.clone();
+        dialog.choose(
+            Some(&parent),
+            Option::<&gio::Cancellable>::None,
+            clone!(@strong state, @strong base_todo => move |result| {
+                match result {
+                    Ok(index) => {
+                        let action = match index {
+                            0 => Some(1),
+                            1 => Some(3),
+                            2 => Some(7),
+                            3 => Some(30),
+                            4 => None,
+                            _ => return,
+                        };
+
+                        let outcome = match action {
+                            Some(days) => state.set_due_in_days(&base_todo, days),
+                            None => state.set_due_sometimes(&base_todo),
+                        };
+
+                        if let Err(err) = outcome {
+                            state.show_error(&t("due_move_error").replace("{}", &describe_error(&err)));
+                        }
+                    }
+                    Err(err) => {
+                        state.show_error(&t("dialog_error").replace("{}", &describe_error(&err.into())));
+                    }
+                }
+            }),
+        );
+    }
+
+    fn offer_journal_recovery(self: &Rc<Self>) {
+        let entries = journal::pending();
+        let Some(latest) = entries.iter().map(|entry| entry.created).max() else {
+            return;
+        };
+        let Some(parent) = self.window.upgrade() else {
+            return;
+        };
+
+        let dialog = AlertDialog::builder()
+            .modal(true)
+            .build();
+        dialog.set_message(&t("journal_found_title"));
+        dialog.set_detail(&t("journal_found_detail").replace("{}", &latest.format("%Y-%m-%d %H:%M:%S").to_string()));
+        dialog.set_buttons(&[&t("journal_discard"), &t("journal_replay")]);
+        dialog.set_default_button(1);
+        dialog.set_cancel_button(0);
+
+        let state = Rc::clone(self);
+        dialog.choose(
+            Some(&parent),
+            Option::<&gio::Cancellable>::None,
+            move |result| {
+                if let Ok(1) = result {
+                    match entries.iter().try_for_each(journal::replay) {
+                        Ok(()) => {
+                            if let Err(err) = state.reload() {
+                                state.show_error(&t("reload_error").replace("{}", &describe_error(&err)));
+                            } else {
+                                state.show_info(&t("journal_replayed"));
+                            }
+                        }
+                        Err(err) => state.show_error(&describe_error(&err)),
+                    }
+                } else {
+                    entries.iter().for_each(journal::discard);
+                }
+            },
+        );
+    }
+
+    /// Writes the unsaved input a moment after the last change, so typing
+    /// does not touch the disk on every key.
+    fn schedule_draft_save(self: &Rc<Self>) {
+        if let Some(source) = self.draft_save_pending.take() {
+            source.remove();
+        }
+        let state = Rc::downgrade(self);
+        let source = glib::timeout_add_seconds_local_once(DRAFT_SAVE_DELAY_SECONDS, move || {
+            if let Some(state) = state.upgrade() {
+                state.draft_save_pending.set(None);
+                state.save_draft();
+            }
+        });
+        self.draft_save_pending.set(Some(source));
+    }
+
+    /// Stores what the quick-add entry and the task dialog hold right now,
+    /// or removes the draft if they hold nothing unsaved.
+    fn save_draft(&self) {
+        if let Some(source) = self.draft_save_pending.take() {
+            source.remove();
+        }
+        let quick_add = self
+            .quick_add_entry
+            .borrow()
+            .as_ref()
+            .map(|entry| entry.text().trim().to_string())
+            .filter(|text| !text.is_empty());
+        let edit = self.edit_draft.borrow().as_ref().and_then(|read| read());
+        if let Err(err) = drafts::store(quick_add, edit) {
+            tracing::warn!("Could not save draft: {err}");
+        }
+    }
+
+    /// Offers the input left unsaved when the app last closed. Runs after
+    /// the first load, so an edited task can be found again.
+    fn offer_draft_recovery(self: &Rc<Self>) {
+        let Some(draft) = drafts::pending() else {
+            return;
+        };
+        let Some(parent) = self.window.upgrade() else {
+            return;
+        };
+
+        let dialog = AlertDialog::builder()
+            .modal(true)
+            .build();
+        dialog.set_message(&t("draft_found_title"));
+        let title = match (&draft.edit, &draft.quick_add) {
+            (Some(edit), _) => edit.fields.title.clone(),
+            (None, Some(text)) => text.clone(),
+            (None, None) => String::new(),
+        };
+        dialog.set_detail(
+            &t("draft_found_detail")
+                .replacen("{}", &draft.saved.format("%Y-%m-%d %H:%M").to_string(), 1)
+                .replacen("{}", &title, 1),
+        );
+        dialog.set_buttons(&[&t("draft_discard"), &t("draft_restore")]);
+        dialog.set_default_button(1);
+        dialog.set_cancel_button(0);
+
+        let state = Rc::clone(self);
+        dialog.choose(
+            Some(&parent),
+            Option::<&gio::Cancellable>::None,
+            move |result| {
+                if let Ok(1) = result {
+                    state.restore_draft(draft);
+                } else {
+                    drafts::discard();
+                }
+            },
+        );
+    }
+
+    fn restore_draft(self: &Rc<Self>, draft: drafts::Draft) {
+        let mut quick_add = draft.quick_add;
+        if let Some(edit) = draft.edit {
+            let task = &edit.task;
+            let current = self
+                .cached_items
+                .borrow()
+                .iter()
+                .find(|item| match &task.key.marker {
+                    Some(marker) => item.key.marker.as_ref() == Some(marker),
+                    None => item.key == task.key && item.title == task.title,
+                })
+                .cloned();
+            match current {
+                Some(todo) => self.open_details_dialog(&todo, Some(&edit.fields)),
+                None => {
+                    // Keeps at least the title if the task is gone.
+                    self.show_error(&t("draft_task_gone"));
+                    quick_add = quick_add.or(Some(edit.fields.title));
+                }
+            }
+        }
+        if let Some(text) = quick_add {
+            if let Some(app) = self.window.upgrade().and_then(|window| window.application()) {
+                app.activate_action("new-task", None);
+            }
+            if let Some(entry) = self.quick_add_entry.borrow().as_ref() {
+                entry.set_text(&text);
+                entry.set_position(-1);
+            }
+        }
+    }
+
+    fn show_cheatsheet(self: &Rc<Self>) {
+        let Some(parent) = self.window.upgrade() else {
+            self.show_error(&t("no_window"));
+            return;
+        };
+
+        let dialog = adw::Window::builder()
+            .title(&t("cheatsheet"))
+            .transient_for(&parent)
+            .modal(true)
+            .default_width(400)
+            .build();
+        dialog.set_destroy_with_parent(true);
+
+        let key_controller = gtk::EventControllerKey::new();
+        let dialog_clone = dialog.clone();
+        key_controller.connect_key_pressed(move |_, keyval, _, _| {
+            if keyval == gdk::Key::Escape {
+                dialog_clone.close();
+                glib::Propagation::Stop
+            } else {
+                glib::Propagation::Proceed
+            }
+        });
+        dialog.add_controller(key_controller);
+
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 12);
+        content.set_margin_top(24);
+        content.set_margin_bottom(24);
+        content.set_margin_start(24);
+        content.set_margin_end(24);
+
+        let grid = gtk::Grid::builder()
+            .column_spacing(24)
+            .row_spacing(8)
+            .build();
+
+        let mut shortcuts = vec![("key_help", "?".to_string())];
+        for (action, label, _) in SHORTCUT_ACTIONS {
+            let accels = self.shortcuts(action);
+            if !accels.is_empty() {
+                let labels: Vec<String> = accels.iter().map(|accel| shortcut_label(accel)).collect();
+                shortcuts.push((label, labels.join(" / ")));
+            }
+        }
+        let list_keys = [
+            ("key_nav", "↑ / ↓"),
+            ("key_toggle", "Space"),
+            ("key_edit", "Enter"),
+            ("key_today", "t"),
+            ("key_tomorrow", "+"),
+            ("key_sometimes", "s"),
+            ("key_nudge_day", "Ctrl+- / Ctrl++, [ / ]"),
+            ("key_nudge_week", "Shift+[ / Shift+]"),
+            ("key_emoji", "Ctrl+."),
+        ];
+        shortcuts.extend(list_keys.iter().map(|(key, shortcut)| (*key, shortcut.to_string())));
+        if self.vim_keys() {
+            shortcuts.push(("vim_keys", "j k x dd / gg G".to_string()));
+        }
+
+        for (i, (key, shortcut)) in shortcuts.iter().enumerate() {
+            let key_label = gtk::Label::builder()
+                .label(shortcut.as_str())
+                .xalign(1.0)
+                .build();
+            key_label.add_css_class("dim-label");
+            
+            let desc_label = gtk::Label::builder()
+                .label(&t(key))
+                .xalign(0.0)
+                .build();
+            
+            grid.attach(&key_label, 0, i as i32, 1, 1);
+            grid.attach(&desc_label, 1, i as i32, 1, 1);
+        }
+
+        content.append(&grid);
+
+        let close_btn = gtk::Button::with_label(&t("close"));
+        close_btn.set_halign(gtk::Align::End);
+        close_btn.set_margin_top(12);
+        let dialog_close = dialog.clone();
+        close_btn.connect_clicked(move |_| {
+            dialog_close.close();
+        });
+        content.append(&close_btn);
+
+        dialog.set_content(Some(&content));
+        dialog.present();
+    }
+
+    fn show_settings_dialog(self: &Rc<Self>, voice_btn: Option<gtk::Button>) {
+        let Some(parent) = self.window.upgrade() else {
+            self.show_error(&t("no_window"));
+            return;
+        };
+
+        // Enforce WebDAV mode
+        self.set_use_webdav(true);
+
+        let dialog = adw::PreferencesWindow::builder()
+            .title(&t("settings"))
+            .transient_for(&parent)
+            .modal(true)
+            .default_width(480)
+            .build();
+
+        // --- General Page ---
+        let general_page = adw::PreferencesPage::builder()
+            .title(&t("general"))
+            .icon_name("preferences-system-symbolic")
+            .build();
+        dialog.add(&general_page);
+
+        let general_group = adw::PreferencesGroup::builder()
+            .title(&t("general"))
+            .build();
+        general_page.add(&general_group);
+
+        let show_done_row = adw::SwitchRow::builder()
+            .title(&t("show_completed"))
+            .active(self.show_completed())
+            .build();
+        show_done_row.add_prefix(&gtk::Image::from_icon_name("view-list-symbolic"));
+        let state_done = Rc::clone(self);
+        show_done_row.connect_active_notify(move |row| {
+            state_done.set_show_completed(row.is_active());
+        });
+        general_group.add(&show_done_row);
+
+        let show_due_row = adw::SwitchRow::builder()
+            .title(&t("show_due_only_mode"))
+            .active(self.show_due_only())
+            .build();
+        show_due_row.add_prefix(&gtk::Image::from_icon_name("appointment-soon-symbolic"));
+        let state_due = Rc::clone(self);
+        show_due_row.connect_active_notify(move |row| {
+            state_due.set_show_due_only(row.is_active());
+        });
+        general_group.add(&show_due_row);
+
+        let vim_row = adw::SwitchRow::builder()
+            .title(t("vim_keys"))
+            .subtitle(t("vim_keys_desc"))
+            .active(self.vim_keys())
+            .build();
+        vim_row.add_prefix(&gtk::Image::from_icon_name("input-keyboard-symbolic"));
+        let state_vim = Rc::clone(self);
+        vim_row.connect_active_notify(move |row| {
+            state_vim.set_vim_keys(row.is_active());
+        });
+        general_group.add(&vim_row);
+
+        let motion_row = adw::SwitchRow::builder()
+            .title(t("reduce_motion"))
+            .subtitle(t("reduce_motion_desc"))
+            .active(self.reduce_motion())
+            .build();
+        motion_row.add_prefix(&gtk::Image::from_icon_name("preferences-desktop-accessibility-symbolic"));
+        let state_motion = Rc::clone(self);
+        motion_row.connect_active_notify(move |row| {
+            state_motion.set_reduce_motion(row.is_active());
+        });
+        general_group.add(&motion_row);
+
+        let confirm_delete_row = adw::SwitchRow::builder()
+            .title(t("confirm_delete"))
+            .subtitle(t("confirm_delete_desc"))
+            .active(!self.preferences.borrow().skip_delete_confirmation)
+            .build();
+        confirm_delete_row.add_prefix(&gtk::Image::from_icon_name("user-trash-symbolic"));
+        let state_confirm = Rc::clone(self);
+        confirm_delete_row.connect_active_notify(move |row| {
+            state_confirm.set_skip_delete_confirmation(!row.is_active());
+        });
+        general_group.add(&confirm_delete_row);
+
+        let poll_row = adw::SpinRow::with_range(1.0, 300.0, 1.0);
+        poll_row.set_title(&t("poll_interval"));
+        poll_row.set_subtitle(&t("poll_interval_desc"));
+        poll_row.set_value(f64::from(self.preferences.borrow().poll_interval));
+        poll_row.add_prefix(&gtk::Image::from_icon_name("view-refresh-symbolic"));
+        let state_poll = Rc::clone(self);
+        poll_row.connect_value_notify(move |row| {
+            state_poll.set_poll_interval(row.value() as u32);
+        });
+        general_group.add(&poll_row);
+
+        let rollover_row = adw::SwitchRow::builder()
+            .title(t("daily_rollover"))
+            .subtitle(t("daily_rollover_desc"))
+            .active(self.preferences.borrow().daily_rollover)
+            .build();
+        rollover_row.add_prefix(&gtk::Image::from_icon_name("x-office-calendar-symbolic"));
+        let state_rollover = Rc::clone(self);
+        rollover_row.connect_active_notify(move |row| {
+            state_rollover.set_daily_rollover(row.is_active());
+        });
+        general_group.add(&rollover_row);
+
+        let aging_row = adw::SpinRow::with_range(0.0, 365.0, 1.0);
+        aging_row.set_title(&t("priority_aging"));
+        aging_row.set_subtitle(&t("priority_aging_desc"));
+        aging_row.set_value(f64::from(self.preferences.borrow().priority_aging_days));
+        aging_row.add_prefix(&gtk::Image::from_icon_name("go-up-symbolic"));
+        let state_aging = Rc::clone(self);
+        aging_row.connect_value_notify(move |row| {
+            state_aging.set_priority_aging_days(row.value() as u32);
+        });
+        general_group.add(&aging_row);
+
+        let accent_names: Vec<String> = std::iter::once(t("accent_system"))
+            .chain(ACCENT_COLORS.iter().map(|(name, _)| t(&format!("accent_{name}"))))
+            .collect();
+        let accent_names: Vec<&str> = accent_names.iter().map(String::as_str).collect();
+        let accent_row = adw::ComboRow::builder()
+            .title(t("accent_color"))
+            .subtitle(t("accent_color_desc"))
+            .model(&gtk::StringList::new(&accent_names))
+            .build();
+        accent_row.add_prefix(&gtk::Image::from_icon_name("preferences-color-symbolic"));
+        let pinned = self.accent().and_then(|accent| ACCENT_COLORS.iter().position(|(name, _)| *name == accent));
+        accent_row.set_selected(pinned.map_or(0, |index| index as u32 + 1));
+        let state_accent = Rc::clone(self);
+        accent_row.connect_selected_notify(move |row| {
+            let accent = (row.selected() as usize).checked_sub(1).and_then(|index| ACCENT_COLORS.get(index));
+            state_accent.set_accent(accent.map(|(name, _)| *name));
+        });
+        general_group.add(&accent_row);
+
+        let metadata_group = adw::PreferencesGroup::builder()
+            .title(t("metadata_line"))
+            .description(t("metadata_line_desc"))
+            .build();
+        general_page.add(&metadata_group);
+        self.fill_metadata_group(&metadata_group, Rc::new(RefCell::new(Vec::new())));
+
+        let agenda_group = adw::PreferencesGroup::builder()
+            .title(t("notifications"))
+            .build();
+        general_page.add(&agenda_group);
+
+        let agenda_row = adw::SwitchRow::builder()
+            .title(t("daily_agenda"))
+            .subtitle(t("daily_agenda_desc"))
+            .active(self.daily_agenda())
+            .build();
+        let state_agenda = Rc::clone(self);
+        agenda_row.connect_active_notify(move |row| {
+            state_agenda.set_daily_agenda(row.is_active());
+        });
+        agenda_group.add(&agenda_row);
+
+        let agenda_time_row = adw::EntryRow::builder()
+            .title(t("agenda_time"))
+            .text(self.agenda_time())
+            .show_apply_button(true)
+            .build();
+        let state_agenda_time = Rc::clone(self);
+        agenda_time_row.connect_apply(move |row| {
+            if !state_agenda_time.set_agenda_time(&row.text()) {
+                state_agenda_time.show_error(&t("invalid_time_error"));
+            }
+        });
+        agenda_group.add(&agenda_time_row);
+
+        let autostart_row = adw::SwitchRow::builder()
+            .title(t("autostart_service"))
+            .subtitle(t("autostart_service_desc"))
+            .active(self.autostart_service())
+            .build();
+        let calendar_row = adw::EntryRow::builder()
+            .title(t("calendar_source"))
+            .text(self.calendar_source())
+            .show_apply_button(true)
+            .build();
+        let state_calendar = Rc::clone(self);
+        calendar_row.connect_apply(move |row| {
+            state_calendar.set_calendar_source(&row.text());
+        });
+        agenda_group.add(&calendar_row);
+
+        let state_autostart = Rc::clone(self);
+        autostart_row.connect_active_notify(move |row| {
+            if let Err(err) = state_autostart.set_autostart_service(row.is_active()) {
+                state_autostart.show_error(&t("autostart_error").replace("{}", &describe_error(&err)));
+            }
+        });
+        agenda_group.add(&autostart_row);
+
+        // --- Shortcuts Page ---
+        let shortcuts_page = adw::PreferencesPage::builder()
+            .title(t("shortcuts"))
+            .icon_name("preferences-desktop-keyboard-shortcuts-symbolic")
+            .build();
+        dialog.add(&shortcuts_page);
+
+        let shortcuts_group = adw::PreferencesGroup::builder()
+            .title(t("shortcuts"))
+            .description(t("shortcuts_desc"))
+            .build();
+        shortcuts_page.add(&shortcuts_group);
+
+        for (action, label, _) in SHORTCUT_ACTIONS {
+            let row = adw::EntryRow::builder()
+                .title(t(label))
+                .text(self.shortcuts(action).join(" "))
+                .show_apply_button(true)
+                .build();
+            let state_shortcut = Rc::clone(self);
+            row.connect_apply(move |row| {
+                if let Err(err) = state_shortcut.set_shortcuts(action, &row.text()) {
+                    state_shortcut.show_error(&describe_error(&err));
+                }
+            });
+
+            let reset_btn = gtk::Button::from_icon_name("edit-undo-symbolic");
+            reset_btn.set_valign(gtk::Align::Center);
+            reset_btn.add_css_class("flat");
+            reset_btn.set_tooltip_text(Some(&t("reset_shortcut")));
+            let state_reset = Rc::clone(self);
+            reset_btn.connect_clicked(clone!(@weak row => move |_| {
+                state_reset.reset_shortcuts(action);
+                row.set_text(&state_reset.shortcuts(action).join(" "));
+            }));
+            row.add_suffix(&reset_btn);
+            shortcuts_group.add(&row);
+        }
+
+        let transfer_group = adw::PreferencesGroup::builder()
+            .title(t("settings_transfer"))
+            .description(t("settings_transfer_desc"))
+            .build();
+        general_page.add(&transfer_group);
+
+        let export_settings_row = adw::ActionRow::builder()
+            .title(t("export_settings"))
+            .activatable(true)
+            .build();
+        export_settings_row.add_suffix(&gtk::Image::from_icon_name("document-save-symbolic"));
+        let state_export = Rc::clone(self);
+        let dialog_export = dialog.clone();
+        export_settings_row.connect_activated(move |_| state_export.export_settings(&dialog_export));
+        transfer_group.add(&export_settings_row);
+
+        let import_settings_row = adw::ActionRow::builder()
+            .title(t("import_settings"))
+            .activatable(true)
+            .build();
+        import_settings_row.add_suffix(&gtk::Image::from_icon_name("document-open-symbolic"));
+        let state_import = Rc::clone(self);
+        let dialog_import = dialog.clone();
+        import_settings_row.connect_activated(move |_| state_import.import_settings(&dialog_import));
+        transfer_group.add(&import_settings_row);
+
+        // --- WebDAV Page ---
+        let webdav_page = adw::PreferencesPage::builder()
+            .title(&t("webdav"))
+            .icon_name("network-server-symbolic")
+            .build();
+        dialog.add(&webdav_page);
+
+        let webdav_group = adw::PreferencesGroup::builder()
+            .title(&t("webdav"))
+            .build();
+        webdav_page.add(&webdav_group);
+
+        let (_, _, wd_path, wd_user, wd_pass) = self.get_webdav_prefs();
+        // Note: wd_url is fetched inside the closure below or we can get it here if needed, 
+        // but we need to bind it to the row.
+        // Let's get the current values again to populate the fields.
+        let (_, wd_url, _, _, _) = self.get_webdav_prefs();
+
+        let url_row = adw::EntryRow::builder()
+            .title(&t("webdav_url"))
+            .text(wd_url.unwrap_or_default())
+            .build();
+        let state_url = Rc::clone(self);
+        url_row.connect_changed(move |row| {
+            state_url.set_webdav_url(row.text().to_string());
+        });
+        webdav_group.add(&url_row);
+
+        let path_row = adw::EntryRow::builder()
+            .title(&t("path_relative"))
+            .text(wd_path.unwrap_or_default())
+            .build();
+        let state_path = Rc::clone(self);
+        path_row.connect_changed(move |row| {
+            state_path.set_webdav_path(row.text().to_string());
+        });
+        webdav_group.add(&path_row);
+
+        let user_row = adw::EntryRow::builder()
+            .title(&t("username"))
+            .text(wd_user.unwrap_or_default())
+            .build();
+        let state_user = Rc::clone(self);
+        user_row.connect_changed(move |row| {
+            state_user.set_webdav_username(row.text().to_string());
+        });
+        webdav_group.add(&user_row);
+
+        let pass_row = adw::PasswordEntryRow::builder()
+            .title(&t("password"))
+            .text(wd_pass.unwrap_or_default())
+            .build();
+        let state_pass = Rc::clone(self);
+        pass_row.connect_changed(move |row| {
+            state_pass.set_webdav_password(row.text().to_string());
+        });
+        webdav_group.add(&pass_row);
+
+        let check_row = adw::ActionRow::builder()
+            .title(&t("check_connection"))
+            .build();
+        let check_button = gtk::Button::builder()
+            .label(&t("check_connection"))
+            .valign(gtk::Align::Center)
+            .build();
+        check_button.add_css_class("flat");
+        check_row.add_suffix(&check_button);
+        
+        let state_for_check = Rc::clone(self);
+        check_button.connect_clicked(move |_| {
+            let (_, url, path, user, pass) = state_for_check.get_webdav_prefs();
+            
+            let Some(u) = url else {
+                state_for_check.show_error(&t("no_url_error"));
+                return;
+            };
+            if u.trim().is_empty() {
+                state_for_check.show_error(&t("no_url_error"));
+                return;
+            }
+
+            let state_bg = state_for_check.clone();
+            let (sender, receiver) = std::sync::mpsc::channel();
+            
+            let u_clone = u.clone();
+            let path_clone = path.clone();
+            let user_clone = user.clone();
+            let pass_clone = pass.clone();
+
+            std::thread::spawn(move || {
+                let result = storage::test_webdav_connection(&u_clone, path_clone.as_deref(), user_clone.as_deref(), pass_clone.as_deref());
+                let _ = sender.send(result);
+            });
+
+            glib::timeout_add_local(std::time::Duration::from_millis(100), move || {
+                match receiver.try_recv() {
+                    Ok(result) => {
                         match result {
                             Ok(_) => state_bg.show_info(&t("connection_success")),
                             Err(e) => {
-                                eprintln!("{}", t("webdav_conn_error").replace("{}", &e.to_string()));
+                                tracing::warn!("{}", t("webdav_conn_error").replace("{}", &e.to_string()));
                                 state_bg.show_error(&t("connection_failed").replace("{}", &e.to_string()));
                             }
                         }
-                        glib::ControlFlow::Break
+                        glib::ControlFlow::Break
+                    }
+                    Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+                    Err(std::sync::mpsc::TryRecvError::Disconnected) => glib::ControlFlow::Break,
+                }
+            });
+        });
+        webdav_group.add(&check_row);
+
+        // --- Voice Page ---
+        let voice_page = adw::PreferencesPage::builder()
+            .title(&t("voice"))
+            .icon_name("audio-input-microphone-symbolic")
+            .build();
+        dialog.add(&voice_page);
+
+        let voice_group = adw::PreferencesGroup::builder()
+            .title(&t("voice"))
+            .build();
+        voice_page.add(&voice_group);
+
+        let progress_bar = gtk::ProgressBar::builder()
+            .visible(false)
+            .show_text(true)
+            .margin_top(6)
+            .margin_bottom(6)
+            .build();
+        voice_group.add(&progress_bar);
+
+        let use_whisper_row = adw::SwitchRow::builder()
+            .title(&t("use_whisper"))
+            .subtitle(&t("whisper_desc"))
+            .active(self.use_whisper())
+            .build();
+        use_whisper_row.add_prefix(&gtk::Image::from_icon_name("audio-input-microphone-symbolic"));
+        
+        let languages = vec!["auto", "en", "de", "es", "fr", "it", "ja", "zh", "nl", "pl", "pt", "ru", "tr", "sv"];
+        let language_names = [
+            t("lang_auto"), t("lang_en"), t("lang_de"), t("lang_es"), t("lang_fr"), 
+            t("lang_it"), t("lang_ja"), t("lang_zh"), t("lang_nl"), t("lang_pl"), 
+            t("lang_pt"), t("lang_ru"), t("lang_tr"), t("lang_sv")
+        ];
+        let language_names_refs: Vec<&str> = language_names.iter().map(|s| s.as_str()).collect();
+        
+        let language_model = gtk::StringList::new(&language_names_refs);
+        
+        let language_row = adw::ComboRow::builder()
+            .title(&t("whisper_language"))
+            .model(&language_model)
+            .build();
+
+        // Set initial selection
+        let current_lang = self.whisper_language();
+        if let Some(idx) = languages.iter().position(|&l| l == current_lang) {
+            language_row.set_selected(idx as u32);
+        }
+
+        let state_lang = Rc::clone(self);
+        let languages_clone = languages.clone();
+        language_row.connect_selected_notify(move |row| {
+            let idx = row.selected() as usize;
+            if idx < languages_clone.len() {
+                state_lang.set_whisper_language(languages_clone[idx].to_string());
+            }
+        });
+
+        let state_whisper = Rc::clone(self);
+        let pb_whisper = progress_bar.clone();
+        let vb_whisper = voice_btn.clone();
+        let lang_row_clone = language_row.clone();
+        
+        // Disable language selection if whisper is disabled
+        language_row.set_sensitive(self.use_whisper());
+
+        use_whisper_row.connect_active_notify(move |row| {
+            if row.is_active() {
+                state_whisper.set_use_whisper(true, Some(pb_whisper.clone()), Some(row.clone()), vb_whisper.clone());
+                lang_row_clone.set_sensitive(true);
+            } else {
+                state_whisper.set_use_whisper(false, None, None, vb_whisper.clone());
+                lang_row_clone.set_sensitive(false);
+            }
+        });
+        voice_group.add(&use_whisper_row);
+        voice_group.add(&language_row);
+
+        // --- About Page ---
+        let about_page = adw::PreferencesPage::builder()
+            .title(&t("about"))
+            .icon_name("help-about-symbolic")
+            .build();
+        dialog.add(&about_page);
+
+        let about_group = adw::PreferencesGroup::builder()
+            .build();
+        about_page.add(&about_group);
+
+        let banner = adw::Bin::builder()
+            .margin_top(12)
+            .margin_bottom(12)
+            .build();
+        let banner_box = gtk::Box::new(gtk::Orientation::Vertical, 6);
+        banner_box.set_halign(gtk::Align::Center);
+        
+        let app_icon = gtk::Image::from_icon_name("me.dumke.Reinschrift");
+        app_icon.set_pixel_size(128);
+        banner_box.append(&app_icon);
+
+        let app_name = gtk::Label::builder()
+            .label("Reinschrift")
+            .css_classes(["title-1"])
+            .build();
+        banner_box.append(&app_name);
+
+        let app_version = gtk::Label::builder()
+            .label(&format!("{} 0.9.30", t("version")))
+            .css_classes(["dim-label"])
+            .build();
+        banner_box.append(&app_version);
+
+        banner.set_child(Some(&banner_box));
+        about_group.add(&banner);
+
+        let info_group = adw::PreferencesGroup::builder()
+            .build();
+        about_page.add(&info_group);
+
+        let dev_row = adw::ActionRow::builder()
+            .title(&t("developer"))
+            .subtitle("Dr. Daniel Dumke")
+            .build();
+        info_group.add(&dev_row);
+
+        let site_row = adw::ActionRow::builder()
+            .title(&t("website"))
+            .subtitle("https://github.com/danst0/ReinschriftTodo")
+            .activatable(true)
+            .build();
+        site_row.connect_activated(|_| {
+            let launcher = gtk::FileLauncher::new(Some(&gio::File::for_uri("https://github.com/danst0/ReinschriftTodo")));
+            launcher.launch(None::<&gtk::Window>, gio::Cancellable::NONE, |_| {});
+        });
+        info_group.add(&site_row);
+
+        let license_row = adw::ActionRow::builder()
+            .title(&t("license"))
+            .subtitle("CC-BY-SA-4.0")
+            .build();
+        info_group.add(&license_row);
+
+        dialog.present();
+    }
+
+    /// Writes the preferences (view, shortcuts, database, …) as one JSON file.
+    /// The WebDAV password stays on this machine.
+    fn export_settings(self: &Rc<Self>, parent: &adw::PreferencesWindow) {
+        let filter = FileFilter::new();
+        filter.set_name(Some("JSON"));
+        filter.add_suffix("json");
+        let filters = gio::ListStore::new::<FileFilter>();
+        filters.append(&filter);
+        let dialog = FileDialog::builder()
+            .title(t("export_settings"))
+            .initial_name("reinschrift-settings.json")
+            .filters(&filters)
+            .build();
+
+        let state = Rc::clone(self);
+        dialog.save(Some(parent), gio::Cancellable::NONE, move |result| {
+            let Some(path) = result.ok().and_then(|file| file.path()) else {
+                return;
+            };
+            let mut preferences = state.preferences.borrow().clone();
+            preferences.webdav_password = None;
+            // Portal ids only mean something in this machine's sandbox.
+            preferences.db_document = None;
+            let bundle = SettingsBundle {
+                format: SETTINGS_BUNDLE_FORMAT,
+                exported: Local::now(),
+                preferences,
+            };
+            let written = serde_json::to_string_pretty(&bundle)
+                .map_err(anyhow::Error::from)
+                .and_then(|json| fs::write(&path, json).map_err(anyhow::Error::from));
+            match written {
+                Ok(_) => state.show_info(&t("exported_to").replace("{}", &path.display().to_string())),
+                Err(err) => state.show_error(&t("write_error").replace("{}", &format!("{}: {err}", path.display()))),
+            }
+        });
+    }
+
+    /// Replaces the preferences with those of an exported bundle. Bookkeeping
+    /// of this machine (sent notifications, the WebDAV password) is kept.
+    fn import_settings(self: &Rc<Self>, parent: &adw::PreferencesWindow) {
+        let filter = FileFilter::new();
+        filter.set_name(Some("JSON"));
+        filter.add_suffix("json");
+        let filters = gio::ListStore::new::<FileFilter>();
+        filters.append(&filter);
+        let dialog = FileDialog::builder()
+            .title(t("import_settings"))
+            .filters(&filters)
+            .build();
+
+        let state = Rc::clone(self);
+        let parent_window = parent.clone();
+        dialog.open(Some(parent), gio::Cancellable::NONE, move |result| {
+            let Some(path) = result.ok().and_then(|file| file.path()) else {
+                return;
+            };
+            let bundle = fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| serde_json::from_str::<SettingsBundle>(&content).map_err(anyhow::Error::from));
+            let mut imported = match bundle {
+                Ok(bundle) if bundle.format <= SETTINGS_BUNDLE_FORMAT => bundle.preferences,
+                Ok(bundle) => {
+                    state.show_error(&t("settings_import_error").replace("{}", &t("settings_format_unknown").replace("{}", &bundle.format.to_string())));
+                    return;
+                }
+                Err(err) => {
+                    state.show_error(&t("settings_import_error").replace("{}", &describe_error(&err)));
+                    return;
+                }
+            };
+            {
+                let current = state.preferences.borrow();
+                imported.agenda_last_sent = current.agenda_last_sent;
+                imported.reminders_checked = current.reminders_checked;
+                imported.rollover_last = current.rollover_last;
+                imported.aging_last = current.aging_last;
+                if imported.webdav_password.is_none() {
+                    imported.webdav_password = current.webdav_password.clone();
+                }
+            }
+            *state.preferences.borrow_mut() = imported;
+            state.persist_preferences();
+            state.apply_imported_backend();
+            parent_window.close();
+            let result = state.reload();
+            state.apply_shortcuts();
+            match result {
+                Ok(_) => state.show_info(&t("settings_imported")),
+                Err(err) => state.show_error(&t("load_error").replace("{}", &describe_error(&err))),
+            }
+        });
+    }
+
+    /// One row per metadata field: visible fields first in display order,
+    /// each with a switch and buttons to move it. Rebuilt after every change.
+    fn fill_metadata_group(self: &Rc<Self>, group: &adw::PreferencesGroup, rows: Rc<RefCell<Vec<adw::ActionRow>>>) {
+        for row in rows.borrow_mut().drain(..) {
+            group.remove(&row);
+        }
+
+        let visible = self.metadata_fields();
+        let mut order: Vec<(MetaField, bool)> = visible.iter().map(|f| (*f, true)).collect();
+        order.extend(MetaField::ALL.iter().filter(|f| !visible.contains(f)).map(|f| (*f, false)));
+
+        let apply = {
+            let state = Rc::clone(self);
+            let group = group.clone();
+            let rows = Rc::clone(&rows);
+            Rc::new(move |order: Vec<(MetaField, bool)>| {
+                let fields: Vec<MetaField> = order.iter().filter(|(_, on)| *on).map(|(f, _)| *f).collect();
+                state.set_metadata_fields(&fields);
+                let state = Rc::clone(&state);
+                let group = group.clone();
+                let rows = Rc::clone(&rows);
+                // Defer the rebuild so the clicked row is not removed inside its own handler.
+                glib::idle_add_local_once(move || state.fill_metadata_group(&group, rows));
+            })
+        };
+
+        for (index, (field, enabled)) in order.iter().enumerate() {
+            let row = adw::ActionRow::builder().title(field.label()).build();
+
+            let up_btn = gtk::Button::builder()
+                .icon_name("go-up-symbolic")
+                .tooltip_text(t("move_up"))
+                .valign(gtk::Align::Center)
+                .sensitive(*enabled && index > 0)
+                .css_classes(["flat"])
+                .build();
+            let down_btn = gtk::Button::builder()
+                .icon_name("go-down-symbolic")
+                .tooltip_text(t("move_down"))
+                .valign(gtk::Align::Center)
+                .sensitive(*enabled && order.get(index + 1).map(|(_, on)| *on).unwrap_or(false))
+                .css_classes(["flat"])
+                .build();
+            let switch = gtk::Switch::builder()
+                .active(*enabled)
+                .valign(gtk::Align::Center)
+                .build();
+            row.add_suffix(&up_btn);
+            row.add_suffix(&down_btn);
+            row.add_suffix(&switch);
+            row.set_activatable_widget(Some(&switch));
+
+            let order_up = order.clone();
+            let apply_up = Rc::clone(&apply);
+            up_btn.connect_clicked(move |_| {
+                let mut order = order_up.clone();
+                order.swap(index, index - 1);
+                apply_up(order);
+            });
+
+            let order_down = order.clone();
+            let apply_down = Rc::clone(&apply);
+            down_btn.connect_clicked(move |_| {
+                let mut order = order_down.clone();
+                order.swap(index, index + 1);
+                apply_down(order);
+            });
+
+            let order_switch = order.clone();
+            let apply_switch = Rc::clone(&apply);
+            switch.connect_active_notify(move |switch| {
+                let mut order = order_switch.clone();
+                let (field, _) = order.remove(index);
+                let visible_count = order.iter().filter(|(_, on)| *on).count();
+                order.insert(visible_count, (field, switch.is_active()));
+                apply_switch(order);
+            });
+
+            group.add(&row);
+            rows.borrow_mut().push(row);
+        }
+    }
+
+    fn set_show_completed(&self, show: bool) {
+        {
+            let mut prefs = self.preferences.borrow_mut();
+            if prefs.show_done == show {
+                return;
+            }
+            prefs.show_done = show;
+        }
+
+        self.persist_preferences();
+        self.repopulate_store();
+    }
+
+    fn set_show_due_only(&self, show: bool) {
+        {
+            let mut prefs = self.preferences.borrow_mut();
+            if prefs.show_due_only == show {
+                return;
+            }
+            prefs.show_due_only = show;
+        }
+
+        self.persist_preferences();
+        self.repopulate_store();
+    }
+
+    fn set_use_whisper(self: &Rc<Self>, use_whisper: bool, progress_bar: Option<gtk::ProgressBar>, switch_row: Option<adw::SwitchRow>, voice_btn: Option<gtk::Button>) {
+        {
+            let mut prefs = self.preferences.borrow_mut();
+            if prefs.use_whisper == use_whisper {
+                return;
+            }
+            prefs.use_whisper = use_whisper;
+        }
+        self.persist_preferences();
+
+        if let Some(btn) = voice_btn {
+            btn.set_visible(use_whisper);
+        }
+
+        if use_whisper {
+            self.ensure_whisper_model(progress_bar, switch_row);
+        } else {
+            let path = self.whisper_model_path();
+            if path.exists() {
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+
+    fn ensure_whisper_model(self: &Rc<Self>, progress_bar: Option<gtk::ProgressBar>, switch_row: Option<adw::SwitchRow>) {
+        let path = self.whisper_model_path();
+        if path.exists() {
+            // Basic integrity check: size should be around 480MB
+            if let Ok(meta) = fs::metadata(&path) {
+                if meta.len() > 450 * 1024 * 1024 {
+                    if let Some(row) = &switch_row {
+                        row.set_sensitive(true);
+                    }
+                    return;
+                }
+            }
+            let _ = fs::remove_file(&path);
+        }
+
+        if let Some(pb) = &progress_bar {
+            pb.set_visible(true);
+            pb.set_fraction(0.0);
+            pb.set_text(Some(&i18n::format_percent(0.0, 0)));
+        }
+
+        if let Some(row) = &switch_row {
+            row.set_sensitive(false);
+        }
+
+        self.show_info(&t("downloading_model"));
+
+        let state = Rc::clone(self);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        
+        std::thread::spawn(move || {
+            let url = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-small.bin";
+            let client = reqwest::blocking::Client::new();
+            let mut response = match client.get(url).send() {
+                Ok(r) => r,
+                Err(e) => {
+                    let _ = sender.send(Err(e.to_string()));
+                    return;
+                }
+            };
+
+            if !response.status().is_success() {
+                let _ = sender.send(Err(format!("HTTP {}", response.status())));
+                return;
+            }
+
+            let total_size = response.content_length().unwrap_or(0);
+            let mut downloaded = 0;
+            let mut buffer = [0; 32768]; // 32KB buffer
+            let mut last_reported_progress = 0.0;
+            
+            if let Some(parent) = path.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+
+            let mut file = match fs::File::create(&path) {
+                Ok(f) => f,
+                Err(e) => {
+                    let _ = sender.send(Err(e.to_string()));
+                    return;
+                }
+            };
+
+            use std::io::Write;
+            loop {
+                match response.read(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        if let Err(e) = file.write_all(&buffer[..n]) {
+                            let _ = sender.send(Err(e.to_string()));
+                            return;
+                        }
+                        downloaded += n as u64;
+                        if total_size > 0 {
+                            let progress = downloaded as f64 / total_size as f64;
+                            // Only report progress if it changed by at least 0.1% or if we are done
+                            if progress - last_reported_progress >= 0.005 || progress >= 1.0 {
+                                let _ = sender.send(Ok(progress));
+                                last_reported_progress = progress;
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        let _ = sender.send(Err(e.to_string()));
+                        return;
+                    }
+                }
+            }
+            let _ = sender.send(Ok(1.0));
+        });
+
+        let pb_clone = progress_bar.clone();
+        let row_clone = switch_row.clone();
+        glib::timeout_add_local(std::time::Duration::from_millis(100), move || {
+            match receiver.try_recv() {
+                Ok(Ok(fraction)) => {
+                    if let Some(pb) = &pb_clone {
+                        pb.set_fraction(fraction);
+                        pb.set_text(Some(&i18n::format_percent(fraction, 0)));
+                    }
+                    if fraction >= 1.0 {
+                        state.show_info(&t("model_download_finished"));
+                        if let Some(pb) = &pb_clone {
+                            pb.set_visible(false);
+                        }
+                        if let Some(row) = &row_clone {
+                            row.set_sensitive(true);
+                        }
+                        return glib::ControlFlow::Break;
+                    }
+                    glib::ControlFlow::Continue
+                }
+                Ok(Err(e)) => {
+                    state.show_error(&format!("{}: {}", t("model_download_error"), e));
+                    if let Some(pb) = &pb_clone {
+                        pb.set_visible(false);
+                    }
+                    if let Some(row) = &row_clone {
+                        row.set_sensitive(true);
+                        row.set_active(false);
+                    }
+                    // Reset preference if download failed
+                    {
+                        let mut prefs = state.preferences.borrow_mut();
+                        prefs.use_whisper = false;
+                    }
+                    state.persist_preferences();
+                    glib::ControlFlow::Break
+                }
+                Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => glib::ControlFlow::Break,
+            }
+        });
+    }
+
+    fn set_whisper_language(&self, language: String) {
+        {
+            let mut prefs = self.preferences.borrow_mut();
+            if prefs.whisper_language == language {
+                return;
+            }
+            prefs.whisper_language = language;
+        }
+        self.persist_preferences();
+    }
+
+    fn set_use_webdav(&self, use_webdav: bool) {
+        {
+            let mut prefs = self.preferences.borrow_mut();
+            if prefs.use_webdav == use_webdav {
+                return;
+            }
+            prefs.use_webdav = use_webdav;
+        }
+        self.persist_preferences();
+        
+        if use_webdav {
+            let (_, url, path, user, pass) = self.get_webdav_prefs();
+            if let Some(u) = url {
+                data::set_backend_config(data::BackendConfig::WebDav {
+                    url: u,
+                    path: path,
+                    username: user,
+                    password: pass,
+                });
+            }
+        } else {
+            data::set_database(&data::todo_path().to_string_lossy());
+        }
+
+        if let Err(err) = self.reload() {
+             self.show_error(&t("load_data_error").replace("{}", &describe_error(&err)));
+        }
+    }
+
+    fn set_webdav_url(&self, url: String) {
+        {
+            let mut prefs = self.preferences.borrow_mut();
+            prefs.webdav_url = Some(url.clone());
+        }
+        self.persist_preferences();
+        
+        let (use_webdav, _, path, user, pass) = self.get_webdav_prefs();
+        if use_webdav {
+             data::set_backend_config(data::BackendConfig::WebDav {
+                url: url,
+                path: path,
+                username: user,
+                password: pass,
+            });
+        }
+    }
+
+    fn set_webdav_path(&self, path: String) {
+        {
+            let mut prefs = self.preferences.borrow_mut();
+            prefs.webdav_path = Some(path.clone());
+        }
+        self.persist_preferences();
+        
+        let (use_webdav, url, _, user, pass) = self.get_webdav_prefs();
+        if use_webdav {
+            if let Some(u) = url {
+                data::set_backend_config(data::BackendConfig::WebDav {
+                    url: u,
+                    path: Some(path),
+                    username: user,
+                    password: pass,
+                });
+            }
+        }
+    }
+
+    fn set_webdav_username(&self, username: String) {
+        {
+            let mut prefs = self.preferences.borrow_mut();
+            prefs.webdav_username = Some(username.clone());
+        }
+        self.persist_preferences();
+
+        let (use_webdav, url, path, _, pass) = self.get_webdav_prefs();
+        if use_webdav {
+            if let Some(u) = url {
+                data::set_backend_config(data::BackendConfig::WebDav {
+                    url: u,
+                    path: path,
+                    username: Some(username),
+                    password: pass,
+                });
+            }
+        }
+    }
+
+    fn set_webdav_password(&self, password: String) {
+        {
+            let mut prefs = self.preferences.borrow_mut();
+            prefs.webdav_password = Some(password.clone());
+        }
+        self.persist_preferences();
+
+        let (use_webdav, url, path, user, _) = self.get_webdav_prefs();
+        if use_webdav {
+            if let Some(u) = url {
+                data::set_backend_config(data::BackendConfig::WebDav {
+                    url: u,
+                    path: path,
+                    username: user,
+                    password: Some(password),
+                });
+            }
+        }
+    }
+
+    fn get_webdav_prefs(&self) -> (bool, Option<String>, Option<String>, Option<String>, Option<String>) {
+        let prefs = self.preferences.borrow();
+        (prefs.use_webdav, prefs.webdav_url.clone(), prefs.webdav_path.clone(), prefs.webdav_username.clone(), prefs.webdav_password.clone())
+    }
+
+
+    fn set_sort_mode(&self, mode: SortMode) {
+        {
+            let mut current = self.sort_mode.borrow_mut();
+            if *current == mode {
+                return;
+            }
+            *current = mode;
+        }
+
+        {
+            let mut prefs = self.preferences.borrow_mut();
+            prefs.sort_mode = Some(mode.as_key().to_string());
+        }
+
+        self.persist_preferences();
+
+        self.repopulate_store();
+    }
+
+    /// Re-applies sort order, sections, search and filters to the loaded
+    /// tasks. Only the models are updated, the file is not read again.
+    fn repopulate_store(&self) {
+        let mut selected_key = None;
+        let mut scroll_pos = None;
+
+        if let Some(scrolled) = self.scrolled_window.borrow().as_ref() {
+            let adj = scrolled.vadjustment();
+            scroll_pos = Some(adj.value());
+        }
+
+        if let Some(list_view) = self.list_view.borrow().as_ref() {
+            if let Some(model) = list_view.model() {
+                if let Ok(selection) = model.downcast::<gtk::SingleSelection>() {
+                    if let Some(todo) = selection.selected_item().and_then(|obj| list_entry_todo(&obj)) {
+                        selected_key = Some(todo.key);
+                    }
+                }
+            }
+        }
+
+        let search_term = self.search_term.borrow().to_lowercase();
+        let mut items = self.cached_items.borrow().clone();
+        self.sort_items(&mut items);
+
+        let today = Local::now().date_naive();
+        let mut criteria = ListCriteria {
+            include_done: self.show_completed(),
+            due_only: self.show_due_only(),
+            searching: !search_term.is_empty(),
+            ..ListCriteria::default()
+        };
+        let task_list = &self.task_list;
+        let mut unused = task_list.task_objects();
+        let mut reuse = |item: &TodoItem| match unused.remove(&item.key) {
+            Some(object) => {
+                object.set_todo(item);
+                object
+            }
+            None => TodoObject::new(item),
+        };
+        let mut entries = Vec::with_capacity(items.len());
+
+        if search_term.is_empty() {
+            let mode = *self.sort_mode.borrow();
+            criteria.collapsed = self.collapsed_sections().into_iter().collect();
+            let mut last_group: Option<String> = None;
+            let timed = criteria.due_only && items.iter().any(|item| is_timed_on(item, today));
+            for item in &items {
+                let label = self.today_group_label(mode, item, today, timed).unwrap_or_default();
+                if !label.is_empty() && last_group.as_ref() != Some(&label) {
+                    let header = task_list.header(&label);
+                    let collapsed = criteria.collapsed.contains(&label);
+                    if header.collapsed() != collapsed {
+                        header.set_collapsed(collapsed);
+                    }
+                    entries.push(header);
+                    last_group = Some(label.clone());
+                }
+                if criteria.accepts(item, today) && !label.is_empty() {
+                    criteria.headers.insert(label.clone());
+                }
+                let object = reuse(item);
+                if object.group() != label {
+                    object.set_group(label);
+                }
+                entries.push(object);
+            }
+        } else {
+            // Suchergebnisse in der aktuellen Liste, dann bei allen offenen
+            // und zuletzt bei den abgeschlossenen Todos.
+            let labels = [t("search_results_current"), t("search_results_open"), t("search_results_done")];
+            let mut categories: [Vec<TodoObject>; 3] = Default::default();
+            for item in &items {
+                let category = if criteria.accepts(item, today) {
+                    0
+                } else if !item.done {
+                    1
+                } else {
+                    2
+                };
+                if item.title.to_lowercase().contains(&search_term) {
+                    criteria.headers.insert(labels[category].clone());
+                }
+                let object = reuse(item);
+                if object.group() != labels[category] {
+                    object.set_group(labels[category].as_str());
+                }
+                categories[category].push(object);
+            }
+            for (label, objects) in labels.iter().zip(categories) {
+                entries.push(task_list.header(label));
+                entries.extend(objects);
+            }
+        }
+
+        task_list.arrange(entries, criteria, &search_term);
+
+        let mut restored = false;
+
+        if let Some(key) = selected_key {
+            if let Some(list_view) = self.list_view.borrow().as_ref() {
+                if let Some(model) = list_view.model() {
+                    if let Ok(selection) = model.downcast::<gtk::SingleSelection>() {
+                        for i in 0..selection.n_items() {
+                            let todo = selection.item(i).and_then(|obj| list_entry_todo(&obj));
+                            if todo.is_some_and(|todo| todo.key == key) {
+                                if selection.selected() != i {
+                                    selection.set_selected(i);
+                                }
+                                list_view.scroll_to(i, gtk::ListScrollFlags::NONE, None);
+                                restored = true;
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        self.update_view_summary();
+
+        if !restored {
+            if let Some(pos) = scroll_pos {
+                if let Some(scrolled) = self.scrolled_window.borrow().as_ref() {
+                    let adj = scrolled.vadjustment();
+                    glib::idle_add_local(move || {
+                        let max = (adj.upper() - adj.page_size()).max(0.0);
+                        adj.set_value(pos.min(max));
+                        glib::ControlFlow::Break
+                    });
+                }
+            }
+        }
+    }
+
+    /// Opens the file or folder the task links to with its default app.
+    fn open_linked_file(self: &Rc<Self>, todo: &TodoItem) {
+        let Some(uri) = todo.file_link() else {
+            return;
+        };
+        let file = gio::File::for_uri(uri);
+        if !file.query_exists(gio::Cancellable::NONE) {
+            self.show_error(&t("linked_file_missing").replace("{}", &linked_file_name(uri)));
+            return;
+        }
+        let launcher = gtk::FileLauncher::new(Some(&file));
+        let state = Rc::clone(self);
+        launcher.launch(self.window.upgrade().as_ref(), gio::Cancellable::NONE, move |result| {
+            if let Err(err) = result {
+                state.show_error(&t("open_linked_file_error").replace("{}", &err.to_string()));
+            }
+        });
+    }
+
+    fn toggle_pinned(self: &Rc<Self>, todo: &TodoItem) {
+        let mut updated = todo.clone();
+        updated.pinned = !todo.pinned;
+        if let Err(err) = self.save_item(todo, &updated) {
+            self.show_error(&t("save_task_error").replace("{}", &describe_error(&err)));
+        }
+    }
+
+    /// Shows the number of listed open tasks and their summed estimate below
+    /// the window title, e.g. "Today: 5 tasks, ~3h 20m".
+    fn update_view_summary(&self) {
+        let Some(title) = self.window_title.borrow().clone() else {
+            return;
+        };
+        let mut count = 0;
+        let mut minutes: u32 = 0;
+        for todo in self.visible_items() {
+            if !todo.done {
+                count += 1;
+                minutes = minutes.saturating_add(todo.estimate.unwrap_or(0));
+            }
+        }
+        let mut summary = t("view_summary_tasks").replace("{}", &count.to_string());
+        if minutes > 0 {
+            summary = t("view_summary_estimate")
+                .replacen("{}", &summary, 1)
+                .replacen("{}", &estimate_label(minutes), 1);
+        }
+        if self.show_due_only() {
+            summary = t("view_summary_today").replace("{}", &summary);
+        }
+        title.set_subtitle(&summary);
+    }
+
+    /// Adopts preferences changed outside the app. Our own writes come back
+    /// here too and are recognised by being equal to what we hold.
+    fn sync_preferences_from_settings(&self) {
+        let Some(settings) = &self.settings else {
+            return;
+        };
+        let fresh: Preferences = match settings::load(settings) {
+            Ok(prefs) => prefs,
+            Err(err) => {
+                tracing::warn!("Could not read GSettings: {err}");
+                return;
+            }
+        };
+        if serde_json::to_value(&fresh).ok() == serde_json::to_value(&*self.preferences.borrow()).ok() {
+            return;
+        }
+        tracing::debug!("preferences changed externally");
+        *self.preferences.borrow_mut() = fresh;
+        self.apply_shortcuts();
+        self.apply_accent();
+        self.apply_reduce_motion();
+        self.repopulate_store();
+    }
+
+    fn persist_preferences(&self) {
+        // Settings changed while trying the demo are not kept.
+        if demo::is_active() {
+            return;
+        }
+        let owner = self.view_state_owner.borrow().clone();
+        if !owner.is_empty() {
+            let mut prefs = self.preferences.borrow_mut();
+            let current = ViewState::of(&prefs);
+            prefs.view_states.insert(owner, current);
+        }
+        let prefs = self.preferences.borrow().clone();
+        if let Err(err) = write_preferences(&prefs) {
+            tracing::error!("{}: {err}", t("save_settings_error"));
+        }
+    }
+
+    /// Saves `updated`, an edit of `base`. Outside changes to the same task
+    /// are merged in; if both sides changed a field, a merge dialog decides.
+    fn save_item(self: &Rc<Self>, base: &TodoItem, updated: &TodoItem) -> Result<()> {
+        let conflict = data::update_todo_merged(base, updated)?;
+        self.reload()?;
+        match conflict {
+            Some(conflict) => self.show_merge_dialog(vec![conflict]),
+            None => self.show_info(&t("updated_task").replace("{}", &updated.title)),
+        }
+        Ok(())
+    }
+
+    /// Lists each conflicting task with both versions of the fields changed
+    /// on both sides and lets the user keep either version per task.
+    fn show_merge_dialog(self: &Rc<Self>, conflicts: Vec<data::MergeConflict>) {
+        let Some(parent) = self.window.upgrade() else {
+            self.show_error(&t("no_window"));
+            return;
+        };
+
+        let dialog = adw::Window::builder()
+            .title(t("merge_title"))
+            .transient_for(&parent)
+            .modal(true)
+            .default_width(560)
+            .build();
+        dialog.set_destroy_with_parent(true);
+
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 12);
+        content.set_margin_top(16);
+        content.set_margin_bottom(16);
+        content.set_margin_start(20);
+        content.set_margin_end(20);
+        content.append(&gtk::Label::builder().label(t("merge_desc")).xalign(0.0).wrap(true).build());
+
+        let mut choices = Vec::new();
+        for conflict in conflicts {
+            let frame = gtk::Box::new(gtk::Orientation::Vertical, 8);
+            frame.add_css_class("card");
+            let inner = gtk::Box::new(gtk::Orientation::Vertical, 8);
+            inner.set_margin_top(12);
+            inner.set_margin_bottom(12);
+            inner.set_margin_start(12);
+            inner.set_margin_end(12);
+            frame.append(&inner);
+
+            let heading = gtk::Label::builder().label(conflict.base.title.as_str()).xalign(0.0).build();
+            heading.add_css_class("heading");
+            inner.append(&heading);
+
+            let grid = gtk::Grid::builder().column_spacing(18).row_spacing(6).column_homogeneous(false).build();
+            for (column, caption) in [(1, t("merge_mine")), (2, t("merge_theirs"))] {
+                let label = gtk::Label::builder().label(caption.as_str()).xalign(0.0).build();
+                label.add_css_class("dim-label");
+                grid.attach(&label, column, 0, 1, 1);
+            }
+            for (row, field) in conflict.fields.iter().enumerate() {
+                let row = row as i32 + 1;
+                grid.attach(&gtk::Label::builder().label(field_label(field).as_str()).xalign(0.0).build(), 0, row, 1, 1);
+                for (column, item) in [(1, &conflict.mine), (2, &conflict.theirs)] {
+                    let value = item.field_text(field).unwrap_or_else(|| "–".to_string());
+                    let label = gtk::Label::builder().label(value.as_str()).xalign(0.0).wrap(true).hexpand(true).build();
+                    grid.attach(&label, column, row, 1, 1);
+                }
+            }
+            inner.append(&grid);
+
+            let keep_mine = gtk::CheckButton::with_label(&t("merge_keep_mine"));
+            let keep_theirs = gtk::CheckButton::with_label(&t("merge_keep_theirs"));
+            keep_theirs.set_group(Some(&keep_mine));
+            keep_mine.set_active(true);
+            let buttons = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+            buttons.append(&keep_mine);
+            buttons.append(&keep_theirs);
+            inner.append(&buttons);
+
+            content.append(&frame);
+            choices.push((conflict, keep_mine));
+        }
+
+        let apply_btn = gtk::Button::with_label(&t("merge_apply"));
+        apply_btn.add_css_class("suggested-action");
+        apply_btn.set_halign(gtk::Align::End);
+        content.append(&apply_btn);
+
+        let scrolled = gtk::ScrolledWindow::builder()
+            .child(&content)
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .propagate_natural_height(true)
+            .max_content_height(600)
+            .build();
+        dialog.set_content(Some(&scrolled));
+
+        let state = Rc::clone(self);
+        apply_btn.connect_clicked(clone!(@weak dialog => move |_| {
+            let mut remaining = Vec::new();
+            for (conflict, keep_mine) in &choices {
+                if !keep_mine.is_active() {
+                    continue;
+                }
+                // The file's version is the new common ancestor; it may have
+                // changed yet again while the dialog was open.
+                match data::update_todo_merged(&conflict.theirs, &conflict.mine) {
+                    Ok(Some(again)) => remaining.push(again),
+                    Ok(None) => {}
+                    Err(err) => state.show_error(&t("save_task_error").replace("{}", &describe_error(&err))),
+                }
+            }
+            dialog.close();
+            if let Err(err) = state.reload() {
+                state.show_error(&t("load_error").replace("{}", &describe_error(&err)));
+            }
+            if !remaining.is_empty() {
+                state.show_merge_dialog(remaining);
+            }
+        }));
+        dialog.present();
+    }
+
+    /// Lists groups of open tasks with (almost) the same title; "Merge" keeps
+    /// the chosen task of a group with the metadata of all and deletes the
+    /// others.
+    fn show_duplicates_dialog(self: &Rc<Self>) {
+        let Some(parent) = self.window.upgrade() else {
+            self.show_error(&t("no_window"));
+            return;
+        };
+        let groups = duplicates::find_duplicates(&self.cached_items.borrow());
+        if groups.is_empty() {
+            self.show_info(&t("duplicates_none"));
+            return;
+        }
+
+        let dialog = adw::Window::builder()
+            .title(t("find_duplicates"))
+            .transient_for(&parent)
+            .modal(true)
+            .default_width(560)
+            .build();
+        dialog.set_destroy_with_parent(true);
+
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 12);
+        content.set_margin_top(16);
+        content.set_margin_bottom(16);
+        content.set_margin_start(20);
+        content.set_margin_end(20);
+        content.append(&gtk::Label::builder().label(t("duplicates_desc")).xalign(0.0).wrap(true).build());
+
+        for group in groups {
+            let frame = gtk::Box::new(gtk::Orientation::Vertical, 8);
+            frame.add_css_class("card");
+            let inner = gtk::Box::new(gtk::Orientation::Vertical, 8);
+            inner.set_margin_top(12);
+            inner.set_margin_bottom(12);
+            inner.set_margin_start(12);
+            inner.set_margin_end(12);
+            frame.append(&inner);
+
+            let mut options: Vec<gtk::CheckButton> = Vec::new();
+            for todo in &group {
+                let button = gtk::CheckButton::new();
+                let text = gtk::Box::new(gtk::Orientation::Vertical, 2);
+                text.append(&gtk::Label::builder().label(todo.title.as_str()).xalign(0.0).wrap(true).build());
+                let details = format_metadata(todo, &MetaField::ALL);
+                if !details.is_empty() {
+                    let label = gtk::Label::builder().label(details.as_str()).xalign(0.0).wrap(true).build();
+                    label.add_css_class("dim-label");
+                    text.append(&label);
+                }
+                button.set_child(Some(&text));
+                if let Some(first) = options.first() {
+                    button.set_group(Some(first));
+                }
+                inner.append(&button);
+                options.push(button);
+            }
+            options[0].set_active(true);
+
+            let merge_btn = gtk::Button::with_label(&t("duplicates_merge"));
+            merge_btn.set_halign(gtk::Align::End);
+            inner.append(&merge_btn);
+            content.append(&frame);
+
+            let state = Rc::clone(self);
+            merge_btn.connect_clicked(clone!(@weak dialog => move |_| {
+                let Some(keep) = options.iter().position(|button| button.is_active()) else {
+                    return;
+                };
+                let others: Vec<TodoItem> =
+                    group.iter().enumerate().filter(|(index, _)| *index != keep).map(|(_, todo)| todo.clone()).collect();
+                dialog.close();
+                match duplicates::merge(&group[keep], &others) {
+                    Ok(()) => state.show_info(&t("duplicates_merged").replace("{}", &group[keep].title)),
+                    Err(err) => state.show_error(&t("save_task_error").replace("{}", &describe_error(&err))),
+                }
+                if let Err(err) = state.reload() {
+                    state.show_error(&t("load_error").replace("{}", &describe_error(&err)));
+                    return;
+                }
+                // Line numbers changed, so the other groups are looked up again.
+                if !duplicates::find_duplicates(&state.cached_items.borrow()).is_empty() {
+                    state.show_duplicates_dialog();
+                }
+            }));
+        }
+
+        let scrolled = gtk::ScrolledWindow::builder()
+            .child(&content)
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .propagate_natural_height(true)
+            .max_content_height(600)
+            .build();
+        dialog.set_content(Some(&scrolled));
+        dialog.present();
+    }
+
+    /// Lists the lines [`repair::scan`] finds with the proposed fix and lets
+    /// the user fix, keep or set aside each one.
+    fn show_repair_dialog(self: &Rc<Self>) {
+        let Some(parent) = self.window.upgrade() else {
+            self.show_error(&t("no_window"));
+            return;
+        };
+        let repairs = match repair::scan() {
+            Ok(repairs) => repairs,
+            Err(err) => {
+                self.show_error(&t("repair_error").replace("{}", &describe_error(&err)));
+                return;
+            }
+        };
+        if repairs.is_empty() {
+            self.show_info(&t("repair_nothing"));
+            return;
+        }
+
+        let dialog = adw::Window::builder()
+            .title(t("repair_title"))
+            .transient_for(&parent)
+            .modal(true)
+            .default_width(560)
+            .build();
+        dialog.set_destroy_with_parent(true);
+
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 12);
+        content.set_margin_top(16);
+        content.set_margin_bottom(16);
+        content.set_margin_start(20);
+        content.set_margin_end(20);
+        content.append(&gtk::Label::builder().label(t("repair_desc")).xalign(0.0).wrap(true).build());
+
+        let mut choices = Vec::new();
+        for repair in repairs {
+            let frame = gtk::Box::new(gtk::Orientation::Vertical, 8);
+            frame.add_css_class("card");
+            let inner = gtk::Box::new(gtk::Orientation::Vertical, 8);
+            inner.set_margin_top(12);
+            inner.set_margin_bottom(12);
+            inner.set_margin_start(12);
+            inner.set_margin_end(12);
+            frame.append(&inner);
+
+            let mut place = t("problem_line").replace("{}", &repair.line.to_string());
+            if let Some(file) = repair.file.as_deref().and_then(|file| file.file_name()) {
+                place = format!("{} · {place}", file.to_string_lossy());
+            }
+            let heading = gtk::Label::builder().label(place.as_str()).xalign(0.0).build();
+            heading.add_css_class("heading");
+            inner.append(&heading);
+            let original = gtk::Label::builder()
+                .label(repair.original.as_str())
+                .xalign(0.0)
+                .wrap(true)
+                .wrap_mode(gtk::pango::WrapMode::WordChar)
+                .selectable(true)
+                .build();
+            original.add_css_class("monospace");
+            inner.append(&original);
+            let problems = gtk::Label::builder().label(repair.problems.join("\n").as_str()).xalign(0.0).wrap(true).build();
+            problems.add_css_class("dim-label");
+            inner.append(&problems);
+            if let Some(proposal) = &repair.proposal {
+                let label = gtk::Label::builder()
+                    .label(t("repair_proposal").replace("{}", proposal).as_str())
+                    .xalign(0.0)
+                    .wrap(true)
+                    .wrap_mode(gtk::pango::WrapMode::WordChar)
+                    .build();
+                label.add_css_class("monospace");
+                inner.append(&label);
+            }
+
+            let buttons = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+            let mut options = Vec::new();
+            for (action, key) in [
+                (RepairAction::Fix, "repair_fix"),
+                (RepairAction::Keep, "repair_keep"),
+                (RepairAction::Quarantine, "repair_quarantine"),
+            ] {
+                let available = match action {
+                    RepairAction::Fix => repair.proposal.is_some(),
+                    RepairAction::Keep => !repair.undecodable,
+                    RepairAction::Quarantine => true,
+                };
+                if !available {
+                    continue;
+                }
+                let button = gtk::CheckButton::with_label(&t(key));
+                if let Some((_, first)) = options.first() {
+                    button.set_group(Some(first));
+                }
+                button.set_active(action == repair.default_action());
+                buttons.append(&button);
+                options.push((action, button));
+            }
+            inner.append(&buttons);
+
+            content.append(&frame);
+            choices.push((repair, options));
+        }
+
+        let apply_btn = gtk::Button::with_label(&t("repair_apply"));
+        apply_btn.add_css_class("suggested-action");
+        apply_btn.set_halign(gtk::Align::End);
+        content.append(&apply_btn);
+
+        let scrolled = gtk::ScrolledWindow::builder()
+            .child(&content)
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .propagate_natural_height(true)
+            .max_content_height(600)
+            .build();
+        dialog.set_content(Some(&scrolled));
+
+        let state = Rc::clone(self);
+        apply_btn.connect_clicked(clone!(@weak dialog => move |_| {
+            let chosen: Vec<(repair::Repair, RepairAction)> = choices
+                .iter()
+                .filter_map(|(repair, options)| {
+                    let (action, _) = options.iter().find(|(_, button)| button.is_active())?;
+                    Some((repair.clone(), *action))
+                })
+                .collect();
+            dialog.close();
+            match repair::apply(&chosen) {
+                Ok(changed) => state.show_info(&t("repair_done").replace("{}", &changed.to_string())),
+                Err(err) => state.show_error(&t("repair_error").replace("{}", &describe_error(&err))),
+            }
+            if let Err(err) = state.reload() {
+                state.show_error(&t("load_error").replace("{}", &describe_error(&err)));
+            }
+        }));
+        dialog.present();
+    }
+
+    fn toggle_recording(self: &Rc<Self>, voice_btn: &gtk::Button, entry: &gtk::Entry) {
+        if self.is_recording.load(AtomicOrdering::SeqCst) {
+            self.is_recording.store(false, AtomicOrdering::SeqCst);
+            voice_btn.remove_css_class("destructive-action");
+            voice_btn.set_icon_name("audio-input-microphone-symbolic");
+            return;
+        }
+
+        let model_path = self.whisper_model_path();
+        if !model_path.exists() {
+            self.show_error(&t("model_not_found"));
+            return;
+        }
+
+        self.is_recording.store(true, AtomicOrdering::SeqCst);
+        voice_btn.add_css_class("destructive-action");
+        voice_btn.set_icon_name("media-record-symbolic");
+
+        let is_recording = self.is_recording.clone();
+        let (sender, receiver) = std::sync::mpsc::channel::<VoiceMsg>();
+        
+        {
+            let state_clone = Rc::clone(self);
+            let voice_btn_clone = voice_btn.clone();
+            let entry_clone = entry.clone();
+            
+            glib::timeout_add_local(std::time::Duration::from_millis(100), move || {
+                let mut finished = false;
+                while let Ok(msg) = receiver.try_recv() {
+                    match msg {
+                        VoiceMsg::Error(e) => {
+                            state_clone.show_error(&e);
+                            voice_btn_clone.remove_css_class("destructive-action");
+                            voice_btn_clone.remove_css_class("pulse");
+                            voice_btn_clone.set_icon_name("audio-input-microphone-symbolic");
+                            state_clone.is_recording.store(false, AtomicOrdering::SeqCst);
+                            finished = true;
+                        }
+                        VoiceMsg::Transcription(text) => {
+                            let current = entry_clone.text();
+                            if current.is_empty() {
+                                entry_clone.set_text(&text);
+                            } else {
+                                entry_clone.set_text(&format!("{} {}", current, text));
+                            }
+                        }
+                        VoiceMsg::Transcribing => {
+                            voice_btn_clone.remove_css_class("destructive-action");
+                            voice_btn_clone.add_css_class("pulse");
+                            voice_btn_clone.set_icon_name("audio-input-microphone-symbolic");
+                        }
+                        VoiceMsg::Finished => {
+                            voice_btn_clone.remove_css_class("destructive-action");
+                            voice_btn_clone.remove_css_class("pulse");
+                            voice_btn_clone.set_icon_name("audio-input-microphone-symbolic");
+                            state_clone.is_recording.store(false, AtomicOrdering::SeqCst);
+                            finished = true;
+                        }
                     }
-                    Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
-                    Err(std::sync::mpsc::TryRecvError::Disconnected) => glib::ControlFlow::Break,
                 }
-            });
+                if finished {
+                    glib::ControlFlow::Break
+                } else {
+                    glib::ControlFlow::Continue
+                }
+            });
+        }
+
+        let language = self.whisper_language();
+
+        std::thread::spawn(move || {
+            let host = cpal::default_host();
+            let device = match host.default_input_device() {
+                Some(d) => d,
+                None => {
+                    let _ = sender.send(VoiceMsg::Error(t("voice_no_input_error")));
+                    return;
+                }
+            };
+
+            let config = match device.default_input_config() {
+                Ok(c) => c,
+                Err(e) => {
+                    let _ = sender.send(VoiceMsg::Error(t("voice_input_config_error").replace("{}", &e.to_string())));
+                    return;
+                }
+            };
+
+            let audio_data = Arc::new(Mutex::new(Vec::new()));
+            let audio_data_clone = audio_data.clone();
+
+            let stream = match device.build_input_stream(
+                &config.clone().into(),
+                move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                    let mut buffer = audio_data_clone.lock().unwrap();
+                    buffer.extend_from_slice(data);
+                },
+                |_| {},
+                None,
+            ) {
+                Ok(s) => s,
+                Err(e) => {
+                    let _ = sender.send(VoiceMsg::Error(t("voice_stream_error").replace("{}", &e.to_string())));
+                    return;
+                }
+            };
+
+            if let Err(e) = stream.play() {
+                let _ = sender.send(VoiceMsg::Error(t("voice_stream_error").replace("{}", &e.to_string())));
+                return;
+            }
+
+            while is_recording.load(AtomicOrdering::SeqCst) {
+                std::thread::sleep(std::time::Duration::from_millis(100));
+            }
+
+            drop(stream);
+
+            let samples = audio_data.lock().unwrap().clone();
+            if samples.is_empty() {
+                let _ = sender.send(VoiceMsg::Finished);
+                return;
+            }
+
+            // Convert to mono if necessary
+            let channels = config.channels() as usize;
+            let mono_samples = if channels > 1 {
+                let mut mono = Vec::with_capacity(samples.len() / channels);
+                for chunk in samples.chunks_exact(channels) {
+                    let sum: f32 = chunk.iter().sum();
+                    mono.push(sum / channels as f32);
+                }
+                mono
+            } else {
+                samples
+            };
+
+            // Resample to 16kHz if necessary (Whisper requirement)
+            let sample_rate = config.sample_rate().0;
+            let samples_16k = if sample_rate != 16000 {
+                let mut resampled = Vec::new();
+                let ratio = sample_rate as f32 / 16000.0;
+                let mut i = 0.0;
+                while i < mono_samples.len() as f32 {
+                    resampled.push(mono_samples[i as usize]);
+                    i += ratio;
+                }
+                resampled
+            } else {
+                mono_samples
+            };
+
+            tracing::info!("Starting transcription ({} samples, {}Hz, language: {})", samples_16k.len(), sample_rate, language);
+            let _ = sender.send(VoiceMsg::Transcribing);
+
+            let ctx = match WhisperContext::new_with_params(
+                &model_path.to_string_lossy(),
+                WhisperContextParameters::default(),
+            ) {
+                Ok(c) => c,
+                Err(e) => {
+                    let _ = sender.send(VoiceMsg::Error(t("voice_model_error").replace("{}", &e.to_string())));
+                    return;
+                }
+            };
+
+            let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+            params.set_n_threads(4);
+            if language != "auto" {
+                params.set_language(Some(&language));
+            } else {
+                params.set_language(None);
+            }
+
+            let mut state_whisper = ctx.create_state().expect("failed to create state");
+            if let Err(e) = state_whisper.full(params, &samples_16k) {
+                let _ = sender.send(VoiceMsg::Error(t("voice_transcription_error").replace("{}", &e.to_string())));
+                return;
+            }
+
+            let num_segments = state_whisper.full_n_segments().expect("failed to get segments");
+            let mut result_text = String::new();
+            for i in 0..num_segments {
+                if let Ok(segment) = state_whisper.full_get_segment_text(i) {
+                    result_text.push_str(&segment);
+                }
+            }
+
+            let final_text = result_text.trim().to_string();
+            if !final_text.is_empty() {
+                let _ = sender.send(VoiceMsg::Transcription(final_text));
+            }
+            let _ = sender.send(VoiceMsg::Finished);
         });
-        webdav_group.add(&check_row);
+    }
 
-        // --- Voice Page ---
-        let voice_page = adw::PreferencesPage::builder()
-            .title(&t("voice"))
-            .icon_name("audio-input-microphone-symbolic")
-            .build();
-        dialog.add(&voice_page);
 
-        let voice_group = adw::PreferencesGroup::builder()
-            .title(&t("voice"))
-            .build();
-        voice_page.add(&voice_group);
+    fn show_details_dialog(self: &Rc<Self>, todo: &TodoItem) {
+        self.open_details_dialog(todo, None);
+    }
 
-        let progress_bar = gtk::ProgressBar::builder()
-            .visible(false)
-            .margin_top(6)
-            .margin_bottom(6)
-            .build();
-        voice_group.add(&progress_bar);
+    /// The task dialog, with the fields as in `draft` if given.
+    fn open_details_dialog(self: &Rc<Self>, todo: &TodoItem, draft: Option<&EditFields>) {
+        let Some(parent) = self.window.upgrade() else {
+            self.show_error(&t("no_window"));
+            return;
+        };
 
-        let use_whisper_row = adw::SwitchRow::builder()
-            .title(&t("use_whisper"))
-            .subtitle(&t("whisper_desc"))
-            .active(self.use_whisper())
-            .build();
-        use_whisper_row.add_prefix(&gtk::Image::from_icon_name("audio-input-microphone-symbolic"));
-        
-        let languages = vec!["auto", "en", "de", "es", "fr", "it", "ja", "zh", "nl", "pl", "pt", "ru", "tr", "sv"];
-        let language_names = [
-            t("lang_auto"), t("lang_en"), t("lang_de"), t("lang_es"), t("lang_fr"), 
-            t("lang_it"), t("lang_ja"), t("lang_zh"), t("lang_nl"), t("lang_pl"), 
-            t("lang_pt"), t("lang_ru"), t("lang_tr"), t("lang_sv")
-        ];
-        let language_names_refs: Vec<&str> = language_names.iter().map(|s| s.as_str()).collect();
-        
-        let language_model = gtk::StringList::new(&language_names_refs);
-        
-        let language_row = adw::ComboRow::builder()
-            .title(&t("whisper_language"))
-            .model(&language_model)
+        let dialog = adw::Window::builder()
+            .title(&t("edit_task"))
+            .transient_for(&parent)
+            .modal(true)
+            .default_width(420)
             .build();
+        dialog.set_destroy_with_parent(true);
 
-        // Set initial selection
-        let current_lang = self.whisper_language();
-        if let Some(idx) = languages.iter().position(|&l| l == current_lang) {
-            language_row.set_selected(idx as u32);
-        }
-
-        let state_lang = Rc::clone(self);
-        let languages_clone = languages.clone();
-        language_row.connect_selected_notify(move |row| {
-            let idx = row.selected() as usize;
-            if idx < languages_clone.len() {
-                state_lang.set_whisper_language(languages_clone[idx].to_string());
-            }
-        });
-
-        let state_whisper = Rc::clone(self);
-        let pb_whisper = progress_bar.clone();
-        let vb_whisper = voice_btn.clone();
-        let lang_row_clone = language_row.clone();
-        
-        // Disable language selection if whisper is disabled
-        language_row.set_sensitive(self.use_whisper());
-
-        use_whisper_row.connect_active_notify(move |row| {
-            if row.is_active() {
-                state_whisper.set_use_whisper(true, Some(pb_whisper.clone()), Some(row.clone()), vb_whisper.clone());
-                lang_row_clone.set_sensitive(true);
+        let key_controller = gtk::EventControllerKey::new();
+        let dialog_clone = dialog.clone();
+        key_controller.connect_key_pressed(move |_, keyval, _, _| {
+            if keyval == gdk::Key::Escape {
+                dialog_clone.close();
+                glib::Propagation::Stop
             } else {
-                state_whisper.set_use_whisper(false, None, None, vb_whisper.clone());
-                lang_row_clone.set_sensitive(false);
+                glib::Propagation::Proceed
             }
         });
-        voice_group.add(&use_whisper_row);
-        voice_group.add(&language_row);
+        dialog.add_controller(key_controller);
 
-        // --- About Page ---
-        let about_page = adw::PreferencesPage::builder()
-            .title(&t("about"))
-            .icon_name("help-about-symbolic")
-            .build();
-        dialog.add(&about_page);
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 12);
+        content.set_margin_top(16);
+        content.set_margin_bottom(16);
+        content.set_margin_start(20);
+        content.set_margin_end(20);
 
-        let about_group = adw::PreferencesGroup::builder()
+        let section_row = gtk::Box::new(gtk::Orientation::Vertical, 4);
+        section_row.append(&gtk::Label::builder().label(&t("section")).xalign(0.0).build());
+        let section_value = gtk::Label::builder()
+            .label(todo.section.as_str())
+            .xalign(0.0)
             .build();
-        about_page.add(&about_group);
+        section_value.add_css_class("dim-label");
+        section_row.append(&section_value);
+        content.append(&section_row);
 
-        let banner = adw::Bin::builder()
-            .margin_top(12)
-            .margin_bottom(12)
-            .build();
-        let banner_box = gtk::Box::new(gtk::Orientation::Vertical, 6);
-        banner_box.set_halign(gtk::Align::Center);
-        
-        let app_icon = gtk::Image::from_icon_name("me.dumke.Reinschrift");
-        app_icon.set_pixel_size(128);
-        banner_box.append(&app_icon);
+        if let (Some(Aging::Raised(original)), Some(priority)) = (todo.aged, todo.priority) {
+            let original = original.map(String::from).unwrap_or_else(|| "–".to_string());
+            let aged_label = gtk::Label::builder()
+                .label(t("aged_detail").replacen("{}", &original, 1).replacen("{}", &priority.to_string(), 1))
+                .wrap(true)
+                .xalign(0.0)
+                .hexpand(true)
+                .build();
+            aged_label.add_css_class("dim-label");
+            let revert_btn = gtk::Button::with_label(&t("aging_revert"));
+            revert_btn.set_valign(gtk::Align::Center);
+            let aged_row = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+            aged_row.append(&aged_label);
+            aged_row.append(&revert_btn);
+            content.append(&aged_row);
+            let state = Rc::clone(self);
+            let aged_todo = todo.clone();
+            revert_btn.connect_clicked(clone!(@weak dialog => move |_| {
+                state.revert_aging(&aged_todo);
+                dialog.close();
+            }));
+        }
 
-        let app_name = gtk::Label::builder()
-            .label("Reinschrift")
-            .css_classes(["title-1"])
-            .build();
-        banner_box.append(&app_name);
+        let title_entry = gtk::Entry::builder().text(&todo.title).hexpand(true).show_emoji_icon(true).build();
+        spelling::attach(&title_entry);
+        let title_row = gtk::Box::new(gtk::Orientation::Vertical, 4);
+        title_row.append(&gtk::Label::builder().label(&t("title")).xalign(0.0).build());
+        title_row.append(&title_entry);
+        content.append(&title_row);
 
-        let app_version = gtk::Label::builder()
-            .label(&format!("{} 0.9.30", t("version")))
-            .css_classes(["dim-label"])
-            .build();
-        banner_box.append(&app_version);
+        let project_entry = gtk::Entry::new();
+        if let Some(project) = &todo.project {
+            project_entry.set_text(project);
+        }
+        let project_row = gtk::Box::new(gtk::Orientation::Vertical, 4);
+        project_row.append(&gtk::Label::builder().label(&t("project_plus")).xalign(0.0).build());
+        project_row.append(&project_entry);
+        content.append(&project_row);
+        install_completion(Rc::downgrade(self), &project_entry, Completion::Project);
 
-        banner.set_child(Some(&banner_box));
-        about_group.add(&banner);
+        let context_entry = gtk::Entry::new();
+        if let Some(context) = &todo.context {
+            context_entry.set_text(context);
+        }
+        let context_row = gtk::Box::new(gtk::Orientation::Vertical, 4);
+        context_row.append(&gtk::Label::builder().label(&t("location_at")).xalign(0.0).build());
+        context_row.append(&context_entry);
+        content.append(&context_row);
+        install_completion(Rc::downgrade(self), &context_entry, Completion::Context);
 
-        let info_group = adw::PreferencesGroup::builder()
-            .build();
-        about_page.add(&info_group);
+        let start_entry = gtk::Entry::new();
+        start_entry.set_placeholder_text(Some("YYYY-MM-DD"));
+        if let Some(start) = todo.start {
+            start_entry.set_text(&start.format("%Y-%m-%d").to_string());
+        }
+        let start_row = gtk::Box::new(gtk::Orientation::Vertical, 4);
+        start_row.append(&gtk::Label::builder().label(t("start_date")).xalign(0.0).build());
+        start_row.append(&start_entry);
+        content.append(&start_row);
 
-        let dev_row = adw::ActionRow::builder()
-            .title(&t("developer"))
-            .subtitle("Dr. Daniel Dumke")
-            .build();
-        info_group.add(&dev_row);
+        let due_entry = gtk::Entry::new();
+        due_entry.set_placeholder_text(Some("YYYY-MM-DD"));
+        if let Some(due) = todo.due {
+            let due_string = due.format("%Y-%m-%d").to_string();
+            due_entry.set_text(&due_string);
+        }
+        let due_row = gtk::Box::new(gtk::Orientation::Vertical, 4);
+        due_row.append(&gtk::Label::builder().label(&t("due_date")).xalign(0.0).build());
+        let due_inputs = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+        due_entry.set_hexpand(true);
+        due_inputs.append(&due_entry);
+        let due_today_btn = gtk::Button::with_label(&t("today"));
+        due_today_btn.add_css_class("flat");
+        due_inputs.append(&due_today_btn);
+        due_row.append(&due_inputs);
+        content.append(&due_row);
 
-        let site_row = adw::ActionRow::builder()
-            .title(&t("website"))
-            .subtitle("https://github.com/danst0/ReinschriftTodo")
-            .activatable(true)
+        let remind_entry = gtk::Entry::new();
+        remind_entry.set_placeholder_text(Some("YYYY-MM-DD HH:MM"));
+        if let Some(remind) = todo.remind {
+            remind_entry.set_text(&remind.format(REMIND_ENTRY_FORMAT).to_string());
+        }
+        let remind_row = gtk::Box::new(gtk::Orientation::Vertical, 4);
+        remind_row.append(&gtk::Label::builder().label(t("reminder")).xalign(0.0).build());
+        let remind_inputs = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+        remind_entry.set_hexpand(true);
+        remind_inputs.append(&remind_entry);
+        let presets_list = gtk::Box::new(gtk::Orientation::Vertical, 2);
+        let presets_popover = gtk::Popover::builder().child(&presets_list).build();
+        let remind_presets_btn = gtk::MenuButton::builder()
+            .icon_name("alarm-symbolic")
+            .tooltip_text(t("reminder_presets"))
+            .popover(&presets_popover)
             .build();
-        site_row.connect_activated(|_| {
-            let launcher = gtk::FileLauncher::new(Some(&gio::File::for_uri("https://github.com/danst0/ReinschriftTodo")));
-            launcher.launch(None::<&gtk::Window>, gio::Cancellable::NONE, |_| {});
-        });
-        info_group.add(&site_row);
-
-        let license_row = adw::ActionRow::builder()
-            .title(&t("license"))
-            .subtitle("CC-BY-SA-4.0")
+        let remind_clear_btn = gtk::Button::builder()
+            .icon_name("edit-clear-symbolic")
+            .tooltip_text(t("clear_reminder"))
             .build();
-        info_group.add(&license_row);
-
-        dialog.present();
-    }
-
-    fn set_show_completed(&self, show: bool) {
-        {
-            let mut prefs = self.preferences.borrow_mut();
-            if prefs.show_done == show {
-                return;
+        remind_presets_btn.add_css_class("flat");
+        remind_clear_btn.add_css_class("flat");
+        remind_inputs.append(&remind_presets_btn);
+        remind_inputs.append(&remind_clear_btn);
+        remind_row.append(&remind_inputs);
+        content.append(&remind_row);
+
+        let estimate_entry = gtk::Entry::new();
+        estimate_entry.set_placeholder_text(Some("1h30m"));
+        if let Some(minutes) = todo.estimate {
+            estimate_entry.set_text(&data::format_estimate(minutes));
+        }
+        let estimate_row = gtk::Box::new(gtk::Orientation::Vertical, 4);
+        estimate_row.append(&gtk::Label::builder().label(t("estimate")).xalign(0.0).build());
+        estimate_row.append(&estimate_entry);
+        content.append(&estimate_row);
+
+        let depends_entry = gtk::Entry::new();
+        depends_entry.set_placeholder_text(Some("^abc, ^def"));
+        depends_entry.set_text(
+            &todo.depends_on.iter().map(|marker| format!("^{marker}")).collect::<Vec<_>>().join(", "),
+        );
+        let depends_row = gtk::Box::new(gtk::Orientation::Vertical, 4);
+        depends_row.append(&gtk::Label::builder().label(t("depends_on")).xalign(0.0).build());
+        depends_row.append(&depends_entry);
+        content.append(&depends_row);
+
+        // The reference is kept as is unless a file or folder gets linked here.
+        let reference = Rc::new(RefCell::new(todo.reference.clone()));
+        let link_row = gtk::Box::new(gtk::Orientation::Vertical, 4);
+        link_row.append(&gtk::Label::builder().label(t("linked_file")).xalign(0.0).build());
+        let link_inputs = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+        let link_label = gtk::Label::builder()
+            .xalign(0.0)
+            .hexpand(true)
+            .ellipsize(pango::EllipsizeMode::Middle)
+            .build();
+        link_label.add_css_class("dim-label");
+        let link_file_btn = gtk::Button::with_label(&t("link_file"));
+        let link_folder_btn = gtk::Button::with_label(&t("link_folder"));
+        let unlink_btn = gtk::Button::builder()
+            .icon_name("edit-clear-symbolic")
+            .tooltip_text(t("unlink_file"))
+            .build();
+        for button in [&link_file_btn, &link_folder_btn, &unlink_btn] {
+            button.add_css_class("flat");
+        }
+        link_inputs.append(&link_label);
+        link_inputs.append(&link_file_btn);
+        link_inputs.append(&link_folder_btn);
+        link_inputs.append(&unlink_btn);
+        link_row.append(&link_inputs);
+        content.append(&link_row);
+
+        let show_link = {
+            let reference = Rc::clone(&reference);
+            let link_label = link_label.clone();
+            let unlink_btn = unlink_btn.clone();
+            move || {
+                let uri = reference.borrow().clone().filter(|r| r.starts_with("file://"));
+                match &uri {
+                    Some(uri) => {
+                        link_label.set_text(&linked_file_name(uri));
+                        link_label.set_tooltip_text(gio::File::for_uri(uri).path().map(|p| p.display().to_string()).as_deref());
+                    }
+                    None => {
+                        link_label.set_text(&t("no_linked_file"));
+                        link_label.set_tooltip_text(None);
+                    }
+                }
+                unlink_btn.set_visible(uri.is_some());
             }
-            prefs.show_done = show;
+        };
+        show_link();
+        let show_link = Rc::new(show_link);
+
+        for folder in [false, true] {
+            let button = if folder { &link_folder_btn } else { &link_file_btn };
+            let reference = Rc::clone(&reference);
+            let show_link = Rc::clone(&show_link);
+            button.connect_clicked(clone!(@weak dialog => move |_| {
+                let file_dialog = FileDialog::builder()
+                    .title(if folder { t("link_folder") } else { t("link_file") })
+                    .build();
+                let reference = Rc::clone(&reference);
+                let show_link = Rc::clone(&show_link);
+                let on_chosen = move |result: Result<gio::File, glib::Error>| {
+                    if let Ok(file) = result {
+                        *reference.borrow_mut() = Some(file.uri().to_string());
+                        show_link();
+                    }
+                };
+                if folder {
+                    file_dialog.select_folder(Some(&dialog), gio::Cancellable::NONE, on_chosen);
+                } else {
+                    file_dialog.open(Some(&dialog), gio::Cancellable::NONE, on_chosen);
+                }
+            }));
         }
-
-        self.persist_preferences();
-        self.repopulate_store();
-    }
-
-    fn set_show_due_only(&self, show: bool) {
         {
-            let mut prefs = self.preferences.borrow_mut();
-            if prefs.show_due_only == show {
-                return;
-            }
-            prefs.show_due_only = show;
+            let reference = Rc::clone(&reference);
+            let show_link = Rc::clone(&show_link);
+            unlink_btn.connect_clicked(move |_| {
+                *reference.borrow_mut() = None;
+                show_link();
+            });
         }
 
-        self.persist_preferences();
-        self.repopulate_store();
-    }
+        let recurrence_values = ["", "daily", "weekly", "monthly"];
+        let recurrence_row = gtk::Box::new(gtk::Orientation::Vertical, 4);
+        recurrence_row.append(&gtk::Label::builder().label(&t("recurrence")).xalign(0.0).build());
+        let recurrence_list = gtk::StringList::new(&[]);
+        recurrence_list.append(&t("recurrence_none"));
+        recurrence_list.append(&t("recurrence_daily"));
+        recurrence_list.append(&t("recurrence_weekly"));
+        recurrence_list.append(&t("recurrence_monthly"));
+        let recurrence_dropdown = gtk::DropDown::new(Some(recurrence_list.clone()), None::<&gtk::Expression>);
+        let rec_index = todo
+            .recurrence
+            .as_deref()
+            .and_then(|r| recurrence_values.iter().position(|v| v == &r))
+            .unwrap_or(0) as u32;
+        recurrence_dropdown.set_selected(rec_index);
+        recurrence_row.append(&recurrence_dropdown);
+        content.append(&recurrence_row);
+
+        let done_check = gtk::CheckButton::with_label(&t("done"));
+        done_check.set_active(todo.done);
+        content.append(&done_check);
 
-    fn set_use_whisper(self: &Rc<Self>, use_whisper: bool, progress_bar: Option<gtk::ProgressBar>, switch_row: Option<adw::SwitchRow>, voice_btn: Option<gtk::Button>) {
-        {
-            let mut prefs = self.preferences.borrow_mut();
-            if prefs.use_whisper == use_whisper {
-                return;
-            }
-            prefs.use_whisper = use_whisper;
+        let history_entries = history::for_task(todo);
+        if !history_entries.is_empty() {
+            content.append(&history_expander(&history_entries));
         }
-        self.persist_preferences();
 
-        if let Some(btn) = voice_btn {
-            btn.set_visible(use_whisper);
-        }
+        let comment_entry = gtk::Entry::new();
+        let comment_row = gtk::Box::new(gtk::Orientation::Vertical, 4);
+        comment_row.append(&gtk::Label::builder().label(&t("comment")).xalign(0.0).build());
+        comment_row.append(&comment_entry);
+        comment_row.set_visible(false);
+        content.append(&comment_row);
 
-        if use_whisper {
-            self.ensure_whisper_model(progress_bar, switch_row);
-        } else {
-            let path = self.whisper_model_path();
-            if path.exists() {
-                let _ = fs::remove_file(path);
+        let read_fields = {
+            let title_entry = title_entry.clone();
+            let project_entry = project_entry.clone();
+            let context_entry = context_entry.clone();
+            let start_entry = start_entry.clone();
+            let due_entry = due_entry.clone();
+            let remind_entry = remind_entry.clone();
+            let estimate_entry = estimate_entry.clone();
+            let depends_entry = depends_entry.clone();
+            let reference = Rc::clone(&reference);
+            let recurrence_dropdown = recurrence_dropdown.clone();
+            let done_check = done_check.clone();
+            move || EditFields {
+                title: title_entry.text().to_string(),
+                project: project_entry.text().to_string(),
+                context: context_entry.text().to_string(),
+                start: start_entry.text().to_string(),
+                due: due_entry.text().to_string(),
+                remind: remind_entry.text().to_string(),
+                estimate: estimate_entry.text().to_string(),
+                depends_on: depends_entry.text().to_string(),
+                reference: reference.borrow().clone(),
+                recurrence: recurrence_values
+                    .get(recurrence_dropdown.selected() as usize)
+                    .unwrap_or(&"")
+                    .to_string(),
+                done: done_check.is_active(),
             }
+        };
+        let unchanged = read_fields();
+        if let Some(fields) = draft {
+            title_entry.set_text(&fields.title);
+            project_entry.set_text(&fields.project);
+            context_entry.set_text(&fields.context);
+            start_entry.set_text(&fields.start);
+            due_entry.set_text(&fields.due);
+            remind_entry.set_text(&fields.remind);
+            estimate_entry.set_text(&fields.estimate);
+            depends_entry.set_text(&fields.depends_on);
+            *reference.borrow_mut() = fields.reference.clone();
+            show_link();
+            let recurrence = recurrence_values.iter().position(|value| *value == fields.recurrence).unwrap_or(0);
+            recurrence_dropdown.set_selected(recurrence as u32);
+            done_check.set_active(fields.done);
         }
-    }
+        let draft_task = todo.clone();
+        *self.edit_draft.borrow_mut() = Some(Box::new(move || {
+            let fields = read_fields();
+            (fields != unchanged).then(|| EditDraft { task: draft_task.clone(), fields })
+        }));
+        let state = Rc::clone(self);
+        let entries = [&title_entry, &project_entry, &context_entry, &start_entry, &due_entry, &remind_entry, &estimate_entry, &depends_entry];
+        for entry in entries {
+            entry.connect_changed(clone!(@weak state => move |_| {
+                state.schedule_draft_save();
+            }));
+        }
+        recurrence_dropdown.connect_selected_notify(clone!(@weak state => move |_| {
+            state.schedule_draft_save();
+        }));
+        done_check.connect_toggled(clone!(@weak state => move |_| {
+            state.schedule_draft_save();
+        }));
+        // Saving, deleting and cancelling all end here; the draft is done with.
+        dialog.connect_close_request(clone!(@weak state => @default-return glib::Propagation::Proceed, move |_| {
+            state.edit_draft.replace(None);
+            state.save_draft();
+            glib::Propagation::Proceed
+        }));
 
-    fn ensure_whisper_model(self: &Rc<Self>, progress_bar: Option<gtk::ProgressBar>, switch_row: Option<adw::SwitchRow>) {
-        let path = self.whisper_model_path();
-        if path.exists() {
-            // Basic integrity check: size should be around 480MB
-            if let Ok(meta) = fs::metadata(&path) {
-                if meta.len() > 450 * 1024 * 1024 {
-                    if let Some(row) = &switch_row {
-                        row.set_sensitive(true);
-                    }
-                    return;
+        let buttons = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+        buttons.set_halign(gtk::Align::End);
+        let cancel_btn = gtk::Button::with_label(&t("cancel"));
+        let delete_btn = gtk::Button::builder()
+            .icon_name("user-trash-symbolic")
+            .tooltip_text(&t("delete"))
+            .css_classes(["destructive-action"])
+            .build();
+        let close_with_comment_btn = gtk::Button::with_label(&t("close_with_comment"));
+        let save_btn = gtk::Button::with_label(&t("save"));
+        save_btn.add_css_class("suggested-action");
+        let copy_link_btn = gtk::Button::builder()
+            .icon_name("insert-link-symbolic")
+            .tooltip_text(t("copy_task_link"))
+            .build();
+        buttons.append(&copy_link_btn);
+        buttons.append(&cancel_btn);
+        buttons.append(&delete_btn);
+        buttons.append(&close_with_comment_btn);
+        buttons.append(&save_btn);
+        content.append(&buttons);
+        dialog.set_content(Some(&content));
+
+        let state_link = Rc::clone(self);
+        let todo_link = todo.clone();
+        copy_link_btn.connect_clicked(move |button| {
+            match data::ensure_marker(&todo_link) {
+                Ok(marker) => {
+                    button.clipboard().set_text(&data::task_uri(&marker));
+                    state_link.show_info(&t("task_link_copied"));
                 }
+                Err(err) => state_link.show_error(&t("update_error").replace("{}", &describe_error(&err))),
             }
-            let _ = fs::remove_file(&path);
-        }
+        });
 
-        if let Some(pb) = &progress_bar {
-            pb.set_visible(true);
-            pb.set_fraction(0.0);
-        }
+        let dialog_cancel = dialog.clone();
+        cancel_btn.connect_clicked(move |_| {
+            dialog_cancel.close();
+        });
 
-        if let Some(row) = &switch_row {
-            row.set_sensitive(false);
-        }
+        let dialog_delete = dialog.clone();
+        let state_delete = self.clone();
+        let todo_delete = todo.clone();
+        delete_btn.connect_clicked(move |_| {
+            state_delete.request_delete(&dialog_delete, &todo_delete, clone!(@weak dialog_delete => move || {
+                dialog_delete.close();
+            }));
+        });
 
-        self.show_info(&t("downloading_model"));
+        let due_entry_for_button = due_entry.clone();
+        due_today_btn.connect_clicked(move |_| {
+            let today = Local::now().date_naive().format("%Y-%m-%d").to_string();
+            due_entry_for_button.set_text(&today);
+        });
 
-        let state = Rc::clone(self);
-        let (sender, receiver) = std::sync::mpsc::channel();
-        
-        std::thread::spawn(move || {
-            let url = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-small.bin";
-            let client = reqwest::blocking::Client::new();
-            let mut response = match client.get(url).send() {
-                Ok(r) => r,
-                Err(e) => {
-                    let _ = sender.send(Err(e.to_string()));
-                    return;
-                }
-            };
+        // The picks follow the due date as typed when the menu opens.
+        presets_popover.connect_show(clone!(@weak presets_list, @weak due_entry, @weak remind_entry => move |popover| {
+            while let Some(child) = presets_list.first_child() {
+                presets_list.remove(&child);
+            }
+            let due = NaiveDate::parse_from_str(due_entry.text().trim(), "%Y-%m-%d").ok();
+            for (label, at) in reminder_presets(due, Local::now().naive_local()) {
+                let button = gtk::Button::with_label(&format!("{label} – {} {}", config::format_date(at.date()), at.format("%H:%M")));
+                button.add_css_class("flat");
+                button.connect_clicked(clone!(@weak popover, @weak remind_entry => move |_| {
+                    remind_entry.set_text(&at.format(REMIND_ENTRY_FORMAT).to_string());
+                    popover.popdown();
+                }));
+                presets_list.append(&button);
+            }
+        }));
+        remind_clear_btn.connect_clicked(clone!(@weak remind_entry => move |_| {
+            remind_entry.set_text("");
+        }));
 
-            if !response.status().is_success() {
-                let _ = sender.send(Err(format!("HTTP {}", response.status())));
+        let dialog_save = dialog.clone();
+        let state_for_save = Rc::clone(self);
+        let base_item = todo.clone();
+        let title_entry_save = title_entry.clone();
+        let project_entry_save = project_entry.clone();
+        let context_entry_save = context_entry.clone();
+        let start_entry_save = start_entry.clone();
+        let due_entry_save = due_entry.clone();
+        let remind_entry_save = remind_entry.clone();
+        let estimate_entry_save = estimate_entry.clone();
+        let depends_entry_save = depends_entry.clone();
+        let done_check_save = done_check.clone();
+        let comment_entry_save = comment_entry.clone();
+        let comment_row_save = comment_row.clone();
+        let recurrence_dropdown_save = recurrence_dropdown.clone();
+        save_btn.connect_clicked(move |_| {
+            let mut title_text = title_entry_save.text().trim().to_string();
+            if title_text.is_empty() {
+                state_for_save.show_error(&t("title_empty_error"));
                 return;
             }
 
-            let total_size = response.content_length().unwrap_or(0);
-            let mut downloaded = 0;
-            let mut buffer = [0; 32768]; // 32KB buffer
-            let mut last_reported_progress = 0.0;
-            
-            if let Some(parent) = path.parent() {
-                let _ = fs::create_dir_all(parent);
+            if comment_row_save.is_visible() {
+                let comment = comment_entry_save.text().trim().to_string();
+                if !comment.is_empty() {
+                    title_text = format!("{} ({})", title_text, comment);
+                }
             }
 
-            let mut file = match fs::File::create(&path) {
-                Ok(f) => f,
-                Err(e) => {
-                    let _ = sender.send(Err(e.to_string()));
-                    return;
-                }
+            let project_text = project_entry_save.text().trim().to_string();
+            let project_value = if project_text.is_empty() {
+                None
+            } else {
+                Some(project_text)
             };
 
-            use std::io::Write;
-            loop {
-                match response.read(&mut buffer) {
-                    Ok(0) => break,
-                    Ok(n) => {
-                        if let Err(e) = file.write_all(&buffer[..n]) {
-                            let _ = sender.send(Err(e.to_string()));
-                            return;
-                        }
-                        downloaded += n as u64;
-                        if total_size > 0 {
-                            let progress = downloaded as f64 / total_size as f64;
-                            // Only report progress if it changed by at least 0.1% or if we are done
-                            if progress - last_reported_progress >= 0.005 || progress >= 1.0 {
-                                let _ = sender.send(Ok(progress));
-                                last_reported_progress = progress;
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        let _ = sender.send(Err(e.to_string()));
+            let context_text = context_entry_save.text().trim().to_string();
+            let context_value = if context_text.is_empty() {
+                None
+            } else {
+                Some(context_text)
+            };
+
+            let due_text = due_entry_save.text().trim().to_string();
+            let due_value = if due_text.is_empty() {
+                None
+            } else {
+                match NaiveDate::parse_from_str(&due_text, "%Y-%m-%d") {
+                    Ok(date) => Some(date),
+                    Err(_) => {
+                        state_for_save.show_error(&t("invalid_date_error"));
                         return;
                     }
                 }
-            }
-            let _ = sender.send(Ok(1.0));
-        });
+            };
 
-        let pb_clone = progress_bar.clone();
-        let row_clone = switch_row.clone();
-        glib::timeout_add_local(std::time::Duration::from_millis(100), move || {
-            match receiver.try_recv() {
-                Ok(Ok(fraction)) => {
-                    if let Some(pb) = &pb_clone {
-                        pb.set_fraction(fraction);
-                    }
-                    if fraction >= 1.0 {
-                        state.show_info(&t("model_download_finished"));
-                        if let Some(pb) = &pb_clone {
-                            pb.set_visible(false);
-                        }
-                        if let Some(row) = &row_clone {
-                            row.set_sensitive(true);
-                        }
-                        return glib::ControlFlow::Break;
+            let start_text = start_entry_save.text().trim().to_string();
+            let start_value = if start_text.is_empty() {
+                None
+            } else {
+                match NaiveDate::parse_from_str(&start_text, "%Y-%m-%d") {
+                    Ok(date) => Some(date),
+                    Err(_) => {
+                        state_for_save.show_error(&t("invalid_date_error"));
+                        return;
                     }
-                    glib::ControlFlow::Continue
                 }
-                Ok(Err(e)) => {
-                    state.show_error(&format!("{}: {}", t("model_download_error"), e));
-                    if let Some(pb) = &pb_clone {
-                        pb.set_visible(false);
-                    }
-                    if let Some(row) = &row_clone {
-                        row.set_sensitive(true);
-                        row.set_active(false);
+            };
+
+            let remind_text = remind_entry_save.text().trim().to_string();
+            let remind_value = if remind_text.is_empty() {
+                None
+            } else {
+                match parse_reminder(&remind_text) {
+                    Some(at) => Some(at),
+                    None => {
+                        state_for_save.show_error(&t("invalid_reminder_error"));
+                        return;
                     }
-                    // Reset preference if download failed
-                    {
-                        let mut prefs = state.preferences.borrow_mut();
-                        prefs.use_whisper = false;
+                }
+            };
+
+            let estimate_text = estimate_entry_save.text().trim().to_string();
+            let estimate_value = if estimate_text.is_empty() {
+                None
+            } else {
+                match data::parse_estimate(&estimate_text) {
+                    Some(minutes) => Some(minutes),
+                    None => {
+                        state_for_save.show_error(&t("invalid_estimate_error"));
+                        return;
                     }
-                    state.persist_preferences();
-                    glib::ControlFlow::Break
                 }
-                Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
-                Err(std::sync::mpsc::TryRecvError::Disconnected) => glib::ControlFlow::Break,
+            };
+
+            let rec_values = ["", "daily", "weekly", "monthly"];
+            let rec_idx = recurrence_dropdown_save.selected() as usize;
+            let recurrence_value = rec_values
+                .get(rec_idx)
+                .map(|s| s.to_string())
+                .filter(|s| !s.is_empty());
+
+            let mut updated = base_item.clone();
+            updated.title = title_text;
+            updated.project = project_value.map(Name::from);
+            updated.context = context_value.map(Name::from);
+            updated.reference = reference.borrow().clone();
+            updated.start = start_value;
+            updated.due = due_value;
+            updated.remind = remind_value;
+            updated.estimate = estimate_value;
+            updated.depends_on = data::parse_dependencies(&depends_entry_save.text());
+            updated.recurrence = recurrence_value;
+            updated.done = done_check_save.is_active();
+
+            if let Err(err) = state_for_save.save_item(&base_item, &updated) {
+                state_for_save.show_error(&t("save_task_error").replace("{}", &describe_error(&err)));
+            } else {
+                dialog_save.close();
             }
         });
-    }
 
-    fn set_whisper_language(&self, language: String) {
-        {
-            let mut prefs = self.preferences.borrow_mut();
-            if prefs.whisper_language == language {
-                return;
-            }
-            prefs.whisper_language = language;
-        }
-        self.persist_preferences();
+        let comment_entry_close = comment_entry.clone();
+        let comment_row_close = comment_row.clone();
+        let close_btn_ref = close_with_comment_btn.clone();
+        let done_check_close = done_check.clone();
+        close_with_comment_btn.connect_clicked(move |_| {
+            comment_row_close.set_visible(true);
+            comment_entry_close.grab_focus();
+            done_check_close.set_active(true);
+            close_btn_ref.set_sensitive(false);
+        });
+
+        dialog.present();
     }
 
-    fn set_use_webdav(&self, use_webdav: bool) {
-        {
-            let mut prefs = self.preferences.borrow_mut();
-            if prefs.use_webdav == use_webdav {
-                return;
-            }
-            prefs.use_webdav = use_webdav;
+    /// Sorts by the chosen mode, with pinned tasks always on top.
+    fn sort_items(&self, items: &mut [TodoItem]) {
+        let collator = Collator::current();
+        match *self.sort_mode.borrow() {
+            SortMode::Topic => items.sort_by_cached_key(|item| project_order(&collator, item)),
+            SortMode::Location => items.sort_by_cached_key(|item| context_order(&collator, item)),
+            SortMode::Date => items.sort_by_cached_key(|item| due_order(&collator, item)),
         }
-        self.persist_preferences();
-        
-        if use_webdav {
-            let (_, url, path, user, pass) = self.get_webdav_prefs();
-            if let Some(u) = url {
-                data::set_backend_config(data::BackendConfig::WebDav {
-                    url: u,
-                    path: path,
-                    username: user,
-                    password: pass,
-                });
-            }
-        } else {
-            let path = data::todo_path();
-            data::set_backend_config(data::BackendConfig::Local(path));
+        if self.show_due_only() {
+            // Today's appointments lead, in the order they come up.
+            let today = Local::now().date_naive();
+            items.sort_by(|a, b| match (is_timed_on(a, today), is_timed_on(b, today)) {
+                (true, true) => a.due_at().cmp(&b.due_at()),
+                (true, false) => Ordering::Less,
+                (false, true) => Ordering::Greater,
+                (false, false) => Ordering::Equal,
+            });
         }
+        items.sort_by(compare_pinned);
+    }
 
-        if let Err(err) = self.reload() {
-             self.show_error(&t("load_data_error").replace("{}", &err.to_string()));
+    /// [`Self::group_label`] in the Today view, where tasks due at a set
+    /// time today get a group of their own once there are any (`timed`).
+    fn today_group_label(&self, mode: SortMode, item: &TodoItem, today: NaiveDate, timed: bool) -> Option<String> {
+        let pinned_group = item.pinned && mode != SortMode::Date;
+        match (timed, is_timed_on(item, today)) {
+            (true, true) if !pinned_group => Some(t("today_timed_group")),
+            (true, false) if mode == SortMode::Date => Some(t("today_any_time_group")),
+            _ => self.group_label(mode, item),
         }
     }
 
-    fn set_webdav_url(&self, url: String) {
-        {
-            let mut prefs = self.preferences.borrow_mut();
-            prefs.webdav_url = Some(url.clone());
+    fn group_label(&self, mode: SortMode, item: &TodoItem) -> Option<String> {
+        if item.pinned && mode != SortMode::Date {
+            return Some(t("pinned_group"));
         }
-        self.persist_preferences();
-        
-        let (use_webdav, _, path, user, pass) = self.get_webdav_prefs();
-        if use_webdav {
-             data::set_backend_config(data::BackendConfig::WebDav {
-                url: url,
-                path: path,
-                username: user,
-                password: pass,
-            });
+        match mode {
+            SortMode::Topic => Some(t("topic_group").replace(
+                "{}",
+                item.project
+                    .as_deref()
+                    .filter(|s| !s.is_empty())
+                    .unwrap_or(&t("no_project"))
+            )),
+            SortMode::Location => Some(t("location_group").replace(
+                "{}",
+                item.context
+                    .as_deref()
+                    .filter(|s| !s.is_empty())
+                    .unwrap_or(&t("no_location"))
+            )),
+            SortMode::Date => None,
         }
     }
 
-    fn set_webdav_path(&self, path: String) {
-        {
-            let mut prefs = self.preferences.borrow_mut();
-            prefs.webdav_path = Some(path.clone());
+    fn show_info(&self, message: &str) {
+        let toast = adw::Toast::builder().title(message).build();
+        self.overlay.add_toast(toast);
+    }
+
+    fn show_error(&self, message: &str) {
+        let toast = adw::Toast::builder()
+            .title(message)
+            .priority(adw::ToastPriority::High)
+            .build();
+        // A change that could not be saved stays on offer until dismissed.
+        if data::has_failed_write() {
+            toast.set_button_label(Some(&t("retry_now")));
+            toast.set_action_name(Some("app.retry-save"));
+            toast.set_timeout(0);
         }
-        self.persist_preferences();
-        
-        let (use_webdav, url, _, user, pass) = self.get_webdav_prefs();
-        if use_webdav {
-            if let Some(u) = url {
-                data::set_backend_config(data::BackendConfig::WebDav {
-                    url: u,
-                    path: Some(path),
-                    username: user,
-                    password: pass,
-                });
-            }
+        self.overlay.add_toast(toast);
+    }
+
+    /// "Retry now" on the toast of a save that failed on every try.
+    fn retry_failed_save(&self) {
+        if let Err(err) = data::retry_failed_write() {
+            self.sync_save_status();
+            self.show_error(&t("retry_save_error").replace("{}", &describe_error(&err)));
+            return;
+        }
+        match self.reload() {
+            Ok(()) => self.show_info(&t("retry_save_done")),
+            Err(err) => self.show_error(&t("reload_error").replace("{}", &describe_error(&err))),
         }
     }
 
-    fn set_webdav_username(&self, username: String) {
-        {
-            let mut prefs = self.preferences.borrow_mut();
-            prefs.webdav_username = Some(username.clone());
+    /// Switches to the database named in freshly imported preferences.
+    fn apply_imported_backend(self: &Rc<Self>) {
+        let prefs = self.preferences.borrow().clone();
+        match (prefs.use_webdav, prefs.webdav_url) {
+            (true, Some(url)) => data::set_backend_config(data::BackendConfig::WebDav {
+                url,
+                path: prefs.webdav_path,
+                username: prefs.webdav_username,
+                password: prefs.webdav_password,
+            }),
+            _ => match prefs.db_path {
+                Some(location) => data::set_database(&location),
+                None => return,
+            },
         }
-        self.persist_preferences();
+        self.install_monitor();
+    }
 
-        let (use_webdav, url, path, _, pass) = self.get_webdav_prefs();
-        if use_webdav {
-            if let Some(u) = url {
-                data::set_backend_config(data::BackendConfig::WebDav {
-                    url: u,
-                    path: path,
-                    username: Some(username),
-                    password: pass,
-                });
+    /// Lets the user pick a local database. The file dialog goes through the
+    /// FileChooser portal, so inside Flatpak the file is granted to us.
+    fn choose_database(self: &Rc<Self>) {
+        let Some(parent) = self.window.upgrade() else {
+            self.show_error(&t("no_window"));
+            return;
+        };
+        let filter = FileFilter::new();
+        filter.set_name(Some("Markdown"));
+        filter.add_mime_type("text/markdown");
+        filter.add_suffix("md");
+        let filters = gio::ListStore::new::<FileFilter>();
+        filters.append(&filter);
+        let dialog = FileDialog::builder()
+            .title(t("open_database"))
+            .filters(&filters)
+            .build();
+
+        let state = Rc::clone(self);
+        dialog.open(Some(&parent), gio::Cancellable::NONE, move |result| {
+            let Ok(file) = result else {
+                return;
+            };
+            // GVfs locations also have a FUSE path, but GIO reaches them without it.
+            match file.path() {
+                Some(path) if file.has_uri_scheme("file") => state.open_database(path),
+                _ => state.open_location(&file.uri()),
             }
-        }
+        });
     }
 
-    fn set_webdav_password(&self, password: String) {
+    /// Makes the local file at `path` the active database and remembers it.
+    fn open_database(self: &Rc<Self>, path: PathBuf) {
+        let path = std::path::absolute(&path).unwrap_or(path);
+        if matches!(data::get_backend_config(), data::BackendConfig::Local(current) if current == path) {
+            return;
+        }
         {
             let mut prefs = self.preferences.borrow_mut();
-            prefs.webdav_password = Some(password.clone());
+            prefs.use_webdav = false;
+            prefs.db_path = Some(path.to_string_lossy().into_owned());
+            prefs.db_document = sandbox::document_id(&path);
         }
         self.persist_preferences();
+        data::set_todo_path(path.clone());
+        tracing::info!(path = %path.display(), "opening database");
 
-        let (use_webdav, url, path, user, _) = self.get_webdav_prefs();
-        if use_webdav {
-            if let Some(u) = url {
-                data::set_backend_config(data::BackendConfig::WebDav {
-                    url: u,
-                    path: path,
-                    username: user,
-                    password: Some(password),
-                });
-            }
+        self.install_monitor();
+        match self.reload() {
+            Ok(_) => self.show_info(&t("using_file").replace("{}", &path.display().to_string())),
+            Err(err) => self.show_error(&t("load_data_error").replace("{}", &describe_error(&err))),
         }
     }
 
-    fn get_webdav_prefs(&self) -> (bool, Option<String>, Option<String>, Option<String>, Option<String>) {
-        let prefs = self.preferences.borrow();
-        (prefs.use_webdav, prefs.webdav_url.clone(), prefs.webdav_path.clone(), prefs.webdav_username.clone(), prefs.webdav_password.clone())
+    /// Creates a database in [`data::standard_todo_path`] and opens it.
+    fn create_standard_database(self: &Rc<Self>) -> bool {
+        let Some(path) = data::standard_todo_path() else {
+            self.show_error(&t("no_data_dir"));
+            return false;
+        };
+        if let Err(err) = data::create_database(&path) {
+            self.show_error(&describe_error(&err));
+            return false;
+        }
+        self.open_database(path);
+        true
     }
 
+    /// Lets the user pick a [`StarterTemplate`] and a place for the new
+    /// file, then opens it.
+    fn show_new_database_dialog(self: &Rc<Self>) {
+        let Some(parent) = self.window.upgrade() else {
+            self.show_error(&t("no_window"));
+            return;
+        };
 
-    fn set_sort_mode(&self, mode: SortMode) {
-        {
-            let mut current = self.sort_mode.borrow_mut();
-            if *current == mode {
-                return;
-            }
-            *current = mode;
-        }
+        let dialog = adw::Window::builder()
+            .title(t("starter_new_database"))
+            .transient_for(&parent)
+            .modal(true)
+            .default_width(440)
+            .build();
+        dialog.set_destroy_with_parent(true);
 
-        {
-            let mut prefs = self.preferences.borrow_mut();
-            prefs.sort_mode = Some(mode.as_key().to_string());
+        let rows = gtk::ListBox::new();
+        rows.add_css_class("boxed-list");
+        rows.set_selection_mode(gtk::SelectionMode::None);
+        for template in StarterTemplate::ALL {
+            let row = adw::ActionRow::builder()
+                .title(template.label())
+                .subtitle(template.description())
+                .activatable(true)
+                .build();
+            row.add_suffix(&gtk::Image::from_icon_name("go-next-symbolic"));
+            let state = Rc::clone(self);
+            row.connect_activated(clone!(@weak dialog, @weak parent => move |_| {
+                dialog.close();
+                let filter = FileFilter::new();
+                filter.set_name(Some("Markdown"));
+                filter.add_suffix("md");
+                let filters = gio::ListStore::new::<FileFilter>();
+                filters.append(&filter);
+                let file_dialog = FileDialog::builder()
+                    .title(template.label())
+                    .initial_name("todo.md")
+                    .filters(&filters)
+                    .build();
+                let state = Rc::clone(&state);
+                file_dialog.save(Some(&parent), gio::Cancellable::NONE, move |result| {
+                    let Some(path) = result.ok().and_then(|file| file.path()) else {
+                        return;
+                    };
+                    match starter::create(&path, template) {
+                        Ok(()) => state.open_database(path),
+                        Err(err) => state.show_error(&describe_error(&err)),
+                    }
+                });
+            }));
+            rows.append(&row);
         }
 
-        self.persist_preferences();
+        let hint = gtk::Label::builder()
+            .label(t("starter_new_database_desc"))
+            .wrap(true)
+            .xalign(0.0)
+            .build();
+        hint.add_css_class("dim-label");
 
-        self.repopulate_store();
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 12);
+        content.set_margin_top(16);
+        content.set_margin_bottom(16);
+        content.set_margin_start(20);
+        content.set_margin_end(20);
+        content.append(&hint);
+        content.append(&rows);
+
+        let toolbar = adw::ToolbarView::new();
+        toolbar.add_top_bar(&adw::HeaderBar::new());
+        toolbar.set_content(Some(&content));
+        dialog.set_content(Some(&toolbar));
+        dialog.present();
     }
 
-    fn repopulate_store(&self) {
-        let mut selected_key = None;
-        let mut scroll_pos = None;
+    /// Onboarding at the first launch: create a database in the standard
+    /// place, open an existing one, import from another app or connect to
+    /// a server, instead of an empty window without a file.
+    fn show_welcome_dialog(self: &Rc<Self>) {
+        let Some(parent) = self.window.upgrade() else {
+            self.show_error(&t("no_window"));
+            return;
+        };
 
-        if let Some(scrolled) = self.scrolled_window.borrow().as_ref() {
-            let adj = scrolled.vadjustment();
-            scroll_pos = Some(adj.value());
-        }
+        let dialog = adw::Window::builder()
+            .title(t("welcome"))
+            .transient_for(&parent)
+            .modal(true)
+            .default_width(480)
+            .build();
+        dialog.set_destroy_with_parent(true);
 
-        if let Some(list_view) = self.list_view.borrow().as_ref() {
-            if let Some(model) = list_view.model() {
-                if let Ok(selection) = model.downcast::<gtk::SingleSelection>() {
-                    let pos = selection.selected();
-                    if pos != gtk::INVALID_LIST_POSITION {
-                        if let Some(obj) = self.store.item(pos) {
-                            if let Ok(boxed) = obj.downcast::<BoxedAnyObject>() {
-                                let entry = boxed.borrow::<ListEntry>();
-                                if let ListEntry::Item(todo) = &*entry {
-                                    selected_key = Some(todo.key.clone());
-                                }
-                            }
-                        }
-                    }
+        let rows = gtk::ListBox::new();
+        rows.add_css_class("boxed-list");
+        rows.set_selection_mode(gtk::SelectionMode::None);
+
+        let standard = data::standard_todo_path().map(|path| path.display().to_string()).unwrap_or_default();
+        let create_row = adw::ActionRow::builder()
+            .title(t("welcome_create"))
+            .subtitle(standard)
+            .activatable(true)
+            .build();
+        create_row.add_prefix(&gtk::Image::from_icon_name("document-new-symbolic"));
+        create_row.add_suffix(&gtk::Image::from_icon_name("go-next-symbolic"));
+        rows.append(&create_row);
+
+        let open_row = adw::ActionRow::builder()
+            .title(t("welcome_open"))
+            .activatable(true)
+            .build();
+        open_row.add_prefix(&gtk::Image::from_icon_name("document-open-symbolic"));
+        open_row.add_suffix(&gtk::Image::from_icon_name("go-next-symbolic"));
+        rows.append(&open_row);
+
+        let import_row = adw::ExpanderRow::builder()
+            .title(t("welcome_import"))
+            .subtitle(t("welcome_import_desc"))
+            .build();
+        import_row.add_prefix(&gtk::Image::from_icon_name("folder-download-symbolic"));
+        for format in ImportFormat::ALL {
+            let format_row = adw::ActionRow::builder()
+                .title(format.label())
+                .activatable(true)
+                .build();
+            let state = Rc::clone(self);
+            format_row.connect_activated(clone!(@weak dialog => move |_| {
+                dialog.close();
+                if state.create_standard_database() {
+                    state.import_from(format);
                 }
-            }
+            }));
+            import_row.add_row(&format_row);
         }
+        rows.append(&import_row);
 
-        let search_term = self.search_term.borrow().to_lowercase();
-        let mut items = self.cached_items.borrow().clone();
-        self.sort_items(&mut items);
-        self.store.remove_all();
-        
-        let include_done = self.show_completed();
-        let due_only = self.show_due_only();
-        let today = Local::now().date_naive();
+        let remote_row = adw::ActionRow::builder()
+            .title(t("welcome_remote"))
+            .subtitle(t("welcome_remote_desc"))
+            .activatable(true)
+            .build();
+        remote_row.add_prefix(&gtk::Image::from_icon_name("network-server-symbolic"));
+        remote_row.add_suffix(&gtk::Image::from_icon_name("go-next-symbolic"));
+        rows.append(&remote_row);
 
-        if search_term.is_empty() {
-            let mode = *self.sort_mode.borrow();
-            let mut last_group: Option<String> = None;
-            for item in items.into_iter().filter(|todo| {
-                let status_ok = include_done || !todo.done;
-                let due_ok = if !due_only {
-                    true
-                } else {
-                    todo.due.map(|d| d <= today).unwrap_or(true)
-                };
-                status_ok && due_ok
-            }) {
-                if let Some(label) = self.group_label(mode, &item) {
-                    if last_group.as_ref() != Some(&label) {
-                        self.store
-                            .append(&BoxedAnyObject::new(ListEntry::Header(label.clone())));
-                        last_group = Some(label);
-                    }
-                }
-                self.store.append(&BoxedAnyObject::new(ListEntry::Item(item)));
-            }
-        } else {
-            // 1. Suchergebnisse in aktueller Liste
-            let current_list_results: Vec<_> = items.iter().filter(|todo| {
-                let status_ok = include_done || !todo.done;
-                let due_ok = if !due_only {
-                    true
-                } else {
-                    todo.due.map(|d| d <= today).unwrap_or(true)
-                };
-                status_ok && due_ok && todo.title.to_lowercase().contains(&search_term)
-            }).cloned().collect();
+        let state = Rc::clone(self);
+        create_row.connect_activated(clone!(@weak dialog => move |_| {
+            dialog.close();
+            state.create_standard_database();
+        }));
+        let state = Rc::clone(self);
+        open_row.connect_activated(clone!(@weak dialog => move |_| {
+            dialog.close();
+            state.choose_database();
+        }));
+        let state = Rc::clone(self);
+        remote_row.connect_activated(clone!(@weak dialog => move |_| {
+            dialog.close();
+            state.show_settings_dialog(None);
+        }));
 
-            if !current_list_results.is_empty() {
-                self.store.append(&BoxedAnyObject::new(ListEntry::Header(t("search_results_current"))));
-                for item in current_list_results.clone() {
-                    self.store.append(&BoxedAnyObject::new(ListEntry::Item(item)));
-                }
-            }
+        let page = adw::StatusPage::builder()
+            .icon_name("me.dumke.Reinschrift")
+            .title(t("welcome_title"))
+            .description(t("welcome_desc"))
+            .child(&rows)
+            .build();
+        let toolbar = adw::ToolbarView::new();
+        toolbar.add_top_bar(&adw::HeaderBar::new());
+        toolbar.set_content(Some(&page));
+        dialog.set_content(Some(&toolbar));
+        dialog.present();
+    }
 
-            // 2. Suchergebnisse bei allen offenen Todos
-            let open_results: Vec<_> = items.iter().filter(|todo| {
-                !todo.done && todo.title.to_lowercase().contains(&search_term)
-            }).cloned().collect();
-            
-            let open_results_filtered: Vec<_> = open_results.into_iter().filter(|todo| {
-                !current_list_results.iter().any(|c| c.key.line_index == todo.key.line_index && c.key.marker == todo.key.marker)
-            }).collect();
+    /// Asks for a URI such as `sftp://nas/todo.md` and opens it.
+    fn show_open_location_dialog(self: &Rc<Self>) {
+        let Some(parent) = self.window.upgrade() else {
+            self.show_error(&t("no_window"));
+            return;
+        };
+        let current = match data::get_backend_config() {
+            data::BackendConfig::Uri(uri) => uri,
+            _ => String::new(),
+        };
+        let uri_row = adw::EntryRow::builder().title(t("location_uri")).text(current).build();
+        let uri_list = gtk::ListBox::new();
+        uri_list.add_css_class("boxed-list");
+        uri_list.set_selection_mode(gtk::SelectionMode::None);
+        uri_list.append(&uri_row);
+
+        let hint = gtk::Label::builder()
+            .label(t("open_location_desc"))
+            .wrap(true)
+            .xalign(0.0)
+            .build();
+        hint.add_css_class("dim-label");
 
-            if !open_results_filtered.is_empty() {
-                self.store.append(&BoxedAnyObject::new(ListEntry::Header(t("search_results_open"))));
-                for item in open_results_filtered {
-                    self.store.append(&BoxedAnyObject::new(ListEntry::Item(item)));
-                }
-            }
+        let open_btn = gtk::Button::with_label(&t("open"));
+        open_btn.add_css_class("suggested-action");
+        open_btn.set_halign(gtk::Align::End);
 
-            // 3. Suchergebnisse bei den abgeschlossenen Todos
-            let done_results: Vec<_> = items.iter().filter(|todo| {
-                todo.done && todo.title.to_lowercase().contains(&search_term)
-            }).cloned().collect();
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 12);
+        content.set_margin_top(16);
+        content.set_margin_bottom(16);
+        content.set_margin_start(20);
+        content.set_margin_end(20);
+        content.append(&uri_list);
+        content.append(&hint);
+        content.append(&open_btn);
 
-            let done_results_filtered: Vec<_> = done_results.into_iter().filter(|todo| {
-                !current_list_results.iter().any(|c| c.key.line_index == todo.key.line_index && c.key.marker == todo.key.marker)
-            }).collect();
+        let toolbar = adw::ToolbarView::new();
+        toolbar.add_top_bar(&adw::HeaderBar::new());
+        toolbar.set_content(Some(&content));
+        let dialog = adw::Window::builder()
+            .title(t("open_location"))
+            .transient_for(&parent)
+            .modal(true)
+            .default_width(440)
+            .content(&toolbar)
+            .build();
+        dialog.set_destroy_with_parent(true);
 
-            if !done_results_filtered.is_empty() {
-                self.store.append(&BoxedAnyObject::new(ListEntry::Header(t("search_results_done"))));
-                for item in done_results_filtered {
-                    self.store.append(&BoxedAnyObject::new(ListEntry::Item(item)));
-                }
+        let state = Rc::clone(self);
+        open_btn.connect_clicked(clone!(@weak dialog, @weak uri_row => move |_| {
+            let uri = uri_row.text().trim().to_string();
+            if !data::is_remote_uri(&uri) {
+                state.show_error(&t("location_invalid").replace("{}", &uri));
+                return;
             }
-        }
+            state.open_location(&uri);
+            dialog.close();
+        }));
+        uri_row.connect_entry_activated(clone!(@weak open_btn => move |_| {
+            open_btn.emit_clicked();
+        }));
+        dialog.present();
+    }
 
-        let mut restored = false;
+    /// Makes the GIO location `uri` the active database and remembers it.
+    fn open_location(self: &Rc<Self>, uri: &str) {
+        if matches!(data::get_backend_config(), data::BackendConfig::Uri(current) if current == uri) {
+            return;
+        }
+        {
+            let mut prefs = self.preferences.borrow_mut();
+            prefs.use_webdav = false;
+            prefs.db_path = Some(uri.to_string());
+            prefs.db_document = None;
+        }
+        self.persist_preferences();
+        data::set_database(uri);
+        tracing::info!(%uri, "opening database location");
+        self.mount_and_reload();
+    }
 
-        if let Some(key) = selected_key {
-            if let Some(list_view) = self.list_view.borrow().as_ref() {
-                if let Some(model) = list_view.model() {
-                    if let Ok(selection) = model.downcast::<gtk::SingleSelection>() {
-                        for i in 0..self.store.n_items() {
-                            if let Some(obj) = self.store.item(i) {
-                                if let Ok(boxed) = obj.downcast::<BoxedAnyObject>() {
-                                    let entry = boxed.borrow::<ListEntry>();
-                                    if let ListEntry::Item(todo) = &*entry {
-                                        if todo.key == key {
-                                            selection.set_selected(i);
-                                            list_view.scroll_to(i, gtk::ListScrollFlags::NONE, None);
-                                            restored = true;
-                                            break;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
+    /// Mounts the share of a remote database if needed, asking for a
+    /// password through the usual GTK prompt, then loads it.
+    fn mount_and_reload(self: &Rc<Self>) {
+        let finish = clone!(@weak self as state => move || {
+            state.install_monitor();
+            match state.reload() {
+                Ok(_) => {
+                    state.show_info(&t("using_file").replace("{}", &data::database_location()));
+                    state.run_daily_passes();
                 }
+                Err(err) => state.show_error(&t("load_data_error").replace("{}", &describe_error(&err))),
             }
-        }
-
-        if !restored {
-            if let Some(pos) = scroll_pos {
-                if let Some(scrolled) = self.scrolled_window.borrow().as_ref() {
-                    let adj = scrolled.vadjustment();
-                    glib::idle_add_local(move || {
-                        let max = (adj.upper() - adj.page_size()).max(0.0);
-                        adj.set_value(pos.min(max));
-                        glib::ControlFlow::Break
-                    });
+        });
+        let Some(file) = unmounted_database() else {
+            finish();
+            return;
+        };
+        let operation = gtk::MountOperation::new(self.window.upgrade().as_ref());
+        let state = Rc::downgrade(self);
+        file.mount_enclosing_volume(gio::MountMountFlags::NONE, Some(&operation), gio::Cancellable::NONE, move |result| {
+            match result {
+                Ok(()) => finish(),
+                Err(err) if err.matches(gio::IOErrorEnum::AlreadyMounted) => finish(),
+                Err(err) => {
+                    if let Some(state) = state.upgrade() {
+                        state.show_error(&t("mount_error").replace("{}", &err.to_string()));
+                    }
+                    // Work from the offline copy until the share is back.
+                    if cache::has_copy() {
+                        finish();
+                    }
                 }
             }
-        }
-    }
-
-    fn persist_preferences(&self) {
-        let prefs = self.preferences.borrow().clone();
-        if let Err(err) = write_preferences(&prefs) {
-            eprintln!("{}: {err}", t("save_settings_error"));
-        }
+        });
     }
 
-    fn save_item(&self, updated: &TodoItem) -> Result<()> {
-        data::update_todo_details(updated)?;
-        self.reload()?;
-        self.show_info(&t("updated_task").replace("{}", &updated.title));
-        Ok(())
+    /// Tasks currently shown in the list, in display order.
+    fn visible_items(&self) -> Vec<TodoItem> {
+        let entries = self.store();
+        (0..entries.n_items())
+            .filter_map(|i| entries.item(i))
+            .filter_map(|obj| list_entry_todo(&obj))
+            .collect()
     }
 
-    fn toggle_recording(self: &Rc<Self>, voice_btn: &gtk::Button, entry: &gtk::Entry) {
-        if self.is_recording.load(AtomicOrdering::SeqCst) {
-            self.is_recording.store(false, AtomicOrdering::SeqCst);
-            voice_btn.remove_css_class("destructive-action");
-            voice_btn.set_icon_name("audio-input-microphone-symbolic");
-            return;
-        }
-
-        let model_path = self.whisper_model_path();
-        if !model_path.exists() {
-            self.show_error(&t("model_not_found"));
+    /// Imports the tasks of another database that are missing here and
+    /// reports what was added.
+    fn merge_file(self: &Rc<Self>) {
+        let Some(parent) = self.window.upgrade() else {
+            self.show_error(&t("no_window"));
             return;
-        }
-
-        self.is_recording.store(true, AtomicOrdering::SeqCst);
-        voice_btn.add_css_class("destructive-action");
-        voice_btn.set_icon_name("media-record-symbolic");
-
-        let is_recording = self.is_recording.clone();
-        let (sender, receiver) = std::sync::mpsc::channel::<VoiceMsg>();
-        
-        {
-            let state_clone = Rc::clone(self);
-            let voice_btn_clone = voice_btn.clone();
-            let entry_clone = entry.clone();
-            
-            glib::timeout_add_local(std::time::Duration::from_millis(100), move || {
-                let mut finished = false;
-                while let Ok(msg) = receiver.try_recv() {
-                    match msg {
-                        VoiceMsg::Error(e) => {
-                            state_clone.show_error(&e);
-                            voice_btn_clone.remove_css_class("destructive-action");
-                            voice_btn_clone.remove_css_class("pulse");
-                            voice_btn_clone.set_icon_name("audio-input-microphone-symbolic");
-                            state_clone.is_recording.store(false, AtomicOrdering::SeqCst);
-                            finished = true;
-                        }
-                        VoiceMsg::Transcription(text) => {
-                            let current = entry_clone.text();
-                            if current.is_empty() {
-                                entry_clone.set_text(&text);
-                            } else {
-                                entry_clone.set_text(&format!("{} {}", current, text));
-                            }
-                        }
-                        VoiceMsg::Transcribing => {
-                            voice_btn_clone.remove_css_class("destructive-action");
-                            voice_btn_clone.add_css_class("pulse");
-                            voice_btn_clone.set_icon_name("audio-input-microphone-symbolic");
-                        }
-                        VoiceMsg::Finished => {
-                            voice_btn_clone.remove_css_class("destructive-action");
-                            voice_btn_clone.remove_css_class("pulse");
-                            voice_btn_clone.set_icon_name("audio-input-microphone-symbolic");
-                            state_clone.is_recording.store(false, AtomicOrdering::SeqCst);
-                            finished = true;
-                        }
-                    }
-                }
-                if finished {
-                    glib::ControlFlow::Break
-                } else {
-                    glib::ControlFlow::Continue
-                }
-            });
-        }
-
-        let language = self.whisper_language();
-
-        std::thread::spawn(move || {
-            let host = cpal::default_host();
-            let device = match host.default_input_device() {
-                Some(d) => d,
-                None => {
-                    let _ = sender.send(VoiceMsg::Error("No input device found".to_string()));
-                    return;
-                }
-            };
+        };
+        let filter = FileFilter::new();
+        filter.set_name(Some("Markdown"));
+        filter.add_mime_type("text/markdown");
+        filter.add_suffix("md");
+        let filters = gio::ListStore::new::<FileFilter>();
+        filters.append(&filter);
+        let dialog = FileDialog::builder()
+            .title(t("merge_file"))
+            .filters(&filters)
+            .build();
 
-            let config = match device.default_input_config() {
-                Ok(c) => c,
-                Err(e) => {
-                    let _ = sender.send(VoiceMsg::Error(format!("Input config error: {}", e)));
-                    return;
-                }
+        let state = Rc::clone(self);
+        dialog.open(Some(&parent), gio::Cancellable::NONE, move |result| {
+            let Some(path) = result.ok().and_then(|file| file.path()) else {
+                return;
             };
-
-            let audio_data = Arc::new(Mutex::new(Vec::new()));
-            let audio_data_clone = audio_data.clone();
-
-            let stream = match device.build_input_stream(
-                &config.clone().into(),
-                move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                    let mut buffer = audio_data_clone.lock().unwrap();
-                    buffer.extend_from_slice(data);
-                },
-                |_| {},
-                None,
-            ) {
-                Ok(s) => s,
-                Err(e) => {
-                    let _ = sender.send(VoiceMsg::Error(format!("Stream error: {}", e)));
+            let report = match import::merge_file(&path) {
+                Ok(report) => report,
+                Err(err) => {
+                    state.show_error(&t("merge_error").replace("{}", &describe_error(&err)));
                     return;
                 }
             };
-
-            if let Err(e) = stream.play() {
-                let _ = sender.send(VoiceMsg::Error(format!("Stream play error: {}", e)));
-                return;
+            if let Err(err) = state.reload() {
+                state.show_error(&t("reload_error").replace("{}", &describe_error(&err)));
             }
 
-            while is_recording.load(AtomicOrdering::SeqCst) {
-                std::thread::sleep(std::time::Duration::from_millis(100));
+            const LISTED: usize = 15;
+            let mut detail = t("merge_summary")
+                .replacen("{}", &report.added.len().to_string(), 1)
+                .replacen("{}", &report.updated.to_string(), 1)
+                .replacen("{}", &report.duplicates.to_string(), 1);
+            for item in report.added.iter().take(LISTED) {
+                detail.push_str(&format!("\n• {}", item.title));
             }
-
-            drop(stream);
-
-            let samples = audio_data.lock().unwrap().clone();
-            if samples.is_empty() {
-                let _ = sender.send(VoiceMsg::Finished);
-                return;
+            if report.added.len() > LISTED {
+                detail.push_str(&format!("\n{}", t("merge_more").replace("{}", &(report.added.len() - LISTED).to_string())));
+            }
+            let alert = AlertDialog::builder().modal(true).build();
+            alert.set_message(&t("merge_done"));
+            alert.set_detail(&detail);
+            if let Some(window) = state.window.upgrade() {
+                alert.show(Some(&window));
             }
+        });
+    }
 
-            // Convert to mono if necessary
-            let channels = config.channels() as usize;
-            let mono_samples = if channels > 1 {
-                let mut mono = Vec::with_capacity(samples.len() / channels);
-                for chunk in samples.chunks_exact(channels) {
-                    let sum: f32 = chunk.iter().sum();
-                    mono.push(sum / channels as f32);
-                }
-                mono
-            } else {
-                samples
-            };
+    /// Open tasks "What should I do now?" chooses from: the listed ones,
+    /// narrowed to the selected tab in the context view.
+    fn suggestion_candidates(&self) -> Vec<TodoItem> {
+        let tab = match self.view_mode() {
+            ViewMode::Contexts => self
+                .context_stack
+                .borrow()
+                .as_ref()
+                .and_then(|stack| stack.visible_child_name())
+                .map(|name| name.to_string()),
+            _ => None,
+        };
+        self.visible_items()
+            .into_iter()
+            .filter(|todo| !todo.done)
+            .filter(|todo| tab.as_ref().is_none_or(|tab| *tab == context_tab_name(todo.context.as_deref())))
+            .collect()
+    }
 
-            // Resample to 16kHz if necessary (Whisper requirement)
-            let sample_rate = config.sample_rate().0;
-            let samples_16k = if sample_rate != 16000 {
-                let mut resampled = Vec::new();
-                let ratio = sample_rate as f32 / 16000.0;
-                let mut i = 0.0;
-                while i < mono_samples.len() as f32 {
-                    resampled.push(mono_samples[i as usize]);
-                    i += ratio;
-                }
-                resampled
-            } else {
-                mono_samples
-            };
+    fn suggestion_skips(&self, todo: &TodoItem) -> u32 {
+        self.preferences
+            .borrow()
+            .suggestion_skips
+            .get(&history::task_id(todo))
+            .copied()
+            .unwrap_or(0)
+    }
 
-            println!("Starting transcription ({} samples, {}Hz, language: {})", samples_16k.len(), sample_rate, language);
-            let _ = sender.send(VoiceMsg::Transcribing);
+    /// Remembers a skip so the task ranks lower next time. Counts of tasks
+    /// that no longer exist are dropped on the way.
+    fn record_suggestion_skip(&self, todo: &TodoItem) {
+        let known: HashSet<String> = self.cached_items.borrow().iter().map(history::task_id).collect();
+        {
+            let mut prefs = self.preferences.borrow_mut();
+            prefs.suggestion_skips.retain(|id, _| known.contains(id));
+            *prefs.suggestion_skips.entry(history::task_id(todo)).or_insert(0) += 1;
+        }
+        self.persist_preferences();
+    }
 
-            let ctx = match WhisperContext::new_with_params(
-                &model_path.to_string_lossy(),
-                WhisperContextParameters::default(),
-            ) {
-                Ok(c) => c,
-                Err(e) => {
-                    let _ = sender.send(VoiceMsg::Error(format!("Whisper error: {}", e)));
-                    return;
-                }
-            };
+    fn forget_suggestion_skips(&self, todo: &TodoItem) {
+        let removed = self.preferences.borrow_mut().suggestion_skips.remove(&history::task_id(todo));
+        if removed.is_some() {
+            self.persist_preferences();
+        }
+    }
 
-            let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
-            params.set_n_threads(4);
-            if language != "auto" {
-                params.set_language(Some(&language));
-            } else {
-                params.set_language(None);
-            }
+    /// Shows the best next task in a card. Skipping shows the runner-up and
+    /// counts against the skipped task; accepting selects it in the list.
+    fn show_suggestion(self: &Rc<Self>) {
+        let Some(parent) = self.window.upgrade() else {
+            self.show_error(&t("no_window"));
+            return;
+        };
+        let candidates = self.suggestion_candidates();
+        if candidates.is_empty() {
+            self.show_info(&t("suggest_empty"));
+            return;
+        }
 
-            let mut state_whisper = ctx.create_state().expect("failed to create state");
-            if let Err(e) = state_whisper.full(params, &samples_16k) {
-                let _ = sender.send(VoiceMsg::Error(format!("Transcription error: {}", e)));
-                return;
-            }
+        let card = adw::StatusPage::builder().icon_name("task-due-symbolic").build();
+        let accept_btn = gtk::Button::with_label(&t("suggest_accept"));
+        accept_btn.add_css_class("suggested-action");
+        accept_btn.add_css_class("pill");
+        let skip_btn = gtk::Button::with_label(&t("focus_skip"));
+        skip_btn.add_css_class("pill");
+        let buttons = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+        buttons.set_halign(gtk::Align::Center);
+        buttons.append(&skip_btn);
+        buttons.append(&accept_btn);
+        card.set_child(Some(&buttons));
+
+        let toolbar = adw::ToolbarView::new();
+        toolbar.add_top_bar(&adw::HeaderBar::new());
+        toolbar.set_content(Some(&card));
+        let dialog = adw::Window::builder()
+            .title(t("suggest_next"))
+            .transient_for(&parent)
+            .modal(true)
+            .default_width(420)
+            .content(&toolbar)
+            .build();
+        dialog.set_destroy_with_parent(true);
 
-            let num_segments = state_whisper.full_n_segments().expect("failed to get segments");
-            let mut result_text = String::new();
-            for i in 0..num_segments {
-                if let Ok(segment) = state_whisper.full_get_segment_text(i) {
-                    result_text.push_str(&segment);
+        // Tasks skipped while the card is open are left out until it closes.
+        let skipped: Rc<RefCell<Vec<data::TodoKey>>> = Rc::new(RefCell::new(Vec::new()));
+        let current: Rc<RefCell<Option<TodoItem>>> = Rc::new(RefCell::new(None));
+        let show_next = {
+            let state = Rc::clone(self);
+            let skipped = Rc::clone(&skipped);
+            let current = Rc::clone(&current);
+            let card = card.clone();
+            let buttons = buttons.clone();
+            move || {
+                let today = Local::now().date_naive();
+                let skipped = skipped.borrow();
+                let remaining: Vec<TodoItem> =
+                    candidates.iter().filter(|todo| !skipped.contains(&todo.key)).cloned().collect();
+                let next = suggest::next_action(&remaining, today, |todo| state.suggestion_skips(todo)).cloned();
+                match &next {
+                    Some(todo) => {
+                        card.set_title(&todo.title);
+                        let description = format_metadata(todo, &state.metadata_fields());
+                        card.set_description((!description.is_empty()).then_some(description.as_str()));
+                    }
+                    None => {
+                        card.set_title(&t("suggest_exhausted"));
+                        card.set_description(None);
+                    }
                 }
+                buttons.set_visible(next.is_some());
+                *current.borrow_mut() = next;
             }
+        };
+        show_next();
+        let show_next = Rc::new(show_next);
 
-            let final_text = result_text.trim().to_string();
-            if !final_text.is_empty() {
-                let _ = sender.send(VoiceMsg::Transcription(final_text));
-            }
-            let _ = sender.send(VoiceMsg::Finished);
+        let state = Rc::clone(self);
+        let skip_current = Rc::clone(&current);
+        let skip_next = Rc::clone(&show_next);
+        skip_btn.connect_clicked(move |_| {
+            let Some(todo) = skip_current.borrow().clone() else {
+                return;
+            };
+            state.record_suggestion_skip(&todo);
+            skipped.borrow_mut().push(todo.key);
+            skip_next();
         });
+
+        let state = Rc::clone(self);
+        accept_btn.connect_clicked(clone!(@weak dialog => move |_| {
+            let Some(todo) = current.borrow().clone() else {
+                return;
+            };
+            state.forget_suggestion_skips(&todo);
+            state.select_item(&todo.key);
+            dialog.close();
+        }));
+        dialog.present();
     }
 
-    fn open_entry_at(self: &Rc<Self>, position: u32) {
-        let Some(obj) = self.store.item(position) else {
+    /// Selects the task with marker or title `id` in the list, or opens its
+    /// details when the current filters hide it.
+    fn reveal_task(self: &Rc<Self>, id: &str) {
+        let todo = data::find_task(&self.cached_items.borrow(), id).cloned();
+        let Some(todo) = todo else {
+            self.show_error(&t("task_link_not_found").replace("{}", id));
             return;
         };
-        let Ok(todo_obj) = obj.downcast::<BoxedAnyObject>() else {
+        let listed = self.visible_items().iter().any(|item| item.key == todo.key);
+        if listed {
+            self.select_item(&todo.key);
+        } else {
+            self.show_details_dialog(&todo);
+        }
+    }
+
+    /// Selects the row of `key` in the main list and scrolls it into view.
+    fn select_item(&self, key: &data::TodoKey) {
+        let Some(list_view) = self.list_view.borrow().clone() else {
             return;
         };
-        let entry = todo_obj.borrow::<ListEntry>();
-        let todo = match &*entry {
-            ListEntry::Item(todo) => todo.clone(),
-            ListEntry::Header(_) => return,
+        let Some(selection) = list_view.model().and_downcast::<gtk::SingleSelection>() else {
+            return;
         };
-        drop(entry);
-        self.show_details_dialog(&todo);
+        let position = (0..selection.n_items())
+            .find(|&i| selection.item(i).and_then(|obj| list_entry_todo(&obj)).is_some_and(|todo| todo.key == *key));
+        if let Some(position) = position {
+            selection.set_selected(position);
+            list_view.scroll_to(position, gtk::ListScrollFlags::FOCUS, None);
+        }
     }
 
-    fn show_details_dialog(self: &Rc<Self>, todo: &TodoItem) {
+    /// Saves a choice of the listed open tasks as a named template.
+    fn show_save_template_dialog(self: &Rc<Self>) {
         let Some(parent) = self.window.upgrade() else {
             self.show_error(&t("no_window"));
             return;
         };
+        let items: Vec<TodoItem> = self.visible_items().into_iter().filter(|todo| !todo.done).collect();
+        if items.is_empty() {
+            self.show_info(&t("suggest_empty"));
+            return;
+        }
+
+        let name_row = adw::EntryRow::builder().title(t("template_name")).build();
+        let name_list = gtk::ListBox::new();
+        name_list.add_css_class("boxed-list");
+        name_list.set_selection_mode(gtk::SelectionMode::None);
+        name_list.append(&name_row);
+
+        let task_list = gtk::Box::new(gtk::Orientation::Vertical, 4);
+        let checks: Vec<gtk::CheckButton> = items
+            .iter()
+            .map(|todo| {
+                let check = gtk::CheckButton::with_label(&todo.title);
+                check.set_active(true);
+                task_list.append(&check);
+                check
+            })
+            .collect();
+        let scrolled = gtk::ScrolledWindow::builder()
+            .child(&task_list)
+            .vexpand(true)
+            .min_content_height(200)
+            .build();
+
+        let hint = gtk::Label::builder()
+            .label(t("template_save_desc"))
+            .wrap(true)
+            .xalign(0.0)
+            .build();
+        hint.add_css_class("dim-label");
 
+        let save_btn = gtk::Button::with_label(&t("save"));
+        save_btn.add_css_class("suggested-action");
+        save_btn.set_halign(gtk::Align::End);
+
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 12);
+        content.set_margin_top(16);
+        content.set_margin_bottom(16);
+        content.set_margin_start(20);
+        content.set_margin_end(20);
+        content.append(&name_list);
+        content.append(&hint);
+        content.append(&scrolled);
+        content.append(&save_btn);
+
+        let toolbar = adw::ToolbarView::new();
+        toolbar.add_top_bar(&adw::HeaderBar::new());
+        toolbar.set_content(Some(&content));
         let dialog = adw::Window::builder()
-            .title(&t("edit_task"))
+            .title(t("template_save"))
             .transient_for(&parent)
             .modal(true)
-            .default_width(420)
+            .default_width(440)
+            .default_height(520)
+            .content(&toolbar)
             .build();
         dialog.set_destroy_with_parent(true);
 
-        let key_controller = gtk::EventControllerKey::new();
-        let dialog_clone = dialog.clone();
-        key_controller.connect_key_pressed(move |_, keyval, _, _| {
-            if keyval == gdk::Key::Escape {
-                dialog_clone.close();
-                glib::Propagation::Stop
-            } else {
-                glib::Propagation::Proceed
+        let state = Rc::clone(self);
+        save_btn.connect_clicked(clone!(@weak dialog, @weak name_row => move |_| {
+            let chosen: Vec<TodoItem> = items
+                .iter()
+                .zip(&checks)
+                .filter(|(_, check)| check.is_active())
+                .map(|(todo, _)| todo.clone())
+                .collect();
+            let name = name_row.text().to_string();
+            match templates::save(&name, &chosen, Local::now().date_naive()) {
+                Ok(_) => {
+                    state.show_info(&t("template_saved").replace("{}", name.trim()));
+                    dialog.close();
+                }
+                Err(err) => state.show_error(&describe_error(&err)),
             }
-        });
-        dialog.add_controller(key_controller);
+        }));
+        dialog.present();
+    }
+
+    /// Adds the tasks of a saved template to a section, due dates counted from today.
+    fn show_use_template_dialog(self: &Rc<Self>) {
+        let Some(parent) = self.window.upgrade() else {
+            self.show_error(&t("no_window"));
+            return;
+        };
+        let names = templates::list();
+        if names.is_empty() {
+            self.show_info(&t("template_none"));
+            return;
+        }
+
+        let name_refs: Vec<&str> = names.iter().map(String::as_str).collect();
+        let template_row = adw::ComboRow::builder()
+            .title(t("template"))
+            .model(&gtk::StringList::new(&name_refs))
+            .build();
+        let default_section = self
+            .cached_items
+            .borrow()
+            .iter()
+            .map(|todo| todo.section.to_string())
+            .find(|section| !section.is_empty())
+            .unwrap_or_default();
+        let section_row = adw::EntryRow::builder()
+            .title(t("section"))
+            .text(default_section)
+            .build();
+        let rows = gtk::ListBox::new();
+        rows.add_css_class("boxed-list");
+        rows.set_selection_mode(gtk::SelectionMode::None);
+        rows.append(&template_row);
+        rows.append(&section_row);
+        install_completion(Rc::downgrade(self), &section_row, Completion::Section);
+
+        let create_btn = gtk::Button::with_label(&t("template_create"));
+        create_btn.add_css_class("suggested-action");
+        create_btn.set_halign(gtk::Align::End);
 
         let content = gtk::Box::new(gtk::Orientation::Vertical, 12);
         content.set_margin_top(16);
         content.set_margin_bottom(16);
         content.set_margin_start(20);
         content.set_margin_end(20);
+        content.append(&rows);
+        content.append(&create_btn);
 
-        let section_row = gtk::Box::new(gtk::Orientation::Vertical, 4);
-        section_row.append(&gtk::Label::builder().label(&t("section")).xalign(0.0).build());
-        let section_value = gtk::Label::builder()
-            .label(&todo.section)
-            .xalign(0.0)
+        let toolbar = adw::ToolbarView::new();
+        toolbar.add_top_bar(&adw::HeaderBar::new());
+        toolbar.set_content(Some(&content));
+        let dialog = adw::Window::builder()
+            .title(t("template_new_from"))
+            .transient_for(&parent)
+            .modal(true)
+            .default_width(420)
+            .content(&toolbar)
             .build();
-        section_value.add_css_class("dim-label");
-        section_row.append(&section_value);
-        content.append(&section_row);
+        dialog.set_destroy_with_parent(true);
 
-        let title_entry = gtk::Entry::builder().text(&todo.title).hexpand(true).build();
-        let title_row = gtk::Box::new(gtk::Orientation::Vertical, 4);
-        title_row.append(&gtk::Label::builder().label(&t("title")).xalign(0.0).build());
-        title_row.append(&title_entry);
-        content.append(&title_row);
+        let state = Rc::clone(self);
+        create_btn.connect_clicked(clone!(@weak dialog, @weak template_row, @weak section_row => move |_| {
+            let Some(name) = names.get(template_row.selected() as usize) else {
+                return;
+            };
+            let result = templates::instantiate(name, &section_row.text(), Local::now().date_naive())
+                .and_then(|items| data::import_todos(&items));
+            match result.and_then(|imported| state.reload().map(|_| imported)) {
+                Ok(imported) => {
+                    state.show_info(&imported.summary());
+                    dialog.close();
+                }
+                Err(err) => state.show_error(&t("import_error").replace("{}", &describe_error(&err))),
+            }
+        }));
+        dialog.present();
+    }
 
-        let project_entry = gtk::Entry::new();
-        if let Some(project) = &todo.project {
-            project_entry.set_text(project);
+    /// Completion streak and a heatmap of the last year, one column per week.
+    fn show_statistics(&self) {
+        let Some(parent) = self.window.upgrade() else {
+            self.show_error(&t("no_window"));
+            return;
+        };
+        let today = Local::now().date_naive();
+        let counts = stats::completions_per_day(&self.cached_items.borrow());
+        let streak = stats::current_streak(&counts, today);
+
+        let first_day = today - Duration::weeks(HEATMAP_WEEKS) + Duration::days(1);
+        let first_monday = first_day - Duration::days(first_day.weekday().num_days_from_monday() as i64);
+        let max = counts.range(first_monday..).map(|(_, count)| *count).max().unwrap_or(0);
+        let total: usize = counts.range(first_day..=today).map(|(_, count)| *count).sum();
+
+        let heatmap = gtk::Grid::builder().column_spacing(3).row_spacing(3).halign(gtk::Align::Center).build();
+        let mut day = first_monday;
+        while day <= today {
+            let count = counts.get(&day).copied().unwrap_or(0);
+            let cell = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+            cell.add_css_class("heat-cell");
+            if count > 0 {
+                // Four steps relative to the busiest day, like the GitHub graph.
+                cell.add_css_class(&format!("heat-{}", (count * 4).div_ceil(max).min(4)));
+            }
+            cell.set_tooltip_text(Some(
+                &t("statistics_day")
+                    .replacen("{}", &config::format_date(day), 1)
+                    .replacen("{}", &i18n::format_count(count), 1),
+            ));
+            let column = (day - first_monday).num_days() / 7;
+            heatmap.attach(&cell, column as i32, day.weekday().num_days_from_monday() as i32, 1, 1);
+            day += Duration::days(1);
         }
-        let project_row = gtk::Box::new(gtk::Orientation::Vertical, 4);
-        project_row.append(&gtk::Label::builder().label(&t("project_plus")).xalign(0.0).build());
-        project_row.append(&project_entry);
-        content.append(&project_row);
 
-        let context_entry = gtk::Entry::new();
-        if let Some(context) = &todo.context {
-            context_entry.set_text(context);
-        }
-        let context_row = gtk::Box::new(gtk::Orientation::Vertical, 4);
-        context_row.append(&gtk::Label::builder().label(&t("location_at")).xalign(0.0).build());
-        context_row.append(&context_entry);
-        content.append(&context_row);
+        let streak_label = gtk::Label::builder()
+            .label(t("statistics_streak").replace("{}", &i18n::format_count(streak)))
+            .xalign(0.0)
+            .build();
+        streak_label.add_css_class("title-2");
+        let total_label = gtk::Label::builder()
+            .label(t("statistics_year").replace("{}", &i18n::format_count(total)))
+            .xalign(0.0)
+            .build();
+        total_label.add_css_class("dim-label");
+        let (done, tasks) = {
+            let items = self.cached_items.borrow();
+            (items.iter().filter(|item| item.done).count(), items.len())
+        };
+        let share_label = gtk::Label::builder()
+            .label(t("statistics_done_share").replace("{}", &i18n::format_percent(done as f64 / tasks.max(1) as f64, 0)))
+            .xalign(0.0)
+            .build();
+        share_label.add_css_class("dim-label");
 
-        let due_entry = gtk::Entry::new();
-        due_entry.set_placeholder_text(Some("YYYY-MM-DD"));
-        if let Some(due) = todo.due {
-            let due_string = due.format("%Y-%m-%d").to_string();
-            due_entry.set_text(&due_string);
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 12);
+        content.set_margin_top(16);
+        content.set_margin_bottom(20);
+        content.set_margin_start(20);
+        content.set_margin_end(20);
+        content.append(&streak_label);
+        content.append(&total_label);
+        content.append(&share_label);
+        content.append(&heatmap);
+
+        let toolbar = adw::ToolbarView::new();
+        toolbar.add_top_bar(&adw::HeaderBar::new());
+        toolbar.set_content(Some(&content));
+        let dialog = adw::Window::builder()
+            .title(t("statistics"))
+            .transient_for(&parent)
+            .modal(true)
+            .content(&toolbar)
+            .build();
+        dialog.set_destroy_with_parent(true);
+        dialog.present();
+    }
+
+    /// Reads an export of another app and shows what would be imported.
+    fn import_from(self: &Rc<Self>, format: ImportFormat) {
+        let Some(parent) = self.window.upgrade() else {
+            self.show_error(&t("no_window"));
+            return;
+        };
+        let filter = FileFilter::new();
+        filter.set_name(Some(&format.label()));
+        for suffix in format.suffixes() {
+            filter.add_suffix(suffix);
         }
-        let due_row = gtk::Box::new(gtk::Orientation::Vertical, 4);
-        due_row.append(&gtk::Label::builder().label(&t("due_date")).xalign(0.0).build());
-        let due_inputs = gtk::Box::new(gtk::Orientation::Horizontal, 6);
-        due_entry.set_hexpand(true);
-        due_inputs.append(&due_entry);
-        let due_today_btn = gtk::Button::with_label(&t("today"));
-        due_today_btn.add_css_class("flat");
-        due_inputs.append(&due_today_btn);
-        due_row.append(&due_inputs);
-        content.append(&due_row);
+        let filters = gio::ListStore::new::<FileFilter>();
+        filters.append(&filter);
+        let dialog = FileDialog::builder()
+            .title(format.label())
+            .filters(&filters)
+            .build();
 
-        let recurrence_values = ["", "daily", "weekly", "monthly"];
-        let recurrence_row = gtk::Box::new(gtk::Orientation::Vertical, 4);
-        recurrence_row.append(&gtk::Label::builder().label(&t("recurrence")).xalign(0.0).build());
-        let recurrence_list = gtk::StringList::new(&[]);
-        recurrence_list.append(&t("recurrence_none"));
-        recurrence_list.append(&t("recurrence_daily"));
-        recurrence_list.append(&t("recurrence_weekly"));
-        recurrence_list.append(&t("recurrence_monthly"));
-        let recurrence_dropdown = gtk::DropDown::new(Some(recurrence_list.clone()), None::<&gtk::Expression>);
-        let rec_index = todo
-            .recurrence
-            .as_deref()
-            .and_then(|r| recurrence_values.iter().position(|v| v == &r))
-            .unwrap_or(0) as u32;
-        recurrence_dropdown.set_selected(rec_index);
-        recurrence_row.append(&recurrence_dropdown);
-        content.append(&recurrence_row);
+        let state = Rc::clone(self);
+        dialog.open(Some(&parent), gio::Cancellable::NONE, move |result| {
+            let Some(path) = result.ok().and_then(|file| file.path()) else {
+                return;
+            };
+            let parsed = fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| format.parse(&content));
+            match parsed {
+                Ok(items) if items.is_empty() => state.show_info(&t("import_nothing")),
+                Ok(items) => state.show_import_preview(items),
+                Err(err) => state.show_error(&t("import_error").replace("{}", &describe_error(&err))),
+            }
+        });
+    }
 
-        let done_check = gtk::CheckButton::with_label(&t("done"));
-        done_check.set_active(todo.done);
-        content.append(&done_check);
+    /// A field to paste a Markdown checklist into, imported through
+    /// [`Self::show_import_preview`].
+    fn show_checklist_import(self: &Rc<Self>) {
+        let Some(parent) = self.window.upgrade() else {
+            self.show_error(&t("no_window"));
+            return;
+        };
+        let dialog = adw::Window::builder()
+            .title(t("import_checklist_title"))
+            .transient_for(&parent)
+            .modal(true)
+            .default_width(520)
+            .default_height(420)
+            .build();
+        dialog.set_destroy_with_parent(true);
 
-        let comment_entry = gtk::Entry::new();
-        let comment_row = gtk::Box::new(gtk::Orientation::Vertical, 4);
-        comment_row.append(&gtk::Label::builder().label(&t("comment")).xalign(0.0).build());
-        comment_row.append(&comment_entry);
-        comment_row.set_visible(false);
-        content.append(&comment_row);
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 12);
+        content.set_margin_top(16);
+        content.set_margin_bottom(16);
+        content.set_margin_start(20);
+        content.set_margin_end(20);
+        let hint = gtk::Label::builder().label(t("import_checklist_hint")).xalign(0.0).wrap(true).build();
+        hint.add_css_class("dim-label");
+        content.append(&hint);
+        let text_view = gtk::TextView::builder().monospace(true).wrap_mode(gtk::WrapMode::WordChar).build();
+        let scrolled = gtk::ScrolledWindow::builder().child(&text_view).vexpand(true).build();
+        scrolled.add_css_class("card");
+        content.append(&scrolled);
+
+        let buttons = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+        buttons.set_halign(gtk::Align::End);
+        let cancel_btn = gtk::Button::with_label(&t("cancel"));
+        let preview_btn = gtk::Button::with_label(&t("import_preview"));
+        preview_btn.add_css_class("suggested-action");
+        buttons.append(&cancel_btn);
+        buttons.append(&preview_btn);
+        content.append(&buttons);
+        dialog.set_content(Some(&content));
+
+        cancel_btn.connect_clicked(clone!(@weak dialog => move |_| {
+            dialog.close();
+        }));
+        let state = Rc::clone(self);
+        preview_btn.connect_clicked(clone!(@weak dialog, @weak text_view => move |_| {
+            let buffer = text_view.buffer();
+            let items = import::markdown_checklist(&buffer.text(&buffer.start_iter(), &buffer.end_iter(), false));
+            if items.is_empty() {
+                state.show_info(&t("import_no_checklist"));
+                return;
+            }
+            dialog.close();
+            state.show_import_preview(items);
+        }));
+        dialog.present();
+        text_view.grab_focus();
+    }
+
+    /// Imports the clipboard text as a Markdown checklist, after the preview.
+    fn paste_checklist(self: &Rc<Self>) {
+        let Some(window) = self.window.upgrade() else {
+            return;
+        };
+        window.clipboard().read_text_async(gio::Cancellable::NONE, clone!(@weak self as state => move |text| {
+            let items = text.ok().flatten().map(|text| import::markdown_checklist(&text)).unwrap_or_default();
+            if items.is_empty() {
+                state.show_info(&t("import_no_checklist"));
+            } else {
+                state.show_import_preview(items);
+            }
+        }));
+    }
+
+    /// Lists the parsed tasks and writes them only after confirmation.
+    fn show_import_preview(self: &Rc<Self>, items: Vec<TodoItem>) {
+        let Some(parent) = self.window.upgrade() else {
+            self.show_error(&t("no_window"));
+            return;
+        };
+
+        let dialog = adw::Window::builder()
+            .title(t("import_preview"))
+            .transient_for(&parent)
+            .modal(true)
+            .default_width(600)
+            .default_height(480)
+            .build();
+        dialog.set_destroy_with_parent(true);
+
+        let grid = gtk::Grid::builder().column_spacing(18).row_spacing(6).build();
+        let captions = [t("section"), t("title"), t("column_due"), t("column_priority"), t("done")];
+        for (column, caption) in captions.iter().enumerate() {
+            let label = gtk::Label::builder().label(caption.as_str()).xalign(0.0).build();
+            label.add_css_class("heading");
+            grid.attach(&label, column as i32, 0, 1, 1);
+        }
+        for (row, item) in items.iter().enumerate() {
+            let cells = [
+                item.section.to_string(),
+                item.title.clone(),
+                item.field_text("due").unwrap_or_default(),
+                item.field_text("priority").unwrap_or_default(),
+                if item.done { "✓".to_string() } else { String::new() },
+            ];
+            for (column, text) in cells.iter().enumerate() {
+                let label = gtk::Label::builder()
+                    .label(text.as_str())
+                    .xalign(0.0)
+                    .ellipsize(pango::EllipsizeMode::End)
+                    .max_width_chars(if column == 1 { 40 } else { 20 })
+                    .build();
+                grid.attach(&label, column as i32, row as i32 + 1, 1, 1);
+            }
+        }
+        let scrolled = gtk::ScrolledWindow::builder()
+            .child(&grid)
+            .vexpand(true)
+            .build();
+
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 12);
+        content.set_margin_top(16);
+        content.set_margin_bottom(16);
+        content.set_margin_start(20);
+        content.set_margin_end(20);
+        content.append(&scrolled);
 
         let buttons = gtk::Box::new(gtk::Orientation::Horizontal, 8);
         buttons.set_halign(gtk::Align::End);
         let cancel_btn = gtk::Button::with_label(&t("cancel"));
-        let delete_btn = gtk::Button::builder()
-            .icon_name("user-trash-symbolic")
-            .tooltip_text(&t("delete"))
-            .css_classes(["destructive-action"])
-            .build();
-        let close_with_comment_btn = gtk::Button::with_label(&t("close_with_comment"));
-        let save_btn = gtk::Button::with_label(&t("save"));
-        save_btn.add_css_class("suggested-action");
+        let import_btn = gtk::Button::with_label(&t("import_confirm").replace("{}", &items.len().to_string()));
+        import_btn.add_css_class("suggested-action");
         buttons.append(&cancel_btn);
-        buttons.append(&delete_btn);
-        buttons.append(&close_with_comment_btn);
-        buttons.append(&save_btn);
+        buttons.append(&import_btn);
         content.append(&buttons);
         dialog.set_content(Some(&content));
 
-        let dialog_cancel = dialog.clone();
-        cancel_btn.connect_clicked(move |_| {
-            dialog_cancel.close();
+        cancel_btn.connect_clicked(clone!(@weak dialog => move |_| {
+            dialog.close();
+        }));
+        let state = Rc::clone(self);
+        import_btn.connect_clicked(clone!(@weak dialog => move |_| {
+            let result = data::import_todos(&items).and_then(|imported| state.reload().map(|_| imported));
+            match result {
+                Ok(imported) => state.show_info(&imported.summary()),
+                Err(err) => state.show_error(&t("import_error").replace("{}", &describe_error(&err))),
+            }
+            dialog.close();
+        }));
+        dialog.present();
+    }
+
+    /// Saves the filtered list as a standalone HTML page.
+    fn export_html(self: &Rc<Self>) {
+        let filter = FileFilter::new();
+        filter.set_name(Some("HTML"));
+        filter.add_mime_type("text/html");
+        filter.add_suffix("html");
+        self.export_file(t("export_html"), "todos.html", filter, |state| {
+            let footer = t("exported_on").replace("{}", &Local::now().format("%Y-%m-%d %H:%M").to_string());
+            export::to_html(&t("app_title"), &state.visible_items(), &footer)
         });
+    }
 
-        let dialog_delete = dialog.clone();
-        let state_delete = self.clone();
-        let todo_delete = todo.clone();
-        delete_btn.connect_clicked(move |_| {
-            if let Err(e) = data::delete_todo(&todo_delete) {
-                state_delete.show_error(&t("delete_error").replace("{}", &e.to_string()));
+    /// Saves the filtered list in the format of `task export`.
+    fn export_taskwarrior(self: &Rc<Self>) {
+        let filter = FileFilter::new();
+        filter.set_name(Some("JSON"));
+        filter.add_mime_type("application/json");
+        filter.add_suffix("json");
+        self.export_file(t("export_taskwarrior"), "todos.json", filter, |state| {
+            // UUIDs come from the markers, so `task import` recognizes the tasks next time.
+            let mut items = state.visible_items();
+            if let Err(err) = data::ensure_markers(&mut items) {
+                tracing::warn!("Exporting without markers: {err:#}");
             }
-            dialog_delete.close();
+            export::to_taskwarrior(&items)
         });
+    }
 
-        let due_entry_for_button = due_entry.clone();
-        due_today_btn.connect_clicked(move |_| {
-            let today = Local::now().date_naive().format("%Y-%m-%d").to_string();
-            due_entry_for_button.set_text(&today);
+    /// Opens a mail with today's and overdue tasks, like `--digest`.
+    fn email_digest(&self) {
+        let today = Local::now().date_naive();
+        let items = self.cached_items.borrow().clone();
+        if let Err(err) = commands::open_mail(&export::digest_subject(today), &export::digest_text(&items, today)) {
+            self.show_error(&describe_error(&err));
+        }
+    }
+
+    /// Asks for a target file and writes what `render` produces into it.
+    fn export_file(
+        self: &Rc<Self>,
+        title: String,
+        initial_name: &str,
+        filter: FileFilter,
+        render: impl Fn(&AppState) -> String + 'static,
+    ) {
+        let Some(parent) = self.window.upgrade() else {
+            self.show_error(&t("no_window"));
+            return;
+        };
+        let filters = gio::ListStore::new::<FileFilter>();
+        filters.append(&filter);
+        let dialog = FileDialog::builder()
+            .title(title)
+            .initial_name(initial_name)
+            .filters(&filters)
+            .build();
+
+        let state = Rc::clone(self);
+        dialog.save(Some(&parent), gio::Cancellable::NONE, move |result| {
+            let Some(path) = result.ok().and_then(|file| file.path()) else {
+                return;
+            };
+            match fs::write(&path, render(&state)) {
+                Ok(_) => state.show_info(&t("exported_to").replace("{}", &path.display().to_string())),
+                Err(err) => state.show_error(&t("write_error").replace("{}", &format!("{}: {err}", path.display()))),
+            }
         });
+    }
 
-        let dialog_save = dialog.clone();
-        let state_for_save = Rc::clone(self);
-        let base_item = todo.clone();
-        let title_entry_save = title_entry.clone();
-        let project_entry_save = project_entry.clone();
-        let context_entry_save = context_entry.clone();
-        let due_entry_save = due_entry.clone();
-        let done_check_save = done_check.clone();
-        let comment_entry_save = comment_entry.clone();
-        let comment_row_save = comment_row.clone();
-        let recurrence_dropdown_save = recurrence_dropdown.clone();
-        save_btn.connect_clicked(move |_| {
-            let mut title_text = title_entry_save.text().trim().to_string();
-            if title_text.is_empty() {
-                state_for_save.show_error(&t("title_empty_error"));
+    /// Watches the database for outside changes; if that fails, the file
+    /// banner says so until a retry works.
+    fn install_monitor(self: &Rc<Self>) {
+        // Only we change the in-memory document.
+        if demo::is_active() {
+            self.file_events.set(true);
+            self.set_file_problem(FileSlot::Watch, None);
+            return;
+        }
+        let file = database_file();
+        let monitor = match file.monitor_file(gio::FileMonitorFlags::NONE, Option::<&gio::Cancellable>::None) {
+            Ok(monitor) => monitor,
+            Err(err) => {
+                tracing::warn!(location = %data::database_location(), "cannot monitor database: {err}");
+                self.file_events.set(false);
+                self.set_file_problem(FileSlot::Watch, Some(FileProblem::Unwatched(err.to_string())));
                 return;
             }
+        };
+        let events = file_events_reliable(&file, &monitor);
+        if !events {
+            tracing::info!(location = %data::database_location(), "file monitoring unreliable, polling every {}s", self.preferences.borrow().poll_interval);
+        }
+        self.file_events.set(events);
+        monitor.connect_changed(clone!(@weak self as state => move |_, _, _, event| {
+            state.handle_file_event(event);
+        }));
+        if let Some(previous) = self.monitor.replace(Some(monitor)) {
+            previous.cancel();
+        }
+        self.set_file_problem(FileSlot::Watch, None);
+        self.watch_included_files();
+    }
 
-            if comment_row_save.is_visible() {
-                let comment = comment_entry_save.text().trim().to_string();
-                if !comment.is_empty() {
-                    title_text = format!("{} ({})", title_text, comment);
+    /// Monitors the files the database `#include`s, following changes of the
+    /// include lines themselves.
+    fn watch_included_files(self: &Rc<Self>) {
+        let wanted = data::included_files();
+        let current: Vec<PathBuf> = self.include_monitors.borrow().iter().map(|(path, _)| path.clone()).collect();
+        if current == wanted {
+            return;
+        }
+        let mut monitors = Vec::new();
+        for path in wanted {
+            match gio::File::for_path(&path).monitor_file(gio::FileMonitorFlags::NONE, gio::Cancellable::NONE) {
+                Ok(monitor) => {
+                    monitor.connect_changed(clone!(@weak self as state => move |_, _, _, event| {
+                        state.handle_file_event(event);
+                    }));
+                    monitors.push((path, monitor));
                 }
+                Err(err) => tracing::warn!(path = %path.display(), "cannot monitor included file: {err}"),
             }
+        }
+        *self.include_monitors.borrow_mut() = monitors;
+    }
 
-            let project_text = project_entry_save.text().trim().to_string();
-            let project_value = if project_text.is_empty() {
-                None
-            } else {
-                Some(project_text)
-            };
+    fn handle_file_event(self: &Rc<Self>, event: gio::FileMonitorEvent) {
+        use gio::FileMonitorEvent as Event;
+        tracing::debug!(?event, "file monitor event");
+        let should_reload = matches!(
+            event,
+            Event::Changed
+                | Event::ChangesDoneHint
+                | Event::Created
+                | Event::Deleted
+                | Event::Moved
+                | Event::Renamed
+                | Event::AttributeChanged
+        );
 
-            let context_text = context_entry_save.text().trim().to_string();
-            let context_value = if context_text.is_empty() {
-                None
-            } else {
-                Some(context_text)
-            };
+        if !should_reload {
+            return;
+        }
 
-            let due_text = due_entry_save.text().trim().to_string();
-            let due_value = if due_text.is_empty() {
-                None
-            } else {
-                match NaiveDate::parse_from_str(&due_text, "%Y-%m-%d") {
-                    Ok(date) => Some(date),
-                    Err(_) => {
-                        state_for_save.show_error(&t("invalid_date_error"));
-                        return;
-                    }
+        // Our own saves trigger the monitor too; they already updated the fingerprint.
+        let external = data::get_fingerprint().ok() != *self.last_fingerprint.borrow();
+        match self.reload() {
+            Ok(_) => {
+                if external {
+                    self.set_persistence(Persistence::ExternalChange);
+                }
+                if matches!(event, Event::ChangesDoneHint | Event::Changed | Event::Created) {
+                    self.show_info(&t("changes_applied"));
+                }
+                // A file put in place of the old one is a new inode, which
+                // some monitors do not follow.
+                if matches!(event, Event::Created | Event::Moved | Event::Renamed) {
+                    self.install_monitor();
                 }
-            };
-
-            let rec_values = ["", "daily", "weekly", "monthly"];
-            let rec_idx = recurrence_dropdown_save.selected() as usize;
-            let recurrence_value = rec_values
-                .get(rec_idx)
-                .map(|s| s.to_string())
-                .filter(|s| !s.is_empty());
-
-            let mut updated = base_item.clone();
-            updated.title = title_text;
-            updated.project = project_value;
-            updated.context = context_value;
-            updated.reference = base_item.reference.clone();
-            updated.due = due_value;
-            updated.recurrence = recurrence_value;
-            updated.done = done_check_save.is_active();
-
-            if let Err(err) = state_for_save.save_item(&updated) {
-                state_for_save.show_error(&t("save_task_error").replace("{}", &err.to_string()));
-            } else {
-                dialog_save.close();
             }
-        });
+            // The missing page says so and waits for the file.
+            Err(_) if self.database_missing.get() => {}
+            Err(err) => {
+                self.show_error(&t("update_failed").replace("{}", &describe_error(&err)));
+            }
+        }
+        self.watch_included_files();
+    }
+}
 
-        let comment_entry_close = comment_entry.clone();
-        let comment_row_close = comment_row.clone();
-        let close_btn_ref = close_with_comment_btn.clone();
-        let done_check_close = done_check.clone();
-        close_with_comment_btn.connect_clicked(move |_| {
-            comment_row_close.set_visible(true);
-            comment_entry_close.grab_focus();
-            done_check_close.set_active(true);
-            close_btn_ref.set_sensitive(false);
-        });
+/// A lasting problem with the database. Toasts vanish before they are read,
+/// so these stay in a banner under the header bar until they are resolved.
+#[derive(Clone, Debug, PartialEq)]
+enum FileProblem {
+    /// The file may not be read; choosing it again helps. A file that is
+    /// gone shows [`create_missing_page`] instead.
+    Missing(String),
+    /// Reading failed otherwise, possibly only for a moment.
+    Unreadable(String),
+    /// Loaded, but changes cannot be saved.
+    ReadOnly,
+    /// The file monitor failed; outside changes are found by polling.
+    Unwatched(String),
+}
 
-        dialog.present();
+/// Which check an [`AppState::file_problems`] entry comes from. Loading goes
+/// first: a missing file also cannot be watched.
+#[derive(Clone, Copy)]
+enum FileSlot {
+    Load,
+    Watch,
+}
+
+impl FileProblem {
+    /// `None` for errors the welcome dialog or the missing page already
+    /// cover.
+    fn from_load_error(err: &anyhow::Error) -> Option<Self> {
+        match err.downcast_ref::<DataError>() {
+            Some(DataError::NoDatabase | DataError::NotFound { .. }) => None,
+            Some(DataError::PermissionDenied { .. }) => Some(FileProblem::Missing(format!("{err:#}"))),
+            _ => Some(FileProblem::Unreadable(format!("{err:#}"))),
+        }
     }
 
-    fn sort_items(&self, items: &mut [TodoItem]) {
-        match *self.sort_mode.borrow() {
-            SortMode::Topic => items.sort_by(compare_by_project),
-            SortMode::Location => items.sort_by(compare_by_context),
-            SortMode::Date => items.sort_by(compare_by_due),
+    fn title(&self) -> String {
+        match self {
+            FileProblem::Missing(message) | FileProblem::Unreadable(message) => message.clone(),
+            FileProblem::ReadOnly => t("file_read_only"),
+            FileProblem::Unwatched(message) => t("monitor_error").replace("{}", message),
         }
     }
 
-    fn group_label(&self, mode: SortMode, item: &TodoItem) -> Option<String> {
-        match mode {
-            SortMode::Topic => Some(t("topic_group").replace(
-                "{}",
-                item.project
-                    .as_deref()
-                    .filter(|s| !s.is_empty())
-                    .unwrap_or(&t("no_project"))
-            )),
-            SortMode::Location => Some(t("location_group").replace(
-                "{}",
-                item.context
-                    .as_deref()
-                    .filter(|s| !s.is_empty())
-                    .unwrap_or(&t("no_location"))
-            )),
-            SortMode::Date => None,
+    fn button_label(&self) -> String {
+        match self {
+            FileProblem::Missing(_) => t("file_choose_again"),
+            FileProblem::Unreadable(_) | FileProblem::ReadOnly | FileProblem::Unwatched(_) => t("retry"),
         }
     }
+}
 
-    fn show_info(&self, message: &str) {
-        let toast = adw::Toast::builder().title(message).build();
-        self.overlay.add_toast(toast);
+/// Whether the local or GIO database may not be written, e.g. after
+/// `chmod -w` or on a read-only share.
+fn database_read_only() -> bool {
+    if !matches!(data::get_backend_config(), data::BackendConfig::Local(_) | data::BackendConfig::Uri(_)) {
+        return false;
     }
+    let attribute = gio::FILE_ATTRIBUTE_ACCESS_CAN_WRITE;
+    database_file()
+        .query_info(attribute, gio::FileQueryInfoFlags::NONE, gio::Cancellable::NONE)
+        .is_ok_and(|info| info.has_attribute(attribute) && !info.boolean(attribute))
+}
 
-    fn show_error(&self, message: &str) {
-        let toast = adw::Toast::builder()
-            .title(message)
-            .priority(adw::ToastPriority::High)
-            .build();
-        self.overlay.add_toast(toast);
+/// `err` for the user: its messages, and for a [`DataError`] what to do
+/// about it.
+fn describe_error(err: &anyhow::Error) -> String {
+    let message = format!("{err:#}");
+    let Some(error) = err.downcast_ref::<DataError>() else {
+        return message;
+    };
+    let hint = match error {
+        // These already say what to do.
+        DataError::NoDatabase | DataError::AmbiguousMarker { .. } | DataError::EmptyTitle | DataError::EmptyName => None,
+        DataError::NotFound { .. } => Some("error_not_found_hint"),
+        DataError::PermissionDenied { .. } if sandbox::in_flatpak() => Some("error_permission_flatpak_hint"),
+        DataError::PermissionDenied { .. } => Some("error_permission_hint"),
+        DataError::Io { writing: true, .. } => Some("error_write_hint"),
+        DataError::Io { writing: false, .. } => Some("error_read_hint"),
+        DataError::ParseError { .. } => Some("error_parse_hint"),
+        DataError::TaskNotFound | DataError::Conflict { .. } | DataError::Outdated => Some("error_reload_hint"),
+        DataError::Busy { .. } => Some("error_busy_hint"),
+    };
+    match hint {
+        Some(key) => format!("{message}\n{}", t(key)),
+        None => message,
     }
+}
 
-    fn install_monitor(self: &Rc<Self>) -> Result<()> {
-        let file = gio::File::for_path(data::todo_path());
-        let monitor = file.monitor_file(gio::FileMonitorFlags::NONE, Option::<&gio::Cancellable>::None)?;
-        monitor.connect_changed(clone!(@weak self as state => move |_, _, _, event| {
-            use gio::FileMonitorEvent as Event;
-            let should_reload = matches!(
-                event,
-                Event::Changed
-                    | Event::ChangesDoneHint
-                    | Event::Created
-                    | Event::Deleted
-                    | Event::Moved
-                    | Event::Renamed
-                    | Event::AttributeChanged
-            );
+/// File or folder name of a `file://` link, for display.
+fn linked_file_name(uri: &str) -> String {
+    gio::File::for_uri(uri)
+        .basename()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| uri.to_string())
+}
 
-            if !should_reload {
-                return;
-            }
+/// Human form of an effort estimate, e.g. `3h 20m`.
+fn estimate_label(minutes: u32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{m}m"),
+        (h, 0) => format!("{h}h"),
+        (h, m) => format!("{h}h {m}m"),
+    }
+}
 
-            match state.reload() {
-                Ok(_) => {
-                    if matches!(event, Event::ChangesDoneHint | Event::Changed | Event::Created) {
-                        state.show_info(&t("changes_applied"));
-                    }
+/// Style class reflecting completion and due state of a row at `today`.
+fn due_urgency_class(item: &TodoItem, today: NaiveDate) -> Option<&'static str> {
+    if item.done {
+        return Some("todo-done");
+    }
+    match item.due {
+        Some(due) if due < today => Some("todo-overdue"),
+        Some(due) if due == today => Some("todo-due-today"),
+        _ => None,
+    }
+}
+
+fn format_metadata(item: &TodoItem, fields: &[MetaField]) -> String {
+    let mut parts = Vec::new();
+    for field in fields {
+        match field {
+            MetaField::Section => {
+                if !item.section.is_empty() {
+                    parts.push(item.section.to_string());
                 }
-                Err(err) => {
-                    state.show_error(&t("update_failed").replace("{}", &err.to_string()));
+            }
+            MetaField::Project => {
+                if let Some(project) = &item.project {
+                    parts.push(format!("+{}", project));
                 }
             }
-        }));
-        *self.monitor.borrow_mut() = Some(monitor);
-        Ok(())
+            MetaField::Context => {
+                if let Some(context) = &item.context {
+                    parts.push(format!("@{}", context));
+                }
+            }
+            MetaField::Due => match item.due {
+                Some(due) if due.year() == 9999 => parts.push(t("sometimes")),
+                Some(_) => parts.push(t("due_label").replace("{}", &format_due_cell(item))),
+                None => {}
+            },
+            MetaField::Estimate => {
+                if let Some(minutes) = item.estimate {
+                    parts.push(format!("⏱ {}", estimate_label(minutes)));
+                }
+            }
+            MetaField::Recurrence => {
+                if let Some(rule) = &item.recurrence {
+                    let label = match rule.as_str() {
+                        "daily" => t("recurrence_daily"),
+                        "weekly" => t("recurrence_weekly"),
+                        "monthly" => t("recurrence_monthly"),
+                        _ => rule.clone(),
+                    };
+                    parts.push(format!("↻ {}", label));
+                }
+            }
+            MetaField::Reference => {
+                if let Some(uri) = item.file_link() {
+                    parts.push(format!("📎 {}", linked_file_name(uri)));
+                } else if let Some(reference) = &item.reference {
+                    parts.push(format!("↗ {}", reference));
+                }
+            }
+            MetaField::Created => {
+                if let Some(created) = item.created {
+                    parts.push(t("created_label").replace("{}", &config::format_date(created)));
+                }
+            }
+        }
     }
+    if let (Some(Aging::Raised(original)), Some(priority)) = (item.aged, item.priority) {
+        let original = original.map(String::from).unwrap_or_else(|| "–".to_string());
+        parts.push(t("aged_label").replacen("{}", &original, 1).replacen("{}", &priority.to_string(), 1));
+    }
+
+    parts.join(" • ")
 }
 
-fn format_metadata(item: &TodoItem) -> String {
-    let mut parts = Vec::new();
-    if !item.section.is_empty() {
-        parts.push(item.section.clone());
-    }
-    if let Some(project) = &item.project {
-        parts.push(format!("+{}", project));
+/// Bump when [`SettingsBundle`] changes incompatibly.
+const SETTINGS_BUNDLE_FORMAT: u32 = 1;
+
+/// File written by "Export settings", meant to be imported on another machine.
+#[derive(Serialize, Deserialize)]
+struct SettingsBundle {
+    format: u32,
+    exported: DateTime<Local>,
+    preferences: Preferences,
+}
+
+/// Where the database of this start was named before the preferences.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DatabaseOrigin {
+    /// Nowhere; the preferences decide.
+    #[default]
+    Preferences,
+    /// In config.toml or `REINSCHRIFT_DATABASE`, which keep deciding only
+    /// as long as they name one.
+    Configured,
+    /// With `--database`, remembered like a database opened in the window.
+    CommandLine,
+}
+
+static DATABASE_ORIGIN: Mutex<DatabaseOrigin> = Mutex::new(DatabaseOrigin::Preferences);
+
+/// Records where the database set in the data layer came from, before the
+/// preferences are applied.
+pub fn set_database_origin(origin: DatabaseOrigin) {
+    if let Ok(mut current) = DATABASE_ORIGIN.lock() {
+        *current = origin;
     }
-    if let Some(context) = &item.context {
-        parts.push(format!("@{}", context));
+}
+
+fn database_origin() -> DatabaseOrigin {
+    DATABASE_ORIGIN.lock().map(|origin| *origin).unwrap_or_default()
+}
+
+/// Points the data layer at the database from the preferences, unless one
+/// was given at startup (see [`DatabaseOrigin`]; one from the command line is
+/// then remembered in `prefs`). Returns whether the default database had to
+/// be created, i.e. this is the first run.
+fn apply_backend_preferences(prefs: &mut Preferences) -> bool {
+    if demo::is_active() {
+        return false;
     }
-    if let Some(due) = item.due {
-        if due.year() == 9999 {
-            parts.push(t("sometimes"));
-        } else {
-            parts.push(t("due_label").replace("{}", &due.to_string()));
+    let current_at_start = data::todo_path();
+    match database_origin() {
+        DatabaseOrigin::CommandLine => {
+            prefs.use_webdav = false;
+            prefs.db_path = Some(current_at_start.to_string_lossy().into_owned());
+            prefs.db_document = sandbox::document_id(&current_at_start);
+        }
+        // Used as given, but not remembered.
+        DatabaseOrigin::Configured => {}
+        DatabaseOrigin::Preferences if prefs.use_webdav => {
+            if let Some(url) = &prefs.webdav_url {
+                data::set_backend_config(data::BackendConfig::WebDav {
+                    url: url.clone(),
+                    path: prefs.webdav_path.clone(),
+                    username: prefs.webdav_username.clone(),
+                    password: prefs.webdav_password.clone(),
+                });
+            }
+        }
+        DatabaseOrigin::Preferences => {
+            if let Some(document) = prefs.db_document.clone().filter(|_| sandbox::in_flatpak()) {
+                // Chosen through the Document portal, whose mount point can move
+                let path = sandbox::document_path(&document);
+                prefs.db_path = Some(path.to_string_lossy().into_owned());
+                data::set_todo_path(path);
+            } else if let Some(db_path) = prefs.db_path.clone() {
+                data::set_database(&db_path);
+            } else if !current_at_start.as_os_str().is_empty() && matches!(data::get_backend_config(), data::BackendConfig::Local(_)) {
+                // No preference either, use the default
+                prefs.db_path = Some(current_at_start.to_string_lossy().into_owned());
+                match data::create_standard_database(&current_at_start) {
+                    Ok(created) => return created,
+                    Err(err) => tracing::warn!("Could not create the default database: {err:#}"),
+                }
+            }
         }
     }
-    if let Some(rule) = &item.recurrence {
-        let label = match rule.as_str() {
-            "daily" => t("recurrence_daily"),
-            "weekly" => t("recurrence_weekly"),
-            "monthly" => t("recurrence_monthly"),
-            _ => rule.clone(),
-        };
-        parts.push(format!("↻ {}", label));
-    }
-    if let Some(reference) = &item.reference {
-        parts.push(format!("↗ {}", reference));
-    }
+    false
+}
 
-    parts.join(" • ")
+/// Selects the same database the window would open, for headless modes.
+pub fn load_saved_backend() {
+    apply_backend_preferences(&mut load_preferences());
 }
 
+/// Reads GSettings if the schema is installed, `preferences.json` otherwise.
 fn load_preferences() -> Preferences {
-    let path = preferences_path();
-    if let Ok(data) = fs::read_to_string(&path) {
-        serde_json::from_str(&data).unwrap_or_default()
-    } else {
-        Preferences::default()
+    if let Some(settings) = settings::open() {
+        settings::migrate(&settings, load_json_preferences);
+        match settings::load(&settings) {
+            Ok(prefs) => return prefs,
+            Err(err) => tracing::warn!("Could not read GSettings, using preferences.json: {err}"),
+        }
     }
+    load_json_preferences().unwrap_or_default()
+}
+
+fn load_json_preferences() -> Option<Preferences> {
+    let data = fs::read_to_string(preferences_path()).ok()?;
+    serde_json::from_str(&data).ok()
 }
 
 fn write_preferences(prefs: &Preferences) -> std::io::Result<()> {
+    if let Some(settings) = settings::open() {
+        return settings::store(&settings, prefs).map_err(std::io::Error::other);
+    }
     let path = preferences_path();
     if let Some(dir) = path.parent() {
         fs::create_dir_all(dir)?;
@@ -2492,43 +9394,40 @@ fn preferences_path() -> PathBuf {
     dir
 }
 
-fn compare_by_project(a: &TodoItem, b: &TodoItem) -> Ordering {
-    compare_option_str(a.project.as_deref(), b.project.as_deref())
-        .then_with(|| lexical_order(&a.section, &b.section))
-        .then_with(|| lexical_order(&a.title, &b.title))
-        .then_with(|| compare_option_str(a.context.as_deref(), b.context.as_deref()))
-}
-
-fn compare_by_context(a: &TodoItem, b: &TodoItem) -> Ordering {
-    compare_option_str(a.context.as_deref(), b.context.as_deref())
-        .then_with(|| lexical_order(&a.section, &b.section))
-        .then_with(|| lexical_order(&a.title, &b.title))
-        .then_with(|| compare_option_str(a.project.as_deref(), b.project.as_deref()))
+/// Pinned tasks first; stable sorts keep the order within both groups.
+fn compare_pinned(a: &TodoItem, b: &TodoItem) -> Ordering {
+    b.pinned.cmp(&a.pinned)
 }
 
-fn compare_by_due(a: &TodoItem, b: &TodoItem) -> Ordering {
-    compare_option_date(a.due, b.due)
-        .then_with(|| compare_by_project(a, b))
+/// Collation keys of the names in a task, in the order a sort compares them.
+type NameKeys = (OptionalKey, CollationKey, CollationKey, OptionalKey);
+
+/// Sort key of "by topic": project, section, title, then context. Tasks
+/// without project or context come after those with one.
+fn project_order(collator: &Collator, item: &TodoItem) -> NameKeys {
+    (
+        collator.optional_key(item.project.as_deref()),
+        collator.key(&item.section),
+        collator.key(&item.title),
+        collator.optional_key(item.context.as_deref()),
+    )
 }
 
-fn compare_option_str(a: Option<&str>, b: Option<&str>) -> Ordering {
-    match (a, b) {
-        (Some(a), Some(b)) => lexical_order(a, b),
-        (Some(_), None) => Ordering::Less,
-        (None, Some(_)) => Ordering::Greater,
-        (None, None) => Ordering::Equal,
-    }
+/// Sort key of "by location", [`project_order`] with context and project swapped.
+fn context_order(collator: &Collator, item: &TodoItem) -> NameKeys {
+    (
+        collator.optional_key(item.context.as_deref()),
+        collator.key(&item.section),
+        collator.key(&item.title),
+        collator.optional_key(item.project.as_deref()),
+    )
 }
 
-fn compare_option_date(a: Option<NaiveDate>, b: Option<NaiveDate>) -> Ordering {
-    match (a, b) {
-        (Some(a), Some(b)) => a.cmp(&b),
-        (Some(_), None) => Ordering::Greater,
-        (None, Some(_)) => Ordering::Less,
-        (None, None) => Ordering::Equal,
-    }
+/// Sort key of "by date": tasks without due date first, then by day and
+/// time, then as [`project_order`].
+fn due_order(collator: &Collator, item: &TodoItem) -> (Option<NaiveDate>, Option<DateTime<Local>>, NameKeys) {
+    (item.due, item.due_at(), project_order(collator, item))
 }
 
-fn lexical_order(a: &str, b: &str) -> Ordering {
-    a.to_ascii_lowercase().cmp(&b.to_ascii_lowercase())
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,437 @@
+//! The window driven through its add, edit, toggle, reload and file monitor
+//! paths, on the in-memory backend.
+//!
+//! GTK needs a display; headless runs use Broadway (see `run_tests.sh`).
+//! Without any display the window tests fail rather than pass unrun. GTK
+//! also only runs on the thread that started it, so every test is handed to
+//! one worker thread and runs there in turn.
+
+use std::panic::{self, UnwindSafe};
+use std::sync::mpsc;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use reinschrift_core::data::BackendConfig;
+use reinschrift_core::storage;
+
+use super::*;
+
+/// Longest time [`Harness::settle`] runs the main loop, for animations that
+/// never leave it idle.
+const SETTLE_TIME: Duration = Duration::from_secs(2);
+
+type Job = Box<dyn FnOnce() + Send>;
+
+fn worker() -> Option<&'static Mutex<mpsc::Sender<Job>>> {
+    static WORKER: OnceLock<Option<Mutex<mpsc::Sender<Job>>>> = OnceLock::new();
+    WORKER
+        .get_or_init(|| {
+            let (started_tx, started_rx) = mpsc::channel();
+            let (jobs_tx, jobs_rx) = mpsc::channel::<Job>();
+            std::thread::spawn(move || {
+                isolate_environment();
+                let started = adw::init().is_ok();
+                let _ = started_tx.send(started);
+                if started {
+                    for job in jobs_rx {
+                        job();
+                    }
+                }
+            });
+            started_rx.recv().unwrap_or(false).then(|| Mutex::new(jobs_tx))
+        })
+        .as_ref()
+}
+
+/// Keeps the tests away from the settings, drafts and caches of the user
+/// running them.
+fn isolate_environment() {
+    let home = std::env::temp_dir().join(format!("reinschrift-ui-tests-{}", std::process::id()));
+    // SAFETY: runs on the worker before GTK starts; the test threads only
+    // wait for it and read no environment meanwhile.
+    unsafe {
+        std::env::set_var("XDG_CONFIG_HOME", home.join("config"));
+        std::env::set_var("XDG_DATA_HOME", home.join("data"));
+        std::env::set_var("XDG_CACHE_HOME", home.join("cache"));
+        std::env::set_var("XDG_STATE_HOME", home.join("state"));
+        std::env::set_var("GSETTINGS_BACKEND", "memory");
+    }
+}
+
+/// Runs `test` on the GTK thread and passes its panic on.
+fn run(test: impl FnOnce() + Send + UnwindSafe + 'static) {
+    let Some(worker) = worker() else {
+        panic!("GTK could not start; run ./run_tests.sh, set GDK_BACKEND=broadway or run under a display");
+    };
+    let (result_tx, result_rx) = mpsc::channel();
+    let job: Job = Box::new(move || {
+        let _ = result_tx.send(panic::catch_unwind(test));
+    });
+    worker.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).send(job).expect("GTK worker running");
+    if let Err(panic) = result_rx.recv().expect("GTK worker answers") {
+        panic::resume_unwind(panic);
+    }
+}
+
+/// The main window on `document`, as `build_ui` makes it.
+struct Harness {
+    window: gtk::Window,
+    state: Rc<AppState>,
+}
+
+impl Harness {
+    fn new(document: &str) -> Self {
+        storage::set_memory_document(document.to_string());
+        data::set_backend_config(BackendConfig::Memory);
+        let app = Application::builder().flags(gio::ApplicationFlags::NON_UNIQUE).build();
+        app.register(gio::Cancellable::NONE).expect("application registers");
+        build_ui(&app, false, false).expect("window builds");
+        let window = app.windows().first().cloned().expect("main window");
+        let state = unsafe { window.data::<Rc<AppState>>("app-state") }
+            .map(|ptr| unsafe { ptr.as_ref() }.clone())
+            .expect("window keeps its state");
+        let harness = Harness { window, state };
+        harness.settle();
+        harness
+    }
+
+    /// Runs the main loop until nothing is pending.
+    fn settle(&self) {
+        let context = glib::MainContext::default();
+        let deadline = Instant::now() + SETTLE_TIME;
+        while Instant::now() < deadline && context.iteration(false) {}
+    }
+
+    /// Titles of the task rows in the list, top to bottom.
+    fn titles(&self) -> Vec<String> {
+        let Some(model) = self.state.list_view.borrow().as_ref().and_then(|view| view.model()) else {
+            return Vec::new();
+        };
+        (0..model.n_items())
+            .filter_map(|position| model.item(position).and_then(|object| list_entry_todo(&object)))
+            .map(|todo| todo.title)
+            .collect()
+    }
+
+    fn task(&self, title: &str) -> TodoItem {
+        self.state
+            .cached_items
+            .borrow()
+            .iter()
+            .find(|item| item.title == title)
+            .cloned()
+            .unwrap_or_else(|| panic!("no task {title:?}"))
+    }
+
+    fn selection(&self) -> gtk::SingleSelection {
+        self.state
+            .list_view
+            .borrow()
+            .as_ref()
+            .and_then(|view| view.model())
+            .and_downcast::<gtk::SingleSelection>()
+            .expect("list selects single rows")
+    }
+
+    fn vadjustment(&self) -> gtk::Adjustment {
+        self.state.scrolled_window.borrow().as_ref().expect("list scrolls").vadjustment()
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        self.window.destroy();
+        self.settle();
+    }
+}
+
+const DOCUMENT: &str = "### Work\n\n- [ ] Write report\n- [ ] Call Anna\n- [ ] Plan trip\n";
+
+fn long_document() -> String {
+    let mut document = String::from("### Work\n\n");
+    for number in 1..=200 {
+        document.push_str(&format!("- [ ] Task {number:03}\n"));
+    }
+    document
+}
+
+#[test]
+fn shows_the_tasks_of_the_document() {
+    run(|| {
+        let harness = Harness::new(DOCUMENT);
+        let titles = harness.titles();
+        for title in ["Write report", "Call Anna", "Plan trip"] {
+            assert!(titles.iter().any(|listed| listed == title), "{title} listed in {titles:?}");
+        }
+    });
+}
+
+#[test]
+fn quick_add_lists_the_new_task() {
+    run(|| {
+        let harness = Harness::new(DOCUMENT);
+        let entry = harness.state.quick_add_entry.borrow().clone().expect("quick-add entry");
+        entry.set_text("Book room");
+        entry.emit_activate();
+        harness.settle();
+
+        assert!(harness.titles().iter().any(|title| title == "Book room"));
+        assert!(storage::memory_document().contains("- [ ] Book room"));
+        assert!(entry.text().is_empty());
+    });
+}
+
+#[test]
+fn edit_renames_the_row() {
+    run(|| {
+        let harness = Harness::new(DOCUMENT);
+        let base = harness.task("Call Anna");
+        let mut edited = base.clone();
+        edited.title = "Call Anna back".to_string();
+        harness.state.save_item(&base, &edited).expect("edit saves");
+        harness.settle();
+
+        let titles = harness.titles();
+        assert!(titles.iter().any(|title| title == "Call Anna back"));
+        assert!(!titles.iter().any(|title| title == "Call Anna"));
+    });
+}
+
+#[test]
+fn toggle_marks_the_task_done() {
+    run(|| {
+        let harness = Harness::new(DOCUMENT);
+        harness.state.toggle_item(&harness.task("Plan trip"), true).expect("toggle saves");
+        harness.settle();
+
+        assert!(harness.task("Plan trip").done);
+        assert!(storage::memory_document().contains("- [x] Plan trip"));
+        let listed = harness.titles().iter().any(|title| title == "Plan trip");
+        assert_eq!(listed, harness.state.show_completed());
+    });
+}
+
+#[test]
+fn polling_picks_up_outside_changes() {
+    run(|| {
+        let harness = Harness::new(DOCUMENT);
+        storage::set_memory_document(format!("{DOCUMENT}- [ ] Added elsewhere\n"));
+        harness.state.check_for_updates().expect("reload");
+        harness.settle();
+
+        assert!(harness.titles().iter().any(|title| title == "Added elsewhere"));
+    });
+}
+
+#[test]
+fn monitor_event_reloads() {
+    run(|| {
+        let harness = Harness::new(DOCUMENT);
+        storage::set_memory_document(DOCUMENT.replace("Write report", "Write summary"));
+        harness.state.handle_file_event(gio::FileMonitorEvent::ChangesDoneHint);
+        harness.settle();
+
+        let titles = harness.titles();
+        assert!(titles.iter().any(|title| title == "Write summary"));
+        assert!(!titles.iter().any(|title| title == "Write report"));
+    });
+}
+
+#[test]
+fn reload_keeps_the_scroll_position() {
+    run(|| {
+        let harness = Harness::new(&long_document());
+        let adjustment = harness.vadjustment();
+        assert!(adjustment.upper() > adjustment.page_size() * 2.0, "list is taller than the window");
+        let position = (adjustment.upper() - adjustment.page_size()) / 2.0;
+        adjustment.set_value(position);
+        harness.settle();
+
+        harness.state.reload().expect("reload");
+        harness.settle();
+        assert!((adjustment.value() - position).abs() < 1.0, "scrolled to {} instead of {position}", adjustment.value());
+    });
+}
+
+#[test]
+fn reload_keeps_the_selection() {
+    run(|| {
+        let harness = Harness::new(&long_document());
+        let selection = harness.selection();
+        let position = (0..selection.n_items())
+            .find(|position| {
+                selection
+                    .item(*position)
+                    .and_then(|object| list_entry_todo(&object))
+                    .is_some_and(|todo| todo.title == "Task 120")
+            })
+            .expect("task listed");
+        selection.set_selected(position);
+
+        storage::set_memory_document(long_document().replace("Task 010", "Task 010 renamed"));
+        harness.state.check_for_updates().expect("reload");
+        harness.settle();
+
+        let selected = selection.selected_item().and_then(|object| list_entry_todo(&object)).map(|todo| todo.title);
+        assert_eq!(selected.as_deref(), Some("Task 120"));
+    });
+}
+
+#[test]
+fn today_lists_timed_tasks_first_by_time() {
+    run(|| {
+        let today = Local::now().date_naive().format("%Y-%m-%d");
+        let harness = Harness::new(&format!(
+            "### Work\n\n- [ ] Lunch due:{today}T12:30\n- [ ] Review due:{today}\n- [ ] Stand-up due:{today}T09:15\n"
+        ));
+        harness.state.set_show_due_only(true);
+        harness.settle();
+
+        assert_eq!(harness.titles(), ["Stand-up", "Lunch", "Review"]);
+        let stand_up = harness.task("Stand-up");
+        assert!(format_metadata(&stand_up, &[MetaField::Due]).ends_with("09:15"));
+        assert!(!format_metadata(&harness.task("Review"), &[MetaField::Due]).contains(':'));
+    });
+}
+
+#[test]
+fn rows_carry_priority_and_density_classes() {
+    run(|| {
+        let harness = Harness::new("- [ ] Write report pri:A\n- [ ] Call Anna +home\n");
+        let classes = |title: &str| {
+            let rows: Vec<TodoRow> = harness.state.rows.borrow().iter().filter_map(|row| row.upgrade()).collect();
+            let row = rows.into_iter().find(|row| row.title_label().text() == title).expect("row is built");
+            row.task_box().css_classes().iter().map(|class| class.to_string()).collect::<Vec<_>>()
+        };
+        let report = classes("Write report");
+        assert!(report.contains(&"todo-priority-a".to_string()) && report.contains(&"todo-compact".to_string()), "{report:?}");
+        let call = classes("Call Anna");
+        assert!(!call.iter().any(|class| class.starts_with("todo-priority-") || class == "todo-compact"), "{call:?}");
+
+        let mut report = harness.task("Write report");
+        report.priority = Some('B');
+        data::update_todo_details(&report).unwrap();
+        harness.state.reload().expect("reload");
+        harness.settle();
+        let report = classes("Write report");
+        assert!(report.contains(&"todo-priority-b".to_string()) && !report.contains(&"todo-priority-a".to_string()), "{report:?}");
+    });
+}
+
+#[test]
+fn creation_dates_are_shown_only_when_chosen() {
+    let task = data::parse_task_text("Renew passport created:2025-01-27").expect("task parses");
+    assert!(!MetaField::defaults().contains(&MetaField::Created));
+    assert_eq!(format_metadata(&task, &MetaField::defaults()), "");
+    let created = config::format_date(NaiveDate::from_ymd_opt(2025, 1, 27).unwrap());
+    assert!(format_metadata(&task, &[MetaField::Created]).contains(&created));
+}
+
+#[test]
+fn reminder_presets_lead_up_to_the_due_date() {
+    let at = |day: u32, hour: u32, minute: u32| NaiveDate::from_ymd_opt(2025, 1, day).and_then(|date| date.and_hms_opt(hour, minute, 0)).unwrap();
+    let now = at(27, 14, 5);
+    let picks: Vec<NaiveDateTime> = reminder_presets(NaiveDate::from_ymd_opt(2025, 1, 31), now).into_iter().map(|(_, at)| at).collect();
+    assert_eq!(picks, [at(27, 15, 5), at(28, 9, 0), at(30, 9, 0), at(29, 9, 0)]);
+
+    // Mornings already past are left out.
+    assert_eq!(reminder_presets(NaiveDate::from_ymd_opt(2025, 1, 28), now).len(), 2);
+    assert_eq!(parse_reminder("2025-01-29 09:00"), Some(at(29, 9, 0)));
+    assert_eq!(parse_reminder("2025-01-29T09:00"), Some(at(29, 9, 0)));
+    assert_eq!(parse_reminder("Wednesday"), None);
+}
+
+#[test]
+fn search_lists_and_marks_the_matches() {
+    assert_eq!(crate::todo_row::match_ranges("Call Anna, call back", "CALL"), [0..4, 11..15]);
+    assert_eq!(crate::todo_row::match_ranges("Äpfel und äpfel", "äp"), [0..3, 11..14]);
+    assert!(crate::todo_row::match_ranges("Plan trip", "").is_empty());
+    run(|| {
+        let harness = Harness::new(DOCUMENT);
+        harness.state.set_search_term("ann");
+        harness.settle();
+        assert_eq!(harness.titles(), ["Call Anna"]);
+
+        harness.state.set_search_term("");
+        harness.settle();
+        assert_eq!(harness.titles().len(), 3);
+    });
+}
+
+#[test]
+fn selected_text_becomes_one_title() {
+    assert_eq!(selection_title("  Read\n  the   article \n").as_deref(), Some("Read the article"));
+    assert_eq!(selection_title(" \n\t"), None);
+}
+
+#[test]
+fn pinned_accent_follows_the_database() {
+    assert!(accent_css("teal").is_some_and(|css| css.contains("#2190a4")));
+    assert_eq!(accent_css("mauve"), None);
+    run(|| {
+        let harness = Harness::new(DOCUMENT);
+        harness.state.set_accent(Some("green"));
+        assert_eq!(harness.state.accent().as_deref(), Some("green"));
+        assert!(harness.state.accent_provider.to_str().contains("#3a944a"));
+
+        harness.state.set_accent(None);
+        assert_eq!(harness.state.accent(), None);
+        assert!(!harness.state.accent_provider.to_str().contains("accent"));
+    });
+}
+
+#[test]
+fn reduced_motion_turns_animations_off() {
+    run(|| {
+        let harness = Harness::new(DOCUMENT);
+        let settings = gtk::Settings::default().expect("GTK settings");
+        let system = settings.is_gtk_enable_animations();
+        let scrolled = harness.state.scrolled_window.borrow().clone().expect("list scrolls");
+
+        harness.state.set_reduce_motion(true);
+        assert!(!settings.is_gtk_enable_animations());
+        assert!(!scrolled.is_kinetic_scrolling());
+
+        harness.state.set_reduce_motion(false);
+        assert_eq!(settings.is_gtk_enable_animations(), system);
+        assert_eq!(scrolled.is_kinetic_scrolling(), system);
+    });
+}
+
+/// Emits `key` on every key controller of `widget`, as a key press would,
+/// and whether one of them handled it.
+fn press(widget: &impl IsA<gtk::Widget>, key: gdk::Key, modifiers: gdk::ModifierType) -> bool {
+    let controllers = widget.as_ref().observe_controllers();
+    (0..controllers.n_items())
+        .filter_map(|position| controllers.item(position).and_downcast::<gtk::EventControllerKey>())
+        .any(|controller| controller.emit_by_name::<bool>("key-pressed", &[&key, &0u32, &modifiers]))
+}
+
+#[test]
+fn row_keys_act_unless_a_type_ahead_is_under_way() {
+    run(|| {
+        let harness = Harness::new(DOCUMENT);
+        let due = |title: &str| harness.task(title).due;
+        let before: Vec<_> = ["Write report", "Call Anna", "Plan trip"].map(due).to_vec();
+        let rows: Vec<TodoRow> = harness.state.rows.borrow().iter().filter_map(|row| row.upgrade()).collect();
+        assert!(!rows.is_empty(), "rows are built");
+        let list_view = harness.state.list_view.borrow().clone().expect("list view");
+        let none = gdk::ModifierType::empty();
+        assert!(!press(&list_view, gdk::Key::s, none), "a row action key starts no type-ahead");
+        assert!(press(&list_view, gdk::Key::w, none), "other letters start one");
+        for key in [gdk::Key::s, gdk::Key::t] {
+            for row in &rows {
+                assert!(!press(row, key, none), "while typing, {key:?} on a row is left to the type-ahead");
+            }
+            assert!(press(&list_view, key, none), "the type-ahead takes {key:?}");
+        }
+        harness.settle();
+        assert_eq!(["Write report", "Call Anna", "Plan trip"].map(due).to_vec(), before);
+
+        harness.state.type_ahead_key.set(None);
+        assert!(rows.iter().any(|row| press(row, gdk::Key::t, none)), "without a type-ahead t works on the row");
+        harness.settle();
+        let today = Some(Local::now().date_naive());
+        assert!(["Write report", "Call Anna", "Plan trip"].map(due).contains(&today));
+    });
+}